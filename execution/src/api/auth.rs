@@ -0,0 +1,75 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use std::net::SocketAddr;
+
+use crate::config::CONFIG;
+
+/// Guard for endpoints that place, change or cancel orders. With
+/// `ORDER_API_TOKEN` set they need `Authorization: Bearer <token>`; without
+/// it they only answer clients on this machine.
+pub async fn require_order_token(request: Request, next: Next) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0);
+    match authorize(request.headers(), peer, CONFIG.order_api_token.as_deref()) {
+        Ok(()) => next.run(request).await,
+        Err((status, error)) => (status, Json(json!({
+            "error": error,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })))
+            .into_response(),
+    }
+}
+
+fn authorize(headers: &HeaderMap, peer: Option<SocketAddr>, token: Option<&str>) -> Result<(), (StatusCode, &'static str)> {
+    let Some(token) = token else {
+        return match peer {
+            Some(peer) if peer.ip().is_loopback() => Ok(()),
+            _ => Err((StatusCode::FORBIDDEN, "Order endpoints only accept local clients unless ORDER_API_TOKEN is set")),
+        };
+    };
+
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if constant_time_eq(presented.as_bytes(), token.as_bytes()) => Ok(()),
+        _ => Err((StatusCode::UNAUTHORIZED, "Missing or invalid API token")),
+    }
+}
+
+/// Compare without stopping at the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_order_endpoints_need_token_or_local_client() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "203.0.113.7:50000".parse().unwrap();
+
+        // No token configured: local clients only
+        assert!(authorize(&HeaderMap::new(), Some(local), None).is_ok());
+        assert_eq!(authorize(&HeaderMap::new(), Some(remote), None).unwrap_err().0, StatusCode::FORBIDDEN);
+        assert!(authorize(&HeaderMap::new(), None, None).is_err());
+
+        // Token configured: everyone, local or not, must present it
+        assert!(authorize(&bearer("s3cret"), Some(remote), Some("s3cret")).is_ok());
+        assert_eq!(authorize(&bearer("guess"), Some(remote), Some("s3cret")).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert!(authorize(&HeaderMap::new(), Some(local), Some("s3cret")).is_err());
+    }
+}
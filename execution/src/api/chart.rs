@@ -11,9 +11,9 @@ use crate::market::ProviderManager;
 #[derive(Debug, Deserialize)]
 pub struct ChartParams {
     #[serde(default = "default_timeframe")]
-    timeframe: String,
+    pub timeframe: String,
     #[serde(default = "default_limit")]
-    limit: usize,
+    pub limit: usize,
 }
 
 fn default_timeframe() -> String {
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Json, Response,
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use tokio_stream::StreamExt;
+
+use crate::bus::{AppBus, AppEvent, BusError, ReplayFrom, TopicMessage};
+
+/// `?topic=orders.>&since=`
+#[derive(Debug, Deserialize)]
+pub struct EventParams {
+    #[serde(default = "default_topic")]
+    pub topic: String,      // Topic pattern, `*` and `>` wildcards
+    pub since: Option<i64>, // Replay logged events from this Unix ms timestamp
+}
+
+fn default_topic() -> String {
+    ">".to_string()
+}
+
+/// GET /api/events?topic=&since= - SSE stream of app bus events (quotes,
+/// candles, signals, orders, fills, risk, news) on a topic pattern
+pub async fn stream_events(Query(params): Query<EventParams>, State(events): State<AppBus>) -> Response {
+    let subscribed = match params.since {
+        // Reading the log is blocking file I/O
+        Some(ts) => {
+            let topic = params.topic.clone();
+            tokio::task::spawn_blocking(move || events.subscribe_events_from(&topic, ReplayFrom::Timestamp(ts)))
+                .await
+                .unwrap_or_else(|e| Err(BusError::Log(e.to_string())))
+        }
+        None => events.subscribe_events(&params.topic).map(|rx| (Vec::new(), rx)),
+    };
+    let (replay, rx) = match subscribed {
+        Ok(subscribed) => subscribed,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": e.to_string(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            })))
+                .into_response();
+        }
+    };
+
+    let stream = tokio_stream::iter(replay)
+        .chain(rx.into_stream())
+        .filter_map(|msg| app_event(&msg))
+        .map(Ok::<_, Infallible>);
+    Sse::new(stream).into_response()
+}
+
+/// Named after the event type, e.g. `event: QuoteUpdate`
+fn app_event(msg: &TopicMessage<AppEvent>) -> Option<Event> {
+    let value = serde_json::to_value(&msg.payload).ok()?;
+    let data = json!({
+        "topic": msg.topic,
+        "timestamp": msg.timestamp / 1_000_000, // Unix ms
+        "data": value["data"],
+    });
+    Some(Event::default().event(value["type"].as_str()?).data(data.to_string()))
+}
@@ -6,7 +6,11 @@ pub mod news;
 pub mod sse;
 pub mod performance;
 pub mod chart;
+pub mod smc;
+pub mod events;
+pub mod orders;
+pub mod auth;
 
-pub use models::LiveSignal;
 pub use performance::get_performance_metrics;
 pub use chart::get_chart_data;
+pub use smc::get_smc_zones;
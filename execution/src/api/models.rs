@@ -1,12 +1,191 @@
-use serde::Serialize;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
+use crate::signal::{
+    EnhancedSignal, SignalDirection, SignalExplanation, SignalGrade, SignalStatus, SignalTargets,
+    SignalTransition, SmcTags, StoredSignal, TrackedSignal,
+};
+
+/// Version of the `WireSignal` schema. Bump on any breaking change
+/// (removed/renamed field or changed meaning); adding optional fields is not breaking.
+pub const SIGNAL_SCHEMA_VERSION: u32 = 1;
+
+/// The signal as emitted by REST, SSE and webhooks. See "Signal Schema" in the README.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WireSignal {
+    pub schema_version: u32,
+    pub id: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub direction: SignalDirection,
+    pub ready: bool, // Actionable (buy or sell)
+    pub score: f64,  // 0-100
+    pub grade: SignalGrade,
+    pub confluence_count: u32,
+    pub power_score: f64,
+    pub whale_score: f64,
+    pub targets: SignalTargets,
+    pub smc_tags: SmcTags,
+    pub indicators: Vec<String>,
+    pub reason: String,
+    pub timestamp: i64, // Unix ms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<SignalExplanation>, // Why: factors, regime, score components
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<SignalStatus>, // Lifecycle state, when tracked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<SignalTransition>,
+}
+
+impl From<&EnhancedSignal> for WireSignal {
+    fn from(signal: &EnhancedSignal) -> Self {
+        Self {
+            schema_version: SIGNAL_SCHEMA_VERSION,
+            id: signal.id.clone(),
+            symbol: signal.symbol.clone(),
+            timeframe: signal.timeframe.clone(),
+            direction: signal.direction.clone(),
+            ready: signal.direction != SignalDirection::Neutral,
+            score: signal.score,
+            grade: signal.grade.clone(),
+            confluence_count: signal.confluence_count,
+            power_score: signal.power_score,
+            whale_score: signal.whale_score,
+            targets: signal.targets.clone(),
+            smc_tags: signal.smc_tags.clone(),
+            indicators: signal.indicators.clone(),
+            reason: signal.reason.clone(),
+            timestamp: signal.timestamp,
+            explanation: signal.explanation.clone(),
+            status: None,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl From<&StoredSignal> for WireSignal {
+    fn from(stored: &StoredSignal) -> Self {
+        Self {
+            status: Some(stored.status),
+            events: stored.events.clone(),
+            ..Self::from(&stored.signal)
+        }
+    }
+}
+
+/// A live-tracked signal: the wire signal plus where it stands right now
+#[derive(Debug, Clone, Serialize)]
+pub struct WireTrackedSignal {
+    #[serde(flatten)]
+    pub signal: WireSignal,
+    pub triggered_at: Option<i64>,
+    pub tp_hit_at: [Option<i64>; 3],
+    pub closed_at: Option<i64>,
+    pub exit_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub mfe_r: f64,
+    pub mae_r: f64,
+}
+
+impl From<&TrackedSignal> for WireTrackedSignal {
+    fn from(tracked: &TrackedSignal) -> Self {
+        Self {
+            signal: WireSignal {
+                status: Some(tracked.status),
+                ..WireSignal::from(&tracked.signal)
+            },
+            triggered_at: tracked.triggered_at,
+            tp_hit_at: tracked.tp_hit_at,
+            closed_at: tracked.closed_at,
+            exit_price: tracked.exit_price,
+            last_price: tracked.last_price,
+            mfe_r: tracked.mfe_r(),
+            mae_r: tracked.mae_r(),
+        }
+    }
+}
+
+/// Legacy shape of `/signals/live`, kept for existing dashboard consumers.
+/// Built from `WireSignal`; new consumers should use `/api/signals/stream`.
 #[derive(Clone, Serialize)]
 pub struct LiveSignal {
+    #[serde(rename = "type")]
+    pub kind: &'static str, // Always "signal"
+    pub id: String,
     pub symbol: String,
     pub horizon: String,
     pub ready: bool,
+    pub score: f64,
     pub tags: Value,
     pub reason: String,
     pub ts_unix_ms: i64,
 }
+
+impl From<&WireSignal> for LiveSignal {
+    fn from(signal: &WireSignal) -> Self {
+        Self {
+            kind: "signal",
+            id: signal.id.clone(),
+            symbol: signal.symbol.clone(),
+            horizon: signal.timeframe.clone(),
+            ready: signal.ready,
+            score: signal.score,
+            tags: json!({
+                "schema_version": signal.schema_version,
+                "direction": signal.direction,
+                "grade": signal.grade,
+                "indicators": signal.indicators,
+                "smc": signal.smc_tags,
+                "targets": signal.targets,
+            }),
+            reason: signal.reason.clone(),
+            ts_unix_ms: signal.timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::SignalBuilder;
+
+    fn signal() -> EnhancedSignal {
+        SignalBuilder::new("BTCUSDT".to_string(), "M15".to_string(), SignalDirection::Buy, 100.0, 98.0)
+            .add_indicator("RSI".to_string())
+            .reason("Buy signal from RSI".to_string())
+            .build(0.8)
+    }
+
+    #[test]
+    fn test_wire_signal_from_enhanced() {
+        let enhanced = signal();
+        let wire = WireSignal::from(&enhanced);
+        assert_eq!(wire.schema_version, SIGNAL_SCHEMA_VERSION);
+        assert_eq!(wire.id, enhanced.id);
+        assert!(wire.ready);
+        assert_eq!(wire.targets.take_profit_1, 104.0);
+
+        let json = serde_json::to_value(&wire).unwrap();
+        assert_eq!(json["direction"], "buy");
+        assert_eq!(json["grade"], serde_json::to_value(&enhanced.grade).unwrap());
+        assert!(json.get("status").is_none());
+        assert!(json.get("events").is_none());
+
+        let parsed: WireSignal = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.symbol, "BTCUSDT");
+    }
+
+    #[test]
+    fn test_live_signal_adapter_keeps_legacy_fields() {
+        let wire = WireSignal::from(&StoredSignal::new(signal()));
+        assert_eq!(wire.status, Some(SignalStatus::PendingEntry));
+
+        let json = serde_json::to_value(LiveSignal::from(&wire)).unwrap();
+        for field in ["symbol", "horizon", "ready", "tags", "reason", "ts_unix_ms"] {
+            assert!(json.get(field).is_some(), "missing legacy field {}", field);
+        }
+        assert_eq!(json["type"], "signal");
+        assert_eq!(json["horizon"], "M15");
+        assert_eq!(json["tags"]["indicators"][0], "RSI");
+    }
+}
@@ -0,0 +1,454 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::engine::algo::volume_profile;
+use crate::engine::triggers::atr;
+use crate::config::CONFIG;
+use crate::engine::{AlgoStatus, BracketConfig, BracketRequest, EngineMessage, OrderManager, OrderParams, OrderRequest, OrderSide, OrderType, QuantumEngine, RouterError, Trail, TrailBy};
+use crate::market::ProviderManager;
+use crate::signal::SignalRepository;
+
+/// VWAP volume profiles come from this many 15 minute candles, about five days
+const PROFILE_CANDLES: usize = 480;
+
+/// ATR trailing stops use a 14 period ATR, smoothed over this many candles
+const ATR_PERIOD: usize = 14;
+const ATR_CANDLES: usize = 100;
+
+/// Body of `POST /api/orders`
+#[derive(Debug, Deserialize)]
+pub struct NewOrderParams {
+    pub symbol: String,
+    pub side: OrderSide,
+    #[serde(default = "default_order_type")]
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub price: Option<f64>,
+    pub stop_price: Option<f64>,
+    /// TWAP/VWAP schedule
+    pub duration_secs: Option<u64>,
+    pub num_slices: Option<u32>,
+    #[serde(default)]
+    pub randomize: f64,
+    pub max_participation: Option<f64>,
+    /// Iceberg slices
+    pub visible_quantity: Option<f64>,
+    #[serde(default)]
+    pub price_jitter_bps: f64,
+    /// Trailing stop distance: an amount, a percentage or an ATR multiple
+    pub trail_amount: Option<f64>,
+    pub trail_percent: Option<f64>,
+    pub trail_atr: Option<f64>,
+    #[serde(default = "default_atr_timeframe")]
+    pub atr_timeframe: String,
+    /// Trailing stop goes out as a limit this far past the trigger price
+    pub limit_offset: Option<f64>,
+}
+
+impl NewOrderParams {
+    fn trail(&self, atr: Option<f64>) -> Option<Trail> {
+        let by = match (self.trail_amount, self.trail_percent, self.trail_atr, atr) {
+            (Some(amount), _, _, _) => TrailBy::Amount(amount),
+            (_, Some(pct), _, _) => TrailBy::Percent(pct),
+            (_, _, Some(multiplier), Some(atr)) => TrailBy::Atr { multiplier, atr },
+            _ => return None,
+        };
+        Some(Trail { by, limit_offset: self.limit_offset })
+    }
+}
+
+fn default_atr_timeframe() -> String {
+    "15".to_string()
+}
+
+fn default_order_type() -> OrderType {
+    OrderType::Market
+}
+
+/// Body of `POST /api/brackets`
+#[derive(Debug, Deserialize)]
+pub struct BracketParams {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub entry_price: Option<f64>, // None enters at market
+    pub stop_price: f64,
+    pub take_profits: Vec<f64>,
+    #[serde(flatten)]
+    pub exits: ExitParams,
+}
+
+/// Body of `POST /api/signals/:id/bracket`
+#[derive(Debug, Deserialize)]
+pub struct SignalBracketParams {
+    pub quantity: f64,
+    #[serde(flatten)]
+    pub exits: ExitParams,
+}
+
+/// Overrides of the configured bracket exits
+#[derive(Debug, Deserialize)]
+pub struct ExitParams {
+    pub tp_splits: Option<Vec<f64>>,
+    pub breakeven_after_tp1: Option<bool>,
+}
+
+impl ExitParams {
+    fn config(self) -> BracketConfig {
+        BracketConfig {
+            tp_splits: self.tp_splits.unwrap_or_else(|| CONFIG.bracket_tp_splits.clone()),
+            breakeven_after_tp1: self.breakeven_after_tp1.unwrap_or(CONFIG.bracket_breakeven),
+        }
+    }
+}
+
+/// Body of `POST /api/engine/kill-switch`
+#[derive(Debug, Deserialize)]
+pub struct KillSwitchParams {
+    #[serde(default = "default_kill_reason")]
+    pub reason: String,
+}
+
+fn default_kill_reason() -> String {
+    "Manual kill switch".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrdersQuery {
+    pub symbol: Option<String>,
+}
+
+/// POST /api/orders - Queue an order for the engine. Accepted means queued;
+/// risk, routing and fills follow on `/api/events?topic=orders.>`. VWAP
+/// orders get a volume profile from recent candles, uniform if there are none;
+/// ATR trailing stops get the ATR of `atr_timeframe` candles.
+pub async fn submit_order(
+    State(engine): State<Arc<QuantumEngine>>,
+    State(oms): State<Arc<OrderManager>>,
+    State(provider_manager): State<Arc<ProviderManager>>,
+    Json(params): Json<NewOrderParams>,
+) -> impl IntoResponse {
+    let volume_profile = match (&params.order_type, params.duration_secs, params.num_slices) {
+        (OrderType::VWAP, Some(duration), Some(slices)) => {
+            match provider_manager.get_candles(&params.symbol, "15", PROFILE_CANDLES).await {
+                Ok(candles) => {
+                    let start = chrono::Utc::now().timestamp_millis();
+                    Some(volume_profile(&candles, start, duration * 1000, slices))
+                }
+                Err(e) => {
+                    tracing::warn!("No volume profile for {}, VWAP slices are uniform: {}", params.symbol, e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let atr = match params.trail_atr {
+        Some(_) => match provider_manager.get_candles(&params.symbol, &params.atr_timeframe, ATR_CANDLES).await {
+            Ok(candles) => atr(&candles, ATR_PERIOD),
+            Err(e) => {
+                tracing::warn!("No candles for {} ATR: {}", params.symbol, e);
+                None
+            }
+        },
+        None => None,
+    };
+    if params.trail_atr.is_some() && atr.is_none() {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": format!("No ATR for {} on {} candles", params.symbol, params.atr_timeframe),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })));
+    }
+    let trail = params.trail(atr);
+
+    let order_id = oms.next_order_id();
+    let request = OrderRequest {
+        order_id,
+        symbol: params.symbol,
+        side: params.side,
+        order_type: params.order_type,
+        quantity: params.quantity,
+        price: params.price,
+        stop_price: params.stop_price,
+        timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        params: OrderParams {
+            duration_secs: params.duration_secs,
+            num_slices: params.num_slices,
+            randomize: params.randomize,
+            max_participation: params.max_participation,
+            volume_profile,
+            visible_quantity: params.visible_quantity,
+            price_jitter_bps: params.price_jitter_bps,
+            trail,
+        },
+    };
+
+    match engine.submit(EngineMessage::NewOrder(request)) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(json!({
+            "order_id": order_id,
+            "status": "queued",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// DELETE /api/orders/:id - Queue a cancel for the engine
+pub async fn cancel_order(
+    Path(order_id): Path<u64>,
+    State(engine): State<Arc<QuantumEngine>>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    if oms.router().get_order(order_id).is_none() {
+        return (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Order {} not found", order_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })));
+    }
+
+    match engine.submit(EngineMessage::CancelOrder(order_id)) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(json!({
+            "order_id": order_id,
+            "status": "cancel_queued",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// POST /api/orders/:id/pause - Stop sending a TWAP/VWAP order's slices;
+/// working children stay
+pub async fn pause_order(
+    Path(order_id): Path<u64>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    algo_response(order_id, oms.pause_algo(order_id))
+}
+
+/// POST /api/orders/:id/resume - Continue a paused TWAP/VWAP order where it stopped
+pub async fn resume_order(
+    Path(order_id): Path<u64>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    algo_response(order_id, oms.resume_algo(order_id))
+}
+
+fn algo_response(order_id: u64, result: Result<AlgoStatus, RouterError>) -> (StatusCode, Json<serde_json::Value>) {
+    match result {
+        Ok(status) => (StatusCode::OK, Json(json!({
+            "algo": status,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(RouterError::OrderNotFound) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("No TWAP/VWAP order {}", order_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/orders/:id/progress - Fill progress of an order worked in child
+/// orders, with its schedule for TWAP/VWAP
+pub async fn get_order_progress(
+    Path(order_id): Path<u64>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    match oms.router().parent_progress(order_id) {
+        Some(progress) => (StatusCode::OK, Json(json!({
+            "progress": progress,
+            "children": oms.router().child_orders(order_id),
+            "algo": oms.algo_status(order_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        None => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Order {} not found", order_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/orders?symbol= - Active orders, or every order for one symbol
+pub async fn get_orders(
+    Query(query): Query<OrdersQuery>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    let mut orders = match &query.symbol {
+        Some(symbol) => oms.router().get_orders_by_symbol(symbol),
+        None => oms.router().get_active_orders(),
+    };
+    orders.sort_by_key(|o| std::cmp::Reverse(o.id));
+
+    (StatusCode::OK, Json(json!({
+        "orders": orders,
+        "count": orders.len(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// POST /api/brackets - Queue an entry with a stop and up to three take profits
+pub async fn submit_bracket(
+    State(engine): State<Arc<QuantumEngine>>,
+    State(oms): State<Arc<OrderManager>>,
+    Json(params): Json<BracketParams>,
+) -> impl IntoResponse {
+    let request = BracketRequest {
+        entry_id: oms.next_order_id(),
+        symbol: params.symbol,
+        side: params.side,
+        quantity: params.quantity,
+        entry_price: params.entry_price,
+        stop_price: params.stop_price,
+        take_profits: params.take_profits,
+        config: params.exits.config(),
+        signal_id: None,
+    };
+    queue_bracket(&engine, request)
+}
+
+/// POST /api/signals/:id/bracket - Queue a bracket on a stored signal's entry,
+/// stop and TP1-TP3
+pub async fn submit_signal_bracket(
+    Path(signal_id): Path<String>,
+    State(engine): State<Arc<QuantumEngine>>,
+    State(oms): State<Arc<OrderManager>>,
+    State(store): State<Arc<dyn SignalRepository>>,
+    Json(params): Json<SignalBracketParams>,
+) -> impl IntoResponse {
+    let stored = match store.get(&signal_id) {
+        Ok(Some(stored)) => stored,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Signal {} not found", signal_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    };
+    match BracketRequest::from_signal(&stored.signal, params.quantity, params.exits.config()) {
+        Ok(request) => queue_bracket(&engine, BracketRequest { entry_id: oms.next_order_id(), ..request }),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+fn queue_bracket(engine: &QuantumEngine, request: BracketRequest) -> (StatusCode, Json<serde_json::Value>) {
+    if let Err(e) = request.validate() {
+        return (StatusCode::BAD_REQUEST, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        })));
+    }
+    let entry_id = request.entry_id;
+    match engine.submit(EngineMessage::NewBracket(request)) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(json!({
+            "entry_id": entry_id,
+            "status": "queued",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/brackets - Every bracket with its orders and position, newest first
+pub async fn get_brackets(State(oms): State<Arc<OrderManager>>) -> impl IntoResponse {
+    let brackets = oms.brackets();
+    (StatusCode::OK, Json(json!({
+        "brackets": brackets,
+        "count": brackets.len(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// GET /api/triggers - Armed stop, stop-limit and trailing stop orders with their current stop
+pub async fn get_triggers(State(oms): State<Arc<OrderManager>>) -> impl IntoResponse {
+    let triggers = oms.triggers();
+    (StatusCode::OK, Json(json!({
+        "triggers": triggers,
+        "count": triggers.len(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// DELETE /api/brackets/:id - Cancel a bracket's working entry and exits
+pub async fn cancel_bracket(
+    Path(entry_id): Path<u64>,
+    State(oms): State<Arc<OrderManager>>,
+) -> impl IntoResponse {
+    match oms.cancel_bracket(entry_id) {
+        Ok(bracket) => (StatusCode::OK, Json(json!({
+            "bracket": bracket,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(RouterError::OrderNotFound) => (StatusCode::NOT_FOUND, Json(json!({
+            "error": format!("Bracket {} not found", entry_id),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/portfolio - Positions, cash, equity and risk state of the OMS
+pub async fn get_portfolio(State(oms): State<Arc<OrderManager>>) -> impl IntoResponse {
+    let risk = oms.risk();
+    (StatusCode::OK, Json(json!({
+        "positions": risk.positions(),
+        "cash": oms.cash(),
+        "equity": oms.equity(),
+        "metrics": risk.calculate_metrics(),
+        "circuit_breaker": risk.get_circuit_breaker_state(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// POST /api/engine/kill-switch - Queue a kill switch on the risk lane: trips
+/// the circuit breaker and cancels every active order ahead of anything queued
+pub async fn kill_switch(
+    State(engine): State<Arc<QuantumEngine>>,
+    Json(params): Json<KillSwitchParams>,
+) -> impl IntoResponse {
+    match engine.submit(EngineMessage::KillSwitch(params.reason)) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(json!({
+            "status": "kill_switch_queued",
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::SERVICE_UNAVAILABLE, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/engine/lanes - Depth, capacity and counters of each engine lane
+pub async fn get_engine_lanes(State(engine): State<Arc<QuantumEngine>>) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({
+        "lanes": engine.lane_stats(),
+        "queue_utilization": engine.queue_utilization(),
+        "running": engine.is_running(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
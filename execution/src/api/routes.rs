@@ -1,27 +1,24 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
+    http::HeaderMap,
     response::sse::{Event, Sse},
 };
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use std::convert::Infallible;
 
 use crate::bus::SignalBus;
-use crate::api::models::LiveSignal;
+use crate::api::models::{LiveSignal, WireSignal};
+use crate::api::sse::{bus_events, create_event, StreamParams};
+use crate::signal::EnhancedSignal;
 
+/// GET /signals/live - Legacy SSE stream, in the `LiveSignal` shape
 pub async fn sse_signals(
-    State(bus): State<SignalBus<LiveSignal>>,
+    Query(params): Query<StreamParams>,
+    headers: HeaderMap,
+    State(bus): State<SignalBus<EnhancedSignal>>,
 ) -> Sse<impl tokio_stream::Stream<Item = Result<Event, Infallible>>> {
-    let rx = bus.subscribe();
+    let (replay, rx) = params.subscribe(&bus, &headers, "sse:/signals/live").await;
 
-    let stream = BroadcastStream::new(rx)
-        .map(|msg| {
-            msg.ok().and_then(|signal| {
-                serde_json::to_string(&signal)
-                    .ok()
-                    .map(|json| Ok(Event::default().data(json)))
-            })
-        })
-        .filter_map(|x| x);
-
-    Sse::new(stream)
+    Sse::new(bus_events(replay, rx, "/api/signals", |signal| {
+        create_event(&LiveSignal::from(&WireSignal::from(&signal))).ok()
+    }))
 }
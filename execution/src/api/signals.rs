@@ -1,47 +1,143 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
+    http::HeaderMap,
     response::{sse::Event, IntoResponse, Json, Sse},
     http::StatusCode,
 };
 use serde_json::json;
-use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 use std::convert::Infallible;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 
-use crate::bus::SignalBus;
-use crate::api::models::LiveSignal;
+use crate::bus::{AppBus, SignalBus};
+use crate::api::models::{WireSignal, WireTrackedSignal};
+use crate::api::sse::{bus_events, create_event, create_named_event, StreamParams};
+use crate::signal::{
+    Calibrator, EnhancedSignal, SignalQuery, SignalRepository, SignalStatsReport, SignalTracker, SignalTransition,
+    StoreError, TrackedSignal, SCORING_PROFILE,
+};
 
-/// GET /api/signals - Get latest signals
+/// GET /api/signals?symbol=&direction=&grade=&timeframe=&from=&to=&cursor=&limit=
+/// Stored signals with their lifecycle, newest first. `from`/`to` are Unix ms;
+/// pass `next_cursor` back as `cursor` for the next page.
 pub async fn get_signals(
-    State(signal_buffer): State<Arc<RwLock<Vec<LiveSignal>>>>,
+    Query(query): Query<SignalQuery>,
+    State(store): State<Arc<dyn SignalRepository>>,
 ) -> impl IntoResponse {
-    // Read from the shared signal buffer
-    let signals = signal_buffer.read().await;
-    let signal_list: Vec<LiveSignal> = signals.clone();
-    
+    match store.query(&query) {
+        Ok(page) => (StatusCode::OK, Json(json!({
+            "signals": page.signals.iter().map(WireSignal::from).collect::<Vec<_>>(),
+            "count": page.signals.len(),
+            "next_cursor": page.next_cursor,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => {
+            let status = match e {
+                StoreError::InvalidCursor(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(json!({
+                "error": e.to_string(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+            })))
+        }
+    }
+}
+
+/// GET /api/signals/stream?policy=&capacity=&from_offset=&since= - SSE
+/// stream of signals in the wire schema, resumable with `Last-Event-ID`.
+/// A "gap" event means messages were missed: reload `/api/signals`.
+pub async fn stream_signals(
+    Query(params): Query<StreamParams>,
+    headers: HeaderMap,
+    State(bus): State<SignalBus<EnhancedSignal>>,
+) -> Sse<impl tokio_stream::Stream<Item = Result<Event, Infallible>>> {
+    let (replay, rx) = params.subscribe(&bus, &headers, "sse:/api/signals/stream").await;
+
+    Sse::new(bus_events(replay, rx, "/api/signals", |signal| {
+        create_event(&WireSignal::from(&signal)).ok()
+    }))
+}
+
+/// GET /api/signals/tracked - Tracked signals with their lifecycle state, newest first
+pub async fn get_tracked_signals(
+    State(tracker): State<Arc<SignalTracker>>,
+) -> impl IntoResponse {
+    let signals: Vec<WireTrackedSignal> = tracker.all().iter().map(WireTrackedSignal::from).collect();
+
     (StatusCode::OK, Json(json!({
-        "signals": signal_list,
-        "count": signal_list.len(),
+        "signals": signals,
+        "count": signals.len(),
         "timestamp": chrono::Utc::now().to_rfc3339(),
     })))
 }
 
-/// GET /api/signals/stream - SSE stream of signals
-pub async fn stream_signals(
-    State(bus): State<SignalBus<LiveSignal>>,
+/// GET /api/signals/stats - Win rate, average R and expectancy of resolved signals
+/// by grade, indicator, SMC setup, timeframe and market type
+pub async fn get_signal_stats(
+    State(store): State<Arc<dyn SignalRepository>>,
+) -> impl IntoResponse {
+    let report = SignalStatsReport::from_signals(&signal_history(store.as_ref()));
+
+    (StatusCode::OK, Json(json!({
+        "stats": report,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// GET /api/signals/calibration - Fit scoring weights to resolved signals and
+/// report how well current and calibrated grades separate winners from losers
+pub async fn get_signal_calibration(
+    State(store): State<Arc<dyn SignalRepository>>,
+) -> impl IntoResponse {
+    match Calibrator::default().calibrate(&SCORING_PROFILE, &signal_history(store.as_ref())) {
+        Ok(report) => (StatusCode::OK, Json(json!({
+            "calibration": report,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({
+            "error": e.to_string(),
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }))),
+    }
+}
+
+/// GET /api/signals/lifecycle?policy=&capacity=&from_offset=&since= - SSE
+/// stream of signal state transitions, resumable with `Last-Event-ID`;
+/// resync from `/api/signals/tracked` after a "gap" event
+pub async fn stream_signal_lifecycle(
+    Query(params): Query<StreamParams>,
+    headers: HeaderMap,
+    State(bus): State<SignalBus<SignalTransition>>,
 ) -> Sse<impl tokio_stream::Stream<Item = Result<Event, Infallible>>> {
-    let rx = bus.subscribe();
-
-    let stream = BroadcastStream::new(rx)
-        .map(|msg| {
-            msg.ok().and_then(|signal| {
-                serde_json::to_string(&signal)
-                    .ok()
-                    .map(|json| Ok(Event::default().data(json)))
-            })
-        })
-        .filter_map(|x| x);
-
-    Sse::new(stream)
+    let (replay, rx) = params.subscribe(&bus, &headers, "sse:/api/signals/lifecycle").await;
+
+    Sse::new(bus_events(replay, rx, "/api/signals/tracked", |transition| {
+        create_named_event("transition", &transition).ok()
+    }))
+}
+
+/// GET /api/bus/stats - Per-subscriber lag and drop counters of the signal buses,
+/// throughput of the app bus
+pub async fn get_bus_stats(
+    State(signals): State<SignalBus<EnhancedSignal>>,
+    State(lifecycle): State<SignalBus<SignalTransition>>,
+    State(events): State<AppBus>,
+) -> impl IntoResponse {
+    (StatusCode::OK, Json(json!({
+        "signals": signals.stats(),
+        "lifecycle": lifecycle.stats(),
+        "app": {
+            "messages_sent": events.messages_sent(),
+            "queue_utilization": events.queue_utilization(),
+            "subscriptions": events.subscription_count(),
+        },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+fn signal_history(store: &dyn SignalRepository) -> Vec<TrackedSignal> {
+    store.history().unwrap_or_else(|e| {
+        tracing::error!("Failed to load signal history: {}", e);
+        Vec::new()
+    })
 }
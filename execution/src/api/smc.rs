@@ -0,0 +1,342 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Json},
+    http::StatusCode,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::api::chart::ChartParams;
+use crate::market::{ProviderCandle, ProviderManager, SymbolUniverse};
+use crate::scanner::timeframe;
+use crate::smc::{
+    BosType, FvgDetector, LiquidityType, SessionLiquidityDetector, SmcStream, SmcStreamConfig,
+    SmcStreams, SmcZone, ZoneData,
+};
+
+#[derive(Debug, Serialize)]
+pub struct SmcResponse {
+    symbol: String,
+    timeframe: String,
+    zones: Vec<ZoneOverlay>,
+    structure: Vec<StructureLine>,
+    count: usize,
+}
+
+/// A price band (or single level when top == bottom) to draw on the chart
+#[derive(Debug, Serialize)]
+pub struct ZoneOverlay {
+    id: u64,
+    kind: &'static str,
+    direction: &'static str,
+    label: String,
+    top: f64,
+    bottom: f64,
+    start_time: i64,       // Seconds, like the chart candles
+    end_time: Option<i64>, // When mitigated/swept; open-ended if None
+    strength: f64,
+    status: String,
+    fill_pct: Option<f64>,
+}
+
+/// A break of structure line from the broken swing to the breaking candle
+#[derive(Debug, Serialize)]
+pub struct StructureLine {
+    kind: &'static str,
+    direction: &'static str,
+    price: f64,
+    start_time: i64, // Broken swing, in seconds like the chart candles
+    end_time: i64,   // Breaking candle
+    strength: f64,
+}
+
+/// Most candles one request replays through the detectors
+const MAX_SMC_CANDLES: usize = 1000;
+
+/// GET /api/smc/{symbol}?timeframe=15&limit=500
+/// Returns active and recently mitigated SMC zones for chart overlays. Zones
+/// come from the symbol's long-lived stream when the scanner keeps one for
+/// this timeframe, otherwise from the same candles as /api/chart/{symbol}
+pub async fn get_smc_zones(
+    Path(symbol): Path<String>,
+    Query(params): Query<ChartParams>,
+    State(provider_manager): State<Arc<ProviderManager>>,
+    State(streams): State<Arc<SmcStreams>>,
+) -> impl IntoResponse {
+    let limit = params.limit.min(MAX_SMC_CANDLES);
+    tracing::info!(
+        "SMC zones requested for {} with timeframe {} and limit {}",
+        symbol,
+        params.timeframe,
+        limit
+    );
+
+    let empty = |symbol: String, timeframe: String| SmcResponse {
+        symbol,
+        timeframe,
+        zones: Vec::new(),
+        structure: Vec::new(),
+        count: 0,
+    };
+
+    let candles = match provider_manager.get_candles(&symbol, &params.timeframe, limit).await {
+        Ok(candles) => candles,
+        Err(e) => {
+            tracing::error!("Failed to fetch candles for SMC zones of {}: {}", symbol, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(empty(symbol, params.timeframe)));
+        }
+    };
+
+    // Replaying the candles is CPU-bound; keep it off the async workers
+    let (sym, timeframe) = (symbol.clone(), params.timeframe.clone());
+    let detect = move || {
+        let label = timeframe::label(&timeframe);
+        if !streams.contains(&sym, &label) {
+            return build_smc_response(&sym, &timeframe, &candles);
+        }
+        // The last candle is still forming; only closed ones go into the shared stream
+        let tuples = candle_tuples(&candles);
+        streams.catch_up(&sym, &label, &tuples[..tuples.len().saturating_sub(1)]);
+        streams
+            .read(&sym, &label, |stream| stream_response(&sym, &timeframe, stream, &tuples))
+            .unwrap_or_else(|| build_smc_response(&sym, &timeframe, &candles))
+    };
+    match tokio::task::spawn_blocking(detect).await {
+        Ok(response) => (StatusCode::OK, Json(response)),
+        Err(e) => {
+            tracing::error!("SMC zone detection for {} failed: {}", symbol, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(empty(symbol, params.timeframe)))
+        }
+    }
+}
+
+/// Replay candles through the streaming detectors and convert the result to
+/// overlays. Detection runs over the stream's rolling window, so the cost per
+/// candle stays fixed however many candles are replayed.
+pub fn build_smc_response(symbol: &str, timeframe: &str, candles: &[ProviderCandle]) -> SmcResponse {
+    let tuples = candle_tuples(candles);
+
+    let mut stream = SmcStream::new(SmcStreamConfig::default());
+    for candle in &tuples {
+        stream.on_candle(*candle);
+    }
+
+    stream_response(symbol, timeframe, &stream, &tuples)
+}
+
+fn candle_tuples(candles: &[ProviderCandle]) -> Vec<(f64, f64, f64, f64, i64)> {
+    candles
+        .iter()
+        .map(|c| (c.open, c.high, c.low, c.close, c.timestamp))
+        .collect()
+}
+
+/// Overlays for a stream's zones and structure breaks, plus balanced price
+/// ranges and session liquidity found in `tuples`
+fn stream_response(
+    symbol: &str,
+    timeframe: &str,
+    stream: &SmcStream,
+    tuples: &[(f64, f64, f64, f64, i64)],
+) -> SmcResponse {
+    let mut zones: Vec<ZoneOverlay> = stream
+        .active_zones()
+        .iter()
+        .chain(stream.recent_zones())
+        .map(zone_overlay)
+        .collect();
+
+    let mut next_id = zones.iter().map(|z| z.id).max().unwrap_or(0) + 1;
+
+    // Balanced price ranges; the older gap of a pair may already be traded into
+    let fvgs: Vec<_> = stream
+        .active_zones()
+        .iter()
+        .chain(stream.recent_zones())
+        .filter_map(|z| match &z.data {
+            ZoneData::FairValueGap(fvg) => Some(fvg.clone()),
+            _ => None,
+        })
+        .collect();
+    for bpr in FvgDetector::default().find_balanced_ranges(&fvgs) {
+        zones.push(ZoneOverlay {
+            id: next_id,
+            kind: "balanced_price_range",
+            direction: "neutral",
+            label: bpr.tag(),
+            top: bpr.top,
+            bottom: bpr.bottom,
+            start_time: bpr.timestamp / 1000,
+            end_time: None,
+            strength: 1.0,
+            status: "active".to_string(),
+            fill_pct: None,
+        });
+        next_id += 1;
+    }
+
+    // Time-based liquidity (previous day/week, session ranges)
+    let sessions = SessionLiquidityDetector::for_market(&SymbolUniverse::market_type_of(symbol));
+    for zone in sessions.detect(tuples) {
+        zones.push(zone_overlay(&SmcZone {
+            id: next_id,
+            data: ZoneData::Liquidity(zone),
+        }));
+        next_id += 1;
+    }
+
+    let structure: Vec<StructureLine> = stream
+        .structure_breaks()
+        .map(|bos| StructureLine {
+            kind: "bos",
+            direction: match bos.bos_type {
+                BosType::Bullish => "bullish",
+                BosType::Bearish => "bearish",
+            },
+            price: bos.break_level,
+            start_time: bos.swing_timestamp / 1000,
+            end_time: bos.timestamp / 1000,
+            strength: bos.strength,
+        })
+        .collect();
+
+    SmcResponse {
+        symbol: symbol.to_string(),
+        timeframe: timeframe.to_string(),
+        count: zones.len(),
+        zones,
+        structure,
+    }
+}
+
+fn zone_overlay(zone: &SmcZone) -> ZoneOverlay {
+    let (kind, direction, label, fill_pct) = match &zone.data {
+        ZoneData::FairValueGap(fvg) => (
+            "fair_value_gap",
+            if zone.is_bullish() { "bullish" } else { "bearish" },
+            fvg.tag(),
+            Some(fvg.fill_pct),
+        ),
+        ZoneData::OrderBlock(ob) => (
+            "order_block",
+            if zone.is_bullish() { "bullish" } else { "bearish" },
+            format!("{:?}", ob.ob_type),
+            None,
+        ),
+        ZoneData::Liquidity(liq) => (
+            "liquidity",
+            match liq.liquidity_type {
+                LiquidityType::BuySideLiquidity => "buy_side",
+                LiquidityType::SellSideLiquidity => "sell_side",
+            },
+            format!("{:?}", liq.source),
+            None,
+        ),
+    };
+
+    ZoneOverlay {
+        id: zone.id,
+        kind,
+        direction,
+        label,
+        top: zone.top(),
+        bottom: zone.bottom(),
+        start_time: zone.created_at() / 1000,
+        end_time: zone.ended_at().map(|ts| ts / 1000),
+        strength: zone.strength(),
+        status: serde_json::to_value(zone.status())
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        fill_pct,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(ts: i64, open: f64, high: f64, low: f64, close: f64) -> ProviderCandle {
+        ProviderCandle {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: ts,
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_smc_response_contains_fvg_and_liquidity() {
+        let candles = vec![
+            candle(1_000, 100.0, 105.0, 95.0, 102.0),
+            candle(2_000, 102.0, 108.0, 101.0, 107.0),
+            candle(3_000, 110.0, 115.0, 108.0, 113.0), // Gap 105-108
+            candle(4_000, 113.0, 115.1, 107.0, 110.0), // Equal high, partial fill
+        ];
+
+        let response = build_smc_response("BTCUSDT", "15", &candles);
+        assert_eq!(response.count, response.zones.len());
+
+        let fvg = response
+            .zones
+            .iter()
+            .find(|z| z.kind == "fair_value_gap")
+            .expect("FVG overlay");
+        assert_eq!(fvg.direction, "bullish");
+        assert_eq!(fvg.top, 108.0);
+        assert_eq!(fvg.bottom, 105.0);
+        assert_eq!(fvg.start_time, 3);
+        assert_eq!(fvg.status, "active");
+        assert_eq!(fvg.label, "Bullish:PartiallyFilled");
+
+        assert!(response
+            .zones
+            .iter()
+            .any(|z| z.kind == "liquidity" && z.direction == "buy_side"));
+    }
+
+    #[test]
+    fn test_zones_outlive_the_detection_window() {
+        let mut candles = vec![
+            candle(1_000, 100.0, 105.0, 95.0, 102.0),
+            candle(2_000, 102.0, 108.0, 101.0, 107.0),
+            candle(3_000, 110.0, 115.0, 108.0, 113.0), // Gap 105-108
+        ];
+        // Far more candles than the window, all above the gap
+        for i in 0..400 {
+            candles.push(candle(4_000 + i * 1_000, 120.0, 121.0, 119.0, 120.0));
+        }
+
+        let response = build_smc_response("BTCUSDT", "15", &candles);
+        assert!(response
+            .zones
+            .iter()
+            .any(|z| z.kind == "fair_value_gap" && z.start_time == 3 && z.status == "active"));
+    }
+
+    #[test]
+    fn test_structure_line_spans_swing_to_break() {
+        let candles = vec![
+            candle(1_000, 100.0, 102.0, 98.0, 101.0),
+            candle(2_000, 101.0, 105.0, 100.0, 104.0), // Swing high at 105
+            candle(3_000, 104.0, 103.0, 99.0, 100.0),
+            candle(4_000, 100.0, 102.0, 99.0, 101.0),
+            candle(5_000, 101.0, 107.0, 100.0, 106.0), // Breaks above 105
+        ];
+
+        let response = build_smc_response("BTCUSDT", "15", &candles);
+        let line = response.structure.iter().find(|l| l.direction == "bullish").unwrap();
+        assert_eq!(line.price, 105.0);
+        assert_eq!((line.start_time, line.end_time), (2, 5));
+    }
+
+    #[test]
+    fn test_smc_response_empty_candles() {
+        let response = build_smc_response("AAPL", "15", &[]);
+        assert_eq!(response.count, 0);
+        assert!(response.structure.is_empty());
+    }
+}
@@ -1,9 +1,13 @@
+use axum::http::HeaderMap;
 use axum::response::sse::Event;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::convert::Infallible;
-use tokio_stream::Stream;
+use tokio_stream::{Stream, StreamExt};
 use std::pin::Pin;
 
+use crate::bus::{BackpressurePolicy, Receiver, RecvError, ReplayFrom, SignalBus, SubscriberOptions};
+
 /// Helper type for SSE streams
 pub type SseStream = Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>;
 
@@ -26,3 +30,108 @@ pub fn create_named_event<T: Serialize>(
 pub fn heartbeat_event() -> Event {
     Event::default().comment("heartbeat")
 }
+
+/// Policies a client may pick. `Block` is not one: a slow client would hold
+/// up every publisher for its timeout.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyParam {
+    DropOldest,
+    DropNewest,
+    Disconnect,
+}
+
+/// `?policy=drop_oldest|drop_newest|disconnect&capacity=&from_offset=&since=`
+/// Backpressure and replay for one SSE client; defaults to `drop_oldest`, live only
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamParams {
+    pub policy: Option<PolicyParam>,
+    pub capacity: Option<usize>, // Max 4096
+    pub from_offset: Option<u64>, // Replay from this offset (inclusive)
+    pub since: Option<i64>,       // Replay from this Unix ms timestamp
+}
+
+impl StreamParams {
+    pub fn options(&self, name: &str) -> SubscriberOptions {
+        let policy = match self.policy.unwrap_or(PolicyParam::DropOldest) {
+            PolicyParam::DropOldest => BackpressurePolicy::DropOldest,
+            PolicyParam::DropNewest => BackpressurePolicy::DropNewest,
+            PolicyParam::Disconnect => BackpressurePolicy::Disconnect,
+        };
+        let options = SubscriberOptions::new(name, policy);
+        match self.capacity {
+            Some(capacity) => options.capacity(capacity.min(4096)),
+            None => options,
+        }
+    }
+
+    /// `Last-Event-ID` (resume after the last event seen) wins over `from_offset`, then `since`
+    pub fn replay_from(&self, headers: &HeaderMap) -> Option<ReplayFrom> {
+        let last_event_id = headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        match (last_event_id, self.from_offset, self.since) {
+            (Some(id), _, _) => Some(ReplayFrom::Offset(id + 1)),
+            (None, Some(offset), _) => Some(ReplayFrom::Offset(offset)),
+            (None, None, Some(ts)) => Some(ReplayFrom::Timestamp(ts)),
+            _ => None,
+        }
+    }
+
+    /// Subscribe an SSE client, replaying from the log when it asked to resume;
+    /// the log is read on a blocking thread
+    pub async fn subscribe<T>(&self, bus: &SignalBus<T>, headers: &HeaderMap, name: &str) -> (Vec<(u64, T)>, Receiver<T>)
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let options = self.options(name);
+        let Some(from) = self.replay_from(headers) else {
+            return (Vec::new(), bus.subscribe_with(options));
+        };
+        let replaying = bus.clone();
+        let replay_options = options.clone();
+        match tokio::task::spawn_blocking(move || replaying.subscribe_from(replay_options, from)).await {
+            Ok(subscribed) => subscribed,
+            Err(e) => {
+                tracing::error!("Replay for {} failed: {}", name, e);
+                (Vec::new(), bus.subscribe_with(options))
+            }
+        }
+    }
+}
+
+/// Named "gap" event: the client missed `missed` messages and should
+/// reload from `resync` before trusting the stream again
+pub fn gap_event(reason: &str, missed: u64, resync: &str) -> Event {
+    let data = json!({
+        "reason": reason,
+        "missed": missed,
+        "resync": resync,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    Event::default().event("gap").data(data.to_string())
+}
+
+/// SSE events for a bus subscription: replayed messages first, then live ones,
+/// each with its sequence as the event id (for `Last-Event-ID` resume), and a
+/// "gap" event whenever the subscriber lagged or was disconnected by its policy
+pub fn bus_events<T, F>(
+    replay: Vec<(u64, T)>,
+    rx: Receiver<T>,
+    resync: &'static str,
+    mut to_event: F,
+) -> impl Stream<Item = Result<Event, Infallible>>
+where
+    F: FnMut(T) -> Option<Event>,
+{
+    tokio_stream::iter(replay.into_iter().map(Ok))
+        .chain(rx.into_stream())
+        .filter_map(move |msg| match msg {
+            Ok((seq, item)) => to_event(item).map(|event| event.id(seq.to_string())),
+            Err(RecvError::Lagged(missed)) => Some(gap_event("lagged", missed, resync)),
+            Err(RecvError::Disconnected(missed)) => Some(gap_event("disconnected", missed, resync)),
+            Err(RecvError::Closed) => None,
+        })
+        .map(Ok)
+}
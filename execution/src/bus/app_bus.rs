@@ -0,0 +1,439 @@
+//! Application event bus
+//!
+//! Components talk to each other through one `AppEvent` stream instead of a
+//! channel per producer. Every event maps to a hierarchical topic:
+//!
+//! | Event           | Topic                          |
+//! |-----------------|--------------------------------|
+//! | `QuoteUpdate`   | `quotes.<symbol>`              |
+//! | `BookUpdate`    | `books.<symbol>[.<venue>]`     |
+//! | `CandleClosed`  | `candles.<symbol>.<timeframe>` |
+//! | `SignalEmitted` | `signals.<symbol>.<timeframe>` |
+//! | `OrderEvent`    | `orders.<symbol>`              |
+//! | `Fill`          | `fills.<symbol>`               |
+//! | `RiskEvent`     | `risk.<kind>`                  |
+//! | `NewsItem`      | `news.<source>`                |
+//!
+//! `AppBus::subscribe::<E>(filter)` returns a typed `Channel<E>` for one event
+//! kind, with `filter` relative to its root (`"BTCUSDT"`, `"*.M15"`, `">"`).
+//! `AppBus::subscribe_events` gives the raw `AppEvent` stream for any pattern.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flume::Receiver;
+use serde::{Deserialize, Serialize};
+
+use super::event_log::{EventLog, ReplayFrom};
+use super::quantum_bus::{QuantumBus, Replayed, TopicMessage};
+use super::BusError;
+use crate::engine::{CircuitBreakerState, Order, OrderSide, OrderState, OrderType};
+use crate::market::providers::{Candle, Quote};
+use crate::metrics::LatencySnapshot;
+use crate::news::NewsItem;
+use crate::scanner::market_data::OrderBook;
+use crate::signal::EnhancedSignal;
+
+/// A candle the provider reported as complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleClosed {
+    pub timeframe: String,
+    pub candle: Candle,
+}
+
+/// Order state change, published by whoever owns the order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub price: Option<f64>,
+    pub state: OrderState,
+    pub exchange: String,
+    pub reason: Option<String>, // Why it was rejected or cancelled
+    pub timestamp: u64,         // Unix ms
+}
+
+impl OrderEvent {
+    pub fn from_order(order: &Order, reason: Option<String>) -> Self {
+        Self {
+            order_id: order.id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            order_type: order.order_type.clone(),
+            quantity: order.quantity,
+            filled_quantity: order.filled_quantity,
+            price: order.price,
+            state: order.state.clone(),
+            exchange: order.exchange.clone(),
+            reason,
+            timestamp: order.updated_at,
+        }
+    }
+}
+
+/// Execution reported by a venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub venue: String,
+    pub timestamp: u64, // Unix ms
+}
+
+/// Risk decisions and state changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RiskEvent {
+    OrderRejected {
+        symbol: String,
+        quantity: f64,
+        price: f64,
+        reason: String,
+        timestamp: u64,
+    },
+    CircuitBreaker {
+        from: CircuitBreakerState,
+        to: CircuitBreakerState,
+        drawdown_pct: f64,
+        timestamp: u64,
+    },
+}
+
+impl RiskEvent {
+    /// Topic segment under `risk.`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RiskEvent::OrderRejected { .. } => "order_rejected",
+            RiskEvent::CircuitBreaker { .. } => "circuit_breaker",
+        }
+    }
+}
+
+/// Everything that travels on the application bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum AppEvent {
+    QuoteUpdate(Quote),
+    BookUpdate(OrderBook), // Consolidated, or one venue's when `venue` is set
+    CandleClosed(CandleClosed),
+    SignalEmitted(Box<EnhancedSignal>),
+    OrderEvent(OrderEvent),
+    Fill(Fill),
+    RiskEvent(RiskEvent),
+    NewsItem(NewsItem),
+}
+
+impl AppEvent {
+    /// Concrete topic the event is published on
+    pub fn topic(&self) -> String {
+        match self {
+            AppEvent::QuoteUpdate(q) => format!("quotes.{}", segment(&q.symbol)),
+            AppEvent::BookUpdate(b) => match &b.venue {
+                Some(venue) => format!("books.{}.{}", segment(&b.symbol), segment(venue)),
+                None => format!("books.{}", segment(&b.symbol)),
+            },
+            AppEvent::CandleClosed(c) => {
+                format!("candles.{}.{}", segment(&c.candle.symbol), segment(&c.timeframe))
+            }
+            AppEvent::SignalEmitted(s) => {
+                format!("signals.{}.{}", segment(&s.symbol), segment(&s.timeframe))
+            }
+            AppEvent::OrderEvent(o) => format!("orders.{}", segment(&o.symbol)),
+            AppEvent::Fill(f) => format!("fills.{}", segment(&f.symbol)),
+            AppEvent::RiskEvent(r) => format!("risk.{}", r.kind()),
+            AppEvent::NewsItem(n) => format!("news.{}", segment(&n.source)),
+        }
+    }
+}
+
+/// Make a symbol, timeframe or source usable as one topic segment
+/// (`BRK.B` -> `BRK_B`, `BTC/USDT` stays as is)
+pub fn segment(value: &str) -> String {
+    let cleaned: String = value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '.' | '*' | '>' | ' ' => '_',
+            c => c,
+        })
+        .collect();
+    if cleaned.is_empty() {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// One `AppEvent` variant, addressable as a typed channel
+pub trait TopicEvent: Into<AppEvent> + Sized {
+    /// First topic segment of every event of this kind
+    const ROOT: &'static str;
+
+    fn from_event(event: AppEvent) -> Option<Self>;
+}
+
+macro_rules! topic_event {
+    ($payload:ty, $variant:ident, $root:literal) => {
+        impl From<$payload> for AppEvent {
+            fn from(payload: $payload) -> Self {
+                AppEvent::$variant(payload)
+            }
+        }
+
+        impl TopicEvent for $payload {
+            const ROOT: &'static str = $root;
+
+            fn from_event(event: AppEvent) -> Option<Self> {
+                match event {
+                    AppEvent::$variant(payload) => Some(payload),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+topic_event!(Quote, QuoteUpdate, "quotes");
+topic_event!(OrderBook, BookUpdate, "books");
+topic_event!(CandleClosed, CandleClosed, "candles");
+topic_event!(OrderEvent, OrderEvent, "orders");
+topic_event!(Fill, Fill, "fills");
+topic_event!(RiskEvent, RiskEvent, "risk");
+topic_event!(NewsItem, NewsItem, "news");
+
+// Boxed on the bus, the other payloads are a fraction of its size
+impl From<EnhancedSignal> for AppEvent {
+    fn from(signal: EnhancedSignal) -> Self {
+        AppEvent::SignalEmitted(Box::new(signal))
+    }
+}
+
+impl TopicEvent for EnhancedSignal {
+    const ROOT: &'static str = "signals";
+
+    fn from_event(event: AppEvent) -> Option<Self> {
+        match event {
+            AppEvent::SignalEmitted(signal) => Some(*signal),
+            _ => None,
+        }
+    }
+}
+
+/// Typed subscription to one event kind
+pub struct Channel<E> {
+    rx: Receiver<TopicMessage<AppEvent>>,
+    _kind: PhantomData<fn() -> E>,
+}
+
+impl<E: TopicEvent> Channel<E> {
+    /// Next event; `None` once the bus is gone
+    pub async fn recv(&self) -> Option<E> {
+        loop {
+            let msg = self.rx.recv_async().await.ok()?;
+            if let Some(event) = E::from_event(msg.payload) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Next event if one is queued
+    pub fn try_recv(&self) -> Option<E> {
+        while let Ok(msg) = self.rx.try_recv() {
+            if let Some(event) = E::from_event(msg.payload) {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Blocking receive for worker threads
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<E> {
+        let msg = self.rx.recv_timeout(timeout).ok()?;
+        E::from_event(msg.payload)
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> usize {
+        self.rx.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rx.is_empty()
+    }
+}
+
+/// Shared handle to the application bus
+#[derive(Clone)]
+pub struct AppBus {
+    bus: Arc<QuantumBus<AppEvent>>,
+}
+
+impl AppBus {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            bus: Arc::new(QuantumBus::new(capacity)),
+        }
+    }
+
+    /// Bus whose events on `topics` (patterns) are appended to `log`
+    pub fn with_log(capacity: usize, log: Arc<EventLog>, topics: &[String]) -> Self {
+        Self {
+            bus: Arc::new(QuantumBus::new(capacity).with_log(log, topics)),
+        }
+    }
+
+    /// Publish on the event's own topic
+    pub fn publish(&self, event: impl Into<AppEvent>) -> Result<(), BusError> {
+        let event = event.into();
+        self.bus.publish(event.topic(), event)
+    }
+
+    /// Publish, logging instead of failing when the bus is saturated
+    pub fn emit(&self, event: impl Into<AppEvent>) {
+        let event = event.into();
+        let topic = event.topic();
+        if let Err(e) = self.bus.publish(topic.clone(), event) {
+            tracing::warn!("Dropped app event on {}: {}", topic, e);
+        }
+    }
+
+    /// Typed channel for one event kind; `filter` is relative to `E::ROOT`
+    pub fn subscribe<E: TopicEvent>(&self, filter: &str) -> Result<Channel<E>, BusError> {
+        let rx = self.bus.subscribe(format!("{}.{}", E::ROOT, filter))?;
+        Ok(Channel { rx, _kind: PhantomData })
+    }
+
+    /// Raw events on any topic pattern, e.g. `>` or `orders.>`
+    pub fn subscribe_events(&self, pattern: &str) -> Result<Receiver<TopicMessage<AppEvent>>, BusError> {
+        self.bus.subscribe(pattern.to_string())
+    }
+
+    /// Raw events replayed from the log, then live ones
+    pub fn subscribe_events_from(
+        &self,
+        pattern: &str,
+        from: ReplayFrom,
+    ) -> Result<Replayed<AppEvent>, BusError> {
+        self.bus.subscribe_from(pattern.to_string(), from)
+    }
+
+    /// Underlying topic bus, for bridges
+    pub fn topic_bus(&self) -> &Arc<QuantumBus<AppEvent>> {
+        &self.bus
+    }
+
+    pub fn messages_sent(&self) -> u64 {
+        self.bus.messages_sent()
+    }
+
+    pub fn queue_utilization(&self) -> f64 {
+        self.bus.queue_utilization()
+    }
+
+    /// See `QuantumBus::wait_idle`
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        self.bus.wait_idle(timeout)
+    }
+
+    pub fn subscription_count(&self) -> usize {
+        self.bus.subscription_count()
+    }
+
+    pub fn latency_snapshot(&self) -> LatencySnapshot {
+        self.bus.latency_snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, price: f64) -> Quote {
+        Quote {
+            symbol: symbol.to_string(),
+            price,
+            timestamp: 0,
+            bid: None,
+            ask: None,
+            volume: None,
+        }
+    }
+
+    #[test]
+    fn test_topics() {
+        assert_eq!(AppEvent::from(quote("BRK.B", 1.0)).topic(), "quotes.BRK_B");
+        let book = OrderBook::for_venue("paper", "BTCUSDT".to_string());
+        assert_eq!(AppEvent::from(book).topic(), "books.BTCUSDT.paper");
+        assert_eq!(AppEvent::from(OrderBook::new("ETHUSDT".to_string())).topic(), "books.ETHUSDT");
+        let candle = CandleClosed {
+            timeframe: "M15".to_string(),
+            candle: Candle {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: 0,
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: 0.0,
+            },
+        };
+        assert_eq!(AppEvent::from(candle).topic(), "candles.BTCUSDT.M15");
+        let risk = RiskEvent::CircuitBreaker {
+            from: CircuitBreakerState::Normal,
+            to: CircuitBreakerState::Triggered,
+            drawdown_pct: -12.0,
+            timestamp: 0,
+        };
+        assert_eq!(AppEvent::from(risk).topic(), "risk.circuit_breaker");
+        assert_eq!(segment(" "), "_");
+    }
+
+    #[test]
+    fn test_typed_channels() {
+        let bus = AppBus::new(64);
+        let btc = bus.subscribe::<Quote>("BTCUSDT").unwrap();
+        let all = bus.subscribe::<Quote>(">").unwrap();
+        let raw = bus.subscribe_events(">").unwrap();
+
+        bus.publish(quote("BTCUSDT", 100.0)).unwrap();
+        bus.publish(quote("ETHUSDT", 10.0)).unwrap();
+        bus.emit(RiskEvent::OrderRejected {
+            symbol: "BTCUSDT".to_string(),
+            quantity: 1.0,
+            price: 100.0,
+            reason: "Position size limit exceeded".to_string(),
+            timestamp: 0,
+        });
+
+        let first = btc.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(first.price, 100.0);
+        assert_eq!(all.recv_timeout(Duration::from_secs(1)).unwrap().symbol, "BTCUSDT");
+        assert_eq!(all.recv_timeout(Duration::from_secs(1)).unwrap().symbol, "ETHUSDT");
+
+        let topics: Vec<String> = (0..3)
+            .map(|_| raw.recv_timeout(Duration::from_secs(1)).unwrap().topic)
+            .collect();
+        assert_eq!(topics, vec!["quotes.BTCUSDT", "quotes.ETHUSDT", "risk.order_rejected"]);
+        assert!(btc.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_event_roundtrip() {
+        let event = AppEvent::from(quote("BTCUSDT", 42.0));
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "QuoteUpdate");
+        assert_eq!(json["data"]["price"], 42.0);
+
+        let back: AppEvent = serde_json::from_value(json).unwrap();
+        assert!(matches!(back, AppEvent::QuoteUpdate(q) if q.symbol == "BTCUSDT"));
+    }
+}
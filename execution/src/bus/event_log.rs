@@ -0,0 +1,491 @@
+//! Durable, segmented event log
+//!
+//! An append-only JSONL log split into segment files (`<first record>.log`).
+//! Every topic has its own monotonic offset, so consumers can replay a topic
+//! from an offset, or any topic pattern from a timestamp, after a restart or
+//! a disconnect. Old segments are deleted by size or age; offsets never go back.
+//! A replay is capped at the newest `replay_records` records within
+//! `replay_age`, and reads segments from a snapshot so appends never wait on it.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::topic;
+
+const OFFSETS_FILE: &str = "offsets.json";
+
+#[derive(Debug, Clone)]
+pub struct EventLogConfig {
+    pub dir: PathBuf,
+    pub segment_bytes: u64,           // Roll to a new segment past this size
+    pub retention_bytes: Option<u64>, // Delete oldest segments above this total
+    pub retention: Option<Duration>,  // Delete segments whose newest record is older
+    pub replay_records: usize,        // Most records one read returns, the newest
+    pub replay_age: Option<Duration>, // Oldest record one read goes back to
+}
+
+impl EventLogConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_bytes: 8 * 1024 * 1024,
+            retention_bytes: Some(256 * 1024 * 1024),
+            retention: Some(Duration::from_secs(7 * 24 * 3600)),
+            replay_records: 1000,
+            replay_age: Some(Duration::from_secs(24 * 3600)),
+        }
+    }
+}
+
+/// Where a replay starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayFrom {
+    Beginning,
+    Offset(u64),    // Inclusive, per topic
+    Timestamp(i64), // Unix ms, inclusive
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogRecord {
+    pub topic: String,
+    pub offset: u64,
+    pub timestamp: i64, // Unix ms
+    pub payload: Value,
+}
+
+impl LogRecord {
+    fn is_after(&self, from: ReplayFrom) -> bool {
+        match from {
+            ReplayFrom::Beginning => true,
+            ReplayFrom::Offset(offset) => self.offset >= offset,
+            ReplayFrom::Timestamp(ts) => self.timestamp >= ts,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EventLogError {
+    Io(String),
+    Serialization(String),
+}
+
+impl std::fmt::Display for EventLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventLogError::Io(e) => write!(f, "Event log I/O error: {}", e),
+            EventLogError::Serialization(e) => write!(f, "Event log serialization error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EventLogError {}
+
+fn io_err(e: std::io::Error) -> EventLogError {
+    EventLogError::Io(e.to_string())
+}
+
+#[derive(Clone)]
+struct Segment {
+    path: PathBuf,
+    base: u64, // Index of its first record across the whole log
+    records: u64,
+    bytes: u64,
+    last_timestamp: i64,
+}
+
+struct State {
+    segments: Vec<Segment>, // Oldest first; the last one is active
+    active: File,
+    offsets: HashMap<String, u64>, // Next offset per topic
+}
+
+pub struct EventLog {
+    config: EventLogConfig,
+    state: Mutex<State>,
+}
+
+impl EventLog {
+    /// Open (or create) a log directory, recovering offsets from its segments
+    pub fn open(config: EventLogConfig) -> Result<Self, EventLogError> {
+        std::fs::create_dir_all(&config.dir).map_err(io_err)?;
+
+        // Offsets of topics whose segments were all deleted by retention
+        let offsets_path = config.dir.join(OFFSETS_FILE);
+        let mut offsets: HashMap<String, u64> = match std::fs::read_to_string(&offsets_path) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| EventLogError::Serialization(e.to_string()))?,
+            Err(_) => HashMap::new(),
+        };
+
+        let mut paths: Vec<(u64, PathBuf)> = std::fs::read_dir(&config.dir)
+            .map_err(io_err)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter_map(|path| {
+                let base = path.file_name()?.to_str()?.strip_suffix(".log")?.parse().ok()?;
+                Some((base, path))
+            })
+            .collect();
+        paths.sort_by_key(|(base, _)| *base);
+
+        let mut segments = Vec::new();
+        for (base, path) in paths {
+            let mut segment = Segment {
+                path,
+                base,
+                records: 0,
+                bytes: 0,
+                last_timestamp: 0,
+            };
+            for record in read_segment(&segment.path, None)? {
+                let next = offsets.entry(record.topic.clone()).or_insert(0);
+                *next = (*next).max(record.offset + 1);
+                segment.last_timestamp = segment.last_timestamp.max(record.timestamp);
+                segment.records += 1;
+            }
+            segment.bytes = std::fs::metadata(&segment.path).map_err(io_err)?.len();
+            segments.push(segment);
+        }
+
+        if segments.is_empty() {
+            segments.push(Segment {
+                path: segment_path(&config.dir, 0),
+                base: 0,
+                records: 0,
+                bytes: 0,
+                last_timestamp: 0,
+            });
+        }
+
+        let active_path = &segments[segments.len() - 1].path;
+        let mut active = open_segment(active_path)?;
+        if !ends_with_newline(&mut active)? {
+            // Terminate a torn last line so the next record starts clean
+            active.write_all(b"\n").map_err(io_err)?;
+        }
+
+        tracing::info!(
+            "Event log opened at {} ({} segments, {} topics)",
+            config.dir.display(),
+            segments.len(),
+            offsets.len()
+        );
+        Ok(Self {
+            config,
+            state: Mutex::new(State {
+                segments,
+                active,
+                offsets,
+            }),
+        })
+    }
+
+    /// Append a record and return its offset within the topic
+    pub fn append(&self, topic: &str, payload: Value) -> Result<u64, EventLogError> {
+        let mut state = self.state.lock();
+        let offset = state.offsets.get(topic).copied().unwrap_or(0);
+        let record = LogRecord {
+            topic: topic.to_string(),
+            offset,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            payload,
+        };
+        let mut line = serde_json::to_string(&record).map_err(|e| EventLogError::Serialization(e.to_string()))?;
+        line.push('\n');
+
+        let active = state.segments.last().expect("log always has an active segment");
+        if active.bytes > 0 && active.bytes + line.len() as u64 > self.config.segment_bytes {
+            self.roll(&mut state)?;
+        }
+
+        state.active.write_all(line.as_bytes()).map_err(io_err)?;
+        state.active.flush().map_err(io_err)?;
+        let active = state.segments.last_mut().expect("log always has an active segment");
+        active.records += 1;
+        active.bytes += line.len() as u64;
+        active.last_timestamp = record.timestamp;
+        state.offsets.insert(record.topic, offset + 1);
+        Ok(offset)
+    }
+
+    /// Records of topics matching `pattern` (wildcards allowed), oldest first:
+    /// the newest `replay_records` of them, none older than `replay_age`.
+    /// Reads a snapshot of the segments, newest first, without the log locked.
+    pub fn read(&self, pattern: &str, from: ReplayFrom) -> Result<Vec<LogRecord>, EventLogError> {
+        let segments = self.state.lock().segments.clone();
+        let cutoff = self
+            .config
+            .replay_age
+            .map(|age| chrono::Utc::now().timestamp_millis() - age.as_millis() as i64);
+        let after = |timestamp: i64| {
+            cutoff.is_none_or(|cutoff| timestamp >= cutoff)
+                && !matches!(from, ReplayFrom::Timestamp(ts) if timestamp < ts)
+        };
+
+        let mut newest_first = Vec::new();
+        let mut count = 0;
+        for segment in segments.iter().rev() {
+            if count >= self.config.replay_records {
+                break;
+            }
+            if segment.records == 0 || !after(segment.last_timestamp) {
+                continue;
+            }
+            // Only what was written at snapshot time: the active segment may be mid-append
+            let records: Vec<LogRecord> = read_segment(&segment.path, Some(segment.bytes))?
+                .into_iter()
+                .filter(|r| topic::matches(pattern, &r.topic) && r.is_after(from) && after(r.timestamp))
+                .collect();
+            count += records.len();
+            newest_first.push(records);
+        }
+
+        let mut records: Vec<LogRecord> = newest_first.into_iter().rev().flatten().collect();
+        let excess = records.len().saturating_sub(self.config.replay_records);
+        records.drain(..excess);
+        Ok(records)
+    }
+
+    /// Skip the next offset on `topic` without writing a record, so a message
+    /// that could not be logged still gets an offset of its own
+    pub fn reserve(&self, topic: &str) -> u64 {
+        let mut state = self.state.lock();
+        let next = state.offsets.entry(topic.to_string()).or_insert(0);
+        *next += 1;
+        *next - 1
+    }
+
+    /// Offset the next record on `topic` will get
+    pub fn next_offset(&self, topic: &str) -> u64 {
+        self.state.lock().offsets.get(topic).copied().unwrap_or(0)
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.state.lock().segments.len()
+    }
+
+    /// Total size of all segments in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.state.lock().segments.iter().map(|s| s.bytes).sum()
+    }
+
+    /// Delete segments past the size or age limit (never the active one)
+    pub fn enforce_retention(&self) -> Result<usize, EventLogError> {
+        let mut state = self.state.lock();
+        self.apply_retention(&mut state)
+    }
+
+    fn roll(&self, state: &mut State) -> Result<(), EventLogError> {
+        let last = state.segments.last().expect("log always has an active segment");
+        let base = last.base + last.records;
+        let path = segment_path(&self.config.dir, base);
+        state.active = open_segment(&path)?;
+        state.segments.push(Segment {
+            path,
+            base,
+            records: 0,
+            bytes: 0,
+            last_timestamp: 0,
+        });
+        self.apply_retention(state)?;
+        Ok(())
+    }
+
+    fn apply_retention(&self, state: &mut State) -> Result<usize, EventLogError> {
+        let cutoff = self
+            .config
+            .retention
+            .map(|age| chrono::Utc::now().timestamp_millis() - age.as_millis() as i64);
+        let mut total: u64 = state.segments.iter().map(|s| s.bytes).sum();
+        let mut removed = 0;
+
+        while state.segments.len() > 1 {
+            let oldest = &state.segments[0];
+            let too_big = self.config.retention_bytes.is_some_and(|max| total > max);
+            let too_old = cutoff.is_some_and(|cutoff| oldest.last_timestamp < cutoff);
+            if !too_big && !too_old {
+                break;
+            }
+
+            if removed == 0 {
+                // Persist offsets first so a restart can't reuse them
+                let json = serde_json::to_string(&state.offsets)
+                    .map_err(|e| EventLogError::Serialization(e.to_string()))?;
+                std::fs::write(self.config.dir.join(OFFSETS_FILE), json).map_err(io_err)?;
+            }
+            let oldest = state.segments.remove(0);
+            std::fs::remove_file(&oldest.path).map_err(io_err)?;
+            total -= oldest.bytes;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+fn segment_path(dir: &Path, base: u64) -> PathBuf {
+    dir.join(format!("{:020}.log", base))
+}
+
+fn open_segment(path: &Path) -> Result<File, EventLogError> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .read(true)
+        .open(path)
+        .map_err(io_err)
+}
+
+/// Records of a segment, or of its first `bytes`; a segment deleted by retention is empty
+fn read_segment(path: &Path, bytes: Option<u64>) -> Result<Vec<LogRecord>, EventLogError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && bytes.is_some() => return Ok(Vec::new()),
+        Err(e) => return Err(io_err(e)),
+    };
+    let reader = BufReader::new(file.take(bytes.unwrap_or(u64::MAX)));
+    let mut records = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let line = line.map_err(io_err)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LogRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => tracing::warn!("Skipping line {} of {}: {}", n + 1, path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+fn ends_with_newline(file: &mut File) -> Result<bool, EventLogError> {
+    let len = file.metadata().map_err(io_err)?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(len - 1)).map_err(io_err)?;
+    file.read_exact(&mut last).map_err(io_err)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_config() -> EventLogConfig {
+        EventLogConfig::new(std::env::temp_dir().join(format!("event-log-{}", uuid::Uuid::new_v4())))
+    }
+
+    #[test]
+    fn test_offsets_per_topic_and_replay() {
+        let config = temp_config();
+        let log = EventLog::open(config.clone()).unwrap();
+
+        assert_eq!(log.append("signals.crypto.BTCUSDT", json!({"n": 1})).unwrap(), 0);
+        assert_eq!(log.append("signals.crypto.ETHUSDT", json!({"n": 2})).unwrap(), 0);
+        assert_eq!(log.append("signals.crypto.BTCUSDT", json!({"n": 3})).unwrap(), 1);
+        assert_eq!(log.append("orders.fill", json!({"n": 4})).unwrap(), 0);
+
+        let btc = log.read("signals.crypto.BTCUSDT", ReplayFrom::Offset(1)).unwrap();
+        assert_eq!(btc.len(), 1);
+        assert_eq!(btc[0].payload["n"], 3);
+
+        let crypto = log.read("signals.>", ReplayFrom::Beginning).unwrap();
+        let n: Vec<i64> = crypto.iter().map(|r| r.payload["n"].as_i64().unwrap()).collect();
+        assert_eq!(n, vec![1, 2, 3]);
+
+        let future = chrono::Utc::now().timestamp_millis() + 60_000;
+        assert!(log.read(">", ReplayFrom::Timestamp(future)).unwrap().is_empty());
+        assert_eq!(log.read(">", ReplayFrom::Timestamp(0)).unwrap().len(), 4);
+
+        // Offsets survive a restart
+        drop(log);
+        let reopened = EventLog::open(config.clone()).unwrap();
+        assert_eq!(reopened.next_offset("signals.crypto.BTCUSDT"), 2);
+        assert_eq!(reopened.append("signals.crypto.BTCUSDT", json!({"n": 5})).unwrap(), 2);
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+
+    #[test]
+    fn test_segments_roll_and_retention_keeps_offsets() {
+        let mut config = temp_config();
+        config.segment_bytes = 200;
+        config.retention_bytes = Some(450);
+        let log = EventLog::open(config.clone()).unwrap();
+
+        for i in 0..20 {
+            log.append("ticks", json!({"i": i, "pad": "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"})).unwrap();
+        }
+        assert!(log.segment_count() > 1);
+        assert!(log.size_bytes() <= 450 + 200);
+
+        // Oldest records are gone, the newest are still readable
+        let records = log.read("ticks", ReplayFrom::Beginning).unwrap();
+        assert!(records[0].offset > 0);
+        assert_eq!(records.last().unwrap().offset, 19);
+
+        // Even if every segment holding a topic is deleted, its offset never restarts
+        log.append("rare", json!({})).unwrap();
+        for i in 0..20 {
+            log.append("ticks", json!({"i": i, "pad": "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"})).unwrap();
+        }
+        drop(log);
+        let reopened = EventLog::open(config.clone()).unwrap();
+        assert!(reopened.read("rare", ReplayFrom::Beginning).unwrap().is_empty());
+        assert_eq!(reopened.next_offset("rare"), 1);
+        assert_eq!(reopened.next_offset("ticks"), 40);
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+
+    #[test]
+    fn test_replay_is_capped() {
+        let mut config = temp_config();
+        config.segment_bytes = 300;
+        config.replay_records = 5;
+        let log = EventLog::open(config.clone()).unwrap();
+        for i in 0..20 {
+            log.append("ticks", json!({"i": i})).unwrap();
+            log.append("other", json!({"i": i})).unwrap();
+        }
+        assert!(log.segment_count() > 2);
+
+        // The newest five, across segments, oldest first
+        let offsets: Vec<u64> = log.read("ticks", ReplayFrom::Beginning).unwrap().iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, vec![15, 16, 17, 18, 19]);
+        assert_eq!(log.read("ticks", ReplayFrom::Offset(18)).unwrap().len(), 2);
+
+        // Nothing older than the replay age, whatever was asked for
+        drop(log);
+        config.replay_age = Some(Duration::from_millis(0));
+        let log = EventLog::open(config.clone()).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(log.read(">", ReplayFrom::Timestamp(0)).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+
+    #[test]
+    fn test_age_retention() {
+        let mut config = temp_config();
+        config.segment_bytes = 100;
+        config.retention = Some(Duration::from_millis(0));
+        let log = EventLog::open(config.clone()).unwrap();
+
+        log.append("a", json!({"pad": "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"})).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        log.append("a", json!({"pad": "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx"})).unwrap();
+
+        // Rolling deleted the expired first segment; the active one is kept
+        assert_eq!(log.segment_count(), 1);
+        assert_eq!(log.read("a", ReplayFrom::Beginning).unwrap()[0].offset, 1);
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+}
@@ -1,30 +1,12 @@
+pub mod app_bus;
+pub mod event_log;
 pub mod quantum_bus;
+pub mod redis_bridge;
+pub mod signal_bus;
+pub mod topic;
 
-use tokio::sync::broadcast;
-
+pub use app_bus::{AppBus, AppEvent, CandleClosed, Fill, OrderEvent, RiskEvent};
+pub use event_log::{EventLog, EventLogConfig, ReplayFrom};
 pub use quantum_bus::{QuantumBus, TopicMessage, BusError};
-
-#[derive(Clone)]
-pub struct SignalBus<T: Clone> {
-    tx: broadcast::Sender<T>,
-}
-
-impl<T: Clone> SignalBus<T> {
-    pub fn new(capacity: usize) -> Self {
-        let (tx, _) = broadcast::channel(capacity);
-        Self { tx }
-    }
-
-    pub fn publish(&self, msg: T) {
-        let _ = self.tx.send(msg);
-    }
-
-    pub fn subscribe(&self) -> broadcast::Receiver<T> {
-        self.tx.subscribe()
-    }
-    
-    /// Get a clone of the sender for creating new publishers
-    pub fn sender(&self) -> broadcast::Sender<T> {
-        self.tx.clone()
-    }
-}
+pub use redis_bridge::{RedisBridge, RedisBridgeConfig};
+pub use signal_bus::{BackpressurePolicy, Receiver, RecvError, SignalBus, SubscriberOptions};
@@ -1,9 +1,14 @@
 use crossbeam::queue::ArrayQueue;
 use flume::{Sender, Receiver, unbounded};
-use std::sync::Arc;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use parking_lot::RwLock;
-use std::time::Instant;
+use std::thread::Thread;
+use std::time::{Duration, Instant};
+
+use super::event_log::{EventLog, ReplayFrom};
+use super::topic::{matches, validate_pattern, validate_topic, TopicTrie};
+use crate::metrics::{LatencyHistogram, LatencySnapshot};
 
 /// Message with topic for pub/sub
 #[derive(Clone, Debug)]
@@ -11,37 +16,85 @@ pub struct TopicMessage<T: Clone> {
     pub topic: String,
     pub payload: T,
     pub timestamp: u64,
+    /// Where the message came from when it was not published locally,
+    /// e.g. `redis:<node id>`; bridges use it to avoid echoing messages back
+    pub source: Option<String>,
 }
 
-/// Topic-based pub/sub bus with sub-microsecond latency
+/// Topic-based pub/sub bus with sub-microsecond latency.
+/// Topics are hierarchical (`signals.crypto.BTCUSDT.M15`); subscriptions may
+/// use `*` and `>` wildcards, see `bus::topic`.
 pub struct QuantumBus<T: Clone + Send> {
     /// Ring buffer for high-speed message passing
     ring_buffer: Arc<ArrayQueue<TopicMessage<T>>>,
-    /// Topic-based subscribers
-    subscribers: Arc<RwLock<HashMap<String, Vec<Sender<TopicMessage<T>>>>>>,
+    /// Subscribers indexed by topic pattern
+    subscribers: Arc<RwLock<TopicTrie<Sender<TopicMessage<T>>>>>,
     /// Metrics
     messages_sent: Arc<std::sync::atomic::AtomicU64>,
     total_latency_ns: Arc<std::sync::atomic::AtomicU64>,
+    latency: Arc<LatencyHistogram>,
+    /// Dispatcher thread, parked while the ring buffer is empty
+    dispatcher: Thread,
+    dispatcher_idle: Arc<AtomicBool>,
+    /// Optional durable log, each message under its own topic; written by the dispatcher
+    log: Arc<OnceLock<BusLog<T>>>,
+}
+
+type Encoder<T> = fn(&T) -> serde_json::Result<serde_json::Value>;
+
+/// The log and the topic patterns written to it
+struct BusLog<T> {
+    log: Arc<EventLog>,
+    encode: Encoder<T>,
+    topics: Vec<String>,
 }
 
+/// Replayed messages and the live receiver that follows them
+pub type Replayed<T> = (Vec<TopicMessage<T>>, Receiver<TopicMessage<T>>);
+
 impl<T: Clone + Send + 'static> QuantumBus<T> {
     /// Create a new quantum bus with specified capacity
     pub fn new(capacity: usize) -> Self {
-        let bus = Self {
-            ring_buffer: Arc::new(ArrayQueue::new(capacity)),
-            subscribers: Arc::new(RwLock::new(HashMap::new())),
-            messages_sent: Arc::new(std::sync::atomic::AtomicU64::new(0)),
-            total_latency_ns: Arc::new(std::sync::atomic::AtomicU64::new(0)),
-        };
+        let ring_buffer = Arc::new(ArrayQueue::new(capacity));
+        let subscribers = Arc::new(RwLock::new(TopicTrie::new()));
+        let messages_sent = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let total_latency_ns = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let latency = Arc::new(LatencyHistogram::new());
+        let dispatcher_idle = Arc::new(AtomicBool::new(false));
+        let log = Arc::new(OnceLock::new());
 
         // Start dispatcher in background
-        bus.start_dispatcher();
-        
-        bus
+        let dispatcher = Dispatcher {
+            ring_buffer: ring_buffer.clone(),
+            log: log.clone(),
+            subscribers: subscribers.clone(),
+            messages_sent: messages_sent.clone(),
+            total_latency_ns: total_latency_ns.clone(),
+            latency: latency.clone(),
+            idle: dispatcher_idle.clone(),
+        };
+        let dispatcher = std::thread::spawn(move || dispatcher.run()).thread().clone();
+
+        Self {
+            ring_buffer,
+            subscribers,
+            messages_sent,
+            total_latency_ns,
+            latency,
+            dispatcher,
+            dispatcher_idle,
+            log,
+        }
     }
 
-    /// Publish a message to a topic
+    /// Publish a message to a concrete topic (no wildcards)
     pub fn publish(&self, topic: String, payload: T) -> Result<(), BusError> {
+        self.publish_from(topic, payload, None)
+    }
+
+    /// Publish a message that originated elsewhere (see `TopicMessage::source`)
+    pub fn publish_from(&self, topic: String, payload: T, source: Option<String>) -> Result<(), BusError> {
+        validate_topic(&topic)?;
         let msg = TopicMessage {
             topic,
             payload,
@@ -49,71 +102,43 @@ impl<T: Clone + Send + 'static> QuantumBus<T> {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_nanos() as u64,
+            source,
         };
 
         self.ring_buffer
             .push(msg)
-            .map_err(|_| BusError::QueueFull)
+            .map_err(|_| BusError::QueueFull)?;
+        if self.dispatcher_idle.load(Ordering::SeqCst) {
+            self.dispatcher.unpark();
+        }
+        Ok(())
     }
 
-    /// Subscribe to a topic
-    pub fn subscribe(&self, topic: String) -> Receiver<TopicMessage<T>> {
+    /// Subscribe to a topic or wildcard pattern, e.g. `signals.crypto.>`
+    pub fn subscribe(&self, pattern: String) -> Result<Receiver<TopicMessage<T>>, BusError> {
         let (tx, rx) = unbounded();
-        
-        let mut subscribers = self.subscribers.write();
-        subscribers
-            .entry(topic)
-            .or_insert_with(Vec::new)
-            .push(tx);
-
-        rx
-    }
-
-    /// Unsubscribe all subscribers from a topic
-    pub fn clear_topic(&self, topic: &str) {
-        let mut subscribers = self.subscribers.write();
-        subscribers.remove(topic);
-    }
-
-    /// Get number of active subscribers for a topic
-    pub fn subscriber_count(&self, topic: &str) -> usize {
-        self.subscribers
-            .read()
-            .get(topic)
-            .map(|v| v.len())
-            .unwrap_or(0)
-    }
-
-    /// Start the message dispatcher
-    fn start_dispatcher(&self) {
-        let ring_buffer = self.ring_buffer.clone();
-        let subscribers = self.subscribers.clone();
-        let messages_sent = self.messages_sent.clone();
-        let total_latency_ns = self.total_latency_ns.clone();
-
-        std::thread::spawn(move || {
-            loop {
-                if let Some(msg) = ring_buffer.pop() {
-                    let start = Instant::now();
-                    
-                    // Dispatch to topic subscribers
-                    let subs = subscribers.read();
-                    if let Some(topic_subs) = subs.get(&msg.topic) {
-                        for sender in topic_subs {
-                            let _ = sender.send(msg.clone());
-                        }
-                    }
-
-                    // Record metrics
-                    messages_sent.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                    let latency = start.elapsed().as_nanos() as u64;
-                    total_latency_ns.fetch_add(latency, std::sync::atomic::Ordering::Relaxed);
-                } else {
-                    // Backpressure handling: small yield when queue is empty
-                    std::thread::yield_now();
-                }
-            }
-        });
+        self.subscribers.write().insert(&pattern, tx)?;
+        Ok(rx)
+    }
+
+    /// Unsubscribe all subscribers registered under exactly this pattern
+    pub fn clear_topic(&self, pattern: &str) {
+        self.subscribers.write().remove(pattern);
+    }
+
+    /// Get number of subscribers registered under exactly this pattern
+    pub fn subscriber_count(&self, pattern: &str) -> usize {
+        self.subscribers.read().count(pattern)
+    }
+
+    /// Subscriptions across all patterns
+    pub fn subscription_count(&self) -> usize {
+        self.subscribers.read().len()
+    }
+
+    /// Get number of subscribers a message on this topic would reach
+    pub fn matching_subscriber_count(&self, topic: &str) -> usize {
+        self.subscribers.read().matches(topic).len()
     }
 
     /// Get average latency in nanoseconds
@@ -128,11 +153,30 @@ impl<T: Clone + Send + 'static> QuantumBus<T> {
         }
     }
 
+    /// p50/p95/p99/max of dispatching one message to its subscribers
+    pub fn latency_snapshot(&self) -> LatencySnapshot {
+        self.latency.snapshot()
+    }
+
     /// Get total messages sent
     pub fn messages_sent(&self) -> u64 {
         self.messages_sent.load(std::sync::atomic::Ordering::Relaxed)
     }
 
+    /// Wait until every published message has been logged and delivered;
+    /// false if the dispatcher is still busy after `timeout`
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !(self.ring_buffer.is_empty() && self.dispatcher_idle.load(Ordering::SeqCst)) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            self.dispatcher.unpark();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
     /// Get queue utilization (0.0 to 1.0)
     pub fn queue_utilization(&self) -> f64 {
         let len = self.ring_buffer.len();
@@ -141,10 +185,137 @@ impl<T: Clone + Send + 'static> QuantumBus<T> {
     }
 }
 
+impl<T> QuantumBus<T>
+where
+    T: Clone + Send + 'static + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// Append messages on topics matching any of `topics` to `log`, each
+    /// under its own topic, from the dispatcher thread before they are
+    /// delivered. Invalid patterns are skipped; only the first log is used.
+    pub fn with_log(self, log: Arc<EventLog>, topics: &[String]) -> Self {
+        let topics = topics
+            .iter()
+            .filter(|p| match validate_pattern(p) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Not logging {}: {}", p, e);
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        let bus_log = BusLog {
+            log,
+            encode: |payload| serde_json::to_value(payload),
+            topics,
+        };
+        if self.log.set(bus_log).is_err() {
+            tracing::warn!("Bus already has an event log; keeping the first one");
+        }
+        self
+    }
+
+    /// Logged messages on topics matching `pattern`, oldest first
+    pub fn replay(&self, pattern: &str, from: ReplayFrom) -> Result<Vec<TopicMessage<T>>, BusError> {
+        let Some(bus_log) = self.log.get() else {
+            return Ok(Vec::new());
+        };
+        let records = bus_log.log.read(pattern, from).map_err(|e| BusError::Log(e.to_string()))?;
+        Ok(records
+            .into_iter()
+            .filter_map(|r| {
+                let payload = serde_json::from_value(r.payload).ok()?;
+                Some(TopicMessage {
+                    topic: r.topic,
+                    payload,
+                    timestamp: r.timestamp as u64 * 1_000_000,
+                    source: None,
+                })
+            })
+            .collect())
+    }
+
+    /// Subscribe, then replay the log from `from`. Messages published while
+    /// joining may show up both in the replay and live.
+    pub fn subscribe_from(
+        &self,
+        pattern: String,
+        from: ReplayFrom,
+    ) -> Result<Replayed<T>, BusError> {
+        let rx = self.subscribe(pattern.clone())?;
+        Ok((self.replay(&pattern, from)?, rx))
+    }
+}
+
+/// How long an idle dispatcher sleeps before checking whether the bus is gone
+const DISPATCHER_IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Moves messages from the ring buffer to matching subscribers
+struct Dispatcher<T: Clone + Send> {
+    ring_buffer: Arc<ArrayQueue<TopicMessage<T>>>,
+    log: Arc<OnceLock<BusLog<T>>>,
+    subscribers: Arc<RwLock<TopicTrie<Sender<TopicMessage<T>>>>>,
+    messages_sent: Arc<std::sync::atomic::AtomicU64>,
+    total_latency_ns: Arc<std::sync::atomic::AtomicU64>,
+    latency: Arc<LatencyHistogram>,
+    idle: Arc<AtomicBool>,
+}
+
+impl<T: Clone + Send> Dispatcher<T> {
+    fn run(self) {
+        loop {
+            if let Some(msg) = self.ring_buffer.pop() {
+                // Logged before delivery, so a subscriber that sees it live can also replay it
+                self.append_to_log(&msg);
+                let start = Instant::now();
+
+                // Dispatch to every matching pattern
+                let mut disconnected = false;
+                for sender in self.subscribers.read().matches(&msg.topic) {
+                    disconnected |= sender.send(msg.clone()).is_err();
+                }
+                if disconnected {
+                    self.subscribers.write().retain(|sender| !sender.is_disconnected());
+                }
+
+                // Record metrics
+                self.messages_sent.fetch_add(1, Ordering::Relaxed);
+                let latency = start.elapsed().as_nanos() as u64;
+                self.total_latency_ns.fetch_add(latency, Ordering::Relaxed);
+                self.latency.record(latency);
+            } else if Arc::strong_count(&self.ring_buffer) == 1 {
+                // The bus was dropped and everything queued was delivered
+                break;
+            } else {
+                // Announce first, then check again, so a publish in between is not missed
+                self.idle.store(true, Ordering::SeqCst);
+                if self.ring_buffer.is_empty() {
+                    std::thread::park_timeout(DISPATCHER_IDLE_TIMEOUT);
+                }
+                self.idle.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn append_to_log(&self, msg: &TopicMessage<T>) {
+        let Some(bus_log) = self.log.get().filter(|l| l.topics.iter().any(|p| matches(p, &msg.topic))) else {
+            return;
+        };
+        let appended = (bus_log.encode)(&msg.payload)
+            .map_err(|e| BusError::Log(e.to_string()))
+            .and_then(|value| bus_log.log.append(&msg.topic, value).map_err(|e| BusError::Log(e.to_string())));
+        if let Err(e) = appended {
+            tracing::warn!("Failed to log message on {}: {}", msg.topic, e);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum BusError {
     QueueFull,
     NoSubscribers,
+    InvalidTopic(String),
+    Log(String),
 }
 
 impl std::fmt::Display for BusError {
@@ -152,6 +323,8 @@ impl std::fmt::Display for BusError {
         match self {
             BusError::QueueFull => write!(f, "Message queue is full"),
             BusError::NoSubscribers => write!(f, "No subscribers for topic"),
+            BusError::InvalidTopic(topic) => write!(f, "Invalid topic or pattern: {}", topic),
+            BusError::Log(e) => write!(f, "Event log error: {}", e),
         }
     }
 }
@@ -163,7 +336,7 @@ mod tests {
     use super::*;
     use std::time::Duration;
 
-    #[derive(Clone, Debug)]
+    #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
     struct TestMessage {
         data: String,
     }
@@ -179,7 +352,7 @@ mod tests {
     fn test_pub_sub() {
         let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
         
-        let rx = bus.subscribe("test.topic".to_string());
+        let rx = bus.subscribe("test.topic".to_string()).unwrap();
         
         let msg = TestMessage {
             data: "Hello".to_string(),
@@ -192,14 +365,28 @@ mod tests {
         
         let received = rx.try_recv();
         assert!(received.is_ok());
+        assert_eq!(bus.latency_snapshot().count, 1);
+    }
+
+    #[test]
+    fn test_idle_dispatcher_parks_until_publish() {
+        let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
+        let rx = bus.subscribe("test.topic".to_string()).unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(bus.dispatcher_idle.load(Ordering::SeqCst));
+
+        // Woken by the publish, well before the idle timeout
+        bus.publish("test.topic".to_string(), TestMessage { data: "wake".to_string() }).unwrap();
+        assert!(rx.recv_timeout(DISPATCHER_IDLE_TIMEOUT / 2).is_ok());
     }
 
     #[test]
     fn test_multiple_subscribers() {
         let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
         
-        let rx1 = bus.subscribe("test.topic".to_string());
-        let rx2 = bus.subscribe("test.topic".to_string());
+        let rx1 = bus.subscribe("test.topic".to_string()).unwrap();
+        let rx2 = bus.subscribe("test.topic".to_string()).unwrap();
         
         assert_eq!(bus.subscriber_count("test.topic"), 2);
         
@@ -219,8 +406,8 @@ mod tests {
     fn test_topic_isolation() {
         let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
         
-        let rx1 = bus.subscribe("topic1".to_string());
-        let rx2 = bus.subscribe("topic2".to_string());
+        let rx1 = bus.subscribe("topic1".to_string()).unwrap();
+        let rx2 = bus.subscribe("topic2".to_string()).unwrap();
         
         let msg = TestMessage {
             data: "Topic1 only".to_string(),
@@ -233,4 +420,68 @@ mod tests {
         assert!(rx1.try_recv().is_ok());
         assert!(rx2.try_recv().is_err()); // Should not receive
     }
+
+    #[test]
+    fn test_wildcard_subscriptions() {
+        let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
+
+        let crypto = bus.subscribe("signals.crypto.>".to_string()).unwrap();
+        let m15 = bus.subscribe("signals.*.*.M15".to_string()).unwrap();
+        assert!(bus.subscribe("signals.>.M15".to_string()).is_err());
+        assert!(bus.publish("signals.*".to_string(), TestMessage { data: "x".to_string() }).is_err());
+        assert_eq!(bus.matching_subscriber_count("signals.crypto.BTCUSDT.M15"), 2);
+
+        bus.publish("signals.crypto.BTCUSDT.M15".to_string(), TestMessage { data: "btc".to_string() }).unwrap();
+        bus.publish("signals.stocks.AAPL.M15".to_string(), TestMessage { data: "aapl".to_string() }).unwrap();
+        bus.publish("signals.crypto.ETHUSDT.H1".to_string(), TestMessage { data: "eth".to_string() }).unwrap();
+
+        std::thread::sleep(Duration::from_millis(10));
+
+        let crypto_topics: Vec<String> = crypto.try_iter().map(|m| m.topic).collect();
+        assert_eq!(crypto_topics, vec!["signals.crypto.BTCUSDT.M15", "signals.crypto.ETHUSDT.H1"]);
+        let m15_data: Vec<String> = m15.try_iter().map(|m| m.payload.data).collect();
+        assert_eq!(m15_data, vec!["btc", "aapl"]);
+    }
+
+    #[test]
+    fn test_dropped_receivers_are_pruned() {
+        let bus: QuantumBus<TestMessage> = QuantumBus::new(1024);
+        let rx = bus.subscribe("orders.>".to_string()).unwrap();
+        drop(rx);
+
+        bus.publish("orders.fill".to_string(), TestMessage { data: "fill".to_string() }).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+
+        assert_eq!(bus.subscriber_count("orders.>"), 0);
+    }
+
+    #[test]
+    fn test_replay_from_log() {
+        use crate::bus::event_log::EventLogConfig;
+
+        let config = EventLogConfig::new(std::env::temp_dir().join(format!("quantum-log-{}", uuid::Uuid::new_v4())));
+        let log = Arc::new(EventLog::open(config.clone()).unwrap());
+        let topics = vec!["orders.>".to_string(), "bad.>.x".to_string()];
+        let bus: QuantumBus<TestMessage> = QuantumBus::new(1024).with_log(log.clone(), &topics);
+
+        bus.publish("orders.new".to_string(), TestMessage { data: "a".to_string() }).unwrap();
+        bus.publish("orders.fill".to_string(), TestMessage { data: "b".to_string() }).unwrap();
+        bus.publish("quotes.BTCUSDT".to_string(), TestMessage { data: "c".to_string() }).unwrap();
+        // Logged by the dispatcher
+        assert!(bus.wait_idle(Duration::from_secs(1)));
+        assert_eq!(log.next_offset("orders.new"), 1);
+        // Quotes are not in the logged topics
+        assert_eq!(log.next_offset("quotes.BTCUSDT"), 0);
+        assert!(bus.replay(">", ReplayFrom::Beginning).unwrap().iter().all(|m| m.topic.starts_with("orders.")));
+
+        let (replay, rx) = bus.subscribe_from("orders.>".to_string(), ReplayFrom::Beginning).unwrap();
+        let data: Vec<String> = replay.into_iter().map(|m| m.payload.data).collect();
+        assert_eq!(data, vec!["a", "b"]);
+
+        bus.publish("orders.cancel".to_string(), TestMessage { data: "d".to_string() }).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(rx.try_recv().unwrap().payload.data, "d");
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
 }
@@ -0,0 +1,407 @@
+//! Redis pub/sub bridge for `QuantumBus`
+//!
+//! Mirrors selected local topics to Redis channels (`<prefix><topic>`) and
+//! republishes matching channels from other processes onto the local bus, so
+//! scanners and the API server can run as separate processes.
+//!
+//! Loop prevention works on two levels:
+//! - messages that arrived from Redis carry a `source` and are never sent back out
+//! - every envelope names the node that sent it; a node ignores its own echoes
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use super::quantum_bus::{QuantumBus, TopicMessage};
+use super::topic;
+
+#[derive(Debug, Clone)]
+pub struct RedisBridgeConfig {
+    pub url: String,
+    pub channel_prefix: String,
+    pub outbound: Vec<String>, // Local topic patterns published to Redis
+    pub inbound: Vec<String>,  // Topic patterns accepted from Redis
+    pub node_id: String,       // Unique per process
+}
+
+impl RedisBridgeConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            channel_prefix: "baconalgo.".to_string(),
+            outbound: Vec::new(),
+            inbound: Vec::new(),
+            node_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn outbound(mut self, pattern: impl Into<String>) -> Self {
+        self.outbound.push(pattern.into());
+        self
+    }
+
+    pub fn inbound(mut self, pattern: impl Into<String>) -> Self {
+        self.inbound.push(pattern.into());
+        self
+    }
+}
+
+/// What goes over the wire, as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeEnvelope<T> {
+    pub origin: String, // Node id of the sender
+    pub topic: String,
+    pub timestamp: u64, // Unix ns, from the original `TopicMessage`
+    pub payload: T,
+}
+
+#[derive(Debug)]
+pub enum BridgeError {
+    Redis(String),
+    Serialization(String),
+    InvalidTopic(String),
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeError::Redis(e) => write!(f, "Redis error: {}", e),
+            BridgeError::Serialization(e) => write!(f, "Bridge serialization error: {}", e),
+            BridgeError::InvalidTopic(e) => write!(f, "Invalid bridge topic: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+impl From<redis::RedisError> for BridgeError {
+    fn from(e: redis::RedisError) -> Self {
+        BridgeError::Redis(e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BridgeStats {
+    pub node_id: String,
+    pub sent: u64,
+    pub received: u64,
+    pub echoes_skipped: u64,
+    pub errors: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+    echoes_skipped: AtomicU64,
+    errors: AtomicU64,
+}
+
+pub struct RedisBridge {
+    config: RedisBridgeConfig,
+    client: redis::Client,
+    counters: Counters,
+}
+
+impl RedisBridge {
+    pub fn new(config: RedisBridgeConfig) -> Result<Self, BridgeError> {
+        for pattern in config.outbound.iter().chain(&config.inbound) {
+            topic::validate_pattern(pattern).map_err(|e| BridgeError::InvalidTopic(e.to_string()))?;
+        }
+        let client = redis::Client::open(config.url.as_str())?;
+        Ok(Self {
+            config,
+            client,
+            counters: Counters::default(),
+        })
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.config.node_id
+    }
+
+    pub fn channel(&self, topic: &str) -> String {
+        format!("{}{}", self.config.channel_prefix, topic)
+    }
+
+    /// Serialize a local message for Redis; `None` for messages that came from elsewhere
+    pub fn encode<T: Serialize + Clone>(&self, msg: &TopicMessage<T>) -> Result<Option<String>, BridgeError> {
+        if msg.source.is_some() {
+            self.counters.echoes_skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+        let envelope = BridgeEnvelope {
+            origin: self.config.node_id.clone(),
+            topic: msg.topic.clone(),
+            timestamp: msg.timestamp,
+            payload: &msg.payload,
+        };
+        serde_json::to_string(&envelope)
+            .map(Some)
+            .map_err(|e| BridgeError::Serialization(e.to_string()))
+    }
+
+    /// Parse a Redis message; `None` for our own echoes and topics not bridged inbound
+    pub fn decode<T: DeserializeOwned>(&self, payload: &str) -> Result<Option<BridgeEnvelope<T>>, BridgeError> {
+        let envelope: BridgeEnvelope<T> =
+            serde_json::from_str(payload).map_err(|e| BridgeError::Serialization(e.to_string()))?;
+        if envelope.origin == self.config.node_id {
+            self.counters.echoes_skipped.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+        if !self.config.inbound.iter().any(|p| topic::matches(p, &envelope.topic)) {
+            return Ok(None);
+        }
+        Ok(Some(envelope))
+    }
+
+    pub fn stats(&self) -> BridgeStats {
+        BridgeStats {
+            node_id: self.config.node_id.clone(),
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            received: self.counters.received.load(Ordering::Relaxed),
+            echoes_skipped: self.counters.echoes_skipped.load(Ordering::Relaxed),
+            errors: self.counters.errors.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Connect and start mirroring. Fails if Redis is unreachable at startup;
+    /// afterwards the inbound side reconnects on its own.
+    pub async fn start<T>(self: Arc<Self>, bus: Arc<QuantumBus<T>>) -> Result<BridgeHandle, BridgeError>
+    where
+        T: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+    {
+        let mut tasks = Vec::new();
+
+        if !self.config.outbound.is_empty() {
+            let conn = redis::aio::ConnectionManager::new(self.client.clone()).await?;
+            for (index, pattern) in self.config.outbound.iter().enumerate() {
+                let rx = bus.subscribe(pattern.clone()).map_err(|e| BridgeError::InvalidTopic(e.to_string()))?;
+                tasks.push(tokio::spawn(self.clone().forward(index, rx, conn.clone())));
+            }
+        }
+
+        if !self.config.inbound.is_empty() {
+            let pubsub = self.subscribe_channels().await?;
+            tasks.push(tokio::spawn(self.clone().receive(pubsub, bus)));
+        }
+
+        tracing::info!(
+            "Redis bridge {} started ({} outbound, {} inbound patterns)",
+            self.config.node_id,
+            self.config.outbound.len(),
+            self.config.inbound.len()
+        );
+        Ok(BridgeHandle { tasks })
+    }
+
+    /// Local -> Redis for one outbound pattern
+    async fn forward<T>(
+        self: Arc<Self>,
+        index: usize,
+        rx: flume::Receiver<TopicMessage<T>>,
+        mut conn: redis::aio::ConnectionManager,
+    ) where
+        T: Clone + Serialize,
+    {
+        while let Ok(msg) = rx.recv_async().await {
+            // A topic matching several outbound patterns is sent once, by the first
+            let first = self.config.outbound.iter().position(|p| topic::matches(p, &msg.topic));
+            if first != Some(index) {
+                continue;
+            }
+            let payload = match self.encode(&msg) {
+                Ok(Some(payload)) => payload,
+                Ok(None) => continue,
+                Err(e) => {
+                    self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("Failed to encode {} for Redis: {}", msg.topic, e);
+                    continue;
+                }
+            };
+            let sent: redis::RedisResult<()> =
+                redis::cmd("PUBLISH").arg(self.channel(&msg.topic)).arg(payload).query_async(&mut conn).await;
+            match sent {
+                Ok(()) => {
+                    self.counters.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("Failed to publish {} to Redis: {}", msg.topic, e);
+                }
+            }
+        }
+    }
+
+    async fn subscribe_channels(&self) -> Result<redis::aio::PubSub, BridgeError> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.psubscribe(format!("{}*", self.config.channel_prefix)).await?;
+        Ok(pubsub)
+    }
+
+    /// Redis -> local, reconnecting when the subscription drops
+    async fn receive<T>(self: Arc<Self>, mut pubsub: redis::aio::PubSub, bus: Arc<QuantumBus<T>>)
+    where
+        T: Clone + Send + Serialize + DeserializeOwned + 'static,
+    {
+        loop {
+            let mut messages = pubsub.into_on_message();
+            while let Some(msg) = messages.next().await {
+                let payload: String = match msg.get_payload() {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!("Unreadable Redis message on {}: {}", msg.get_channel_name(), e);
+                        continue;
+                    }
+                };
+                match self.decode::<T>(&payload) {
+                    Ok(Some(envelope)) => {
+                        let source = Some(format!("redis:{}", envelope.origin));
+                        match bus.publish_from(envelope.topic, envelope.payload, source) {
+                            Ok(()) => {
+                                self.counters.received.fetch_add(1, Ordering::Relaxed);
+                            }
+                            Err(e) => {
+                                self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                                tracing::warn!("Failed to republish Redis message: {}", e);
+                            }
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!("Invalid Redis message on {}: {}", msg.get_channel_name(), e);
+                    }
+                }
+            }
+
+            tracing::warn!("Redis bridge subscription lost, reconnecting");
+            pubsub = loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                match self.subscribe_channels().await {
+                    Ok(pubsub) => break pubsub,
+                    Err(e) => tracing::warn!("Redis bridge reconnect failed: {}", e),
+                }
+            };
+        }
+    }
+}
+
+/// Running bridge tasks; dropping the handle stops the bridge
+pub struct BridgeHandle {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl BridgeHandle {
+    /// Stop mirroring (same as dropping the handle)
+    pub fn stop(self) {}
+}
+
+impl Drop for BridgeHandle {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(topic: &str, source: Option<&str>) -> TopicMessage<u32> {
+        TopicMessage {
+            topic: topic.to_string(),
+            payload: 7,
+            timestamp: 1,
+            source: source.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_loop_prevention() {
+        let config = RedisBridgeConfig::new("redis://127.0.0.1:6379").outbound("signals.>").inbound("signals.>");
+        let a = RedisBridge::new(config.clone()).unwrap();
+        let b = RedisBridge::new(RedisBridgeConfig::new("redis://127.0.0.1:6379").inbound("signals.>")).unwrap();
+
+        // Messages that came in from Redis are not sent back out
+        assert!(a.encode(&message("signals.crypto", Some("redis:other"))).unwrap().is_none());
+
+        let wire = a.encode(&message("signals.crypto", None)).unwrap().unwrap();
+        // The sender ignores its own echo, other nodes accept it
+        assert!(a.decode::<u32>(&wire).unwrap().is_none());
+        let envelope = b.decode::<u32>(&wire).unwrap().unwrap();
+        assert_eq!(envelope.topic, "signals.crypto");
+        assert_eq!(envelope.payload, 7);
+        assert_eq!(envelope.origin, a.node_id());
+
+        // Topics outside the inbound patterns are ignored
+        let orders = a.encode(&message("orders.fill", None)).unwrap().unwrap();
+        assert!(b.decode::<u32>(&orders).unwrap().is_none());
+        assert!(b.decode::<u32>("not json").is_err());
+        assert_eq!(a.stats().echoes_skipped, 2);
+        assert_eq!(a.channel("signals.crypto"), "baconalgo.signals.crypto");
+
+        assert!(RedisBridge::new(RedisBridgeConfig::new("redis://127.0.0.1:6379").outbound("a.>.b")).is_err());
+    }
+
+    /// Redis for integration tests: `REDIS_URL` or a local redis-server.
+    /// Panics when it isn't reachable, so an ignored test run on purpose can't pass silently.
+    async fn local_redis() -> String {
+        let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let client = redis::Client::open(url.as_str()).expect("invalid REDIS_URL");
+        let ping = async {
+            let mut conn = client.get_multiplexed_async_connection().await.ok()?;
+            redis::cmd("PING").query_async::<String>(&mut conn).await.ok()
+        };
+        match tokio::time::timeout(Duration::from_millis(500), ping).await {
+            Ok(Some(_)) => url,
+            _ => panic!("no redis-server at {}", url),
+        }
+    }
+
+    async fn recv_within(rx: &flume::Receiver<TopicMessage<u32>>, ms: u64) -> Option<TopicMessage<u32>> {
+        tokio::time::timeout(Duration::from_millis(ms), rx.recv_async()).await.ok()?.ok()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a redis-server (REDIS_URL or 127.0.0.1:6379); run with --ignored"]
+    async fn test_two_processes_share_topics_over_redis() {
+        let url = local_redis().await;
+        let prefix = format!("test.{}.", uuid::Uuid::new_v4());
+        let config = |url: &str| RedisBridgeConfig {
+            channel_prefix: prefix.clone(),
+            ..RedisBridgeConfig::new(url).outbound("signals.>").inbound("signals.>")
+        };
+
+        let scanner_bus = Arc::new(QuantumBus::<u32>::new(1024));
+        let api_bus = Arc::new(QuantumBus::<u32>::new(1024));
+        let scanner = Arc::new(RedisBridge::new(config(&url)).unwrap());
+        let api = Arc::new(RedisBridge::new(config(&url)).unwrap());
+        let _scanner_handle = scanner.clone().start(scanner_bus.clone()).await.unwrap();
+        let _api_handle = api.clone().start(api_bus.clone()).await.unwrap();
+
+        let scanner_rx = scanner_bus.subscribe("signals.>".to_string()).unwrap();
+        let api_rx = api_bus.subscribe("signals.>".to_string()).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        scanner_bus.publish("signals.crypto.BTCUSDT".to_string(), 42).unwrap();
+
+        let received = recv_within(&api_rx, 2000).await.expect("message bridged to the other bus");
+        assert_eq!(received.payload, 42);
+        assert_eq!(received.source, Some(format!("redis:{}", scanner.node_id())));
+
+        // The publisher sees its own message once, and nothing bounces back
+        assert!(recv_within(&scanner_rx, 100).await.is_some());
+        assert!(recv_within(&scanner_rx, 300).await.is_none());
+        assert!(recv_within(&api_rx, 300).await.is_none());
+        assert_eq!(scanner.stats().sent, 1);
+        assert_eq!(api.stats().sent, 0);
+        assert_eq!(api.stats().received, 1);
+    }
+}
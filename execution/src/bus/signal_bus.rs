@@ -0,0 +1,677 @@
+//! Fan-out bus with per-subscriber backpressure
+//!
+//! Every subscriber gets its own bounded queue and a `BackpressurePolicy`
+//! deciding what happens when it falls behind. Messages a subscriber misses are
+//! reported once as `RecvError::Lagged(n)` before its next message, so
+//! consumers (SSE streams) can tell clients to resync from the store.
+//!
+//! Every message gets a sequence number. With an `EventLog` attached
+//! (`SignalBus::with_log`) it is the message's offset in the log topic, and
+//! `subscribe_from` replays from an offset or timestamp before going live;
+//! the log's replay cap bounds how far back that goes.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use super::event_log::{EventLog, EventLogError, ReplayFrom};
+
+/// What to do with a new message when a subscriber's queue is full
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum BackpressurePolicy {
+    #[default]
+    DropOldest,                  // Evict the oldest queued message
+    DropNewest,                  // Discard the new message
+    Disconnect,                  // Drop the subscriber
+    Block { timeout_ms: u64 },   // Wait for space (async `send` only), then discard
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriberOptions {
+    pub name: String,
+    pub policy: BackpressurePolicy,
+    pub capacity: Option<usize>, // Defaults to the bus capacity
+}
+
+impl SubscriberOptions {
+    pub fn new(name: impl Into<String>, policy: BackpressurePolicy) -> Self {
+        Self {
+            name: name.into(),
+            policy,
+            capacity: None,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    Lagged(u64),       // Messages missed since the last receive
+    Disconnected(u64), // Dropped by the `Disconnect` policy; messages discarded
+    Closed,            // The bus is gone
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Lagged(u64),
+    Disconnected(u64),
+    Closed,
+}
+
+impl std::fmt::Display for RecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecvError::Lagged(n) => write!(f, "Subscriber lagged, {} messages missed", n),
+            RecvError::Disconnected(n) => write!(f, "Subscriber disconnected, {} messages discarded", n),
+            RecvError::Closed => write!(f, "Bus closed"),
+        }
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// Counters for one subscriber
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriberStats {
+    pub id: u64,
+    pub name: String,
+    pub policy: BackpressurePolicy,
+    pub capacity: usize,
+    pub queued: usize,    // Current lag
+    pub max_lag: usize,   // Deepest the queue has been
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BusStats {
+    pub capacity: usize,
+    pub published: u64,
+    pub next_sequence: u64,
+    pub log_topic: Option<String>, // Set when backed by an event log
+    pub dropped: u64,      // Across all subscribers, including gone ones
+    pub disconnected: u64, // Subscribers dropped by the `Disconnect` policy
+    pub subscribers: Vec<SubscriberStats>,
+}
+
+struct Subscriber<T> {
+    id: u64,
+    name: String,
+    policy: BackpressurePolicy,
+    capacity: usize,
+    queue: Mutex<VecDeque<(u64, T)>>,
+    ready: Notify,
+    space: Notify,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    missed: AtomicU64, // Not yet reported as a gap
+    discarded: AtomicU64,
+    max_lag: AtomicU64,
+    disconnected: AtomicBool,
+    closed: AtomicBool, // Bus dropped
+    detached: AtomicBool, // Receiver dropped
+}
+
+enum Offer<T> {
+    Queued,
+    Dropped,
+    Full((u64, T)),
+}
+
+impl<T> Subscriber<T> {
+    fn is_gone(&self) -> bool {
+        self.detached.load(Ordering::Relaxed) || self.disconnected.load(Ordering::Relaxed)
+    }
+
+    /// Try to queue without waiting; `Full` is only returned for `Block`
+    fn offer(&self, msg: (u64, T)) -> Offer<T> {
+        let mut queue = self.queue.lock();
+        if queue.len() < self.capacity {
+            queue.push_back(msg);
+            self.max_lag.fetch_max(queue.len() as u64, Ordering::Relaxed);
+            drop(queue);
+            self.ready.notify_one();
+            return Offer::Queued;
+        }
+
+        match self.policy {
+            BackpressurePolicy::DropOldest => {
+                queue.pop_front();
+                queue.push_back(msg);
+                drop(queue);
+                self.record_drop(1);
+                self.ready.notify_one();
+                Offer::Queued
+            }
+            BackpressurePolicy::DropNewest => {
+                drop(queue);
+                self.record_drop(1);
+                Offer::Dropped
+            }
+            BackpressurePolicy::Disconnect => {
+                let discarded = queue.len() as u64 + 1;
+                queue.clear();
+                drop(queue);
+                self.dropped.fetch_add(discarded, Ordering::Relaxed);
+                self.discarded.store(discarded, Ordering::Relaxed);
+                self.disconnected.store(true, Ordering::Relaxed);
+                self.ready.notify_one();
+                Offer::Dropped
+            }
+            BackpressurePolicy::Block { .. } => Offer::Full(msg),
+        }
+    }
+
+    fn record_drop(&self, n: u64) {
+        self.dropped.fetch_add(n, Ordering::Relaxed);
+        self.missed.fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> SubscriberStats {
+        SubscriberStats {
+            id: self.id,
+            name: self.name.clone(),
+            policy: self.policy,
+            capacity: self.capacity,
+            queued: self.queue.lock().len(),
+            max_lag: self.max_lag.load(Ordering::Relaxed) as usize,
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Event log binding of a bus: one log topic, payloads as JSON
+struct BusLog<T> {
+    log: Arc<EventLog>,
+    topic: String,
+    encode: fn(&T) -> serde_json::Result<serde_json::Value>,
+    decode: fn(serde_json::Value) -> serde_json::Result<T>,
+}
+
+struct Inner<T> {
+    capacity: usize,
+    subscribers: RwLock<Vec<Arc<Subscriber<T>>>>,
+    sequence: Mutex<u64>, // Next sequence; held while stamping so a subscriber's replay boundary is exact
+    log: Option<BusLog<T>>,
+    next_id: AtomicU64,
+    published: AtomicU64,
+    dropped: AtomicU64,
+    disconnected: AtomicU64,
+}
+
+impl<T> Inner<T> {
+    /// Assign the next sequence (appending to the log, if any) and snapshot
+    /// the subscribers that should get the message
+    fn stamp(&self, msg: &T) -> (u64, Vec<Arc<Subscriber<T>>>) {
+        let mut next = self.sequence.lock();
+        let seq = match &self.log {
+            Some(bus_log) => {
+                let appended = (bus_log.encode)(msg)
+                    .map_err(|e| EventLogError::Serialization(e.to_string()))
+                    .and_then(|payload| bus_log.log.append(&bus_log.topic, payload));
+                match appended {
+                    Ok(offset) => offset,
+                    Err(e) => {
+                        // Leave a gap in the log rather than reuse the offset
+                        tracing::warn!("Failed to log message on {}: {}", bus_log.topic, e);
+                        bus_log.log.reserve(&bus_log.topic)
+                    }
+                }
+            }
+            None => *next,
+        };
+        *next = seq + 1;
+        self.published.fetch_add(1, Ordering::Relaxed);
+        (seq, self.subscribers.read().clone())
+    }
+
+    /// Forget receivers that are gone, keeping their counters in the totals
+    fn prune(&self) {
+        let mut subscribers = self.subscribers.write();
+        subscribers.retain(|sub| {
+            if !sub.is_gone() {
+                return true;
+            }
+            self.dropped.fetch_add(sub.dropped.load(Ordering::Relaxed), Ordering::Relaxed);
+            if sub.disconnected.load(Ordering::Relaxed) {
+                self.disconnected.fetch_add(1, Ordering::Relaxed);
+            }
+            false
+        });
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        for sub in self.subscribers.get_mut().iter() {
+            sub.closed.store(true, Ordering::Relaxed);
+            sub.ready.notify_one();
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SignalBus<T: Clone> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T: Clone + serde::Serialize + serde::de::DeserializeOwned> SignalBus<T> {
+    /// A bus whose messages are also appended to `topic` of an event log.
+    /// Sequence numbers continue from the log's next offset.
+    pub fn with_log(capacity: usize, log: Arc<EventLog>, topic: impl Into<String>) -> Self {
+        let topic = topic.into();
+        let next = log.next_offset(&topic);
+        Self::build(
+            capacity,
+            next,
+            Some(BusLog {
+                log,
+                topic,
+                encode: |msg| serde_json::to_value(msg),
+                decode: serde_json::from_value,
+            }),
+        )
+    }
+}
+
+impl<T: Clone> SignalBus<T> {
+    /// `capacity` is the default queue size of each subscriber
+    pub fn new(capacity: usize) -> Self {
+        Self::build(capacity, 0, None)
+    }
+
+    fn build(capacity: usize, sequence: u64, log: Option<BusLog<T>>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                capacity: capacity.max(1),
+                subscribers: RwLock::new(Vec::new()),
+                sequence: Mutex::new(sequence),
+                log,
+                next_id: AtomicU64::new(1),
+                published: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+                disconnected: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Publish without waiting. A full `Block` subscriber can't be waited on
+    /// here, so it misses the message as with `DropNewest`; use `send` from async code.
+    pub fn publish(&self, msg: T) {
+        let (seq, subscribers) = self.inner.stamp(&msg);
+        let mut pruned = false;
+        for sub in subscribers {
+            if let Offer::Full(_) = sub.offer((seq, msg.clone())) {
+                sub.record_drop(1);
+            }
+            pruned |= sub.is_gone();
+        }
+        if pruned {
+            self.inner.prune();
+        }
+    }
+
+    /// Publish, waiting up to their timeout for `Block` subscribers to make room
+    pub async fn send(&self, msg: T) {
+        let (seq, subscribers) = self.inner.stamp(&msg);
+        let mut pruned = false;
+
+        for sub in subscribers {
+            if let Offer::Full(mut pending) = sub.offer((seq, msg.clone())) {
+                let BackpressurePolicy::Block { timeout_ms } = sub.policy else {
+                    unreachable!("only Block subscribers report Full");
+                };
+                let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+                loop {
+                    let space = sub.space.notified();
+                    if tokio::time::timeout_at(deadline, space).await.is_err() || sub.is_gone() {
+                        sub.record_drop(1);
+                        break;
+                    }
+                    match sub.offer(pending) {
+                        Offer::Full(msg) => pending = msg,
+                        _ => break,
+                    }
+                }
+            }
+            pruned |= sub.is_gone();
+        }
+        if pruned {
+            self.inner.prune();
+        }
+    }
+
+    /// Subscribe with the default policy (`DropOldest` at the bus capacity)
+    pub fn subscribe(&self) -> Receiver<T> {
+        self.subscribe_with(SubscriberOptions::new("anonymous", BackpressurePolicy::default()))
+    }
+
+    pub fn subscribe_with(&self, options: SubscriberOptions) -> Receiver<T> {
+        let _stamping = self.inner.sequence.lock();
+        self.register(options)
+    }
+
+    /// Replay logged messages from `from`, then continue live with no gap or
+    /// duplicate in between. Without a log (or if reading it fails) nothing is replayed.
+    /// The subscriber is registered first; the log is read without blocking publishers.
+    pub fn subscribe_from(&self, options: SubscriberOptions, from: ReplayFrom) -> (Vec<(u64, T)>, Receiver<T>) {
+        let (live_from, rx) = {
+            let next = self.inner.sequence.lock();
+            (*next, self.register(options))
+        };
+        let replay = match &self.inner.log {
+            Some(bus_log) => match bus_log.log.read(&bus_log.topic, from) {
+                Ok(records) => records
+                    .into_iter()
+                    .filter(|r| r.offset < live_from)
+                    .filter_map(|r| (bus_log.decode)(r.payload).ok().map(|msg| (r.offset, msg)))
+                    .collect(),
+                Err(e) => {
+                    tracing::warn!("Failed to replay {}: {}", bus_log.topic, e);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        (replay, rx)
+    }
+
+    fn register(&self, options: SubscriberOptions) -> Receiver<T> {
+        let sub = Arc::new(Subscriber {
+            id: self.inner.next_id.fetch_add(1, Ordering::Relaxed),
+            name: options.name,
+            policy: options.policy,
+            capacity: options.capacity.unwrap_or(self.inner.capacity),
+            queue: Mutex::new(VecDeque::new()),
+            ready: Notify::new(),
+            space: Notify::new(),
+            delivered: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            missed: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+            max_lag: AtomicU64::new(0),
+            disconnected: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            detached: AtomicBool::new(false),
+        });
+        self.inner.subscribers.write().push(sub.clone());
+        Receiver { sub, reported: false }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.inner.subscribers.read().iter().filter(|s| !s.is_gone()).count()
+    }
+
+    /// Lag and drop counters, per subscriber and in total
+    pub fn stats(&self) -> BusStats {
+        let subscribers: Vec<SubscriberStats> = self
+            .inner
+            .subscribers
+            .read()
+            .iter()
+            .filter(|s| !s.is_gone())
+            .map(|s| s.stats())
+            .collect();
+        let live_dropped: u64 = self.inner.subscribers.read().iter().map(|s| s.dropped.load(Ordering::Relaxed)).sum();
+        let live_disconnected = self
+            .inner
+            .subscribers
+            .read()
+            .iter()
+            .filter(|s| s.disconnected.load(Ordering::Relaxed))
+            .count() as u64;
+
+        BusStats {
+            capacity: self.inner.capacity,
+            published: self.inner.published.load(Ordering::Relaxed),
+            next_sequence: *self.inner.sequence.lock(),
+            log_topic: self.inner.log.as_ref().map(|l| l.topic.clone()),
+            dropped: self.inner.dropped.load(Ordering::Relaxed) + live_dropped,
+            disconnected: self.inner.disconnected.load(Ordering::Relaxed) + live_disconnected,
+            subscribers,
+        }
+    }
+}
+
+/// Receiving end of one subscription; dropping it unsubscribes
+pub struct Receiver<T> {
+    sub: Arc<Subscriber<T>>,
+    reported: bool, // Disconnect already returned once
+}
+
+impl<T> Receiver<T> {
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        self.recv_sequenced().await.map(|(_, msg)| msg)
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.try_recv_sequenced().map(|(_, msg)| msg)
+    }
+
+    /// Receive a message with its sequence number
+    pub async fn recv_sequenced(&mut self) -> Result<(u64, T), RecvError> {
+        loop {
+            match self.try_recv_sequenced() {
+                Ok(msg) => return Ok(msg),
+                Err(TryRecvError::Lagged(n)) => return Err(RecvError::Lagged(n)),
+                Err(TryRecvError::Disconnected(n)) => return Err(RecvError::Disconnected(n)),
+                Err(TryRecvError::Closed) => return Err(RecvError::Closed),
+                Err(TryRecvError::Empty) => self.sub.ready.notified().await,
+            }
+        }
+    }
+
+    pub fn try_recv_sequenced(&mut self) -> Result<(u64, T), TryRecvError> {
+        if self.sub.disconnected.load(Ordering::Relaxed) {
+            if self.reported {
+                return Err(TryRecvError::Closed);
+            }
+            self.reported = true;
+            return Err(TryRecvError::Disconnected(self.sub.discarded.load(Ordering::Relaxed)));
+        }
+
+        let missed = self.sub.missed.swap(0, Ordering::Relaxed);
+        if missed > 0 {
+            return Err(TryRecvError::Lagged(missed));
+        }
+
+        match self.sub.queue.lock().pop_front() {
+            Some(msg) => {
+                self.sub.delivered.fetch_add(1, Ordering::Relaxed);
+                self.sub.space.notify_one();
+                Ok(msg)
+            }
+            None if self.sub.closed.load(Ordering::Relaxed) => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Messages waiting in this subscriber's queue
+    pub fn lag(&self) -> usize {
+        self.sub.queue.lock().len()
+    }
+
+    /// Stream of sequenced messages and gaps, ending when the bus closes or after a disconnect
+    pub fn into_stream(self) -> impl tokio_stream::Stream<Item = Result<(u64, T), RecvError>> {
+        futures_util::stream::unfold(self, |mut rx| async move {
+            match rx.recv_sequenced().await {
+                Err(RecvError::Closed) => None,
+                item => Some((item, rx)),
+            }
+        })
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.sub.detached.store(true, Ordering::Relaxed);
+        self.sub.space.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(rx: &mut Receiver<u32>) -> Vec<Result<u32, TryRecvError>> {
+        let mut out = Vec::new();
+        loop {
+            match rx.try_recv() {
+                Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return out,
+                item => out.push(item),
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_oldest_and_drop_newest() {
+        let bus = SignalBus::new(2);
+        let mut oldest = bus.subscribe();
+        let mut newest = bus.subscribe_with(SubscriberOptions::new("newest", BackpressurePolicy::DropNewest));
+
+        for i in 1..=4 {
+            bus.publish(i);
+        }
+
+        assert_eq!(drain(&mut oldest), vec![Err(TryRecvError::Lagged(2)), Ok(3), Ok(4)]);
+        assert_eq!(drain(&mut newest), vec![Err(TryRecvError::Lagged(2)), Ok(1), Ok(2)]);
+
+        let stats = bus.stats();
+        assert_eq!(stats.published, 4);
+        assert_eq!(stats.dropped, 4);
+        assert_eq!(stats.subscribers[1].name, "newest");
+        assert_eq!(stats.subscribers[1].max_lag, 2);
+        assert_eq!(stats.subscribers[1].delivered, 2);
+    }
+
+    #[test]
+    fn test_disconnect_policy() {
+        let bus = SignalBus::new(2);
+        let mut slow = bus.subscribe_with(SubscriberOptions::new("slow", BackpressurePolicy::Disconnect));
+        let mut other = bus.subscribe();
+
+        for i in 1..=3 {
+            bus.publish(i);
+        }
+
+        assert_eq!(slow.try_recv(), Err(TryRecvError::Disconnected(3)));
+        assert_eq!(slow.try_recv(), Err(TryRecvError::Closed));
+        assert_eq!(bus.subscriber_count(), 1);
+        assert_eq!(bus.stats().disconnected, 1);
+        assert_eq!(other.try_recv(), Err(TryRecvError::Lagged(1)));
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_space_then_times_out() {
+        let bus = SignalBus::new(1);
+        let policy = BackpressurePolicy::Block { timeout_ms: 500 };
+        let mut rx = bus.subscribe_with(SubscriberOptions::new("blocking", policy));
+
+        bus.send(1).await;
+        let reader = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            (rx.recv().await, rx.recv().await)
+        });
+        // Waits until the reader makes room instead of dropping
+        bus.send(2).await;
+        let (first, second) = reader.await.unwrap();
+        assert_eq!((first, second), (Ok(1), Ok(2)));
+
+        // Nobody reads: the message is dropped after the timeout
+        let policy = BackpressurePolicy::Block { timeout_ms: 10 };
+        let mut stuck = bus.subscribe_with(SubscriberOptions::new("stuck", policy));
+        bus.send(3).await;
+        bus.send(4).await;
+        assert_eq!(stuck.try_recv(), Err(TryRecvError::Lagged(1)));
+        assert_eq!(stuck.try_recv(), Ok(3));
+        assert_eq!(bus.subscriber_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_receiver_unsubscribes_and_close_ends_stream() {
+        use tokio_stream::StreamExt;
+
+        let bus = SignalBus::new(4);
+        let rx = bus.subscribe();
+        drop(bus.subscribe());
+        bus.publish(1);
+        assert_eq!(bus.subscriber_count(), 1);
+
+        bus.publish(2);
+        drop(bus);
+        let items: Vec<Result<(u64, u32), RecvError>> = rx.into_stream().collect().await;
+        assert_eq!(items, vec![Ok((0, 1)), Ok((1, 2))]);
+    }
+
+    #[test]
+    fn test_log_backed_replay_then_live() {
+        use super::super::event_log::EventLogConfig;
+
+        let config = EventLogConfig::new(std::env::temp_dir().join(format!("bus-log-{}", uuid::Uuid::new_v4())));
+        let log = Arc::new(EventLog::open(config.clone()).unwrap());
+        let bus: SignalBus<u32> = SignalBus::with_log(8, log.clone(), "numbers");
+        for i in 10..13 {
+            bus.publish(i);
+        }
+
+        let options = SubscriberOptions::new("resume", BackpressurePolicy::DropOldest);
+        let (replay, mut rx) = bus.subscribe_from(options, ReplayFrom::Offset(1));
+        assert_eq!(replay, vec![(1, 11), (2, 12)]);
+        bus.publish(13);
+        assert_eq!(rx.try_recv_sequenced(), Ok((3, 13)));
+        assert_eq!(bus.stats().log_topic.as_deref(), Some("numbers"));
+
+        // A new bus over the same log continues the sequence
+        drop(bus);
+        let bus: SignalBus<u32> = SignalBus::with_log(8, log, "numbers");
+        let (replay, _rx) = bus.subscribe_from(SubscriberOptions::new("late", BackpressurePolicy::DropOldest), ReplayFrom::Beginning);
+        assert_eq!(replay.len(), 4);
+        assert_eq!(bus.stats().next_sequence, 4);
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+
+    #[test]
+    fn test_unlogged_message_keeps_its_own_sequence() {
+        use super::super::event_log::EventLogConfig;
+
+        let config = EventLogConfig::new(std::env::temp_dir().join(format!("bus-log-{}", uuid::Uuid::new_v4())));
+        let log = Arc::new(EventLog::open(config.clone()).unwrap());
+        // Zero fails to encode, so it is delivered but not logged
+        let bus: SignalBus<u32> = SignalBus::build(
+            8,
+            0,
+            Some(BusLog {
+                log: log.clone(),
+                topic: "numbers".to_string(),
+                encode: |msg| match msg {
+                    0 => Err(serde_json::from_str::<serde_json::Value>("!").unwrap_err()),
+                    n => serde_json::to_value(n),
+                },
+                decode: serde_json::from_value,
+            }),
+        );
+        let mut rx = bus.subscribe();
+        for i in [1, 0, 2] {
+            bus.publish(i);
+        }
+
+        let delivered: Vec<(u64, u32)> = (0..3).map(|_| rx.try_recv_sequenced().unwrap()).collect();
+        assert_eq!(delivered, vec![(0, 1), (1, 0), (2, 2)]);
+        let logged: Vec<u64> = log.read("numbers", ReplayFrom::Beginning).unwrap().iter().map(|r| r.offset).collect();
+        assert_eq!(logged, vec![0, 2]);
+
+        std::fs::remove_dir_all(&config.dir).ok();
+    }
+}
@@ -0,0 +1,275 @@
+//! Hierarchical topics and wildcard subscriptions
+//!
+//! Topics are dot-separated segments, e.g. `signals.crypto.BTCUSDT.M15`.
+//! Subscription patterns may use two wildcards:
+//! - `*` matches exactly one segment (`signals.*.BTCUSDT.*`)
+//! - `>` matches one or more trailing segments and must be last (`orders.>`)
+//!
+//! `TopicTrie` stores subscriptions by pattern segment, so routing a message
+//! walks at most one branch per segment instead of scanning every subscriber.
+
+use std::collections::HashMap;
+
+use super::BusError;
+
+pub const SINGLE_WILDCARD: &str = "*";
+pub const TAIL_WILDCARD: &str = ">";
+
+/// Validate a concrete topic (no wildcards)
+pub fn validate_topic(topic: &str) -> Result<(), BusError> {
+    for segment in topic.split('.') {
+        if segment.is_empty() || segment == SINGLE_WILDCARD || segment == TAIL_WILDCARD {
+            return Err(BusError::InvalidTopic(topic.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Validate a subscription pattern
+pub fn validate_pattern(pattern: &str) -> Result<(), BusError> {
+    let segments: Vec<&str> = pattern.split('.').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let misplaced_tail = *segment == TAIL_WILDCARD && i != segments.len() - 1;
+        if segment.is_empty() || misplaced_tail {
+            return Err(BusError::InvalidTopic(pattern.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Check a topic against a pattern without building a trie
+pub fn matches(pattern: &str, topic: &str) -> bool {
+    let mut topic_segments = topic.split('.');
+    for segment in pattern.split('.') {
+        if segment == TAIL_WILDCARD {
+            return topic_segments.next().is_some();
+        }
+        match topic_segments.next() {
+            Some(t) if segment == SINGLE_WILDCARD || segment == t => {}
+            _ => return false,
+        }
+    }
+    topic_segments.next().is_none()
+}
+
+struct Node<V> {
+    children: HashMap<String, Node<V>>,
+    single: Option<Box<Node<V>>>, // `*` branch
+    tail: Vec<V>,                 // Subscribers on `prefix.>`
+    values: Vec<V>,               // Subscribers ending exactly here
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self {
+            children: HashMap::new(),
+            single: None,
+            tail: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<V> Node<V> {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.single.is_none() && self.tail.is_empty() && self.values.is_empty()
+    }
+
+    fn collect<'a>(&'a self, segments: &[&str], out: &mut Vec<&'a V>) {
+        let Some((head, rest)) = segments.split_first() else {
+            out.extend(self.values.iter());
+            return;
+        };
+
+        out.extend(self.tail.iter());
+        if let Some(child) = self.children.get(*head) {
+            child.collect(rest, out);
+        }
+        if let Some(single) = &self.single {
+            single.collect(rest, out);
+        }
+    }
+
+    fn retain(&mut self, keep: &mut impl FnMut(&V) -> bool) -> usize {
+        let before = self.tail.len() + self.values.len();
+        self.tail.retain(|v| keep(v));
+        self.values.retain(|v| keep(v));
+        let mut removed = before - self.tail.len() - self.values.len();
+
+        for child in self.children.values_mut() {
+            removed += child.retain(keep);
+        }
+        self.children.retain(|_, child| !child.is_empty());
+        if let Some(single) = &mut self.single {
+            removed += single.retain(keep);
+            if single.is_empty() {
+                self.single = None;
+            }
+        }
+        removed
+    }
+}
+
+/// Subscriptions indexed by pattern segment
+pub struct TopicTrie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for TopicTrie<V> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> TopicTrie<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a subscription under a pattern
+    pub fn insert(&mut self, pattern: &str, value: V) -> Result<(), BusError> {
+        validate_pattern(pattern)?;
+
+        let mut node = &mut self.root;
+        for segment in pattern.split('.') {
+            node = match segment {
+                TAIL_WILDCARD => {
+                    node.tail.push(value);
+                    self.len += 1;
+                    return Ok(());
+                }
+                SINGLE_WILDCARD => node.single.get_or_insert_with(Box::default),
+                _ => node.children.entry(segment.to_string()).or_default(),
+            };
+        }
+        node.values.push(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// All subscriptions whose pattern matches a concrete topic
+    pub fn matches(&self, topic: &str) -> Vec<&V> {
+        let segments: Vec<&str> = topic.split('.').collect();
+        let mut out = Vec::new();
+        self.root.collect(&segments, &mut out);
+        out
+    }
+
+    /// Subscriptions registered under exactly this pattern
+    pub fn count(&self, pattern: &str) -> usize {
+        let mut node = &self.root;
+        for segment in pattern.split('.') {
+            let next = match segment {
+                TAIL_WILDCARD => return node.tail.len(),
+                SINGLE_WILDCARD => node.single.as_deref(),
+                _ => node.children.get(segment),
+            };
+            match next {
+                Some(n) => node = n,
+                None => return 0,
+            }
+        }
+        node.values.len()
+    }
+
+    /// Remove every subscription registered under exactly this pattern
+    pub fn remove(&mut self, pattern: &str) -> usize {
+        let mut node = &mut self.root;
+        for segment in pattern.split('.') {
+            let next = match segment {
+                TAIL_WILDCARD => {
+                    let removed = std::mem::take(&mut node.tail).len();
+                    self.len -= removed;
+                    return removed;
+                }
+                SINGLE_WILDCARD => node.single.as_deref_mut(),
+                _ => node.children.get_mut(segment),
+            };
+            match next {
+                Some(n) => node = n,
+                None => return 0,
+            }
+        }
+        let removed = std::mem::take(&mut node.values).len();
+        self.len -= removed;
+        removed
+    }
+
+    /// Drop subscriptions for which `keep` returns false; prunes empty branches
+    pub fn retain(&mut self, mut keep: impl FnMut(&V) -> bool) -> usize {
+        let removed = self.root.retain(&mut keep);
+        self.len -= removed;
+        removed
+    }
+
+    /// Total number of subscriptions
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(trie: &TopicTrie<&'static str>, topic: &str) -> Vec<&'static str> {
+        let mut names: Vec<&str> = trie.matches(topic).into_iter().copied().collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_wildcard_routing() {
+        let mut trie = TopicTrie::new();
+        trie.insert("signals.crypto.BTCUSDT.M15", "exact").unwrap();
+        trie.insert("signals.crypto.>", "crypto").unwrap();
+        trie.insert("signals.*.BTCUSDT.*", "btc").unwrap();
+        trie.insert("orders.>", "orders").unwrap();
+        trie.insert(">", "all").unwrap();
+
+        assert_eq!(matched(&trie, "signals.crypto.BTCUSDT.M15"), vec!["all", "btc", "crypto", "exact"]);
+        assert_eq!(matched(&trie, "signals.crypto.ETHUSDT.H1"), vec!["all", "crypto"]);
+        assert_eq!(matched(&trie, "signals.stocks.BTCUSDT.D1"), vec!["all", "btc"]);
+        // `>` needs at least one more segment, `*` exactly one
+        assert_eq!(matched(&trie, "signals.crypto"), vec!["all"]);
+        assert_eq!(matched(&trie, "signals.crypto.BTCUSDT.M15.extra"), vec!["all", "crypto"]);
+        assert_eq!(matched(&trie, "orders.fill.123"), vec!["all", "orders"]);
+        assert_eq!(trie.len(), 5);
+    }
+
+    #[test]
+    fn test_remove_and_retain() {
+        let mut trie = TopicTrie::new();
+        trie.insert("a.b", 1).unwrap();
+        trie.insert("a.b", 2).unwrap();
+        trie.insert("a.*", 3).unwrap();
+        trie.insert("a.>", 4).unwrap();
+
+        assert_eq!(trie.count("a.b"), 2);
+        assert_eq!(trie.count("a.*"), 1);
+        assert_eq!(trie.remove("a.b"), 2);
+        assert_eq!(trie.count("a.b"), 0);
+        assert_eq!(trie.retain(|v| *v != 3), 1);
+        assert_eq!(trie.matches("a.b"), vec![&4]);
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut trie = TopicTrie::new();
+        assert!(trie.insert("a.>.b", 1).is_err());
+        assert!(trie.insert("a..b", 1).is_err());
+        assert!(trie.insert("", 1).is_err());
+        assert!(validate_topic("signals.*").is_err());
+        assert!(validate_topic("signals.crypto").is_ok());
+
+        assert!(matches("signals.*.BTCUSDT", "signals.crypto.BTCUSDT"));
+        assert!(matches("signals.>", "signals.crypto.BTCUSDT"));
+        assert!(!matches("signals.>", "signals"));
+        assert!(!matches("signals.*", "signals.crypto.BTCUSDT"));
+    }
+}
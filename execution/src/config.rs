@@ -1,4 +1,5 @@
 use lazy_static::lazy_static;
+use std::collections::HashMap;
 use std::env;
 
 lazy_static! {
@@ -29,15 +30,45 @@ pub struct Config {
     
     // Redis
     pub redis_url: String,
+    pub redis_bridge_outbound: Vec<String>, // App bus patterns mirrored to Redis
+    pub redis_bridge_inbound: Vec<String>,  // Redis topics accepted onto the app bus
     
     // Server Settings
     pub server_port: u16,
     pub cors_origins: Vec<String>,
+    pub order_api_token: Option<String>, // None limits order endpoints to local clients
     
     // Scanner Settings
     pub scan_interval_secs: u64,
     pub scan_symbols_limit: usize,
+    pub scan_timeframe: String, // Candle interval in provider form: "5", "15", "60", "240", "D"
     pub rate_limit_per_min: u32,
+    
+    // Signal Settings
+    pub scoring_profile_path: Option<String>,
+    pub signal_store_path: String,
+    pub signal_store_max_signals: usize, // Newest signals kept; older ones are dropped
+    pub signal_store_compact_mb: u64,    // Rewrite the store file with only the kept signals past this size
+    pub event_log_dir: Option<String>, // None disables the bus event log
+    pub event_log_topics: Vec<String>, // App bus patterns logged; quotes are left out by default
+    pub event_log_retention_hours: u64,
+    pub event_log_retention_mb: u64,
+    pub event_log_replay_max: usize, // Most records one replay returns
+    pub event_log_replay_hours: u64, // How far back a replay goes
+    
+    // SMC Sessions, keyed by STOCK, ETF, CRYPTO or FUTURE
+    pub session_days: HashMap<String, String>,  // SESSION_DAY_<MARKET>: "<time zone>@HH:MM" trading day rollover
+    pub session_hours: HashMap<String, String>, // SESSIONS_<MARKET>: "asia=<time zone>@HH:MM-HH:MM,..."
+    
+    // Order Management
+    pub oms_initial_capital: f64,
+    pub paper_fee_rate: f64,
+    pub engine_workers: usize,
+    pub engine_busy_spin: bool,              // Spin instead of parking idle workers
+    pub engine_pending_path: Option<String>, // None drains the queue on shutdown instead
+    pub engine_conflate: bool,               // Keep only the latest queued market update per symbol
+    pub bracket_tp_splits: Vec<f64>,         // Share of a bracket's position each take profit closes
+    pub bracket_breakeven: bool,             // Move a bracket's stop to the entry price after TP1
 }
 
 impl Config {
@@ -71,6 +102,8 @@ impl Config {
             // Redis
             redis_url: env::var("REDIS_URL")
                 .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+            redis_bridge_outbound: patterns_from_env("REDIS_BRIDGE_OUTBOUND"),
+            redis_bridge_inbound: patterns_from_env("REDIS_BRIDGE_INBOUND"),
             
             // Server Settings
             server_port: env::var("SERVER_PORT")
@@ -82,6 +115,7 @@ impl Config {
                 .split(',')
                 .map(|s| s.trim().to_string())
                 .collect(),
+            order_api_token: env::var("ORDER_API_TOKEN").ok().filter(|s| !s.is_empty()),
             
             // Scanner Settings
             scan_interval_secs: env::var("SCAN_INTERVAL_SECS")
@@ -92,10 +126,80 @@ impl Config {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(100),
+            scan_timeframe: env::var("SCAN_TIMEFRAME")
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(|| "15".to_string()),
             rate_limit_per_min: env::var("RATE_LIMIT_PER_MIN")
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(60),
+            
+            // Signal Settings
+            scoring_profile_path: env::var("SCORING_PROFILE_PATH").ok(),
+            signal_store_path: env::var("SIGNAL_STORE_PATH")
+                .unwrap_or_else(|_| "data/signals.jsonl".to_string()),
+            signal_store_max_signals: env::var("SIGNAL_STORE_MAX_SIGNALS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10_000),
+            signal_store_compact_mb: env::var("SIGNAL_STORE_COMPACT_MB")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(64),
+            event_log_dir: match env::var("EVENT_LOG_DIR") {
+                Ok(dir) if dir.is_empty() => None,
+                Ok(dir) => Some(dir),
+                Err(_) => Some("data/events".to_string()),
+            },
+            event_log_topics: match env::var("EVENT_LOG_TOPICS") {
+                Ok(_) => patterns_from_env("EVENT_LOG_TOPICS"),
+                Err(_) => ["candles.>", "signals.>", "orders.>", "fills.>", "risk.>", "news.>"]
+                    .map(String::from)
+                    .to_vec(),
+            },
+            event_log_retention_hours: env::var("EVENT_LOG_RETENTION_HOURS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(168), // 7 days
+            event_log_retention_mb: env::var("EVENT_LOG_RETENTION_MB")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(256),
+            event_log_replay_max: env::var("EVENT_LOG_REPLAY_MAX")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1000),
+            event_log_replay_hours: env::var("EVENT_LOG_REPLAY_HOURS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(24),
+            
+            // SMC Sessions
+            session_days: by_market("SESSION_DAY_"),
+            session_hours: by_market("SESSIONS_"),
+            
+            // Order Management
+            oms_initial_capital: env::var("OMS_INITIAL_CAPITAL")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(100_000.0),
+            paper_fee_rate: env::var("PAPER_FEE_RATE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0.001), // 10 bps
+            engine_workers: env::var("ENGINE_WORKERS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1),
+            engine_busy_spin: env::var("ENGINE_WAIT").is_ok_and(|s| s == "spin"),
+            engine_pending_path: env::var("ENGINE_PENDING_PATH").ok().filter(|s| !s.is_empty()),
+            engine_conflate: env::var("ENGINE_CONFLATE").map(|s| s != "false").unwrap_or(true),
+            bracket_tp_splits: env::var("BRACKET_TP_SPLITS")
+                .ok()
+                .and_then(|s| s.split(',').map(|p| p.trim().parse().ok()).collect())
+                .unwrap_or_else(|| vec![0.5, 0.3, 0.2]),
+            bracket_breakeven: env::var("BRACKET_BREAKEVEN").map(|s| s == "true").unwrap_or(false),
         }
     }
     
@@ -154,6 +258,32 @@ impl Config {
     pub fn has_database(&self) -> bool {
         self.supabase_url.is_some() && self.supabase_key.is_some()
     }
+
+    /// Check if the app bus should be bridged to Redis
+    pub fn has_redis_bridge(&self) -> bool {
+        !self.redis_bridge_outbound.is_empty() || !self.redis_bridge_inbound.is_empty()
+    }
+}
+
+/// Comma-separated topic patterns, empty when unset
+fn patterns_from_env(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// `<prefix><MARKET>` values keyed by market, for those that are set
+fn by_market(prefix: &str) -> HashMap<String, String> {
+    ["STOCK", "ETF", "CRYPTO", "FUTURE"]
+        .iter()
+        .filter_map(|market| {
+            let value = env::var(format!("{}{}", prefix, market)).ok()?;
+            (!value.trim().is_empty()).then(|| (market.to_string(), value))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -0,0 +1,421 @@
+//! Execution algorithms: TWAP and VWAP parents sliced into child orders
+//!
+//! A parent's quantity is spread over `slices` points in `duration`: evenly
+//! for TWAP, in proportion to a historical intraday volume profile for VWAP.
+//! Times and sizes can be randomized so the schedule is harder to spot, and a
+//! participation cap keeps what was sent below a share of the market volume
+//! seen since the start.
+//!
+//! `AlgoScheduler` only decides how much is due; `OrderManager` polls it,
+//! sends the children through the router and reports volume. The schedule
+//! runs on active time, so a pause pushes the rest of it back.
+
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::order_router::{Order, OrderType};
+use crate::market::providers::Candle;
+
+const DAY_MS: i64 = 86_400_000;
+
+/// Quantities below this are not worth a child order
+const MIN_CHILD: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AlgoKind {
+    Twap,
+    Vwap,
+}
+
+impl AlgoKind {
+    pub fn of(order_type: &OrderType) -> Option<AlgoKind> {
+        match order_type {
+            OrderType::TWAP => Some(AlgoKind::Twap),
+            OrderType::VWAP => Some(AlgoKind::Vwap),
+            _ => None,
+        }
+    }
+}
+
+/// How a parent is worked
+#[derive(Clone, Debug)]
+pub struct AlgoSpec {
+    pub kind: AlgoKind,
+    pub duration_ms: u64,
+    pub slices: u32,
+    /// 0.0 to 1.0: each slice time moves up to this share of the slice
+    /// interval, each slice size up to this share of itself
+    pub randomize: f64,
+    /// Max share of market volume since the start, e.g. 0.1 for 10%
+    pub max_participation: Option<f64>,
+    /// VWAP slice weights; uniform when missing
+    pub volume_profile: Option<Vec<f64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlgoState {
+    Running,
+    Paused,
+    Completed,
+    Cancelled,
+    Expired, // Duration over and the participation cap kept the rest back
+}
+
+/// What `poll` wants done
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlgoAction {
+    /// Send a child order for `quantity`
+    Child { parent_id: u64, quantity: f64 },
+    /// Stop working the parent; what is not filled stays unfilled
+    Expired { parent_id: u64 },
+}
+
+/// Schedule position of one algo, for the API
+#[derive(Clone, Debug, Serialize)]
+pub struct AlgoStatus {
+    pub parent_id: u64,
+    pub symbol: String,
+    pub kind: AlgoKind,
+    pub state: AlgoState,
+    pub elapsed_ms: u64, // Active time, pauses excluded
+    pub duration_ms: u64,
+    pub slices_due: usize,
+    pub slices: usize,
+    pub scheduled_quantity: f64, // Due by now
+    pub market_volume: f64,      // Seen since the start
+}
+
+/// One point of the schedule: by `offset_ms` of active time, `cumulative` of the parent is due
+#[derive(Clone, Debug)]
+struct Slice {
+    offset_ms: u64,
+    cumulative: f64,
+}
+
+struct Algo {
+    symbol: String,
+    kind: AlgoKind,
+    quantity: f64,
+    duration_ms: u64,
+    slices: Vec<Slice>,
+    max_participation: Option<f64>,
+    state: AlgoState,
+    active_ms: u64,
+    last_poll: u64,
+    market_volume: f64,
+}
+
+impl Algo {
+    fn slices_due(&self) -> usize {
+        self.slices.iter().take_while(|s| s.offset_ms <= self.active_ms).count()
+    }
+
+    fn scheduled(&self) -> f64 {
+        match self.slices_due() {
+            0 => 0.0,
+            n => self.slices[n - 1].cumulative * self.quantity,
+        }
+    }
+
+    fn status(&self, parent_id: u64) -> AlgoStatus {
+        AlgoStatus {
+            parent_id,
+            symbol: self.symbol.clone(),
+            kind: self.kind,
+            state: self.state,
+            elapsed_ms: self.active_ms,
+            duration_ms: self.duration_ms,
+            slices_due: self.slices_due(),
+            slices: self.slices.len(),
+            scheduled_quantity: self.scheduled(),
+            market_volume: self.market_volume,
+        }
+    }
+}
+
+/// Build the schedule: slice `i` nominally at `i * interval`, jittered and
+/// resized by up to `randomize`, sizes normalized to add up to 1
+fn schedule(spec: &AlgoSpec, rng: &mut impl Rng) -> Vec<Slice> {
+    let count = spec.slices.max(1) as usize;
+    let interval = spec.duration_ms as f64 / count as f64;
+    let randomize = spec.randomize.clamp(0.0, 1.0);
+
+    let base: Vec<f64> = match (&spec.kind, &spec.volume_profile) {
+        (AlgoKind::Vwap, Some(profile)) if profile.len() == count && profile.iter().sum::<f64>() > 0.0 => {
+            profile.iter().map(|w| w.max(0.0)).collect()
+        }
+        _ => vec![1.0; count],
+    };
+    let weights: Vec<f64> = base
+        .iter()
+        .map(|w| w * (1.0 + randomize * rng.gen_range(-1.0..=1.0)))
+        .collect();
+    let total: f64 = weights.iter().sum();
+
+    let mut offsets: Vec<u64> = (0..count)
+        .map(|i| {
+            let jitter = if i == 0 { 0.0 } else { randomize * interval * rng.gen_range(-0.5..=0.5) };
+            (i as f64 * interval + jitter).clamp(0.0, spec.duration_ms as f64) as u64
+        })
+        .collect();
+    offsets.sort_unstable();
+
+    let mut cumulative = 0.0;
+    offsets
+        .into_iter()
+        .zip(weights)
+        .map(|(offset_ms, weight)| {
+            cumulative += weight / total;
+            Slice { offset_ms, cumulative: cumulative.min(1.0) }
+        })
+        .collect()
+}
+
+/// Running TWAP/VWAP parents by id
+#[derive(Default)]
+pub struct AlgoScheduler {
+    algos: Mutex<HashMap<u64, Algo>>,
+}
+
+impl AlgoScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start working `parent` at `now_ms`
+    pub fn start(&self, parent: &Order, spec: AlgoSpec, now_ms: u64, rng: &mut impl Rng) {
+        let algo = Algo {
+            symbol: parent.symbol.clone(),
+            kind: spec.kind,
+            quantity: parent.quantity,
+            duration_ms: spec.duration_ms,
+            slices: schedule(&spec, rng),
+            max_participation: spec.max_participation.filter(|p| *p > 0.0),
+            state: AlgoState::Running,
+            active_ms: 0,
+            last_poll: now_ms,
+            market_volume: 0.0,
+        };
+        self.algos.lock().insert(parent.id, algo);
+    }
+
+    /// Traded volume on `symbol`, counted towards participation caps
+    pub fn on_volume(&self, symbol: &str, volume: f64) {
+        if volume <= 0.0 {
+            return;
+        }
+        for algo in self.algos.lock().values_mut().filter(|a| a.symbol == symbol && a.state == AlgoState::Running) {
+            algo.market_volume += volume;
+        }
+    }
+
+    /// Advance every running algo to `now_ms`. `committed` is what a parent
+    /// already has filled or working in children; whatever the schedule has
+    /// due beyond it, within the participation cap, becomes a child.
+    pub fn poll(&self, now_ms: u64, committed: impl Fn(u64) -> f64) -> Vec<AlgoAction> {
+        let mut actions = Vec::new();
+        for (&parent_id, algo) in self.algos.lock().iter_mut() {
+            // Callers on other threads may pass a slightly older time
+            let elapsed = now_ms.saturating_sub(algo.last_poll);
+            algo.last_poll = algo.last_poll.max(now_ms);
+            if algo.state != AlgoState::Running {
+                continue;
+            }
+            algo.active_ms += elapsed;
+
+            let committed = committed(parent_id);
+            if committed >= algo.quantity - MIN_CHILD {
+                continue;
+            }
+            let due = algo.scheduled() - committed;
+            let allowed = match algo.max_participation {
+                Some(cap) => (cap * algo.market_volume - committed).max(0.0),
+                None => f64::INFINITY,
+            };
+            let quantity = due.min(allowed);
+            if quantity > MIN_CHILD {
+                actions.push(AlgoAction::Child { parent_id, quantity });
+            }
+
+            // Schedule done, cap holding the rest back
+            if algo.active_ms >= algo.duration_ms && quantity < algo.quantity - committed - MIN_CHILD {
+                algo.state = AlgoState::Expired;
+                actions.push(AlgoAction::Expired { parent_id });
+            }
+        }
+        actions
+    }
+
+    pub fn pause(&self, parent_id: u64) -> bool {
+        self.transition(parent_id, AlgoState::Running, AlgoState::Paused)
+    }
+
+    pub fn resume(&self, parent_id: u64) -> bool {
+        self.transition(parent_id, AlgoState::Paused, AlgoState::Running)
+    }
+
+    /// Stop scheduling; the caller cancels working children
+    pub fn cancel(&self, parent_id: u64) -> bool {
+        self.finish(parent_id, AlgoState::Cancelled)
+    }
+
+    /// Move a running or paused algo to a final state
+    pub fn finish(&self, parent_id: u64, state: AlgoState) -> bool {
+        let mut algos = self.algos.lock();
+        match algos.get_mut(&parent_id) {
+            Some(algo) if matches!(algo.state, AlgoState::Running | AlgoState::Paused) => {
+                algo.state = state;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn status(&self, parent_id: u64) -> Option<AlgoStatus> {
+        self.algos.lock().get(&parent_id).map(|algo| algo.status(parent_id))
+    }
+
+    fn transition(&self, parent_id: u64, from: AlgoState, to: AlgoState) -> bool {
+        let mut algos = self.algos.lock();
+        match algos.get_mut(&parent_id) {
+            Some(algo) if algo.state == from => {
+                algo.state = to;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// VWAP weights for `slices` equal windows of `duration_ms` from `start_ms`:
+/// the average candle volume at the same time of day in `candles`. Windows
+/// without history get the average of the others; all zeros if there is none.
+pub fn volume_profile(candles: &[Candle], start_ms: i64, duration_ms: u64, slices: u32) -> Vec<f64> {
+    let count = slices.max(1) as usize;
+    let interval = (duration_ms / count as u64).max(1) as i64;
+    let mut sums = vec![(0.0, 0usize); count];
+
+    for candle in candles {
+        let time_of_day = candle.timestamp.rem_euclid(DAY_MS);
+        let offset = (time_of_day - start_ms.rem_euclid(DAY_MS)).rem_euclid(DAY_MS);
+        let slot = (offset / interval) as usize;
+        if slot < count {
+            sums[slot].0 += candle.volume;
+            sums[slot].1 += 1;
+        }
+    }
+
+    let averages: Vec<Option<f64>> = sums.iter().map(|&(sum, n)| (n > 0).then(|| sum / n as f64)).collect();
+    let known: Vec<f64> = averages.iter().flatten().copied().collect();
+    let fill = if known.is_empty() { 0.0 } else { known.iter().sum::<f64>() / known.len() as f64 };
+    averages.into_iter().map(|a| a.unwrap_or(fill)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::OrderSide;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn parent(quantity: f64) -> Order {
+        Order::new(1, "BTCUSDT".to_string(), OrderSide::Buy, OrderType::TWAP, quantity, None)
+    }
+
+    fn spec(kind: AlgoKind, randomize: f64, max_participation: Option<f64>) -> AlgoSpec {
+        AlgoSpec {
+            kind,
+            duration_ms: 4_000,
+            slices: 4,
+            randomize,
+            max_participation,
+            volume_profile: None,
+        }
+    }
+
+    fn children(actions: &[AlgoAction]) -> f64 {
+        actions
+            .iter()
+            .map(|a| match a {
+                AlgoAction::Child { quantity, .. } => *quantity,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_twap_schedule_and_pause() {
+        let scheduler = AlgoScheduler::new();
+        let mut rng = StdRng::seed_from_u64(7);
+        scheduler.start(&parent(8.0), spec(AlgoKind::Twap, 0.0, None), 0, &mut rng);
+
+        // First slice at once, then one every second; committed quantity is not sent again
+        assert_eq!(children(&scheduler.poll(0, |_| 0.0)), 2.0);
+        assert_eq!(children(&scheduler.poll(500, |_| 2.0)), 0.0);
+        assert_eq!(children(&scheduler.poll(1_000, |_| 2.0)), 2.0);
+
+        // Paused time does not count
+        assert!(scheduler.pause(1));
+        assert!(scheduler.poll(5_000, |_| 4.0).is_empty());
+        assert!(scheduler.resume(1));
+        assert_eq!(children(&scheduler.poll(5_500, |_| 4.0)), 0.0);
+        assert_eq!(children(&scheduler.poll(6_000, |_| 4.0)), 2.0);
+        assert_eq!(scheduler.status(1).unwrap().elapsed_ms, 2_000);
+
+        assert!(scheduler.cancel(1));
+        assert!(scheduler.poll(9_000, |_| 6.0).is_empty());
+        assert_eq!(scheduler.status(1).unwrap().state, AlgoState::Cancelled);
+    }
+
+    #[test]
+    fn test_randomized_schedule_adds_up() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let slices = schedule(&spec(AlgoKind::Twap, 0.5, None), &mut rng);
+        assert_eq!(slices.len(), 4);
+        assert!((slices[3].cumulative - 1.0).abs() < 1e-9);
+        assert!(slices.windows(2).all(|w| w[0].offset_ms <= w[1].offset_ms && w[0].cumulative < w[1].cumulative));
+        assert!(slices.iter().all(|s| s.offset_ms <= 4_000));
+    }
+
+    #[test]
+    fn test_vwap_profile_and_participation() {
+        // History: twice the volume in the second hour of the window
+        let candle = |hour: i64, volume: f64| Candle {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: hour * 3_600_000,
+            open: 1.0,
+            high: 1.0,
+            low: 1.0,
+            close: 1.0,
+            volume,
+        };
+        let candles = [candle(0, 100.0), candle(1, 200.0), candle(24, 100.0), candle(25, 200.0)];
+        let profile = volume_profile(&candles, 2 * DAY_MS, 3 * 3_600_000, 3);
+        assert_eq!(profile, vec![100.0, 200.0, 150.0]);
+
+        let scheduler = AlgoScheduler::new();
+        let mut rng = StdRng::seed_from_u64(1);
+        let vwap = AlgoSpec { volume_profile: Some(vec![1.0, 3.0, 0.0, 0.0]), ..spec(AlgoKind::Vwap, 0.0, Some(0.5)) };
+        scheduler.start(&parent(8.0), vwap, 0, &mut rng);
+
+        // 2 due, but half of 2 traded is only 1
+        scheduler.on_volume("BTCUSDT", 2.0);
+        assert_eq!(scheduler.poll(0, |_| 0.0), vec![AlgoAction::Child { parent_id: 1, quantity: 1.0 }]);
+        scheduler.on_volume("BTCUSDT", 100.0);
+        assert_eq!(children(&scheduler.poll(1_000, |_| 1.0)), 7.0);
+
+        // Past the end with the cap still binding
+        let scheduler = AlgoScheduler::new();
+        scheduler.start(&parent(8.0), spec(AlgoKind::Twap, 0.0, Some(0.1)), 0, &mut rng);
+        scheduler.on_volume("BTCUSDT", 10.0);
+        let actions = scheduler.poll(4_000, |_| 0.0);
+        assert_eq!(actions, vec![
+            AlgoAction::Child { parent_id: 1, quantity: 1.0 },
+            AlgoAction::Expired { parent_id: 1 },
+        ]);
+        assert_eq!(scheduler.status(1).unwrap().state, AlgoState::Expired);
+    }
+}
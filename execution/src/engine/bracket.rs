@@ -0,0 +1,330 @@
+//! Bracket orders: an entry with a protective stop and up to three take profits
+//!
+//! The exits go out once the entry has filled: one stop for the whole position
+//! and one limit per take profit, sized by `tp_splits`. They form one
+//! one-cancels-other group (`Order::oco_group`, the entry's id): the stop
+//! filling cancels the take profits, the last take profit cancels the stop,
+//! and each one before it shrinks the stop to what is still open. With
+//! `breakeven_after_tp1` the stop also moves to the average entry price once
+//! TP1 has filled.
+
+use serde::{Deserialize, Serialize};
+
+use super::order_router::OrderSide;
+use crate::signal::{EnhancedSignal, SignalDirection};
+
+/// Position left below this counts as closed
+const MIN_OPEN: f64 = 1e-9;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BracketConfig {
+    /// Share of the position each take profit closes, TP1 first
+    pub tp_splits: Vec<f64>,
+    pub breakeven_after_tp1: bool,
+}
+
+impl Default for BracketConfig {
+    fn default() -> Self {
+        Self {
+            tp_splits: vec![0.5, 0.3, 0.2],
+            breakeven_after_tp1: false,
+        }
+    }
+}
+
+/// Everything needed to open a bracket
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BracketRequest {
+    pub entry_id: u64, // 0 to assign one
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    /// Limit entry; None enters at market
+    pub entry_price: Option<f64>,
+    pub stop_price: f64,
+    pub take_profits: Vec<f64>,
+    #[serde(default)]
+    pub config: BracketConfig,
+    /// Signal the bracket trades, if any
+    #[serde(default)]
+    pub signal_id: Option<String>,
+}
+
+impl BracketRequest {
+    /// Limit entry at the signal's entry, stop at its stop loss, TP1-TP3 as take profits
+    pub fn from_signal(signal: &EnhancedSignal, quantity: f64, config: BracketConfig) -> Result<Self, BracketError> {
+        let side = match signal.direction {
+            SignalDirection::Buy => OrderSide::Buy,
+            SignalDirection::Sell => OrderSide::Sell,
+            SignalDirection::Neutral => return Err(BracketError::NoDirection),
+        };
+        let targets = &signal.targets;
+        let request = Self {
+            entry_id: 0,
+            symbol: signal.symbol.clone(),
+            side,
+            quantity,
+            entry_price: Some(targets.entry),
+            stop_price: targets.stop_loss,
+            take_profits: vec![targets.take_profit_1, targets.take_profit_2, targets.take_profit_3],
+            config,
+            signal_id: Some(signal.id.clone()),
+        };
+        request.validate()?;
+        Ok(request)
+    }
+
+    /// Stop on the losing side of the entry, take profits on the winning side
+    /// in order, and a positive split for each of them
+    pub fn validate(&self) -> Result<(), BracketError> {
+        if self.quantity <= 0.0 {
+            return Err(BracketError::InvalidQuantity);
+        }
+        if self.take_profits.is_empty() || self.take_profits.len() > 3 {
+            return Err(BracketError::InvalidTakeProfit(self.take_profits.len()));
+        }
+        let splits = &self.config.tp_splits;
+        if splits.len() < self.take_profits.len() || splits[..self.take_profits.len()].iter().any(|s| *s <= 0.0) {
+            return Err(BracketError::InvalidSplits);
+        }
+
+        // Positive means in the position's favour
+        let gain = |from: f64, to: f64| match self.side {
+            OrderSide::Buy => to - from,
+            OrderSide::Sell => from - to,
+        };
+        if let Some(entry) = self.entry_price {
+            if gain(entry, self.stop_price) >= 0.0 {
+                return Err(BracketError::InvalidStop);
+            }
+        }
+        let mut previous = self.entry_price.unwrap_or(self.stop_price);
+        for (i, tp) in self.take_profits.iter().enumerate() {
+            if gain(previous, *tp) <= 0.0 || gain(self.stop_price, *tp) <= 0.0 {
+                return Err(BracketError::InvalidTakeProfit(i + 1));
+            }
+            previous = *tp;
+        }
+        Ok(())
+    }
+
+    /// Take profit sizes for a filled entry of `filled`; the last one takes
+    /// the rounding rest so they add up exactly
+    pub fn tp_quantities(&self, filled: f64) -> Vec<f64> {
+        let count = self.take_profits.len();
+        let splits = &self.config.tp_splits[..count];
+        let total: f64 = splits.iter().sum();
+        let mut quantities: Vec<f64> = splits.iter().map(|s| filled * s / total).collect();
+        quantities[count - 1] = filled - quantities[..count - 1].iter().sum::<f64>();
+        quantities
+    }
+
+    pub fn exit_side(&self) -> OrderSide {
+        match self.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BracketState {
+    PendingEntry,
+    Open,      // Entry filled, exits working
+    Closed,    // Stop or the last take profit filled
+    Cancelled, // Before the entry filled
+}
+
+/// A bracket and the orders working it
+#[derive(Clone, Debug, Serialize)]
+pub struct Bracket {
+    pub entry_id: u64,
+    pub request: BracketRequest,
+    pub state: BracketState,
+    pub entry_filled: f64,
+    pub entry_cost: f64,
+    pub stop_id: Option<u64>,
+    pub take_profit_ids: Vec<u64>,
+    pub take_profit_filled: f64,
+    pub stop_price: f64,  // Current trigger, moved to breakeven after TP1 if configured
+    pub stop_moved: bool, // Moved to breakeven
+}
+
+/// What a fill on one of the bracket's orders requires
+#[derive(Clone, Debug, PartialEq)]
+pub enum BracketAction {
+    /// Entry filled: send the stop and take profits
+    PlaceExits,
+    /// Position closed: cancel these
+    Cancel(Vec<u64>),
+    /// Resize or move the stop
+    AmendStop { stop_id: u64, quantity: f64, stop_price: f64 },
+    None,
+}
+
+impl Bracket {
+    pub fn new(entry_id: u64, request: BracketRequest) -> Self {
+        Self {
+            entry_id,
+            stop_price: request.stop_price,
+            request,
+            state: BracketState::PendingEntry,
+            entry_filled: 0.0,
+            entry_cost: 0.0,
+            stop_id: None,
+            take_profit_ids: Vec::new(),
+            take_profit_filled: 0.0,
+            stop_moved: false,
+        }
+    }
+
+    pub fn avg_entry_price(&self) -> Option<f64> {
+        (self.entry_filled > 0.0).then(|| self.entry_cost / self.entry_filled)
+    }
+
+    /// Position still held
+    pub fn open_quantity(&self) -> f64 {
+        match self.state {
+            BracketState::Open => (self.entry_filled - self.take_profit_filled).max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Book a fill on order `order_id` and say what has to happen next.
+    /// `entry_done` is whether the entry order has now fully filled,
+    /// `tp1_done` whether the first take profit has.
+    pub fn on_fill(&mut self, order_id: u64, quantity: f64, price: f64, entry_done: bool, tp1_done: bool) -> BracketAction {
+        if order_id == self.entry_id {
+            self.entry_filled += quantity;
+            self.entry_cost += quantity * price;
+            if entry_done && self.state == BracketState::PendingEntry {
+                self.state = BracketState::Open;
+                return BracketAction::PlaceExits;
+            }
+            return BracketAction::None;
+        }
+        if self.state != BracketState::Open {
+            return BracketAction::None;
+        }
+
+        if self.stop_id == Some(order_id) {
+            self.state = BracketState::Closed;
+            return BracketAction::Cancel(self.take_profit_ids.clone());
+        }
+        if !self.take_profit_ids.contains(&order_id) {
+            return BracketAction::None;
+        }
+        self.take_profit_filled += quantity;
+        let Some(stop_id) = self.stop_id else {
+            return BracketAction::None;
+        };
+        let open = self.entry_filled - self.take_profit_filled;
+        if open <= MIN_OPEN {
+            self.state = BracketState::Closed;
+            return BracketAction::Cancel(vec![stop_id]);
+        }
+        if tp1_done && self.request.config.breakeven_after_tp1 && !self.stop_moved {
+            if let Some(breakeven) = self.avg_entry_price() {
+                self.stop_price = breakeven;
+                self.stop_moved = true;
+            }
+        }
+        BracketAction::AmendStop { stop_id, quantity: open, stop_price: self.stop_price }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BracketError {
+    NoDirection,
+    InvalidQuantity,
+    InvalidStop,
+    InvalidTakeProfit(usize),
+    InvalidSplits,
+    NotFound,
+}
+
+impl std::fmt::Display for BracketError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BracketError::NoDirection => write!(f, "Neutral signal has no side to trade"),
+            BracketError::InvalidQuantity => write!(f, "Quantity must be positive"),
+            BracketError::InvalidStop => write!(f, "Stop must be on the losing side of the entry"),
+            BracketError::InvalidTakeProfit(0) => write!(f, "One to three take profits required"),
+            BracketError::InvalidTakeProfit(n) if *n > 3 => write!(f, "One to three take profits required"),
+            BracketError::InvalidTakeProfit(n) => {
+                write!(f, "TP{} must be beyond the entry and the previous take profit", n)
+            }
+            BracketError::InvalidSplits => write!(f, "Each take profit needs a positive size split"),
+            BracketError::NotFound => write!(f, "Bracket not found"),
+        }
+    }
+}
+
+impl std::error::Error for BracketError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(take_profits: Vec<f64>) -> BracketRequest {
+        BracketRequest {
+            entry_id: 0,
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            quantity: 10.0,
+            entry_price: Some(100.0),
+            stop_price: 95.0,
+            take_profits,
+            config: BracketConfig { breakeven_after_tp1: true, ..Default::default() },
+            signal_id: None,
+        }
+    }
+
+    #[test]
+    fn test_from_signal() {
+        let signal = EnhancedSignal::new("BTCUSDT".to_string(), "15m".to_string(), SignalDirection::Sell, 100.0, 104.0);
+        let bracket = BracketRequest::from_signal(&signal, 3.0, BracketConfig::default()).unwrap();
+        assert_eq!((bracket.side, bracket.entry_price, bracket.stop_price), (OrderSide::Sell, Some(100.0), 104.0));
+        assert_eq!(bracket.take_profits, vec![
+            signal.targets.take_profit_1,
+            signal.targets.take_profit_2,
+            signal.targets.take_profit_3,
+        ]);
+        assert_eq!(bracket.signal_id, Some(signal.id.clone()));
+
+        let neutral = EnhancedSignal::new("BTCUSDT".to_string(), "15m".to_string(), SignalDirection::Neutral, 100.0, 104.0);
+        assert_eq!(BracketRequest::from_signal(&neutral, 3.0, BracketConfig::default()).unwrap_err(), BracketError::NoDirection);
+    }
+
+    #[test]
+    fn test_validation_and_splits() {
+        assert!(request(vec![110.0, 120.0]).validate().is_ok());
+        assert_eq!(request(vec![120.0, 110.0]).validate(), Err(BracketError::InvalidTakeProfit(2)));
+        assert_eq!(request(vec![]).validate(), Err(BracketError::InvalidTakeProfit(0)));
+        assert_eq!(BracketRequest { stop_price: 101.0, ..request(vec![110.0]) }.validate(), Err(BracketError::InvalidStop));
+
+        // Two take profits use the first two splits, scaled to the whole fill
+        let quantities = request(vec![110.0, 120.0]).tp_quantities(8.0);
+        assert_eq!(quantities, vec![5.0, 3.0]);
+        assert_eq!(request(vec![110.0, 120.0, 130.0]).tp_quantities(10.0), vec![5.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_fills() {
+        let mut bracket = Bracket::new(1, request(vec![110.0, 120.0, 130.0]));
+        assert_eq!(bracket.on_fill(1, 4.0, 100.0, false, false), BracketAction::None);
+        assert_eq!(bracket.on_fill(1, 6.0, 101.0, true, false), BracketAction::PlaceExits);
+        bracket.stop_id = Some(2);
+        bracket.take_profit_ids = vec![3, 4, 5];
+
+        // TP1 shrinks the stop and moves it to the average entry
+        assert_eq!(
+            bracket.on_fill(3, 5.0, 110.0, false, true),
+            BracketAction::AmendStop { stop_id: 2, quantity: 5.0, stop_price: 100.6 }
+        );
+        assert_eq!(bracket.open_quantity(), 5.0);
+        assert_eq!(bracket.on_fill(2, 5.0, 100.6, false, true), BracketAction::Cancel(vec![3, 4, 5]));
+        assert_eq!((bracket.state, bracket.open_quantity()), (BracketState::Closed, 0.0));
+    }
+}
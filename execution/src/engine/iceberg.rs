@@ -0,0 +1,79 @@
+//! Iceberg orders: only a slice of the parent is at the venue at a time
+//!
+//! The parent stays in the router; `OrderManager` sends a child of about
+//! `visible_quantity` and, once it has filled, the next one, until the parent
+//! is done. Slice sizes and prices can be randomized so the refills are harder
+//! to spot; prices only move away from the market, never past the parent's limit.
+
+use rand::Rng;
+
+use super::order_router::{Order, OrderSide};
+
+/// Less than this left is not worth another slice
+const MIN_SLICE: f64 = 1e-9;
+
+/// How an iceberg parent is shown
+#[derive(Clone, Debug)]
+pub struct IcebergSpec {
+    pub visible_quantity: f64,
+    /// 0.0 to 0.9: each slice is up to this share bigger or smaller
+    pub randomize: f64,
+    /// Each slice's limit is up to this many bps behind the parent's
+    pub price_jitter_bps: f64,
+}
+
+/// The next child to show
+#[derive(Clone, Debug, PartialEq)]
+pub struct IcebergSlice {
+    pub quantity: f64,
+    pub price: Option<f64>,
+}
+
+impl IcebergSpec {
+    /// Next child for `parent`, None once nothing is left
+    pub fn next_slice(&self, parent: &Order, rng: &mut impl Rng) -> Option<IcebergSlice> {
+        let remaining = parent.remaining_quantity();
+        if remaining <= MIN_SLICE {
+            return None;
+        }
+
+        let randomize = self.randomize.clamp(0.0, 0.9);
+        let size = self.visible_quantity * (1.0 + randomize * rng.gen_range(-1.0..=1.0));
+        // Take the rest rather than leave a sliver behind
+        let quantity = if remaining - size <= MIN_SLICE { remaining } else { size };
+
+        let jitter = self.price_jitter_bps.max(0.0) / 10_000.0 * rng.gen_range(0.0..=1.0);
+        let price = parent.price.map(|limit| match parent.side {
+            OrderSide::Buy => limit * (1.0 - jitter),
+            OrderSide::Sell => limit * (1.0 + jitter),
+        });
+        Some(IcebergSlice { quantity, price })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::OrderType;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_slices() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut parent = Order::new(1, "BTCUSDT".to_string(), OrderSide::Sell, OrderType::Iceberg, 10.0, Some(100.0));
+        let spec = IcebergSpec { visible_quantity: 2.0, randomize: 0.25, price_jitter_bps: 50.0 };
+
+        for _ in 0..20 {
+            let slice = spec.next_slice(&parent, &mut rng).unwrap();
+            assert!((1.5..=2.5).contains(&slice.quantity));
+            assert!((100.0..=100.5).contains(&slice.price.unwrap()));
+        }
+
+        // The last slice is whatever is left
+        parent.filled_quantity = 9.0;
+        assert_eq!(spec.next_slice(&parent, &mut rng).unwrap().quantity, 1.0);
+        parent.filled_quantity = 10.0;
+        assert!(spec.next_slice(&parent, &mut rng).is_none());
+    }
+}
@@ -0,0 +1,308 @@
+//! Priority lanes for engine messages
+//!
+//! Each message class has its own bounded queue and workers always take from
+//! the most urgent non-empty lane, so a cancel or kill switch never waits
+//! behind a burst of market data:
+//!
+//! 1. `Risk` - kill switch and shutdown
+//! 2. `Cancel` - cancels and amends
+//! 3. `NewOrder`
+//! 4. `MarketData` - optionally conflated to the latest update per symbol
+//!
+//! Lower lanes only make progress while the ones above are empty.
+
+use crossbeam::queue::ArrayQueue;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::quantum_engine::{EngineError, EngineMessage, MarketUpdate};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lane {
+    Risk,
+    Cancel,
+    NewOrder,
+    MarketData,
+}
+
+impl Lane {
+    /// Most urgent first
+    pub const ALL: [Lane; 4] = [Lane::Risk, Lane::Cancel, Lane::NewOrder, Lane::MarketData];
+
+    pub fn of(msg: &EngineMessage) -> Lane {
+        match msg {
+            EngineMessage::KillSwitch(_) | EngineMessage::Shutdown => Lane::Risk,
+            EngineMessage::CancelOrder(_) | EngineMessage::ModifyOrder(..) => Lane::Cancel,
+            EngineMessage::NewOrder(_) | EngineMessage::NewBracket(_) => Lane::NewOrder,
+            EngineMessage::MarketData(_) => Lane::MarketData,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How queued market updates are kept
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Conflation {
+    /// Every update, in order
+    None,
+    /// Only the newest update per symbol, in the order symbols first arrived
+    LatestPerSymbol,
+}
+
+/// Queue bound of each lane
+#[derive(Clone, Copy, Debug)]
+pub struct LaneCapacity {
+    pub risk: usize,
+    pub cancel: usize,
+    pub new_order: usize,
+    pub market_data: usize, // Symbols when conflated, updates otherwise
+}
+
+impl LaneCapacity {
+    /// Same bound for every lane
+    pub fn uniform(capacity: usize) -> Self {
+        Self {
+            risk: capacity,
+            cancel: capacity,
+            new_order: capacity,
+            market_data: capacity,
+        }
+    }
+
+    fn of(&self, lane: Lane) -> usize {
+        match lane {
+            Lane::Risk => self.risk,
+            Lane::Cancel => self.cancel,
+            Lane::NewOrder => self.new_order,
+            Lane::MarketData => self.market_data,
+        }
+        .max(1)
+    }
+}
+
+impl Default for LaneCapacity {
+    fn default() -> Self {
+        Self {
+            risk: 1024,
+            cancel: 16384,
+            new_order: 16384,
+            market_data: 65536,
+        }
+    }
+}
+
+/// Counters of one lane
+#[derive(Debug, Clone, Serialize)]
+pub struct LaneStats {
+    pub lane: Lane,
+    pub depth: usize,
+    pub capacity: usize,
+    pub submitted: u64,
+    pub processed: u64,
+    pub rejected: u64,  // Lane was full
+    pub conflated: u64, // Market updates replaced by a newer one before processing
+}
+
+/// Latest update per symbol, oldest symbol first
+#[derive(Default)]
+struct ConflatedQueue {
+    order: VecDeque<String>,
+    latest: HashMap<String, MarketUpdate>,
+}
+
+enum LaneQueue {
+    Fifo(Box<ArrayQueue<EngineMessage>>), // Cache-padded, so boxed
+    Conflated(Mutex<ConflatedQueue>),
+}
+
+struct LaneState {
+    queue: LaneQueue,
+    capacity: usize,
+    submitted: AtomicU64,
+    processed: AtomicU64,
+    rejected: AtomicU64,
+    conflated: AtomicU64,
+}
+
+impl LaneState {
+    fn new(queue: LaneQueue, capacity: usize) -> Self {
+        Self {
+            queue,
+            capacity,
+            submitted: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            conflated: AtomicU64::new(0),
+        }
+    }
+
+    fn push(&self, msg: EngineMessage) -> Result<(), EngineError> {
+        let pushed = match (&self.queue, msg) {
+            (LaneQueue::Conflated(queue), EngineMessage::MarketData(update)) => {
+                let mut queue = queue.lock();
+                if let Some(queued) = queue.latest.get_mut(&update.symbol) {
+                    *queued = update;
+                    self.conflated.fetch_add(1, Ordering::Relaxed);
+                    true
+                } else if queue.order.len() < self.capacity {
+                    queue.order.push_back(update.symbol.clone());
+                    queue.latest.insert(update.symbol.clone(), update);
+                    true
+                } else {
+                    false
+                }
+            }
+            (LaneQueue::Fifo(queue), msg) => queue.push(msg).is_ok(),
+            (LaneQueue::Conflated(_), _) => false,
+        };
+
+        if pushed {
+            self.submitted.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        } else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            Err(EngineError::QueueFull)
+        }
+    }
+
+    fn pop(&self) -> Option<EngineMessage> {
+        let msg = match &self.queue {
+            LaneQueue::Fifo(queue) => queue.pop(),
+            LaneQueue::Conflated(queue) => {
+                let mut queue = queue.lock();
+                let symbol = queue.order.pop_front()?;
+                queue.latest.remove(&symbol).map(EngineMessage::MarketData)
+            }
+        }?;
+        self.processed.fetch_add(1, Ordering::Relaxed);
+        Some(msg)
+    }
+
+    fn len(&self) -> usize {
+        match &self.queue {
+            LaneQueue::Fifo(queue) => queue.len(),
+            LaneQueue::Conflated(queue) => queue.lock().order.len(),
+        }
+    }
+}
+
+/// The engine's message queues, one per lane
+pub struct Lanes {
+    lanes: [LaneState; 4],
+}
+
+impl Lanes {
+    pub fn new(capacity: LaneCapacity, conflation: Conflation) -> Self {
+        let lane = |lane: Lane| {
+            let capacity = capacity.of(lane);
+            let queue = match (lane, conflation) {
+                (Lane::MarketData, Conflation::LatestPerSymbol) => {
+                    LaneQueue::Conflated(Mutex::new(ConflatedQueue::default()))
+                }
+                _ => LaneQueue::Fifo(Box::new(ArrayQueue::new(capacity))),
+            };
+            LaneState::new(queue, capacity)
+        };
+        Self {
+            lanes: Lane::ALL.map(lane),
+        }
+    }
+
+    /// Queue on the message's lane; `QueueFull` only if that lane is full
+    pub fn push(&self, msg: EngineMessage) -> Result<(), EngineError> {
+        self.lanes[Lane::of(&msg).index()].push(msg)
+    }
+
+    /// Next message from the most urgent non-empty lane
+    pub fn pop(&self) -> Option<EngineMessage> {
+        self.lanes.iter().find_map(|lane| lane.pop())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lanes.iter().map(|lane| lane.capacity).sum()
+    }
+
+    pub fn stats(&self) -> Vec<LaneStats> {
+        Lane::ALL
+            .iter()
+            .zip(&self.lanes)
+            .map(|(lane, state)| LaneStats {
+                lane: *lane,
+                depth: state.len(),
+                capacity: state.capacity,
+                submitted: state.submitted.load(Ordering::Relaxed),
+                processed: state.processed.load(Ordering::Relaxed),
+                rejected: state.rejected.load(Ordering::Relaxed),
+                conflated: state.conflated.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(symbol: &str, price: f64) -> EngineMessage {
+        EngineMessage::MarketData(MarketUpdate {
+            symbol: symbol.to_string(),
+            price,
+            volume: 0.0,
+            timestamp: 0,
+        })
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let lanes = Lanes::new(LaneCapacity::uniform(8), Conflation::None);
+        lanes.push(tick("BTCUSDT", 1.0)).unwrap();
+        lanes.push(EngineMessage::CancelOrder(1)).unwrap();
+        lanes.push(EngineMessage::KillSwitch("test".to_string())).unwrap();
+        lanes.push(EngineMessage::ModifyOrder(2, 10.0)).unwrap();
+
+        let order: Vec<Lane> = std::iter::from_fn(|| lanes.pop()).map(|m| Lane::of(&m)).collect();
+        assert_eq!(order, vec![Lane::Risk, Lane::Cancel, Lane::Cancel, Lane::MarketData]);
+    }
+
+    #[test]
+    fn test_conflation_and_capacity() {
+        let capacity = LaneCapacity { market_data: 2, cancel: 1, ..LaneCapacity::default() };
+        let lanes = Lanes::new(capacity, Conflation::LatestPerSymbol);
+        lanes.push(tick("BTCUSDT", 1.0)).unwrap();
+        lanes.push(tick("ETHUSDT", 2.0)).unwrap();
+        lanes.push(tick("BTCUSDT", 3.0)).unwrap();
+        assert!(matches!(lanes.push(tick("SOLUSDT", 4.0)), Err(EngineError::QueueFull)));
+
+        // A full market data lane does not block cancels, and vice versa
+        lanes.push(EngineMessage::CancelOrder(1)).unwrap();
+        assert!(lanes.push(EngineMessage::CancelOrder(2)).is_err());
+        assert!(matches!(lanes.pop(), Some(EngineMessage::CancelOrder(1))));
+
+        let prices: Vec<(String, f64)> = std::iter::from_fn(|| lanes.pop())
+            .filter_map(|m| match m {
+                EngineMessage::MarketData(u) => Some((u.symbol, u.price)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(prices, vec![("BTCUSDT".to_string(), 3.0), ("ETHUSDT".to_string(), 2.0)]);
+
+        let stats = lanes.stats();
+        let market = &stats[3];
+        assert_eq!((market.submitted, market.processed, market.rejected, market.conflated), (3, 2, 1, 1));
+        assert_eq!(stats[1].rejected, 1);
+    }
+}
@@ -1,7 +1,21 @@
 pub mod quantum_engine;
+pub mod algo;
+pub mod bracket;
+pub mod iceberg;
+pub mod lanes;
 pub mod order_router;
 pub mod risk_manager;
+pub mod routing;
+pub mod triggers;
+pub mod venue;
+pub mod oms;
 
-pub use quantum_engine::{QuantumEngine, EngineMessage, EngineMetrics, EngineError, MessageHandler};
+pub use quantum_engine::{QuantumEngine, EngineMessage, EngineMetrics, EngineError, MessageHandler, OrderParams, OrderRequest, EngineConfig, WaitStrategy, StopMode};
+pub use algo::AlgoStatus;
+pub use bracket::{BracketConfig, BracketRequest};
+pub use lanes::Conflation;
 pub use order_router::{OrderRouter, Order, OrderType, OrderSide, OrderState, ExchangeRoute, RouterError};
+pub use triggers::{Trail, TrailBy};
 pub use risk_manager::{RiskManager, Position, PortfolioMetrics, RiskLimits, CircuitBreakerState, RiskError};
+pub use venue::PaperVenue;
+pub use oms::{default_route, OrderManager};
@@ -0,0 +1,1165 @@
+//! Order management
+//!
+//! `OrderManager` is the engine handler that takes an order from request to
+//! fill: risk check, routing, execution venue, then position and equity
+//! updates from the fills. Every state change goes out on the app bus as an
+//! `OrderEvent` (`orders.<symbol>`) and every execution as a `Fill`
+//! (`fills.<symbol>`); risk rejections also show up as `RiskEvent`s.
+//!
+//! TWAP, VWAP and iceberg orders are not sent anywhere themselves: the parent
+//! is held in the router and worked in child orders. `run_algos` sends what a
+//! TWAP/VWAP schedule has due; an iceberg shows its next slice when one fills.
+//! Brackets send their entry as an ordinary order and the exits once it has
+//! filled; fills on the exits cancel or resize the rest of the group.
+//! Stop, stop-limit and trailing stop orders wait in the trigger book and go
+//! out as market or limit orders when a price update reaches them.
+
+use dashmap::DashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use super::algo::{AlgoAction, AlgoKind, AlgoScheduler, AlgoSpec, AlgoState, AlgoStatus};
+use super::bracket::{Bracket, BracketAction, BracketRequest, BracketState};
+use super::iceberg::IcebergSpec;
+use super::order_router::{
+    ExchangeRoute, Order, OrderRouter, OrderSide, OrderState, OrderType, RouterError, ALGO_EXCHANGE, ICEBERG_EXCHANGE,
+    SPLIT_EXCHANGE, TRIGGER_EXCHANGE,
+};
+use super::quantum_engine::{EngineError, EngineMessage, MarketUpdate, MessageHandler, OrderParams, OrderRequest};
+use super::risk_manager::RiskManager;
+use super::triggers::{Trigger, TriggerBook, Triggered};
+use super::venue::ExecutionVenue;
+use crate::bus::{AppBus, Fill, OrderEvent};
+
+pub struct OrderManager {
+    router: Arc<OrderRouter>,
+    risk: Arc<RiskManager>,
+    venues: RwLock<HashMap<String, Arc<dyn ExecutionVenue>>>,
+    events: AppBus,
+    cash: Mutex<f64>,
+    last_prices: DashMap<String, f64>,
+    last_volumes: DashMap<String, f64>,
+    algos: AlgoScheduler,
+    algo_poll: Mutex<()>, // One `run_algos` at a time, or both would send the same slice
+    icebergs: DashMap<u64, IcebergSpec>,
+    replenishing: Mutex<HashSet<u64>>, // Icebergs in `replenish`, so a fill inside it does not recurse
+    brackets: Mutex<HashMap<u64, Bracket>>, // By entry order id
+    triggers: TriggerBook,
+}
+
+impl OrderManager {
+    pub fn new(router: Arc<OrderRouter>, risk: Arc<RiskManager>, events: AppBus, initial_capital: f64) -> Self {
+        Self {
+            router,
+            risk,
+            venues: RwLock::new(HashMap::new()),
+            events,
+            cash: Mutex::new(initial_capital),
+            last_prices: DashMap::new(),
+            last_volumes: DashMap::new(),
+            algos: AlgoScheduler::new(),
+            algo_poll: Mutex::new(()),
+            icebergs: DashMap::new(),
+            replenishing: Mutex::new(HashSet::new()),
+            brackets: Mutex::new(HashMap::new()),
+            triggers: TriggerBook::new(),
+        }
+    }
+
+    /// Register a venue and the route the router uses to reach it
+    pub fn add_venue(&self, mut route: ExchangeRoute, venue: Arc<dyn ExecutionVenue>) {
+        route.name = venue.name().to_string();
+        self.venues.write().insert(route.name.clone(), venue);
+        self.router.add_route(route);
+    }
+
+    pub fn router(&self) -> &Arc<OrderRouter> {
+        &self.router
+    }
+
+    pub fn risk(&self) -> &Arc<RiskManager> {
+        &self.risk
+    }
+
+    /// Order id for a request submitted to the engine later
+    pub fn next_order_id(&self) -> u64 {
+        self.router.next_order_id()
+    }
+
+    pub fn cash(&self) -> f64 {
+        *self.cash.lock()
+    }
+
+    /// Cash plus positions marked at their last price
+    pub fn equity(&self) -> f64 {
+        self.cash() + self.risk.positions().iter().map(|p| p.market_value()).sum::<f64>()
+    }
+
+    /// Risk check, route, send to the venue and apply any immediate fills.
+    /// TWAP/VWAP orders start their schedule instead, icebergs show their first
+    /// slice, stops are armed.
+    pub fn submit(&self, request: &OrderRequest) -> Result<Order, RouterError> {
+        let id = match request.order_id {
+            0 => self.router.next_order_id(),
+            id => id,
+        };
+        let mut order = Order::new(
+            id,
+            request.symbol.clone(),
+            request.side.clone(),
+            request.order_type.clone(),
+            request.quantity,
+            request.price,
+        );
+        order.stop_price = request.stop_price;
+        order.duration_secs = request.params.duration_secs;
+        order.num_slices = request.params.num_slices;
+        order.visible_quantity = request.params.visible_quantity;
+        order.trail = request.params.trail.clone();
+
+        let reference = request
+            .price
+            .or(request.stop_price)
+            .or_else(|| self.last_prices.get(&request.symbol).map(|p| *p));
+        let Some(reference) = reference else {
+            return Err(self.reject(order, format!("No reference price for {}", request.symbol)));
+        };
+        if let Err(e) = self.risk.check_order_risk(&order.symbol, signed(&order.side, order.quantity), reference) {
+            return Err(self.reject(order, e.to_string()));
+        }
+
+        if let Some(kind) = AlgoKind::of(&order.order_type) {
+            return self.start_algo(order, kind, &request.params);
+        }
+        if order.order_type == OrderType::Iceberg {
+            return self.start_iceberg(order, &request.params);
+        }
+        self.place_or_arm(order)
+    }
+
+    fn place_or_arm(&self, order: Order) -> Result<Order, RouterError> {
+        match order.order_type {
+            OrderType::Stop | OrderType::StopLimit | OrderType::TrailingStop => self.arm(order),
+            _ => self.place(order),
+        }
+    }
+
+    /// Route a risk-checked order, then send it, or each leg of a split, to its venue
+    fn place(&self, order: Order) -> Result<Order, RouterError> {
+        let order = match self.router.route_order(order.clone()) {
+            Ok(routed) => routed,
+            // A triggered stop was already stored when it was armed
+            Err(e) if self.router.get_order(order.id).is_some() => return Err(self.reject_routed(order, e.to_string())),
+            Err(e) => return Err(self.reject(order, e.to_string())),
+        };
+        self.publish(&order, order.routing.as_ref().map(|r| r.reason.clone()));
+
+        if order.exchange != SPLIT_EXCHANGE {
+            return self.send_to_venue(order);
+        }
+
+        // Split: every leg goes to its own venue, the parent collects their fills
+        let legs = self.router.child_orders(order.id);
+        let mut rejected = Vec::new();
+        for leg in legs.iter().cloned() {
+            self.publish(&leg, leg.routing.as_ref().map(|r| r.reason.clone()));
+            if let Err(e) = self.send_to_venue(leg) {
+                rejected.push(e.to_string());
+            }
+        }
+        if rejected.len() == legs.len() {
+            return Err(self.reject_routed(order, rejected.join("; ")));
+        }
+        Ok(self.router.get_order(order.id).unwrap_or(order))
+    }
+
+    fn send_to_venue(&self, order: Order) -> Result<Order, RouterError> {
+        let Some(venue) = self.venues.read().get(&order.exchange).cloned() else {
+            return Err(self.reject_routed(order, "Venue not connected".to_string()));
+        };
+        match venue.submit(&order) {
+            Ok(fills) => {
+                for fill in fills {
+                    self.apply_fill(fill);
+                }
+                Ok(self.router.get_order(order.id).unwrap_or(order))
+            }
+            Err(e) => Err(self.reject_routed(order, e.to_string())),
+        }
+    }
+
+    /// Hold a stop in the trigger book; one already through the last price
+    /// triggers straight away
+    fn arm(&self, order: Order) -> Result<Order, RouterError> {
+        let order = match self.router.hold_order(order.clone(), TRIGGER_EXCHANGE) {
+            Ok(held) => held,
+            Err(e) => return Err(self.reject(order, e.to_string())),
+        };
+        self.triggers.arm(&order, now_ms());
+        let reason = match (&order.trail, order.stop_price) {
+            (Some(trail), _) => format!("Armed, trailing by {:?}", trail.by),
+            (None, stop) => format!("Armed, stop at {}", stop.unwrap_or_default()),
+        };
+        self.publish(&order, Some(reason));
+
+        let last = self.last_prices.get(&order.symbol).map(|p| *p);
+        if let Some(last) = last {
+            self.check_triggers(&order.symbol, last);
+        }
+        Ok(self.router.get_order(order.id).unwrap_or(order))
+    }
+
+    /// Move trailing stops and send whatever `price` triggers
+    fn check_triggers(&self, symbol: &str, price: f64) {
+        let update = self.triggers.on_price(symbol, price, now_ms());
+        for (order_id, stop) in update.moved {
+            let Some(order) = self.router.get_order(order_id) else { continue };
+            if let Ok(moved) = self.router.amend_stop(order_id, order.quantity, stop) {
+                self.publish(&moved, Some(format!("Trailing stop at {}", stop)));
+            }
+        }
+        for triggered in update.triggered {
+            self.fire(triggered);
+        }
+    }
+
+    /// Convert a triggered stop and place it; the trigger time and price stay on the order
+    fn fire(&self, triggered: Triggered) {
+        let order = match self.router.trigger_order(&triggered) {
+            Ok(order) => order,
+            Err(e) => {
+                tracing::warn!("Triggered order {} not sent: {}", triggered.order_id, e);
+                return;
+            }
+        };
+        self.publish(&order, Some(format!("Triggered at {} (stop {})", triggered.price, triggered.stop)));
+        if let Err(e) = self.place(order) {
+            tracing::error!("Triggered order {} not placed: {}", triggered.order_id, e);
+        }
+    }
+
+    /// Armed stops and trailing stops
+    pub fn triggers(&self) -> Vec<Trigger> {
+        self.triggers.armed()
+    }
+
+    /// Hold a TWAP/VWAP parent in the router and send its first slice
+    fn start_algo(&self, order: Order, kind: AlgoKind, params: &OrderParams) -> Result<Order, RouterError> {
+        let order = match self.router.hold_order(order.clone(), ALGO_EXCHANGE) {
+            Ok(held) => held,
+            Err(e) => return Err(self.reject(order, e.to_string())),
+        };
+        let spec = AlgoSpec {
+            kind,
+            duration_ms: order.duration_secs.unwrap_or(0) * 1000,
+            slices: order.num_slices.unwrap_or(1),
+            randomize: params.randomize,
+            max_participation: params.max_participation,
+            volume_profile: params.volume_profile.clone(),
+        };
+        let reason = format!("{:?} over {}s in {} slices", kind, order.duration_secs.unwrap_or(0), spec.slices);
+        self.algos.start(&order, spec, now_ms(), &mut rand::thread_rng());
+        self.publish(&order, Some(reason));
+
+        self.run_algos(now_ms());
+        Ok(self.router.get_order(order.id).unwrap_or(order))
+    }
+
+    /// Send the child orders that TWAP/VWAP schedules have due by `now_ms`
+    pub fn run_algos(&self, now_ms: u64) {
+        let Some(_polling) = self.algo_poll.try_lock() else {
+            return;
+        };
+        // Filled, or still working in children
+        let committed = |parent_id: u64| -> f64 {
+            self.router
+                .child_orders(parent_id)
+                .iter()
+                .map(|child| if child.is_active() { child.quantity } else { child.filled_quantity })
+                .sum()
+        };
+        for action in self.algos.poll(now_ms, committed) {
+            match action {
+                AlgoAction::Child { parent_id, quantity } => self.send_slice(parent_id, quantity),
+                AlgoAction::Expired { parent_id } => {
+                    let unfilled = self.router.get_order(parent_id).map_or(0.0, |p| p.remaining_quantity());
+                    let _ = self.end(parent_id, OrderState::Expired, format!("Participation cap left {} unfilled", unfilled));
+                }
+            }
+        }
+    }
+
+    /// One slice of an algo parent at the parent's price
+    fn send_slice(&self, parent_id: u64, quantity: f64) {
+        let Some(parent) = self.router.get_order(parent_id).filter(|p| p.is_active()) else {
+            self.algos.cancel(parent_id);
+            return;
+        };
+        let _ = self.send_child(&parent, quantity, parent.price);
+    }
+
+    /// Risk check and place a child of `parent`: market, or limit at `price`.
+    /// If it cannot be placed the parent is cancelled rather than retried.
+    fn send_child(&self, parent: &Order, quantity: f64, price: Option<f64>) -> Result<Order, RouterError> {
+        let order_type = if price.is_some() { OrderType::Limit } else { OrderType::Market };
+        let mut child = Order::new(
+            self.router.next_order_id(),
+            parent.symbol.clone(),
+            parent.side.clone(),
+            order_type,
+            quantity,
+            price,
+        );
+        child.parent_id = Some(parent.id);
+
+        let reference = price.or_else(|| self.last_prices.get(&parent.symbol).map(|p| *p));
+        let result = match reference {
+            Some(reference) => match self.risk.check_order_risk(&child.symbol, signed(&child.side, quantity), reference) {
+                Ok(()) => self.place(child),
+                Err(e) => Err(self.reject(child, e.to_string())),
+            },
+            None => Err(self.reject(child, format!("No reference price for {}", parent.symbol))),
+        };
+        if let Err(e) = &result {
+            let _ = self.end(parent.id, OrderState::Cancelled, format!("Child order failed: {}", e));
+        }
+        result
+    }
+
+    /// Hold an iceberg parent in the router and show its first slice
+    fn start_iceberg(&self, order: Order, params: &OrderParams) -> Result<Order, RouterError> {
+        let order = match self.router.hold_order(order.clone(), ICEBERG_EXCHANGE) {
+            Ok(held) => held,
+            Err(e) => return Err(self.reject(order, e.to_string())),
+        };
+        let spec = IcebergSpec {
+            visible_quantity: order.visible_quantity.unwrap_or(order.quantity),
+            randomize: params.randomize,
+            price_jitter_bps: params.price_jitter_bps,
+        };
+        let reason = format!("Iceberg showing {} of {}", spec.visible_quantity, order.quantity);
+        self.icebergs.insert(order.id, spec);
+        self.publish(&order, Some(reason));
+
+        self.replenish(order.id);
+        let order = self.router.get_order(order.id).unwrap_or(order);
+        match order.state {
+            OrderState::Cancelled => Err(RouterError::ExchangeError(format!("Iceberg {} could not show a slice", order.id))),
+            _ => Ok(order),
+        }
+    }
+
+    /// Show the iceberg's next slice if none is working. Slices that fill on
+    /// arrival are replaced here in a loop, not from `apply_fill`.
+    fn replenish(&self, parent_id: u64) {
+        if !self.replenishing.lock().insert(parent_id) {
+            return;
+        }
+        while let Some(parent) = self.router.get_order(parent_id).filter(|p| p.is_active()) {
+            if self.router.child_orders(parent_id).iter().any(|child| child.is_active()) {
+                break;
+            }
+            let Some(slice) = self.icebergs.get(&parent_id).and_then(|spec| spec.next_slice(&parent, &mut rand::thread_rng())) else {
+                break;
+            };
+            if self.send_child(&parent, slice.quantity, slice.price).is_err() {
+                break;
+            }
+        }
+        self.replenishing.lock().remove(&parent_id);
+    }
+
+    /// Open a bracket: the entry goes out now, the stop and take profits once it has filled
+    pub fn submit_bracket(&self, request: &BracketRequest) -> Result<Bracket, RouterError> {
+        let entry_id = match request.entry_id {
+            0 => self.router.next_order_id(),
+            id => id,
+        };
+        let entry = OrderRequest {
+            order_id: entry_id,
+            symbol: request.symbol.clone(),
+            side: request.side.clone(),
+            order_type: if request.entry_price.is_some() { OrderType::Limit } else { OrderType::Market },
+            quantity: request.quantity,
+            price: request.entry_price,
+            stop_price: None,
+            timestamp: now_ms(),
+            params: OrderParams::default(),
+        };
+        if let Err(e) = request.validate() {
+            let order = Order::new(entry_id, entry.symbol, entry.side, entry.order_type, entry.quantity, entry.price);
+            return Err(self.reject(order, e.to_string()));
+        }
+
+        // Registered first: a market entry fills inside `submit`
+        self.brackets.lock().insert(entry_id, Bracket::new(entry_id, request.clone()));
+        if let Err(e) = self.submit(&entry) {
+            self.brackets.lock().remove(&entry_id);
+            return Err(e);
+        }
+        self.bracket(entry_id).ok_or(RouterError::OrderNotFound)
+    }
+
+    /// Cancel whatever of a bracket is still working. A filled entry's position
+    /// stays open, without its stop.
+    pub fn cancel_bracket(&self, entry_id: u64) -> Result<Bracket, RouterError> {
+        let orders = {
+            let mut brackets = self.brackets.lock();
+            let bracket = brackets.get_mut(&entry_id).ok_or(RouterError::OrderNotFound)?;
+            if !matches!(bracket.state, BracketState::PendingEntry | BracketState::Open) {
+                return Err(RouterError::OrderNotActive);
+            }
+            bracket.state = BracketState::Cancelled;
+            let mut orders = vec![entry_id];
+            orders.extend(bracket.stop_id);
+            orders.extend(&bracket.take_profit_ids);
+            orders
+        };
+        for order_id in orders {
+            let _ = self.end(order_id, OrderState::Cancelled, "Bracket cancelled".to_string());
+        }
+        self.bracket(entry_id).ok_or(RouterError::OrderNotFound)
+    }
+
+    pub fn bracket(&self, entry_id: u64) -> Option<Bracket> {
+        self.brackets.lock().get(&entry_id).cloned()
+    }
+
+    /// Every bracket, newest first
+    pub fn brackets(&self) -> Vec<Bracket> {
+        let mut brackets: Vec<Bracket> = self.brackets.lock().values().cloned().collect();
+        brackets.sort_by_key(|b| std::cmp::Reverse(b.entry_id));
+        brackets
+    }
+
+    /// A fill on a bracket's entry or one of its exits
+    fn on_bracket_fill(&self, order: &Order, fill: &Fill) {
+        let group = order.oco_group.unwrap_or(order.id);
+        let action = {
+            let mut brackets = self.brackets.lock();
+            let Some(bracket) = brackets.get_mut(&group) else {
+                return;
+            };
+            let tp1_done = bracket
+                .take_profit_ids
+                .first()
+                .and_then(|id| self.router.get_order(*id))
+                .is_some_and(|tp1| tp1.state == OrderState::Filled);
+            bracket.on_fill(order.id, fill.quantity, fill.price, order.state == OrderState::Filled, tp1_done)
+        };
+
+        match action {
+            BracketAction::PlaceExits => self.place_exits(group),
+            BracketAction::Cancel(order_ids) => {
+                for order_id in order_ids {
+                    let _ = self.end(order_id, OrderState::Cancelled, "One-cancels-other".to_string());
+                }
+            }
+            BracketAction::AmendStop { stop_id, quantity, stop_price } => {
+                if let Err(e) = self.amend_stop(stop_id, quantity, stop_price) {
+                    tracing::error!("Bracket {} stop {} not amended: {}", group, stop_id, e);
+                }
+            }
+            BracketAction::None => {}
+        }
+    }
+
+    /// Stop for the whole filled entry and a limit per take profit, all in the
+    /// entry's OCO group. Exits only reduce the position, so no risk check.
+    fn place_exits(&self, entry_id: u64) {
+        let exits = {
+            let mut brackets = self.brackets.lock();
+            let Some(bracket) = brackets.get_mut(&entry_id) else {
+                return;
+            };
+            let request = &bracket.request;
+            let exit = |order_type: OrderType, quantity: f64, price: Option<f64>| {
+                let mut order = Order::new(
+                    self.router.next_order_id(),
+                    request.symbol.clone(),
+                    request.exit_side(),
+                    order_type,
+                    quantity,
+                    price,
+                );
+                order.oco_group = Some(entry_id);
+                order
+            };
+
+            let mut stop = exit(OrderType::Stop, bracket.entry_filled, None);
+            stop.stop_price = Some(bracket.stop_price);
+            let take_profits: Vec<Order> = request
+                .take_profits
+                .iter()
+                .zip(request.tp_quantities(bracket.entry_filled))
+                .map(|(price, quantity)| exit(OrderType::Limit, quantity, Some(*price)))
+                .collect();
+
+            // Ids are recorded before sending: an exit may fill on arrival
+            bracket.stop_id = Some(stop.id);
+            bracket.take_profit_ids = take_profits.iter().map(|tp| tp.id).collect();
+            std::iter::once(stop).chain(take_profits).collect::<Vec<Order>>()
+        };
+
+        for exit in exits {
+            if let Err(e) = self.place_or_arm(exit) {
+                tracing::error!("Bracket {} exit not placed: {}", entry_id, e);
+            }
+        }
+    }
+
+    /// New size and trigger for a stop: re-armed if it waits in the trigger
+    /// book, otherwise the venue gets it in place of the old one
+    fn amend_stop(&self, order_id: u64, quantity: f64, stop_price: f64) -> Result<Order, RouterError> {
+        let order = self.router.get_order(order_id).ok_or(RouterError::OrderNotFound)?;
+        if order.exchange == TRIGGER_EXCHANGE {
+            let amended = self.router.amend_stop(order_id, quantity, stop_price)?;
+            self.triggers.set_stop(order_id, stop_price);
+            self.publish(&amended, Some(format!("Stop {} at {}", quantity, stop_price)));
+            let last = self.last_prices.get(&order.symbol).map(|p| *p);
+            if let Some(last) = last {
+                self.check_triggers(&order.symbol, last);
+            }
+            return Ok(self.router.get_order(order_id).unwrap_or(amended));
+        }
+        let venue = self.venues.read().get(&order.exchange).cloned();
+        if let Some(venue) = &venue {
+            if !venue.cancel(order_id) {
+                return Err(RouterError::OrderNotActive);
+            }
+        }
+
+        let amended = self.router.amend_stop(order_id, quantity, stop_price)?;
+        self.publish(&amended, Some(format!("Stop {} at {}", quantity, stop_price)));
+        if let Some(venue) = venue {
+            match venue.submit(&amended) {
+                Ok(fills) => fills.into_iter().for_each(|fill| self.apply_fill(fill)),
+                Err(e) => return Err(self.reject_routed(amended, e.to_string())),
+            }
+        }
+        Ok(self.router.get_order(order_id).unwrap_or(amended))
+    }
+
+    /// Pause an algo parent; its schedule resumes where it left off
+    pub fn pause_algo(&self, order_id: u64) -> Result<AlgoStatus, RouterError> {
+        self.switch_algo(order_id, self.algos.pause(order_id), "Paused")
+    }
+
+    pub fn resume_algo(&self, order_id: u64) -> Result<AlgoStatus, RouterError> {
+        self.switch_algo(order_id, self.algos.resume(order_id), "Resumed")
+    }
+
+    pub fn algo_status(&self, order_id: u64) -> Option<AlgoStatus> {
+        self.algos.status(order_id)
+    }
+
+    fn switch_algo(&self, order_id: u64, switched: bool, reason: &str) -> Result<AlgoStatus, RouterError> {
+        let status = self.algos.status(order_id).ok_or(RouterError::OrderNotFound)?;
+        if !switched {
+            return Err(RouterError::OrderNotActive);
+        }
+        if let Some(order) = self.router.get_order(order_id) {
+            self.publish(&order, Some(reason.to_string()));
+        }
+        Ok(status)
+    }
+
+    /// Pull the order from its venue and mark it cancelled; a split order's
+    /// active legs and an algo's working children are cancelled with it
+    pub fn cancel(&self, order_id: u64) -> Result<Order, RouterError> {
+        self.end(order_id, OrderState::Cancelled, "Cancelled".to_string())
+    }
+
+    /// Cancel the order at its venue and its active children, then set `state`
+    fn end(&self, order_id: u64, state: OrderState, reason: String) -> Result<Order, RouterError> {
+        let order = self.router.get_order(order_id).ok_or(RouterError::OrderNotFound)?;
+        if !order.is_active() {
+            return Err(RouterError::OrderNotActive);
+        }
+        self.algos.finish(order_id, if state == OrderState::Expired { AlgoState::Expired } else { AlgoState::Cancelled });
+        self.icebergs.remove(&order_id);
+        self.triggers.disarm(order_id);
+        if let Some(bracket) = self.brackets.lock().get_mut(&order_id) {
+            if bracket.state == BracketState::PendingEntry {
+                bracket.state = BracketState::Cancelled;
+            }
+        }
+        for leg in self.router.child_orders(order_id).into_iter().filter(|leg| leg.is_active()) {
+            let _ = self.cancel(leg.id);
+        }
+        if let Some(venue) = self.venues.read().get(&order.exchange) {
+            venue.cancel(order_id);
+        }
+        let ended = match state {
+            OrderState::Cancelled => self.router.cancel_order(order_id)?,
+            state => {
+                self.router.update_order_state(order_id, state)?;
+                self.router.get_order(order_id).ok_or(RouterError::OrderNotFound)?
+            }
+        };
+        self.publish(&ended, Some(reason));
+        Ok(ended)
+    }
+
+    /// New limit price: the venue gets the amended order in place of the old
+    /// one; for a split, algo or iceberg order, every active child does, and
+    /// later slices go out at the new price
+    pub fn amend(&self, order_id: u64, price: f64) -> Result<Order, RouterError> {
+        let order = self.router.get_order(order_id).ok_or(RouterError::OrderNotFound)?;
+        if order.exchange == TRIGGER_EXCHANGE {
+            let amended = self.router.amend_price(order_id, price)?;
+            self.triggers.set_limit(order_id, price);
+            self.publish(&amended, Some(format!("Amended to {}", price)));
+            return Ok(amended);
+        }
+        if [SPLIT_EXCHANGE, ALGO_EXCHANGE, ICEBERG_EXCHANGE].contains(&order.exchange.as_str()) {
+            let amended = self.router.amend_price(order_id, price)?;
+            for leg in self.router.child_orders(order_id).into_iter().filter(|leg| leg.is_active()) {
+                self.amend(leg.id, price)?;
+            }
+            self.publish(&amended, Some(format!("Amended to {}", price)));
+            return Ok(self.router.get_order(order_id).unwrap_or(amended));
+        }
+        let venue = self.venues.read().get(&order.exchange).cloned();
+        if let Some(venue) = &venue {
+            if !venue.cancel(order_id) {
+                return Err(RouterError::OrderNotActive);
+            }
+        }
+
+        let amended = self.router.amend_price(order_id, price)?;
+        self.publish(&amended, Some(format!("Amended to {}", price)));
+        if let Some(venue) = venue {
+            match venue.submit(&amended) {
+                Ok(fills) => fills.into_iter().for_each(|fill| self.apply_fill(fill)),
+                Err(e) => return Err(self.reject_routed(amended, e.to_string())),
+            }
+        }
+        Ok(self.router.get_order(order_id).unwrap_or(amended))
+    }
+
+    /// Trip the circuit breaker so nothing new passes the risk check except
+    /// orders reducing a position, then cancel every active order but the
+    /// armed stops protecting a position. Returns how many were cancelled.
+    pub fn kill_switch(&self, reason: &str) -> usize {
+        self.risk.trip_circuit_breaker(reason);
+        self.router
+            .get_active_orders()
+            .into_iter()
+            .filter(|order| !self.is_protective(order))
+            .filter(|order| self.cancel(order.id).is_ok())
+            .count()
+    }
+
+    /// An armed stop that would only reduce its symbol's position
+    fn is_protective(&self, order: &Order) -> bool {
+        order.exchange == TRIGGER_EXCHANGE
+            && self.risk.reduces_position(&order.symbol, signed(&order.side, order.remaining_quantity()))
+    }
+
+    /// Remember the price, let venues fill resting orders, check triggers, then
+    /// mark positions. `volume` is the session volume of a quote, so the
+    /// increase since the last update is what traded; it counts towards
+    /// participation caps. Updates without volume (0.0) do not.
+    pub fn on_market_data(&self, update: &MarketUpdate) {
+        self.last_prices.insert(update.symbol.clone(), update.price);
+        let last_volume = if update.volume > 0.0 { self.last_volumes.insert(update.symbol.clone(), update.volume) } else { None };
+        if let Some(last) = last_volume {
+            // A drop is a new session starting from zero
+            let traded = if update.volume >= last { update.volume - last } else { update.volume };
+            self.algos.on_volume(&update.symbol, traded);
+        }
+
+        let venues: Vec<_> = self.venues.read().values().cloned().collect();
+        for venue in venues {
+            for fill in venue.on_market_data(update) {
+                self.apply_fill(fill);
+            }
+        }
+        // After the venues, so a triggered market order fills at this price
+        self.check_triggers(&update.symbol, update.price);
+
+        if self.risk.mark_price(&update.symbol, update.price) {
+            self.risk.update_equity(self.equity());
+        }
+        self.run_algos(now_ms());
+    }
+
+    /// Book a venue fill: order state, position, cash and equity
+    pub fn apply_fill(&self, fill: Fill) {
+        let order = match self.router.fill_order(fill.order_id, fill.quantity) {
+            Ok(order) => order,
+            Err(e) => {
+                tracing::error!("Fill for unknown order {}: {}", fill.order_id, e);
+                return;
+            }
+        };
+
+        let signed = signed(&fill.side, fill.quantity);
+        self.risk.update_position(fill.symbol.clone(), signed, fill.price);
+        *self.cash.lock() -= signed * fill.price + fill.fee;
+        self.risk.update_equity(self.equity());
+
+        self.events.emit(fill.clone());
+        self.publish(&order, None);
+        self.on_bracket_fill(&order, &fill);
+        let mut parent_id = order.parent_id;
+        while let Some(parent) = parent_id.and_then(|id| self.router.get_order(id)) {
+            if parent.state == OrderState::Filled {
+                self.algos.finish(parent.id, AlgoState::Completed);
+                self.icebergs.remove(&parent.id);
+            }
+            self.publish(&parent, None);
+            if parent.exchange == ICEBERG_EXCHANGE {
+                self.replenish(parent.id);
+            }
+            self.on_bracket_fill(&parent, &fill);
+            parent_id = parent.parent_id;
+        }
+    }
+
+    fn publish(&self, order: &Order, reason: Option<String>) {
+        self.events.emit(OrderEvent::from_order(order, reason));
+    }
+
+    /// Rejected before the router stored it
+    fn reject(&self, mut order: Order, reason: String) -> RouterError {
+        tracing::warn!("Order {} {} rejected: {}", order.id, order.symbol, reason);
+        order.state = OrderState::Rejected;
+        self.publish(&order, Some(reason.clone()));
+        RouterError::ValidationFailed(reason)
+    }
+
+    /// Rejected by the venue after routing
+    fn reject_routed(&self, order: Order, reason: String) -> RouterError {
+        tracing::warn!("Order {} {} rejected by {}: {}", order.id, order.symbol, order.exchange, reason);
+        let _ = self.router.update_order_state(order.id, OrderState::Rejected);
+        let order = self.router.get_order(order.id).unwrap_or(order);
+        self.publish(&order, Some(reason.clone()));
+        RouterError::ExchangeError(reason)
+    }
+}
+
+impl MessageHandler for OrderManager {
+    fn handle(&self, msg: &EngineMessage) -> Result<(), EngineError> {
+        let result = match msg {
+            EngineMessage::NewOrder(request) => self.submit(request).map(|_| ()),
+            EngineMessage::NewBracket(request) => self.submit_bracket(request).map(|_| ()),
+            EngineMessage::CancelOrder(order_id) => self.cancel(*order_id).map(|_| ()),
+            EngineMessage::ModifyOrder(order_id, price) => self.amend(*order_id, *price).map(|_| ()),
+            EngineMessage::MarketData(update) => {
+                self.on_market_data(update);
+                Ok(())
+            }
+            EngineMessage::KillSwitch(reason) => {
+                let cancelled = self.kill_switch(reason);
+                tracing::error!("Kill switch ({}): {} orders cancelled", reason, cancelled);
+                Ok(())
+            }
+            EngineMessage::Shutdown => Ok(()),
+        };
+        result.map_err(|e| EngineError::ProcessingError(e.to_string()))
+    }
+}
+
+/// Quantity as a position change: negative for a sell
+fn signed(side: &OrderSide, quantity: f64) -> f64 {
+    match side {
+        OrderSide::Buy => quantity,
+        OrderSide::Sell => -quantity,
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Route for a venue that takes every basic order type
+pub fn default_route(priority: u8, fee_rate: f64) -> ExchangeRoute {
+    ExchangeRoute {
+        name: String::new(),
+        priority,
+        fee_rate,
+        min_order_size: 0.0,
+        max_order_size: f64::MAX,
+        supports_order_types: vec![
+            OrderType::Market,
+            OrderType::Limit,
+            OrderType::Stop,
+            OrderType::StopLimit,
+            OrderType::PostOnly,
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::RiskEvent;
+    use crate::engine::BracketConfig;
+    use crate::engine::CircuitBreakerState;
+    use crate::engine::venue::PaperVenue;
+    use crate::engine::{Trail, TrailBy};
+    use std::time::Duration;
+
+    fn oms(events: &AppBus) -> OrderManager {
+        let risk = Arc::new(RiskManager::new(100_000.0).with_events(events.clone()));
+        let oms = OrderManager::new(Arc::new(OrderRouter::new()), risk, events.clone(), 100_000.0);
+        oms.add_venue(default_route(1, 0.0), Arc::new(PaperVenue::new("paper", 0.0)));
+        oms
+    }
+
+    fn request(side: OrderSide, order_type: OrderType, quantity: f64, price: Option<f64>) -> OrderRequest {
+        OrderRequest {
+            order_id: 0,
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type,
+            quantity,
+            price,
+            stop_price: None,
+            timestamp: 0,
+            params: Default::default(),
+        }
+    }
+
+    fn price(oms: &OrderManager, price: f64) {
+        oms.on_market_data(&MarketUpdate {
+            symbol: "BTCUSDT".to_string(),
+            price,
+            volume: 0.0,
+            timestamp: 0,
+        });
+    }
+
+    #[test]
+    fn test_order_to_fill() {
+        let events = AppBus::new(256);
+        let orders = events.subscribe::<OrderEvent>(">").unwrap();
+        let fills = events.subscribe::<Fill>(">").unwrap();
+        let oms = oms(&events);
+
+        price(&oms, 100.0);
+        let order = oms.submit(&request(OrderSide::Buy, OrderType::Market, 10.0, None)).unwrap();
+        assert_eq!(order.state, OrderState::Filled);
+        assert_eq!(oms.cash(), 99_000.0);
+
+        // Resting limit fills when the price comes down, position is marked
+        let limit = oms.submit(&request(OrderSide::Sell, OrderType::Limit, 5.0, Some(110.0))).unwrap();
+        assert_eq!(limit.state, OrderState::New);
+        price(&oms, 111.0);
+        assert_eq!(oms.router().get_order(limit.id).unwrap().state, OrderState::Filled);
+        assert_eq!(oms.risk().positions()[0].quantity, 5.0);
+        assert!((oms.equity() - 100_105.0).abs() < 1e-6);
+
+        let timeout = Duration::from_secs(1);
+        let states: Vec<OrderState> = (0..4).map(|_| orders.recv_timeout(timeout).unwrap().state).collect();
+        assert_eq!(states, vec![OrderState::New, OrderState::Filled, OrderState::New, OrderState::Filled]);
+        assert_eq!(fills.recv_timeout(timeout).unwrap().price, 100.0);
+        assert_eq!(fills.recv_timeout(timeout).unwrap().price, 110.0);
+    }
+
+    #[test]
+    fn test_rejections_and_cancel() {
+        let events = AppBus::new(256);
+        let orders = events.subscribe::<OrderEvent>(">").unwrap();
+        let risk = events.subscribe::<RiskEvent>(">").unwrap();
+        let oms = oms(&events);
+
+        // No price yet, then over the position size limit
+        assert!(oms.submit(&request(OrderSide::Buy, OrderType::Market, 1.0, None)).is_err());
+        price(&oms, 100.0);
+        assert!(oms.submit(&request(OrderSide::Buy, OrderType::Market, 5000.0, None)).is_err());
+        assert!(matches!(risk.recv_timeout(Duration::from_secs(1)), Some(RiskEvent::OrderRejected { .. })));
+
+        let limit = oms.submit(&request(OrderSide::Buy, OrderType::Limit, 1.0, Some(90.0))).unwrap();
+        let amended = oms.amend(limit.id, 95.0).unwrap();
+        assert_eq!(amended.price, Some(95.0));
+        let cancelled = oms.cancel(limit.id).unwrap();
+        assert_eq!(cancelled.state, OrderState::Cancelled);
+        assert!(oms.cancel(limit.id).is_err());
+
+        let timeout = Duration::from_secs(1);
+        let events: Vec<(OrderState, Option<String>)> = (0..5)
+            .map(|_| orders.recv_timeout(timeout).map(|e| (e.state, e.reason)).unwrap())
+            .collect();
+        assert_eq!(events[0].0, OrderState::Rejected);
+        assert_eq!(events[1], (OrderState::Rejected, Some("Position size limit exceeded".to_string())));
+        assert_eq!(events[2].0, OrderState::New);
+        assert_eq!(events[3], (OrderState::New, Some("Amended to 95".to_string())));
+        assert_eq!(events[4].0, OrderState::Cancelled);
+    }
+
+    #[test]
+    fn test_split_across_venues() {
+        let events = AppBus::new(256);
+        let risk = Arc::new(RiskManager::new(100_000.0));
+        let oms = OrderManager::new(Arc::new(OrderRouter::new()), risk, events.clone(), 100_000.0);
+        for (name, fee_rate) in [("cheap", 0.0005), ("dear", 0.001)] {
+            let route = ExchangeRoute { max_order_size: 4.0, ..default_route(1, fee_rate) };
+            oms.add_venue(route, Arc::new(PaperVenue::new(name, fee_rate)));
+        }
+        price(&oms, 100.0);
+
+        let order = oms.submit(&request(OrderSide::Buy, OrderType::Market, 6.0, None)).unwrap();
+        assert_eq!(order.exchange, SPLIT_EXCHANGE);
+        assert_eq!((order.state, order.filled_quantity), (OrderState::Filled, 6.0));
+        assert!(order.routing.unwrap().reason.starts_with("Split across 2 routes"));
+
+        let legs: Vec<(String, f64)> = oms.router().child_orders(order.id).into_iter().map(|o| (o.exchange, o.quantity)).collect();
+        assert_eq!(legs, vec![("cheap".to_string(), 4.0), ("dear".to_string(), 2.0)]);
+        assert_eq!(oms.risk().positions()[0].quantity, 6.0);
+
+        // Cancelling a resting split order pulls every leg
+        let limit = oms.submit(&request(OrderSide::Buy, OrderType::Limit, 6.0, Some(90.0))).unwrap();
+        oms.cancel(limit.id).unwrap();
+        assert!(oms.router().child_orders(limit.id).iter().all(|leg| leg.state == OrderState::Cancelled));
+    }
+
+    fn algo(quantity: f64, duration_secs: u64, num_slices: u32, max_participation: Option<f64>) -> OrderRequest {
+        OrderRequest {
+            params: OrderParams {
+                duration_secs: Some(duration_secs),
+                num_slices: Some(num_slices),
+                max_participation,
+                ..Default::default()
+            },
+            ..request(OrderSide::Buy, OrderType::TWAP, quantity, None)
+        }
+    }
+
+    #[test]
+    fn test_twap_slices() {
+        let events = AppBus::new(256);
+        let oms = oms(&events);
+        price(&oms, 100.0);
+
+        // 4 slices 500ms apart, the first one at once
+        let start = now_ms();
+        let parent = oms.submit(&algo(4.0, 2, 4, None)).unwrap();
+        assert_eq!((parent.exchange.as_str(), parent.filled_quantity), (ALGO_EXCHANGE, 1.0));
+        oms.run_algos(start + 600);
+        assert_eq!(oms.router().parent_progress(parent.id).unwrap().filled_quantity, 2.0);
+
+        // Paused time does not count towards the schedule
+        oms.pause_algo(parent.id).unwrap();
+        oms.run_algos(start + 5_000);
+        assert_eq!(oms.router().child_orders(parent.id).len(), 2);
+        oms.resume_algo(parent.id).unwrap();
+        oms.run_algos(start + 5_500);
+        let progress = oms.router().parent_progress(parent.id).unwrap();
+        assert_eq!((progress.filled_quantity, progress.children, progress.progress_pct), (3.0, 3, 75.0));
+
+        let cancelled = oms.cancel(parent.id).unwrap();
+        assert_eq!((cancelled.state, cancelled.filled_quantity), (OrderState::Cancelled, 3.0));
+        assert_eq!(oms.algo_status(parent.id).unwrap().state, AlgoState::Cancelled);
+        oms.run_algos(start + 10_000);
+        assert_eq!(oms.router().child_orders(parent.id).len(), 3);
+        assert!(oms.pause_algo(parent.id).is_err());
+    }
+
+    #[test]
+    fn test_participation_cap() {
+        let events = AppBus::new(256);
+        let oms = oms(&events);
+        let tick = |volume: f64| {
+            oms.on_market_data(&MarketUpdate { symbol: "BTCUSDT".to_string(), price: 100.0, volume, timestamp: 0 })
+        };
+        tick(1_000.0);
+
+        // Nothing traded yet, so nothing is sent
+        let parent = oms.submit(&algo(4.0, 60, 1, Some(0.5))).unwrap();
+        assert!(oms.router().child_orders(parent.id).is_empty());
+
+        // Half of each 4 traded
+        tick(1_004.0);
+        assert_eq!(oms.router().get_order(parent.id).unwrap().filled_quantity, 2.0);
+        tick(1_008.0);
+        let parent = oms.router().get_order(parent.id).unwrap();
+        assert_eq!((parent.state, parent.filled_quantity), (OrderState::Filled, 4.0));
+        assert_eq!(oms.algo_status(parent.id).unwrap().state, AlgoState::Completed);
+        assert_eq!(oms.risk().positions()[0].quantity, 4.0);
+    }
+
+    #[test]
+    fn test_iceberg() {
+        let events = AppBus::new(256);
+        let oms = OrderManager::new(Arc::new(OrderRouter::new()), Arc::new(RiskManager::new(100_000.0)), events, 100_000.0);
+        let venue = Arc::new(PaperVenue::new("paper", 0.0));
+        oms.add_venue(default_route(1, 0.0), venue.clone());
+        price(&oms, 101.0);
+
+        let iceberg = OrderRequest {
+            params: OrderParams { visible_quantity: Some(2.0), ..Default::default() },
+            ..request(OrderSide::Buy, OrderType::Iceberg, 5.0, Some(100.0))
+        };
+        let parent = oms.submit(&iceberg).unwrap();
+        assert_eq!(parent.exchange, ICEBERG_EXCHANGE);
+        let resting: Vec<f64> = venue.resting_orders().iter().map(|o| o.quantity).collect();
+        assert_eq!(resting, vec![2.0]);
+
+        // The slice fills; the next ones are marketable at 99 and fill on arrival
+        price(&oms, 99.0);
+        let parent = oms.router().get_order(parent.id).unwrap();
+        assert_eq!((parent.state, parent.filled_quantity), (OrderState::Filled, 5.0));
+        let slices: Vec<f64> = oms.router().child_orders(parent.id).iter().map(|o| o.quantity).collect();
+        assert_eq!(slices, vec![2.0, 2.0, 1.0]);
+        assert!(venue.resting_orders().is_empty());
+
+        // Cancelling the parent pulls the shown slice
+        price(&oms, 101.0);
+        let parent = oms.submit(&iceberg).unwrap();
+        oms.cancel(parent.id).unwrap();
+        assert!(venue.resting_orders().is_empty());
+        assert!(oms.router().child_orders(parent.id).iter().all(|o| o.state == OrderState::Cancelled));
+        price(&oms, 99.0);
+        assert_eq!(oms.router().child_orders(parent.id).len(), 1);
+    }
+
+    #[test]
+    fn test_bracket() {
+        let events = AppBus::new(256);
+        let oms = OrderManager::new(Arc::new(OrderRouter::new()), Arc::new(RiskManager::new(100_000.0)), events, 100_000.0);
+        let venue = Arc::new(PaperVenue::new("paper", 0.0));
+        oms.add_venue(default_route(1, 0.0), venue.clone());
+        price(&oms, 100.0);
+
+        let request = BracketRequest {
+            entry_id: 0,
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            quantity: 10.0,
+            entry_price: None,
+            stop_price: 95.0,
+            take_profits: vec![110.0, 120.0, 130.0],
+            config: BracketConfig { breakeven_after_tp1: true, ..Default::default() },
+            signal_id: None,
+        };
+        let bracket = oms.submit_bracket(&request).unwrap();
+        assert_eq!((bracket.state, bracket.entry_filled), (BracketState::Open, 10.0));
+        let mut resting: Vec<(OrderType, f64)> = venue.resting_orders().into_iter().map(|o| (o.order_type, o.quantity)).collect();
+        resting.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(resting, vec![(OrderType::Limit, 2.0), (OrderType::Limit, 3.0), (OrderType::Limit, 5.0)]);
+        let armed: Vec<(u64, Option<f64>)> = oms.triggers().into_iter().map(|t| (t.order_id, t.stop)).collect();
+        assert_eq!(armed, vec![(bracket.stop_id.unwrap(), Some(95.0))]);
+
+        // TP1 halves the position, the stop follows it to breakeven
+        price(&oms, 111.0);
+        let stop = oms.router().get_order(bracket.stop_id.unwrap()).unwrap();
+        assert_eq!((stop.quantity, stop.stop_price), (5.0, Some(100.0)));
+
+        // Stopped out: the other take profits are cancelled
+        price(&oms, 99.0);
+        let bracket = oms.bracket(bracket.entry_id).unwrap();
+        assert_eq!(bracket.state, BracketState::Closed);
+        assert!(venue.resting_orders().is_empty() && oms.triggers().is_empty());
+        let states: Vec<OrderState> = bracket.take_profit_ids.iter().map(|id| oms.router().get_order(*id).unwrap().state).collect();
+        assert_eq!(states, vec![OrderState::Filled, OrderState::Cancelled, OrderState::Cancelled]);
+        assert_eq!(oms.risk().positions()[0].quantity, 0.0);
+        assert!((oms.cash() - 100_045.0).abs() < 1e-6); // 5 at 110, 5 at 99 after the gap
+
+        // Entry not reached yet: cancelling pulls it and nothing else is sent
+        let pending = oms.submit_bracket(&BracketRequest { entry_price: Some(96.0), ..request }).unwrap();
+        assert_eq!(pending.state, BracketState::PendingEntry);
+        assert_eq!(oms.cancel_bracket(pending.entry_id).unwrap().state, BracketState::Cancelled);
+        assert!(venue.resting_orders().is_empty());
+        assert!(oms.cancel_bracket(pending.entry_id).is_err());
+    }
+
+    #[test]
+    fn test_stop_triggers() {
+        let events = AppBus::new(256);
+        let oms = OrderManager::new(Arc::new(OrderRouter::new()), Arc::new(RiskManager::new(100_000.0)), events, 100_000.0);
+        let venue = Arc::new(PaperVenue::new("paper", 0.0));
+        oms.add_venue(default_route(1, 0.0), venue.clone());
+        price(&oms, 100.0);
+        oms.submit(&request(OrderSide::Buy, OrderType::Market, 2.0, None)).unwrap();
+
+        // Held by the OMS, not the venue, until the price gets there
+        let stop = oms.submit(&OrderRequest { stop_price: Some(95.0), ..request(OrderSide::Sell, OrderType::Stop, 1.0, None) }).unwrap();
+        let trailing = oms
+            .submit(&OrderRequest {
+                params: OrderParams {
+                    trail: Some(Trail { by: TrailBy::Percent(5.0), limit_offset: Some(1.0) }),
+                    ..Default::default()
+                },
+                ..request(OrderSide::Sell, OrderType::TrailingStop, 1.0, None)
+            })
+            .unwrap();
+        assert_eq!((stop.exchange.as_str(), trailing.stop_price), (TRIGGER_EXCHANGE, Some(95.0)));
+        assert!(venue.resting_orders().is_empty());
+
+        // The trailing stop follows the high to 114, a pullback does not lower it
+        price(&oms, 120.0);
+        price(&oms, 115.0);
+        assert_eq!(oms.router().get_order(trailing.id).unwrap().stop_price, Some(114.0));
+
+        // Triggers as a limit 1 below the trigger price; the fixed stop stays armed
+        price(&oms, 113.5);
+        let triggered = oms.router().get_order(trailing.id).unwrap();
+        assert_eq!((triggered.order_type, triggered.price, triggered.state), (OrderType::Limit, Some(112.5), OrderState::Filled));
+        assert_eq!(triggered.trigger_price, Some(113.5));
+        assert!(triggered.triggered_at.is_some());
+        assert_eq!(oms.triggers().len(), 1);
+
+        // A gap through the stop goes out at market and fills at the new price
+        price(&oms, 90.0);
+        let triggered = oms.router().get_order(stop.id).unwrap();
+        assert_eq!((triggered.order_type, triggered.state, triggered.trigger_price), (OrderType::Market, OrderState::Filled, Some(90.0)));
+        assert_eq!(oms.risk().positions()[0].quantity, 0.0);
+        assert!((oms.cash() - (100_000.0 - 200.0 + 113.5 + 90.0)).abs() < 1e-6);
+
+        // Cancelling disarms; a trailing stop without a trail is rejected
+        let stop = oms.submit(&OrderRequest { stop_price: Some(80.0), ..request(OrderSide::Sell, OrderType::Stop, 1.0, None) }).unwrap();
+        oms.cancel(stop.id).unwrap();
+        assert!(oms.triggers().is_empty());
+        assert!(oms.submit(&request(OrderSide::Sell, OrderType::TrailingStop, 1.0, None)).is_err());
+    }
+
+    #[test]
+    fn test_kill_switch() {
+        let events = AppBus::new(256);
+        let risk = events.subscribe::<RiskEvent>(">").unwrap();
+        let oms = oms(&events);
+        price(&oms, 100.0);
+
+        let limit = oms.submit(&request(OrderSide::Buy, OrderType::Limit, 1.0, Some(90.0))).unwrap();
+        oms.handle(&EngineMessage::KillSwitch("test".to_string())).unwrap();
+        assert_eq!(oms.router().get_order(limit.id).unwrap().state, OrderState::Cancelled);
+        assert_eq!(oms.risk().get_circuit_breaker_state(), CircuitBreakerState::Triggered);
+        assert!(matches!(
+            risk.recv_timeout(Duration::from_secs(1)),
+            Some(RiskEvent::CircuitBreaker { to: CircuitBreakerState::Triggered, .. })
+        ));
+
+        // Nothing new gets through until the breaker is reset
+        assert!(oms.submit(&request(OrderSide::Buy, OrderType::Market, 1.0, None)).is_err());
+    }
+
+    #[test]
+    fn test_kill_switch_keeps_stops_and_allows_closing() {
+        let events = AppBus::new(256);
+        let oms = oms(&events);
+        price(&oms, 100.0);
+        oms.submit(&request(OrderSide::Buy, OrderType::Market, 2.0, None)).unwrap();
+        let stop = oms.submit(&OrderRequest { stop_price: Some(95.0), ..request(OrderSide::Sell, OrderType::Stop, 2.0, None) }).unwrap();
+        let entry = oms.submit(&OrderRequest { stop_price: Some(110.0), ..request(OrderSide::Buy, OrderType::Stop, 1.0, None) }).unwrap();
+
+        // The protective stop stays armed, the stop that would add to the position goes
+        assert_eq!(oms.kill_switch("test"), 1);
+        assert_eq!(oms.router().get_order(entry.id).unwrap().state, OrderState::Cancelled);
+        let armed: Vec<u64> = oms.triggers().iter().map(|t| t.order_id).collect();
+        assert_eq!(armed, vec![stop.id]);
+
+        // Adding is rejected, closing goes through
+        assert!(oms.submit(&request(OrderSide::Buy, OrderType::Market, 1.0, None)).is_err());
+        assert!(oms.submit(&request(OrderSide::Sell, OrderType::Market, 3.0, None)).is_err());
+        let close = oms.submit(&request(OrderSide::Sell, OrderType::Market, 2.0, None)).unwrap();
+        assert_eq!(close.state, OrderState::Filled);
+        assert_eq!(oms.risk().positions()[0].quantity, 0.0);
+    }
+}
@@ -1,10 +1,27 @@
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use super::routing::{plan_route, RoutingDecision};
+use super::triggers::{Trail, Triggered};
+use crate::scanner::market_data::MarketDataProcessor;
+
+/// `Order::exchange` of a parent whose quantity was split across venues
+pub const SPLIT_EXCHANGE: &str = "split";
+
+/// `Order::exchange` of a TWAP/VWAP parent, worked in child orders by the OMS
+pub const ALGO_EXCHANGE: &str = "algo";
+
+/// `Order::exchange` of an iceberg parent, shown one child slice at a time by the OMS
+pub const ICEBERG_EXCHANGE: &str = "iceberg";
+
+/// `Order::exchange` of a stop or trailing stop waiting in the OMS trigger book
+pub const TRIGGER_EXCHANGE: &str = "trigger";
+
 /// Order types supported by the router
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderType {
     Market,
     Limit,
@@ -14,17 +31,18 @@ pub enum OrderType {
     VWAP,
     Iceberg,
     PostOnly,
+    TrailingStop,
 }
 
 /// Order side
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderSide {
     Buy,
     Sell,
 }
 
 /// Order state
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum OrderState {
     New,
     PartiallyFilled,
@@ -35,7 +53,7 @@ pub enum OrderState {
 }
 
 /// Order structure
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Order {
     pub id: u64,
     pub symbol: String,
@@ -56,6 +74,19 @@ pub struct Order {
     
     // Iceberg parameters
     pub visible_quantity: Option<f64>,
+
+    // Trailing stop parameters
+    pub trail: Option<Trail>,
+    /// When a stop or trailing stop triggered, and the price that did it
+    pub triggered_at: Option<u64>,
+    pub trigger_price: Option<f64>,
+
+    /// Set on child orders: the order they execute part of
+    pub parent_id: Option<u64>,
+    /// Set on bracket exits: the entry's id, shared by the stop and take profits
+    pub oco_group: Option<u64>,
+    /// Where the router sent the order and why
+    pub routing: Option<RoutingDecision>,
 }
 
 impl Order {
@@ -88,6 +119,12 @@ impl Order {
             duration_secs: None,
             num_slices: None,
             visible_quantity: None,
+            trail: None,
+            triggered_at: None,
+            trigger_price: None,
+            parent_id: None,
+            oco_group: None,
+            routing: None,
         }
     }
 
@@ -122,6 +159,20 @@ impl ExchangeRoute {
     }
 }
 
+/// Fill progress of an order worked in child orders
+#[derive(Clone, Debug, Serialize)]
+pub struct ParentProgress {
+    pub order_id: u64,
+    pub state: OrderState,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub working_quantity: f64,   // Open in active children
+    pub remaining_quantity: f64, // Not yet sent
+    pub children: usize,
+    pub active_children: usize,
+    pub progress_pct: f64,
+}
+
 /// Order validation result
 #[derive(Debug)]
 pub enum ValidationResult {
@@ -137,6 +188,8 @@ pub struct OrderRouter {
     orders: Arc<RwLock<HashMap<u64, Order>>>,
     /// Exchange routes
     routes: Arc<RwLock<Vec<ExchangeRoute>>>,
+    /// Order books for cost-aware routing
+    market_data: Option<Arc<MarketDataProcessor>>,
 }
 
 impl OrderRouter {
@@ -145,9 +198,21 @@ impl OrderRouter {
             next_order_id: AtomicU64::new(1),
             orders: Arc::new(RwLock::new(HashMap::new())),
             routes: Arc::new(RwLock::new(Vec::new())),
+            market_data: None,
         }
     }
 
+    /// Score routes against venue books from `market_data` (`OrderBook::venue`
+    /// set to the route name), not only on fees and priority
+    pub fn with_market_data(mut self, market_data: Arc<MarketDataProcessor>) -> Self {
+        self.market_data = Some(market_data);
+        self
+    }
+
+    pub fn market_data(&self) -> Option<&Arc<MarketDataProcessor>> {
+        self.market_data.as_ref()
+    }
+
     /// Add an exchange route
     pub fn add_route(&self, route: ExchangeRoute) {
         let mut routes = self.routes.write();
@@ -185,9 +250,27 @@ impl OrderRouter {
             }
         }
 
-        if order.order_type == OrderType::TWAP {
-            if order.duration_secs.is_none() || order.num_slices.is_none() {
-                return ValidationResult::Invalid("TWAP requires duration and slices".to_string());
+        if order.order_type == OrderType::TrailingStop {
+            match &order.trail {
+                Some(trail) if trail.is_valid() => {}
+                Some(_) => {
+                    return ValidationResult::Invalid("Trailing distance must be positive".to_string());
+                }
+                None => {
+                    return ValidationResult::Invalid("Trailing stop requires a trail".to_string());
+                }
+            }
+        }
+
+        if matches!(order.order_type, OrderType::TWAP | OrderType::VWAP) {
+            match (order.duration_secs, order.num_slices) {
+                (Some(duration), Some(slices)) if duration > 0 && slices > 0 => {}
+                (Some(_), Some(_)) => {
+                    return ValidationResult::Invalid(format!("{:?} duration and slices must be positive", order.order_type));
+                }
+                _ => {
+                    return ValidationResult::Invalid(format!("{:?} requires duration and slices", order.order_type));
+                }
             }
         }
 
@@ -196,6 +279,9 @@ impl OrderRouter {
                 return ValidationResult::Invalid("Iceberg requires visible quantity".to_string());
             }
             if let Some(visible) = order.visible_quantity {
+                if visible <= 0.0 {
+                    return ValidationResult::Invalid("Visible quantity must be positive".to_string());
+                }
                 if visible >= order.quantity {
                     return ValidationResult::Invalid(
                         "Visible quantity must be less than total".to_string()
@@ -207,7 +293,9 @@ impl OrderRouter {
         ValidationResult::Valid
     }
 
-    /// Route an order to best exchange
+    /// Route an order to the cheapest exchange, see `engine::routing`. When it
+    /// has to be split the parent is stored with `exchange` `SPLIT_EXCHANGE`
+    /// and one child order per venue, found with `child_orders`.
     pub fn route_order(&self, mut order: Order) -> Result<Order, RouterError> {
         // Validate order
         match self.validate_order(&order) {
@@ -219,21 +307,79 @@ impl OrderRouter {
         }
 
         // Find best exchange
-        let routes = self.routes.read();
-        let best_route = routes
-            .iter()
-            .find(|route| route.can_handle(&order))
-            .ok_or(RouterError::NoRouteAvailable)?;
+        let decision = {
+            let routes = self.routes.read();
+            let md = self.market_data.as_deref();
+            plan_route(
+                &order,
+                &routes,
+                |venue| md.and_then(|md| md.get_venue_order_book(venue, &order.symbol)),
+                md.and_then(|md| md.get_order_book(&order.symbol)).as_ref(),
+            )?
+        };
+
+        let children: Vec<Order> = if decision.is_split() {
+            order.exchange = SPLIT_EXCHANGE.to_string();
+            let legs = decision.legs.len();
+            decision
+                .legs
+                .iter()
+                .enumerate()
+                .map(|(i, leg)| {
+                    let mut child = order.clone();
+                    child.id = self.next_order_id();
+                    child.quantity = leg.quantity;
+                    child.exchange = leg.venue.clone();
+                    child.parent_id = Some(order.id);
+                    child.routing = Some(RoutingDecision {
+                        legs: vec![leg.clone()],
+                        reason: format!("Leg {} of {} of order {}", i + 1, legs, order.id),
+                        candidates: decision.candidates,
+                    });
+                    child
+                })
+                .collect()
+        } else {
+            order.exchange = decision.legs[0].venue.clone();
+            Vec::new()
+        };
+        tracing::debug!("Order {} routed: {}", order.id, decision.reason);
+        order.routing = Some(decision);
 
-        order.exchange = best_route.name.clone();
-        
         // Store order
         let mut orders = self.orders.write();
         orders.insert(order.id, order.clone());
+        for child in children {
+            orders.insert(child.id, child);
+        }
+
+        Ok(order)
+    }
 
+    /// Validate and store an order that is not sent to a venue itself, such as
+    /// a TWAP/VWAP parent; `exchange` says who works it
+    pub fn hold_order(&self, mut order: Order, exchange: &str) -> Result<Order, RouterError> {
+        if let ValidationResult::Invalid(msg) = self.validate_order(&order) {
+            return Err(RouterError::ValidationFailed(msg));
+        }
+        order.exchange = exchange.to_string();
+        self.orders.write().insert(order.id, order.clone());
         Ok(order)
     }
 
+    /// Orders executing part of `parent_id`, in id order
+    pub fn child_orders(&self, parent_id: u64) -> Vec<Order> {
+        let mut children: Vec<Order> = self
+            .orders
+            .read()
+            .values()
+            .filter(|order| order.parent_id == Some(parent_id))
+            .cloned()
+            .collect();
+        children.sort_by_key(|order| order.id);
+        children
+    }
+
     /// Update order state
     pub fn update_order_state(&self, order_id: u64, state: OrderState) -> Result<(), RouterError> {
         let mut orders = self.orders.write();
@@ -250,24 +396,126 @@ impl OrderRouter {
         Ok(())
     }
 
-    /// Fill order (partial or complete)
+    /// Fill order (partial or complete); a child's fill also counts towards its
+    /// parent, and so on up to the top-level order
     pub fn fill_order(&self, order_id: u64, quantity: f64) -> Result<Order, RouterError> {
         let mut orders = self.orders.write();
         let order = orders
             .get_mut(&order_id)
             .ok_or(RouterError::OrderNotFound)?;
 
+        Self::apply_fill(order, quantity);
+        let filled = order.clone();
+
+        let mut parent_id = filled.parent_id;
+        while let Some(parent) = parent_id.and_then(|id| orders.get_mut(&id)) {
+            Self::apply_fill(parent, quantity);
+            parent_id = parent.parent_id;
+        }
+        Ok(filled)
+    }
+
+    /// How far a parent is: filled, working in active children, and the rest
+    pub fn parent_progress(&self, parent_id: u64) -> Option<ParentProgress> {
+        let orders = self.orders.read();
+        let parent = orders.get(&parent_id)?;
+        let children: Vec<&Order> = orders.values().filter(|o| o.parent_id == Some(parent_id)).collect();
+        let working_quantity = children.iter().filter(|c| c.is_active()).map(|c| c.remaining_quantity()).sum();
+
+        Some(ParentProgress {
+            order_id: parent_id,
+            state: parent.state.clone(),
+            quantity: parent.quantity,
+            filled_quantity: parent.filled_quantity,
+            working_quantity,
+            remaining_quantity: (parent.remaining_quantity() - working_quantity).max(0.0),
+            children: children.len(),
+            active_children: children.iter().filter(|c| c.is_active()).count(),
+            progress_pct: if parent.quantity > 0.0 { parent.filled_quantity / parent.quantity * 100.0 } else { 0.0 },
+        })
+    }
+
+    fn apply_fill(order: &mut Order, quantity: f64) {
         order.filled_quantity += quantity;
         order.updated_at = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as u64;
 
-        if order.filled_quantity >= order.quantity {
+        if order.filled_quantity >= order.quantity - 1e-9 {
             order.state = OrderState::Filled;
         } else {
             order.state = OrderState::PartiallyFilled;
         }
+    }
+
+    /// Change the limit price of an active order
+    pub fn amend_price(&self, order_id: u64, price: f64) -> Result<Order, RouterError> {
+        if price <= 0.0 {
+            return Err(RouterError::ValidationFailed("Price must be positive".to_string()));
+        }
+        let mut orders = self.orders.write();
+        let order = orders
+            .get_mut(&order_id)
+            .ok_or(RouterError::OrderNotFound)?;
+
+        if !order.is_active() {
+            return Err(RouterError::OrderNotActive);
+        }
+
+        order.price = Some(price);
+        order.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        Ok(order.clone())
+    }
+
+    /// Turn an armed stop into the market or limit order it triggered as
+    pub fn trigger_order(&self, triggered: &Triggered) -> Result<Order, RouterError> {
+        let mut orders = self.orders.write();
+        let order = orders
+            .get_mut(&triggered.order_id)
+            .ok_or(RouterError::OrderNotFound)?;
+
+        if !order.is_active() {
+            return Err(RouterError::OrderNotActive);
+        }
+
+        order.order_type = triggered.order_type.clone();
+        order.price = triggered.limit;
+        order.stop_price = Some(triggered.stop);
+        order.triggered_at = Some(triggered.timestamp);
+        order.trigger_price = Some(triggered.price);
+        order.updated_at = triggered.timestamp;
+
+        Ok(order.clone())
+    }
+
+    /// Change an active stop order's size and trigger price
+    pub fn amend_stop(&self, order_id: u64, quantity: f64, stop_price: f64) -> Result<Order, RouterError> {
+        if stop_price <= 0.0 {
+            return Err(RouterError::ValidationFailed("Stop price must be positive".to_string()));
+        }
+        let mut orders = self.orders.write();
+        let order = orders
+            .get_mut(&order_id)
+            .ok_or(RouterError::OrderNotFound)?;
+
+        if !order.is_active() {
+            return Err(RouterError::OrderNotActive);
+        }
+        if quantity <= order.filled_quantity {
+            return Err(RouterError::ValidationFailed("Quantity must exceed the filled quantity".to_string()));
+        }
+
+        order.quantity = quantity;
+        order.stop_price = Some(stop_price);
+        order.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
 
         Ok(order.clone())
     }
@@ -399,4 +647,33 @@ mod tests {
             ValidationResult::Invalid(_)
         ));
     }
+
+    #[test]
+    fn test_algo_parent_progress() {
+        let router = OrderRouter::new();
+        router.add_route(ExchangeRoute {
+            name: "paper".to_string(),
+            priority: 1,
+            fee_rate: 0.0,
+            min_order_size: 0.0,
+            max_order_size: f64::MAX,
+            supports_order_types: vec![OrderType::Market],
+        });
+
+        let id = router.next_order_id();
+        let mut parent = Order::new(id, "BTCUSDT".to_string(), OrderSide::Buy, OrderType::VWAP, 10.0, None);
+        assert!(router.hold_order(parent.clone(), ALGO_EXCHANGE).is_err());
+        parent.duration_secs = Some(60);
+        parent.num_slices = Some(5);
+        router.hold_order(parent, ALGO_EXCHANGE).unwrap();
+
+        let mut child = Order::new(router.next_order_id(), "BTCUSDT".to_string(), OrderSide::Buy, OrderType::Market, 4.0, None);
+        child.parent_id = Some(id);
+        let child = router.route_order(child).unwrap();
+        router.fill_order(child.id, 1.0).unwrap();
+
+        let progress = router.parent_progress(id).unwrap();
+        assert_eq!((progress.filled_quantity, progress.working_quantity, progress.remaining_quantity), (1.0, 3.0, 6.0));
+        assert_eq!((progress.children, progress.active_children, progress.progress_pct), (1, 1, 10.0));
+    }
 }
@@ -1,26 +1,42 @@
-use crossbeam::queue::ArrayQueue;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use std::sync::atomic::{AtomicU64, AtomicBool, Ordering};
+use std::io::{BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::thread::{JoinHandle, Thread};
+use std::time::{Duration, Instant};
+
+use super::bracket::BracketRequest;
+use super::lanes::{Conflation, LaneCapacity, LaneStats, Lanes};
+use super::order_router::{OrderSide, OrderType};
+use super::triggers::Trail;
+use crate::bus::{AppBus, AppEvent, BusError};
+use crate::scanner::market_data::{MarketDataEvent, MarketDataProcessor};
+use crate::metrics::{LatencyHistogram, LatencySnapshot, RateMeter};
 
 // Global allocator for zero-allocation hot paths
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
 /// Message types for the quantum engine
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum EngineMessage {
     NewOrder(OrderRequest),
+    /// Entry with a stop and take profits, see `engine::bracket`
+    NewBracket(BracketRequest),
     CancelOrder(u64),
     ModifyOrder(u64, f64),
     MarketData(MarketUpdate),
+    /// Halt trading: trip the circuit breaker and cancel every active order
+    KillSwitch(String),
     Shutdown,
 }
 
 /// Order request structure
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub order_id: u64,
     pub symbol: String,
@@ -28,25 +44,35 @@ pub struct OrderRequest {
     pub order_type: OrderType,
     pub quantity: f64,
     pub price: Option<f64>,
+    pub stop_price: Option<f64>,
     pub timestamp: u64,
+    #[serde(default)]
+    pub params: OrderParams,
 }
 
-#[derive(Clone, Debug)]
-pub enum OrderSide {
-    Buy,
-    Sell,
-}
-
-#[derive(Clone, Debug)]
-pub enum OrderType {
-    Market,
-    Limit,
-    Stop,
-    StopLimit,
+/// Parameters only some order types use
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OrderParams {
+    /// TWAP/VWAP: schedule length and number of child orders
+    pub duration_secs: Option<u64>,
+    pub num_slices: Option<u32>,
+    /// TWAP/VWAP: 0.0 to 1.0 jitter on slice times and sizes; iceberg: on slice sizes
+    #[serde(default)]
+    pub randomize: f64,
+    /// TWAP/VWAP: max share of market volume, e.g. 0.1
+    pub max_participation: Option<f64>,
+    /// VWAP: weight of each slice, from historical intraday volume
+    pub volume_profile: Option<Vec<f64>>,
+    /// Iceberg: size of each shown slice, and how far (bps) behind the limit a slice may be priced
+    pub visible_quantity: Option<f64>,
+    #[serde(default)]
+    pub price_jitter_bps: f64,
+    /// Trailing stop: distance from the best price and optional limit offset
+    pub trail: Option<Trail>,
 }
 
 /// Market data update
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MarketUpdate {
     pub symbol: String,
     pub price: f64,
@@ -61,6 +87,11 @@ pub struct EngineMetrics {
     pub total_latency_ns: AtomicU64,
     pub max_latency_ns: AtomicU64,
     pub min_latency_ns: AtomicU64,
+    pub handler_panics: AtomicU64,
+    /// Per-message handling time
+    pub latency: LatencyHistogram,
+    pub message_rate: RateMeter,
+    pub order_rate: RateMeter,
 }
 
 impl EngineMetrics {
@@ -71,11 +102,16 @@ impl EngineMetrics {
             total_latency_ns: AtomicU64::new(0),
             max_latency_ns: AtomicU64::new(0),
             min_latency_ns: AtomicU64::new(u64::MAX),
+            handler_panics: AtomicU64::new(0),
+            latency: LatencyHistogram::new(),
+            message_rate: RateMeter::new(),
+            order_rate: RateMeter::new(),
         }
     }
 
     pub fn record_latency(&self, latency_ns: u64) {
         self.total_latency_ns.fetch_add(latency_ns, Ordering::Relaxed);
+        self.latency.record(latency_ns);
         
         // Update max
         let mut current_max = self.max_latency_ns.load(Ordering::Relaxed);
@@ -111,6 +147,11 @@ impl EngineMetrics {
         let count = self.messages_received.load(Ordering::Relaxed);
         if count == 0 { 0 } else { total / count }
     }
+
+    /// p50/p95/p99/max of message handling time
+    pub fn latency_snapshot(&self) -> LatencySnapshot {
+        self.latency.snapshot()
+    }
 }
 
 impl Default for EngineMetrics {
@@ -119,18 +160,81 @@ impl Default for EngineMetrics {
     }
 }
 
+/// What an idle worker does while the queue is empty
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaitStrategy {
+    /// Spin on the queue: lowest latency, one core per worker
+    BusySpin,
+    /// Park until `submit` wakes the worker, or `timeout` passes
+    Park { timeout: Duration },
+}
+
+/// What `stop` does with messages still queued
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopMode {
+    /// Process them on the stopping thread before returning
+    Drain,
+    /// Write them to this JSONL file; the next `start` queues them again
+    Persist(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    /// Worker threads; with more than one, messages may be handled out of order
+    pub workers: usize,
+    pub wait: WaitStrategy,
+    pub stop_mode: StopMode,
+    pub lanes: LaneCapacity,
+    pub conflation: Conflation,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            wait: WaitStrategy::Park { timeout: Duration::from_millis(100) },
+            stop_mode: StopMode::Drain,
+            lanes: LaneCapacity::default(),
+            conflation: Conflation::LatestPerSymbol,
+        }
+    }
+}
+
+/// Parked workers, woken when a message arrives
+#[derive(Default)]
+struct Waker {
+    idle: AtomicUsize,
+    threads: Mutex<Vec<Thread>>,
+}
+
+impl Waker {
+    fn wake(&self) {
+        if self.idle.load(Ordering::SeqCst) > 0 {
+            for thread in self.threads.lock().iter() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
 /// Lock-free quantum execution engine
 pub struct QuantumEngine {
-    /// Lock-free ring buffer for incoming messages
-    message_queue: Arc<ArrayQueue<EngineMessage>>,
+    /// Incoming messages, one bounded queue per priority lane
+    message_queue: Arc<Lanes>,
     /// Performance metrics
     metrics: Arc<EngineMetrics>,
     /// Running flag
     running: Arc<AtomicBool>,
     /// Message processors (using SmallVec for zero-allocation up to 8 handlers)
-    handlers: Arc<RwLock<SmallVec<[Arc<dyn MessageHandler>; 8]>>>,
+    handlers: Arc<Handlers>,
+    config: EngineConfig,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    waker: Arc<Waker>,
 }
 
+/// Registered handlers, in the order they run
+type Handlers = RwLock<SmallVec<[Arc<dyn MessageHandler>; 8]>>;
+
 /// Trait for message handlers
 pub trait MessageHandler: Send + Sync {
     fn handle(&self, msg: &EngineMessage) -> Result<(), EngineError>;
@@ -141,6 +245,7 @@ pub enum EngineError {
     QueueFull,
     InvalidMessage,
     ProcessingError(String),
+    Persistence(String),
 }
 
 impl std::fmt::Display for EngineError {
@@ -149,6 +254,7 @@ impl std::fmt::Display for EngineError {
             EngineError::QueueFull => write!(f, "Message queue is full"),
             EngineError::InvalidMessage => write!(f, "Invalid message"),
             EngineError::ProcessingError(msg) => write!(f, "Processing error: {}", msg),
+            EngineError::Persistence(msg) => write!(f, "Engine persistence error: {}", msg),
         }
     }
 }
@@ -156,13 +262,24 @@ impl std::fmt::Display for EngineError {
 impl std::error::Error for EngineError {}
 
 impl QuantumEngine {
-    /// Create a new quantum engine with specified capacity
+    /// Create a new quantum engine with specified capacity on every lane
     pub fn new(capacity: usize) -> Self {
+        Self::with_config(EngineConfig {
+            lanes: LaneCapacity::uniform(capacity),
+            ..EngineConfig::default()
+        })
+    }
+
+    /// Engine whose workers, wait strategy, lanes and stop behaviour come from `config`
+    pub fn with_config(config: EngineConfig) -> Self {
         Self {
-            message_queue: Arc::new(ArrayQueue::new(capacity)),
+            message_queue: Arc::new(Lanes::new(config.lanes, config.conflation)),
             metrics: Arc::new(EngineMetrics::new()),
             running: Arc::new(AtomicBool::new(false)),
             handlers: Arc::new(RwLock::new(SmallVec::new())),
+            config,
+            workers: Mutex::new(Vec::new()),
+            waker: Arc::new(Waker::default()),
         }
     }
 
@@ -171,49 +288,144 @@ impl QuantumEngine {
         self.handlers.write().push(handler);
     }
 
-    /// Submit a message to the engine (lock-free)
+    /// Submit a message on its priority lane; `QueueFull` only if that lane is full
     pub fn submit(&self, msg: EngineMessage) -> Result<(), EngineError> {
-        self.message_queue
-            .push(msg)
-            .map_err(|_| EngineError::QueueFull)
+        self.message_queue.push(msg)?;
+        self.waker.wake();
+        Ok(())
     }
 
-    /// Start the engine processing loop
-    pub fn start(&self) {
+    /// Feed the engine's `MarketData` on a dedicated thread that ends with the
+    /// engine: quotes on the app bus, and trades and quotes (at the mid) pushed
+    /// into `market_data`. Books on the app bus go into `market_data` for
+    /// routing. This thread becomes the processor's only event consumer.
+    pub fn feed_market_data(
+        &self,
+        events: &AppBus,
+        market_data: Arc<MarketDataProcessor>,
+    ) -> Result<JoinHandle<()>, BusError> {
+        let quotes = events.subscribe_events("quotes.>")?;
+        let books = events.subscribe_events("books.>")?;
+        let queue = Arc::downgrade(&self.message_queue);
+        let waker = self.waker.clone();
+
+        Ok(std::thread::spawn(move || loop {
+            let fed = flume::Selector::new()
+                .recv(&quotes, |msg| match msg.map(|m| m.payload) {
+                    Ok(AppEvent::QuoteUpdate(quote)) => Feed::Update(MarketUpdate {
+                        symbol: quote.symbol,
+                        price: quote.price,
+                        volume: quote.volume.unwrap_or(0.0),
+                        timestamp: quote.timestamp.max(0) as u64,
+                    }),
+                    Ok(_) => Feed::Nothing,
+                    Err(_) => Feed::Closed,
+                })
+                .recv(&books, |msg| match msg.map(|m| m.payload) {
+                    Ok(AppEvent::BookUpdate(book)) => {
+                        market_data.update_order_book(book);
+                        Feed::Nothing
+                    }
+                    Ok(_) => Feed::Nothing,
+                    Err(_) => Feed::Closed,
+                })
+                // Trades carry no session volume, so these updates leave participation alone
+                .recv(market_data.events(), |event| match event {
+                    Ok(MarketDataEvent::Trade(trade)) => Feed::Update(MarketUpdate {
+                        symbol: trade.symbol,
+                        price: trade.price,
+                        volume: 0.0,
+                        timestamp: trade.timestamp,
+                    }),
+                    Ok(MarketDataEvent::QuoteUpdate { symbol, bid, ask, timestamp }) => Feed::Update(MarketUpdate {
+                        symbol,
+                        price: (bid + ask) / 2.0,
+                        volume: 0.0,
+                        timestamp,
+                    }),
+                    _ => Feed::Nothing,
+                })
+                .wait_timeout(Duration::from_millis(100));
+            let Some(queue) = queue.upgrade() else { break };
+            let update = match fed {
+                Ok(Feed::Update(update)) => update,
+                Ok(Feed::Closed) => break,
+                _ => continue,
+            };
+            match queue.push(EngineMessage::MarketData(update)) {
+                Ok(()) => waker.wake(),
+                Err(_) => tracing::debug!("Engine market data lane full, update dropped"),
+            }
+        }))
+    }
+
+    /// Start the worker threads; messages persisted by the last `stop` are
+    /// queued first. If they can't be restored the engine stays stopped.
+    pub fn start(&self) -> Result<(), EngineError> {
+        let mut workers = self.workers.lock();
+        if self.running.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        if let StopMode::Persist(path) = &self.config.stop_mode {
+            let restored = self.restore(path)?;
+            if restored > 0 {
+                tracing::info!("Engine restored {} persisted messages", restored);
+            }
+        }
+
         self.running.store(true, Ordering::Release);
+        for i in 0..self.config.workers.max(1) {
+            let worker = Worker {
+                queue: self.message_queue.clone(),
+                metrics: self.metrics.clone(),
+                running: self.running.clone(),
+                handlers: self.handlers.clone(),
+                waker: self.waker.clone(),
+                wait: self.config.wait,
+            };
+            let handle = std::thread::Builder::new()
+                .name(format!("engine-{}", i))
+                .spawn(move || worker.run())
+                .map_err(|e| {
+                    self.running.store(false, Ordering::Release);
+                    EngineError::ProcessingError(e.to_string())
+                })?;
+            self.waker.threads.lock().push(handle.thread().clone());
+            workers.push(handle);
+        }
+        Ok(())
     }
 
-    /// Stop the engine
-    pub fn stop(&self) {
+    /// Stop the workers, wait for them, then drain or persist what is still
+    /// queued. Returns how many messages were drained or persisted.
+    pub fn stop(&self) -> Result<usize, EngineError> {
         self.running.store(false, Ordering::Release);
+        for thread in self.waker.threads.lock().drain(..) {
+            thread.unpark();
+        }
+        for handle in self.workers.lock().drain(..) {
+            if handle.join().is_err() {
+                tracing::error!("Engine worker exited with a panic");
+            }
+        }
+
+        match &self.config.stop_mode {
+            StopMode::Drain => self.process_messages(),
+            StopMode::Persist(path) => self.persist(path),
+        }
     }
 
-    /// Process messages (call this in a tight loop)
+    /// Process messages (call this in a tight loop when not using `start`)
     pub fn process_messages(&self) -> Result<usize, EngineError> {
         let mut processed = 0;
         
         while let Some(msg) = self.message_queue.pop() {
-            let start = Instant::now();
-            
-            self.metrics.messages_received.fetch_add(1, Ordering::Relaxed);
-            
-            // Process message with all handlers
-            let handlers = self.handlers.read();
-            for handler in handlers.iter() {
-                if let Err(e) = handler.handle(&msg) {
-                    tracing::warn!("Handler error: {}", e);
-                }
-            }
-            
-            // Record latency
-            let latency_ns = start.elapsed().as_nanos() as u64;
-            self.metrics.record_latency(latency_ns);
-            
+            dispatch(&self.handlers, &self.metrics, &msg);
             processed += 1;
             
             // Check for shutdown
             if matches!(msg, EngineMessage::Shutdown) {
-                self.stop();
+                self.running.store(false, Ordering::Release);
                 break;
             }
         }
@@ -221,6 +433,69 @@ impl QuantumEngine {
         Ok(processed)
     }
 
+    /// Append queued messages to `path`, one JSON object per line
+    fn persist(&self, path: &PathBuf) -> Result<usize, EngineError> {
+        if self.message_queue.is_empty() {
+            return Ok(0);
+        }
+        let io_error = |e: std::io::Error| EngineError::Persistence(format!("{}: {}", path.display(), e));
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path).map_err(io_error)?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        let mut persisted = 0;
+        while let Some(msg) = self.message_queue.pop() {
+            let line = serde_json::to_string(&msg).map_err(|e| EngineError::Persistence(e.to_string()))?;
+            writeln!(writer, "{}", line).map_err(io_error)?;
+            persisted += 1;
+        }
+        writer.flush().map_err(io_error)?;
+        tracing::info!("Engine persisted {} queued messages to {}", persisted, path.display());
+        Ok(persisted)
+    }
+
+    /// Queue messages persisted by a previous `stop`, then remove the file.
+    /// On error the file keeps every message that was not queued.
+    fn restore(&self, path: &PathBuf) -> Result<usize, EngineError> {
+        let io_error = |e: std::io::Error| EngineError::Persistence(format!("{}: {}", path.display(), e));
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        // Read the whole file first so a read error leaves the queue untouched
+        let mut messages = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line.map_err(io_error)?;
+            match serde_json::from_str::<EngineMessage>(&line) {
+                Ok(msg) => messages.push(msg),
+                Err(e) => tracing::warn!("Skipping unreadable persisted message: {}", e),
+            }
+        }
+
+        let total = messages.len();
+        let mut messages = messages.into_iter();
+        while let Some(msg) = messages.next() {
+            if let Err(e) = self.message_queue.push(msg.clone()) {
+                let queued = total - messages.len() - 1;
+                let mut rest = String::new();
+                for msg in std::iter::once(msg).chain(messages) {
+                    let line = serde_json::to_string(&msg).map_err(|e| EngineError::Persistence(e.to_string()))?;
+                    rest.push_str(&line);
+                    rest.push('\n');
+                }
+                std::fs::write(path, rest).map_err(io_error)?;
+                tracing::warn!("Engine queued {} of {} persisted messages before: {}", queued, total, e);
+                return Err(e);
+            }
+        }
+        std::fs::remove_file(path).map_err(io_error)?;
+        Ok(total)
+    }
+
     /// Get current metrics
     pub fn metrics(&self) -> &Arc<EngineMetrics> {
         &self.metrics
@@ -231,12 +506,91 @@ impl QuantumEngine {
         self.running.load(Ordering::Acquire)
     }
 
-    /// Get queue utilization (0.0 to 1.0)
+    /// Get queue utilization across all lanes (0.0 to 1.0)
     pub fn queue_utilization(&self) -> f64 {
         let capacity = self.message_queue.capacity();
         let len = self.message_queue.len();
         len as f64 / capacity as f64
     }
+
+    /// Depth, capacity and counters of each lane, most urgent first
+    pub fn lane_stats(&self) -> Vec<LaneStats> {
+        self.message_queue.stats()
+    }
+}
+
+/// What one wakeup of the market data feeder produced
+enum Feed {
+    Update(MarketUpdate),
+    Nothing,
+    Closed, // The app bus is gone
+}
+
+/// State one worker thread needs, cloned from the engine
+struct Worker {
+    queue: Arc<Lanes>,
+    metrics: Arc<EngineMetrics>,
+    running: Arc<AtomicBool>,
+    handlers: Arc<Handlers>,
+    waker: Arc<Waker>,
+    wait: WaitStrategy,
+}
+
+impl Worker {
+    /// Until `stop` or a `Shutdown` message; whatever is left is up to `stop`
+    fn run(self) {
+        while self.running.load(Ordering::Acquire) {
+            if let Some(msg) = self.queue.pop() {
+                dispatch(&self.handlers, &self.metrics, &msg);
+                if matches!(msg, EngineMessage::Shutdown) {
+                    self.running.store(false, Ordering::Release);
+                }
+                continue;
+            }
+
+            match self.wait {
+                WaitStrategy::BusySpin => std::hint::spin_loop(),
+                WaitStrategy::Park { timeout } => {
+                    // Announce first, then check again, so a submit in between is not missed
+                    self.waker.idle.fetch_add(1, Ordering::SeqCst);
+                    if self.queue.is_empty() && self.running.load(Ordering::Acquire) {
+                        std::thread::park_timeout(timeout);
+                    }
+                    self.waker.idle.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+/// Run every handler on one message; a panicking handler is logged and
+/// counted, the others and the worker carry on
+fn dispatch(
+    handlers: &Handlers,
+    metrics: &EngineMetrics,
+    msg: &EngineMessage,
+) {
+    let start = Instant::now();
+    metrics.messages_received.fetch_add(1, Ordering::Relaxed);
+    metrics.message_rate.mark();
+    if matches!(msg, EngineMessage::NewOrder(_) | EngineMessage::NewBracket(_)) {
+        metrics.orders_processed.fetch_add(1, Ordering::Relaxed);
+        metrics.order_rate.mark();
+    }
+
+    let handlers = handlers.read();
+    for handler in handlers.iter() {
+        match catch_unwind(AssertUnwindSafe(|| handler.handle(msg))) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("Handler error: {}", e),
+            Err(_) => {
+                metrics.handler_panics.fetch_add(1, Ordering::Relaxed);
+                tracing::error!("Handler panicked on {:?}", msg);
+            }
+        }
+    }
+
+    metrics.record_latency(start.elapsed().as_nanos() as u64);
 }
 
 #[cfg(test)]
@@ -269,7 +623,9 @@ mod tests {
             order_type: OrderType::Market,
             quantity: 1.0,
             price: None,
+            stop_price: None,
             timestamp: 0,
+            params: Default::default(),
         });
         
         assert!(engine.submit(msg).is_ok());
@@ -285,4 +641,256 @@ mod tests {
         assert!(engine.submit(msg.clone()).is_ok());
         assert!(matches!(engine.submit(msg), Err(EngineError::QueueFull)));
     }
+
+    struct Counter(AtomicU64);
+
+    impl MessageHandler for Counter {
+        fn handle(&self, _msg: &EngineMessage) -> Result<(), EngineError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    struct Panicker;
+
+    impl MessageHandler for Panicker {
+        fn handle(&self, msg: &EngineMessage) -> Result<(), EngineError> {
+            if matches!(msg, EngineMessage::CancelOrder(_)) {
+                panic!("handler bug");
+            }
+            Ok(())
+        }
+    }
+
+    fn wait_for(counter: &Counter, n: u64) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while counter.0.load(Ordering::SeqCst) < n {
+            if Instant::now() > deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+
+    #[test]
+    fn test_workers_and_panic_isolation() {
+        for wait in [WaitStrategy::BusySpin, WaitStrategy::Park { timeout: Duration::from_secs(5) }] {
+            let config = EngineConfig {
+                workers: 2,
+                wait,
+                lanes: LaneCapacity::uniform(64),
+                ..EngineConfig::default()
+            };
+            let engine = QuantumEngine::with_config(config);
+            let counter = Arc::new(Counter(AtomicU64::new(0)));
+            engine.add_handler(Arc::new(Panicker));
+            engine.add_handler(counter.clone());
+            engine.start().unwrap();
+
+            // Parked workers are woken by submit, not by the 5s timeout
+            for i in 0..10 {
+                engine.submit(EngineMessage::CancelOrder(i)).unwrap();
+            }
+            assert!(wait_for(&counter, 10), "{:?}", wait);
+            assert_eq!(engine.metrics().handler_panics.load(Ordering::Relaxed), 10);
+            assert!(engine.is_running());
+
+            assert_eq!(engine.stop().unwrap(), 0);
+            assert!(!engine.is_running());
+        }
+    }
+
+    #[test]
+    fn test_shutdown_message_stops_workers() {
+        let engine = QuantumEngine::new(16);
+        engine.start().unwrap();
+        engine.submit(EngineMessage::Shutdown).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while engine.is_running() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!engine.is_running());
+        engine.stop().unwrap();
+    }
+
+    #[test]
+    fn test_stop_drains_or_persists() {
+        let engine = QuantumEngine::new(16);
+        let counter = Arc::new(Counter(AtomicU64::new(0)));
+        engine.add_handler(counter.clone());
+        for i in 0..3 {
+            engine.submit(EngineMessage::CancelOrder(i)).unwrap();
+        }
+        assert_eq!(engine.stop().unwrap(), 3);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 3);
+
+        let path = std::env::temp_dir().join(format!("engine-{}", uuid::Uuid::new_v4())).join("pending.jsonl");
+        let config = EngineConfig { stop_mode: StopMode::Persist(path.clone()), ..EngineConfig::default() };
+        let engine = QuantumEngine::with_config(config.clone());
+        engine.submit(EngineMessage::ModifyOrder(7, 101.5)).unwrap();
+        engine.submit(EngineMessage::CancelOrder(8)).unwrap();
+        assert_eq!(engine.stop().unwrap(), 2);
+        assert!(path.exists());
+
+        // The next engine picks them up on start
+        let engine = QuantumEngine::with_config(config);
+        let counter = Arc::new(Counter(AtomicU64::new(0)));
+        engine.add_handler(counter.clone());
+        engine.start().unwrap();
+        assert!(wait_for(&counter, 2));
+        assert!(!path.exists());
+        engine.stop().unwrap();
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_failed_restore_keeps_engine_stopped_and_file() {
+        let path = std::env::temp_dir().join(format!("engine-{}", uuid::Uuid::new_v4())).join("pending.jsonl");
+        let config = EngineConfig { stop_mode: StopMode::Persist(path.clone()), ..EngineConfig::default() };
+        let engine = QuantumEngine::with_config(config.clone());
+        for i in 0..3 {
+            engine.submit(EngineMessage::CancelOrder(i)).unwrap();
+        }
+        assert_eq!(engine.stop().unwrap(), 3);
+
+        // Two cancel slots: the third persisted cancel doesn't fit
+        let small = EngineConfig { lanes: LaneCapacity::uniform(2), ..config.clone() };
+        let engine = QuantumEngine::with_config(small);
+        assert!(matches!(engine.start(), Err(EngineError::QueueFull)));
+        assert!(!engine.is_running());
+        let rest = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(rest.lines().count(), 1);
+
+        // Once there is room, start restores the rest and removes the file
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        engine.add_handler(recorder.clone());
+        engine.process_messages().unwrap();
+        engine.start().unwrap();
+        assert!(engine.is_running());
+        assert!(!path.exists());
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while recorder.0.lock().len() < 3 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        engine.stop().unwrap();
+        let handled = recorder.0.lock();
+        assert!(matches!(handled.as_slice(), [
+            EngineMessage::CancelOrder(0),
+            EngineMessage::CancelOrder(1),
+            EngineMessage::CancelOrder(2),
+        ]));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    struct Recorder(Mutex<Vec<EngineMessage>>);
+
+    impl MessageHandler for Recorder {
+        fn handle(&self, msg: &EngineMessage) -> Result<(), EngineError> {
+            self.0.lock().push(msg.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cancel_overtakes_market_data() {
+        let engine = QuantumEngine::new(4);
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        engine.add_handler(recorder.clone());
+
+        // Conflated: a thousand ticks on one symbol take one slot
+        for i in 0..1000 {
+            let update = MarketUpdate { symbol: "BTCUSDT".to_string(), price: i as f64, volume: 0.0, timestamp: i };
+            engine.submit(EngineMessage::MarketData(update)).unwrap();
+        }
+        engine.submit(EngineMessage::CancelOrder(1)).unwrap();
+        assert_eq!(engine.process_messages().unwrap(), 2);
+
+        let handled = recorder.0.lock();
+        assert!(matches!(handled[0], EngineMessage::CancelOrder(1)));
+        assert!(matches!(&handled[1], EngineMessage::MarketData(u) if u.price == 999.0));
+        let market = &engine.lane_stats()[3];
+        assert_eq!((market.submitted, market.conflated, market.processed), (1000, 999, 1));
+    }
+
+    #[test]
+    fn test_market_data_from_bus() {
+        use crate::market::Quote;
+        use crate::scanner::market_data::{OrderBook, OrderBookLevel};
+
+        let events = AppBus::new(16);
+        let market_data = Arc::new(MarketDataProcessor::new());
+        let engine = QuantumEngine::new(16);
+        let feeder = engine.feed_market_data(&events, market_data.clone()).unwrap();
+
+        let mut book = OrderBook::for_venue("paper", "BTCUSDT".to_string());
+        book.asks.push(OrderBookLevel { price: 100.5, quantity: 1.0 });
+        events.publish(book).unwrap();
+
+        events
+            .publish(Quote {
+                symbol: "BTCUSDT".to_string(),
+                price: 100.0,
+                timestamp: 1,
+                bid: None,
+                ask: None,
+                volume: Some(2.0),
+            })
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while engine.queue_utilization() == 0.0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(engine.process_messages().unwrap(), 1);
+        assert_eq!(engine.metrics().messages_received.load(Ordering::Relaxed), 1);
+        assert_eq!(engine.metrics().latency_snapshot().count, 1);
+
+        // The book is kept for routing; it is not market data for the engine
+        while market_data.get_venue_order_book("paper", "BTCUSDT").is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(market_data.get_venue_order_book("paper", "BTCUSDT").unwrap().asks[0].price, 100.5);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(engine.process_messages().unwrap(), 0);
+
+        drop(engine);
+        feeder.join().unwrap();
+    }
+
+    #[test]
+    fn test_market_data_from_processor() {
+        use crate::scanner::market_data::{Trade, TradeSide};
+
+        let market_data = Arc::new(MarketDataProcessor::new());
+        let engine = QuantumEngine::new(16);
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        engine.add_handler(recorder.clone());
+        let events = AppBus::new(16);
+        let feeder = engine.feed_market_data(&events, market_data.clone()).unwrap();
+
+        market_data.process_trade(Trade {
+            symbol: "BTCUSDT".to_string(),
+            price: 100.0,
+            quantity: 0.5,
+            side: TradeSide::Buy,
+            timestamp: 1,
+        });
+        market_data.process_quote("ETHUSDT".to_string(), 10.0, 11.0);
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut processed = 0;
+        while processed < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            processed += engine.process_messages().unwrap();
+        }
+        assert_eq!(processed, 2);
+        let handled = recorder.0.lock();
+        assert!(handled.iter().any(|m| matches!(m, EngineMessage::MarketData(u) if u.symbol == "BTCUSDT" && u.price == 100.0)));
+        assert!(handled.iter().any(|m| matches!(m, EngineMessage::MarketData(u) if u.symbol == "ETHUSDT" && u.price == 10.5)));
+        drop(handled);
+
+        drop(engine);
+        feeder.join().unwrap();
+    }
 }
@@ -1,9 +1,12 @@
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::bus::{AppBus, RiskEvent};
+
 /// Position information
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Position {
     pub symbol: String,
     pub quantity: f64,
@@ -24,7 +27,7 @@ impl Position {
 }
 
 /// Portfolio metrics
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct PortfolioMetrics {
     pub total_value: f64,
     pub total_pnl: f64,
@@ -82,7 +85,7 @@ impl Default for RiskLimits {
 }
 
 /// Circuit breaker state
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CircuitBreakerState {
     Normal,
     Warning,
@@ -105,6 +108,8 @@ pub struct RiskManager {
     trade_history: Arc<RwLock<Vec<TradeRecord>>>,
     /// Initial capital
     initial_capital: f64,
+    /// Where rejections and circuit breaker changes are published
+    events: Option<AppBus>,
 }
 
 #[derive(Clone, Debug)]
@@ -125,9 +130,16 @@ impl RiskManager {
             daily_returns: Arc::new(RwLock::new(Vec::new())),
             trade_history: Arc::new(RwLock::new(Vec::new())),
             initial_capital,
+            events: None,
         }
     }
 
+    /// Publish rejections and circuit breaker changes as `RiskEvent`s
+    pub fn with_events(mut self, events: AppBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     /// Calculate position size using Kelly Criterion
     pub fn calculate_kelly_position_size(
         &self,
@@ -149,8 +161,35 @@ impl RiskManager {
         capital * safe_kelly
     }
 
-    /// Check if order is allowed based on risk limits
-    pub fn check_order_risk(&self, _symbol: &str, quantity: f64, price: f64) -> Result<(), RiskError> {
+    /// Check if order is allowed based on risk limits. `quantity` is signed,
+    /// negative for a sell. An order that only reduces a position always
+    /// passes, breaker or not, so positions can still be closed.
+    pub fn check_order_risk(&self, symbol: &str, quantity: f64, price: f64) -> Result<(), RiskError> {
+        if self.reduces_position(symbol, quantity) {
+            return Ok(());
+        }
+        let result = self.evaluate_order_risk(quantity, price);
+        if let (Err(e), Some(events)) = (&result, &self.events) {
+            events.emit(RiskEvent::OrderRejected {
+                symbol: symbol.to_string(),
+                quantity,
+                price,
+                reason: e.to_string(),
+                timestamp: now_ms(),
+            });
+        }
+        result
+    }
+
+    /// Whether a signed `quantity` takes `symbol`'s position towards flat without flipping it
+    pub fn reduces_position(&self, symbol: &str, quantity: f64) -> bool {
+        self.positions
+            .read()
+            .get(symbol)
+            .is_some_and(|pos| pos.quantity * quantity < 0.0 && quantity.abs() <= pos.quantity.abs() + 1e-9)
+    }
+
+    fn evaluate_order_risk(&self, quantity: f64, price: f64) -> Result<(), RiskError> {
         // Check circuit breaker
         let breaker_state = self.circuit_breaker.read();
         if *breaker_state == CircuitBreakerState::Triggered {
@@ -165,10 +204,10 @@ impl RiskManager {
             return Err(RiskError::PositionSizeLimitExceeded);
         }
 
-        // Check concentration
-        let metrics = self.calculate_metrics();
-        if metrics.total_value > 0.0 {
-            let position_pct = (order_value / metrics.total_value) * 100.0;
+        // Check concentration against the latest equity (cash plus positions)
+        let equity = self.equity_curve.read().last().copied().unwrap_or(self.initial_capital);
+        if equity > 0.0 {
+            let position_pct = (order_value / equity) * 100.0;
             if position_pct > limits.max_concentration {
                 return Err(RiskError::ConcentrationLimitExceeded);
             }
@@ -177,19 +216,13 @@ impl RiskManager {
         Ok(())
     }
 
-    /// Update position
+    /// Apply a signed fill. Reducing or closing a position realizes PnL on
+    /// the closed quantity and records it as a trade; flipping it opens the
+    /// remainder at `price`.
     pub fn update_position(&self, symbol: String, quantity: f64, price: f64) {
         let mut positions = self.positions.write();
-        
-        if let Some(pos) = positions.get_mut(&symbol) {
-            let total_cost = pos.avg_price * pos.quantity + price * quantity;
-            pos.quantity += quantity;
-            if pos.quantity != 0.0 {
-                pos.avg_price = total_cost / pos.quantity;
-            }
-            pos.current_price = price;
-            pos.update_pnl();
-        } else {
+
+        let Some(pos) = positions.get_mut(&symbol) else {
             positions.insert(
                 symbol.clone(),
                 Position {
@@ -201,7 +234,57 @@ impl RiskManager {
                     realized_pnl: 0.0,
                 },
             );
+            return;
+        };
+
+        if pos.quantity * quantity < 0.0 {
+            let direction = pos.quantity.signum();
+            let closed = quantity.abs().min(pos.quantity.abs());
+            let pnl = (price - pos.avg_price) * closed * direction;
+            pos.realized_pnl += pnl;
+            self.trade_history.write().push(TradeRecord {
+                symbol: symbol.clone(),
+                pnl,
+                return_pct: if pos.avg_price > 0.0 { (price / pos.avg_price - 1.0) * 100.0 * direction } else { 0.0 },
+                timestamp: now_ms(),
+            });
+
+            pos.quantity += quantity;
+            if pos.quantity.abs() < 1e-12 {
+                pos.quantity = 0.0;
+            } else if pos.quantity.signum() != direction {
+                pos.avg_price = price;
+            }
+        } else {
+            let total_cost = pos.avg_price * pos.quantity + price * quantity;
+            pos.quantity += quantity;
+            if pos.quantity != 0.0 {
+                pos.avg_price = total_cost / pos.quantity;
+            }
         }
+        pos.current_price = price;
+        pos.update_pnl();
+    }
+
+    /// Mark an open position to `price`; symbols without a position are ignored
+    pub fn mark_price(&self, symbol: &str, price: f64) -> bool {
+        let mut positions = self.positions.write();
+        let Some(pos) = positions.get_mut(symbol) else {
+            return false;
+        };
+        pos.current_price = price;
+        pos.update_pnl();
+        true
+    }
+
+    /// Trades closed so far, each reduction of a position counting as one
+    pub fn trade_count(&self) -> usize {
+        self.trade_history.read().len()
+    }
+
+    /// Snapshot of all positions
+    pub fn positions(&self) -> Vec<Position> {
+        self.positions.read().values().cloned().collect()
     }
 
     /// Calculate real-time VaR (95% confidence)
@@ -330,6 +413,7 @@ impl RiskManager {
         let limits = self.limits.read();
         
         let mut breaker = self.circuit_breaker.write();
+        let previous = breaker.clone();
         if current_dd.abs() >= limits.max_drawdown_pct {
             *breaker = CircuitBreakerState::Triggered;
             tracing::error!("Circuit breaker TRIGGERED! Drawdown: {:.2}%", current_dd);
@@ -339,6 +423,18 @@ impl RiskManager {
         } else {
             *breaker = CircuitBreakerState::Normal;
         }
+        self.publish_breaker_change(previous, breaker.clone(), current_dd);
+    }
+
+    fn publish_breaker_change(&self, from: CircuitBreakerState, to: CircuitBreakerState, drawdown_pct: f64) {
+        if let Some(events) = self.events.as_ref().filter(|_| from != to) {
+            events.emit(RiskEvent::CircuitBreaker {
+                from,
+                to,
+                drawdown_pct,
+                timestamp: now_ms(),
+            });
+        }
     }
 
     /// Calculate comprehensive portfolio metrics
@@ -402,9 +498,31 @@ impl RiskManager {
 
     /// Reset circuit breaker (manual intervention)
     pub fn reset_circuit_breaker(&self) {
+        // Equity before breaker, same lock order as `update_equity`
+        let drawdown_pct = self
+            .equity_curve
+            .read()
+            .last()
+            .map(|eq| (eq - self.initial_capital) / self.initial_capital * 100.0)
+            .unwrap_or(0.0);
         let mut breaker = self.circuit_breaker.write();
-        *breaker = CircuitBreakerState::Normal;
+        let previous = std::mem::replace(&mut *breaker, CircuitBreakerState::Normal);
         tracing::info!("Circuit breaker manually reset");
+        self.publish_breaker_change(previous, CircuitBreakerState::Normal, drawdown_pct);
+    }
+
+    /// Trigger the circuit breaker regardless of drawdown (kill switch)
+    pub fn trip_circuit_breaker(&self, reason: &str) {
+        let drawdown_pct = self
+            .equity_curve
+            .read()
+            .last()
+            .map(|eq| (eq - self.initial_capital) / self.initial_capital * 100.0)
+            .unwrap_or(0.0);
+        let mut breaker = self.circuit_breaker.write();
+        let previous = std::mem::replace(&mut *breaker, CircuitBreakerState::Triggered);
+        tracing::error!("Circuit breaker TRIGGERED manually: {}", reason);
+        self.publish_breaker_change(previous, CircuitBreakerState::Triggered, drawdown_pct);
     }
 
     /// Get circuit breaker state
@@ -413,6 +531,13 @@ impl RiskManager {
     }
 }
 
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 #[derive(Debug, Clone)]
 pub enum RiskError {
     CircuitBreakerTriggered,
@@ -457,4 +582,56 @@ mod tests {
         rm.update_equity(75000.0); // 25% drawdown
         assert_eq!(rm.get_circuit_breaker_state(), CircuitBreakerState::Triggered);
     }
+
+    #[test]
+    fn test_realized_trades() {
+        let rm = RiskManager::new(100000.0);
+        rm.update_position("BTCUSDT".to_string(), 2.0, 100.0);
+        rm.update_position("BTCUSDT".to_string(), -1.0, 110.0); // +10
+        rm.update_position("BTCUSDT".to_string(), -2.0, 90.0); // -10, then short 1 at 90
+
+        let position = &rm.positions()[0];
+        assert_eq!((position.quantity, position.avg_price, position.realized_pnl), (-1.0, 90.0, 0.0));
+        assert_eq!(rm.trade_count(), 2);
+        let metrics = rm.calculate_metrics();
+        assert_eq!((metrics.win_rate, metrics.profit_factor), (50.0, 1.0));
+    }
+
+    #[test]
+    fn test_reducing_orders_pass_tripped_breaker() {
+        let rm = RiskManager::new(100000.0);
+        rm.update_position("ETHUSDT".to_string(), 2.0, 10.0);
+        rm.trip_circuit_breaker("test");
+
+        assert!(rm.check_order_risk("ETHUSDT", -2.0, 10.0).is_ok());
+        assert!(rm.check_order_risk("ETHUSDT", -3.0, 10.0).is_err()); // Flips it
+        assert!(rm.check_order_risk("ETHUSDT", 1.0, 10.0).is_err());
+    }
+
+    #[test]
+    fn test_risk_events() {
+        let events = AppBus::new(64);
+        let rx = events.subscribe::<RiskEvent>(">").unwrap();
+        let rm = RiskManager::new(100000.0).with_events(events);
+
+        rm.update_equity(75000.0);
+        assert!(rm.check_order_risk("BTCUSDT", 1.0, 100.0).is_err());
+        rm.reset_circuit_breaker();
+        rm.reset_circuit_breaker(); // No change, no event
+
+        let timeout = std::time::Duration::from_secs(1);
+        assert!(matches!(
+            rx.recv_timeout(timeout),
+            Some(RiskEvent::CircuitBreaker { to: CircuitBreakerState::Triggered, .. })
+        ));
+        assert!(matches!(
+            rx.recv_timeout(timeout),
+            Some(RiskEvent::OrderRejected { reason, .. }) if reason == "Circuit breaker is triggered"
+        ));
+        assert!(matches!(
+            rx.recv_timeout(timeout),
+            Some(RiskEvent::CircuitBreaker { from: CircuitBreakerState::Triggered, to: CircuitBreakerState::Normal, .. })
+        ));
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(100)).is_none());
+    }
 }
@@ -0,0 +1,301 @@
+//! Cost-aware route selection
+//!
+//! Every route that supports the order type is scored on what filling there
+//! is expected to cost, in basis points against the best price quoted on any
+//! venue: the fee plus the price impact of walking that venue's book from
+//! `MarketDataProcessor`. Higher route priority takes `PRIORITY_BPS` per level
+//! off the score, so it only decides near-ties.
+//!
+//! An order goes whole to the cheapest route that can take it. When none can,
+//! because of `max_order_size` or because a market order is larger than the
+//! visible depth, it is split across routes, cheapest first.
+
+use serde::Serialize;
+
+use super::order_router::{ExchangeRoute, Order, OrderSide, OrderType, RouterError};
+use crate::scanner::market_data::{OrderBook, OrderBookLevel};
+
+/// Score bonus per priority level, in basis points
+pub const PRIORITY_BPS: f64 = 0.1;
+
+/// Quantities below this are treated as fully allocated
+const EPSILON: f64 = 1e-9;
+
+/// Part of an order sent to one venue
+#[derive(Clone, Debug, Serialize)]
+pub struct RouteLeg {
+    pub venue: String,
+    pub quantity: f64,
+    pub expected_price: Option<f64>, // Average over the book levels it is expected to take
+    pub cost_bps: f64,               // Fee plus impact
+}
+
+/// Where an order was sent and why
+#[derive(Clone, Debug, Serialize)]
+pub struct RoutingDecision {
+    pub legs: Vec<RouteLeg>,
+    pub reason: String,
+    pub candidates: usize, // Routes supporting the order type
+}
+
+impl RoutingDecision {
+    pub fn is_split(&self) -> bool {
+        self.legs.len() > 1
+    }
+}
+
+/// One route with the book levels an order on `side` would take there
+struct Candidate<'a> {
+    route: &'a ExchangeRoute,
+    levels: Option<Vec<OrderBookLevel>>, // None without a book for this venue
+}
+
+impl Candidate<'_> {
+    /// Largest quantity this route can take: its size limit, and for market
+    /// orders the visible depth (limit orders rest for the remainder)
+    fn capacity(&self, order: &Order) -> f64 {
+        let depth = match (&self.levels, &order.order_type) {
+            (Some(levels), OrderType::Market) => levels.iter().map(|l| l.quantity).sum(),
+            _ => f64::INFINITY,
+        };
+        self.route.max_order_size.min(depth)
+    }
+
+    /// Average price of `quantity` walking the book, the remainder at the
+    /// limit price or `fallback`
+    fn expected_price(&self, order: &Order, quantity: f64, fallback: Option<f64>) -> Option<f64> {
+        let rest_price = order.price.or(fallback);
+        let Some(levels) = &self.levels else {
+            return rest_price;
+        };
+
+        let (mut taken, mut value) = (0.0, 0.0);
+        for level in levels {
+            let take = level.quantity.min(quantity - taken);
+            if take <= 0.0 {
+                break;
+            }
+            taken += take;
+            value += take * level.price;
+        }
+        let remainder = quantity - taken;
+        if remainder > EPSILON {
+            value += remainder * rest_price.or(levels.last().map(|l| l.price))?;
+        }
+        Some(value / quantity)
+    }
+
+    /// Fee plus impact against `reference`, in basis points; lower is better
+    fn cost_bps(&self, order: &Order, quantity: f64, reference: Option<f64>) -> (f64, Option<f64>) {
+        let fee_bps = self.route.fee_rate * 10_000.0;
+        let expected = self.expected_price(order, quantity, reference);
+        let impact_bps = match (expected, reference) {
+            (Some(price), Some(reference)) if reference > 0.0 => match order.side {
+                OrderSide::Buy => (price / reference - 1.0) * 10_000.0,
+                OrderSide::Sell => (1.0 - price / reference) * 10_000.0,
+            },
+            _ => 0.0,
+        };
+        (fee_bps + impact_bps, expected)
+    }
+
+    fn score(&self, cost_bps: f64) -> f64 {
+        cost_bps - self.route.priority as f64 * PRIORITY_BPS
+    }
+}
+
+/// Levels an order on `side` takes, best first; limit orders only those at or
+/// better than their limit
+fn taking_levels(book: &OrderBook, order: &Order) -> Vec<OrderBookLevel> {
+    let levels = match order.side {
+        OrderSide::Buy => &book.asks,
+        OrderSide::Sell => &book.bids,
+    };
+    levels
+        .iter()
+        .filter(|level| match (order.price, &order.side) {
+            (Some(limit), OrderSide::Buy) if order.order_type != OrderType::Market => level.price <= limit,
+            (Some(limit), OrderSide::Sell) if order.order_type != OrderType::Market => level.price >= limit,
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Best top-of-book for the taking side across venue books, else the consolidated book
+fn reference_price(order: &Order, venue_books: &[Option<OrderBook>], consolidated: Option<&OrderBook>) -> Option<f64> {
+    let top = |book: &OrderBook| match order.side {
+        OrderSide::Buy => book.best_ask().map(|l| l.price),
+        OrderSide::Sell => book.best_bid().map(|l| l.price),
+    };
+    let best = venue_books.iter().flatten().filter_map(top).reduce(|a, b| match order.side {
+        OrderSide::Buy => a.min(b),
+        OrderSide::Sell => a.max(b),
+    });
+    best.or_else(|| consolidated.and_then(top))
+}
+
+/// Decide where `order` goes. `venue_book` returns the book of one venue for
+/// the order's symbol; `consolidated` is the symbol's venue-less book, used
+/// for the reference price only.
+pub fn plan_route(
+    order: &Order,
+    routes: &[ExchangeRoute],
+    venue_book: impl Fn(&str) -> Option<OrderBook>,
+    consolidated: Option<&OrderBook>,
+) -> Result<RoutingDecision, RouterError> {
+    let eligible: Vec<&ExchangeRoute> = routes
+        .iter()
+        .filter(|route| route.supports_order_types.contains(&order.order_type))
+        .collect();
+    if eligible.is_empty() {
+        return Err(RouterError::NoRouteAvailable);
+    }
+
+    let books: Vec<Option<OrderBook>> = eligible.iter().map(|route| venue_book(&route.name)).collect();
+    let reference = reference_price(order, &books, consolidated);
+    let candidates: Vec<Candidate> = eligible
+        .iter()
+        .zip(books)
+        .map(|(route, book)| Candidate {
+            route,
+            levels: book.map(|book| taking_levels(&book, order)),
+        })
+        .collect();
+
+    let leg = |candidate: &Candidate, quantity: f64| {
+        let (cost_bps, expected_price) = candidate.cost_bps(order, quantity, reference);
+        RouteLeg {
+            venue: candidate.route.name.clone(),
+            quantity,
+            expected_price,
+            cost_bps,
+        }
+    };
+    let by_score = |a: &(&Candidate, RouteLeg), b: &(&Candidate, RouteLeg)| {
+        a.0.score(a.1.cost_bps).total_cmp(&b.0.score(b.1.cost_bps))
+    };
+
+    // Whole order on one route if any can take it
+    let whole = candidates
+        .iter()
+        .filter(|c| order.quantity >= c.route.min_order_size && order.quantity <= c.capacity(order) + EPSILON)
+        .map(|c| (c, leg(c, order.quantity)))
+        .min_by(by_score);
+    if let Some((candidate, leg)) = whole {
+        let reason = format!(
+            "{}: {:.2} bps expected (fee {:.2} bps), best of {} route(s)",
+            leg.venue,
+            leg.cost_bps,
+            candidate.route.fee_rate * 10_000.0,
+            candidates.len()
+        );
+        return Ok(RoutingDecision { legs: vec![leg], reason, candidates: candidates.len() });
+    }
+
+    // Otherwise fill the cheapest route to capacity, then the next
+    let mut remaining = order.quantity;
+    let mut unused: Vec<&Candidate> = candidates.iter().collect();
+    let mut legs = Vec::new();
+    while remaining > EPSILON {
+        let next = unused
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let quantity = remaining.min(c.capacity(order));
+                (quantity >= c.route.min_order_size && quantity > EPSILON).then(|| (i, (*c, leg(c, quantity))))
+            })
+            .min_by(|a, b| by_score(&a.1, &b.1));
+        let Some((index, (_, leg))) = next else {
+            return Err(RouterError::ValidationFailed(format!(
+                "Only {} of {} can be routed within size limits and visible depth",
+                order.quantity - remaining,
+                order.quantity
+            )));
+        };
+        remaining -= leg.quantity;
+        legs.push(leg);
+        unused.remove(index);
+    }
+
+    let reason = format!(
+        "Split across {} routes, larger than any one can take: {}",
+        legs.len(),
+        legs.iter()
+            .map(|leg| format!("{} {} at {:.2} bps", leg.venue, leg.quantity, leg.cost_bps))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(RoutingDecision { legs, reason, candidates: candidates.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(name: &str, priority: u8, fee_rate: f64, max_order_size: f64) -> ExchangeRoute {
+        ExchangeRoute {
+            name: name.to_string(),
+            priority,
+            fee_rate,
+            min_order_size: 0.0,
+            max_order_size,
+            supports_order_types: vec![OrderType::Market, OrderType::Limit],
+        }
+    }
+
+    fn book(asks: &[(f64, f64)]) -> OrderBook {
+        let mut book = OrderBook::new("BTCUSDT".to_string());
+        book.asks = asks.iter().map(|&(price, quantity)| OrderBookLevel { price, quantity }).collect();
+        book
+    }
+
+    fn buy(order_type: OrderType, quantity: f64, price: Option<f64>) -> Order {
+        Order::new(1, "BTCUSDT".to_string(), OrderSide::Buy, order_type, quantity, price)
+    }
+
+    #[test]
+    fn test_cheapest_route_wins() {
+        // Without books only fees and priority count
+        let routes = vec![route("a", 5, 0.0010, f64::MAX), route("b", 1, 0.0005, f64::MAX)];
+        let decision = plan_route(&buy(OrderType::Market, 1.0, None), &routes, |_| None, None).unwrap();
+        assert_eq!(decision.legs[0].venue, "b");
+        assert_eq!(decision.candidates, 2);
+
+        // Same fee: priority decides
+        let routes = vec![route("a", 5, 0.001, f64::MAX), route("b", 1, 0.001, f64::MAX)];
+        let decision = plan_route(&buy(OrderType::Market, 1.0, None), &routes, |_| None, None).unwrap();
+        assert_eq!(decision.legs[0].venue, "a");
+
+        // A cheaper fee loses to a better book
+        let routes = vec![route("a", 1, 0.0010, f64::MAX), route("b", 1, 0.0005, f64::MAX)];
+        let books = |venue: &str| Some(if venue == "a" { book(&[(100.0, 5.0)]) } else { book(&[(100.5, 5.0)]) });
+        let decision = plan_route(&buy(OrderType::Market, 1.0, None), &routes, books, None).unwrap();
+        assert_eq!(decision.legs[0].venue, "a");
+        assert_eq!(decision.legs[0].expected_price, Some(100.0));
+        assert!((decision.legs[0].cost_bps - 10.0).abs() < 1e-9);
+        assert!(decision.reason.starts_with("a: 10.00 bps"), "{}", decision.reason);
+    }
+
+    #[test]
+    fn test_split_on_depth_and_size() {
+        let routes = vec![route("a", 1, 0.001, f64::MAX), route("b", 1, 0.001, f64::MAX)];
+        let books = |venue: &str| Some(if venue == "a" { book(&[(100.0, 3.0)]) } else { book(&[(100.1, 2.0), (100.2, 5.0)]) });
+        let decision = plan_route(&buy(OrderType::Market, 5.0, None), &routes, books, None).unwrap();
+        // Only b shows enough depth for all 5, so it goes whole there rather than split
+        assert_eq!(decision.legs.len(), 1);
+        assert_eq!(decision.legs[0].venue, "b");
+
+        let decision = plan_route(&buy(OrderType::Market, 9.0, None), &routes, books, None).unwrap();
+        assert!(decision.is_split());
+        let legs: Vec<(&str, f64)> = decision.legs.iter().map(|l| (l.venue.as_str(), l.quantity)).collect();
+        assert_eq!(legs, vec![("a", 3.0), ("b", 6.0)]);
+
+        // Size limits without books
+        let routes = vec![route("a", 1, 0.001, 4.0), route("b", 1, 0.002, 4.0)];
+        let decision = plan_route(&buy(OrderType::Limit, 6.0, Some(100.0)), &routes, |_| None, None).unwrap();
+        let legs: Vec<(&str, f64)> = decision.legs.iter().map(|l| (l.venue.as_str(), l.quantity)).collect();
+        assert_eq!(legs, vec![("a", 4.0), ("b", 2.0)]);
+        assert!(plan_route(&buy(OrderType::Limit, 9.0, Some(100.0)), &routes, |_| None, None).is_err());
+    }
+}
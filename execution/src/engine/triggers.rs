@@ -0,0 +1,324 @@
+//! Trigger engine: stop, stop-limit and trailing stop orders held until the
+//! price reaches them
+//!
+//! `OrderManager` arms every such order here instead of sending it to a venue
+//! and feeds it each price update, from the provider quote feed and from
+//! trades and quotes pushed into `MarketDataProcessor`. A triggered order goes
+//! out as a market order, or a limit order for stop-limits and trailing stops
+//! with a `limit_offset`. A buy triggers at or above its stop, a sell at or below.
+//!
+//! A trailing stop follows the best price since it was armed (highest for a
+//! sell, lowest for a buy) at a fixed amount, a percentage of that price, or a
+//! multiple of the ATR.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::order_router::{Order, OrderSide, OrderType};
+use crate::market::providers::Candle;
+
+/// How far a trailing stop stays behind the best price
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailBy {
+    Amount(f64),
+    Percent(f64),
+    Atr { multiplier: f64, atr: f64 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trail {
+    pub by: TrailBy,
+    /// Go out as a limit this far past the trigger price instead of at market
+    pub limit_offset: Option<f64>,
+}
+
+impl Trail {
+    /// Distance from the best price `extreme`
+    pub fn distance(&self, extreme: f64) -> f64 {
+        match self.by {
+            TrailBy::Amount(amount) => amount,
+            TrailBy::Percent(pct) => extreme * pct / 100.0,
+            TrailBy::Atr { multiplier, atr } => multiplier * atr,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let distance = match self.by {
+            TrailBy::Amount(amount) => amount,
+            TrailBy::Percent(pct) if pct < 100.0 => pct,
+            TrailBy::Percent(_) => return false,
+            TrailBy::Atr { multiplier, atr } => multiplier * atr,
+        };
+        distance > 0.0 && self.limit_offset.is_none_or(|offset| offset >= 0.0)
+    }
+}
+
+/// An armed order, for the API
+#[derive(Clone, Debug, Serialize)]
+pub struct Trigger {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    /// None for a trailing stop until its first price
+    pub stop: Option<f64>,
+    /// Limit of a stop-limit
+    pub limit: Option<f64>,
+    pub trail: Option<Trail>,
+    /// Best price seen by a trailing stop
+    pub extreme: Option<f64>,
+    pub armed_at: u64,
+}
+
+impl Trigger {
+    fn reached(&self, price: f64) -> bool {
+        self.stop.is_some_and(|stop| match self.side {
+            OrderSide::Buy => price >= stop,
+            OrderSide::Sell => price <= stop,
+        })
+    }
+
+    /// Follow a new best price; true if the stop moved
+    fn trail(&mut self, price: f64) -> bool {
+        let Some(trail) = &self.trail else {
+            return false;
+        };
+        let better = match (self.extreme, &self.side) {
+            (None, _) => true,
+            (Some(extreme), OrderSide::Sell) => price > extreme,
+            (Some(extreme), OrderSide::Buy) => price < extreme,
+        };
+        if !better {
+            return false;
+        }
+        let distance = trail.distance(price);
+        self.extreme = Some(price);
+        self.stop = Some(match self.side {
+            OrderSide::Sell => price - distance,
+            OrderSide::Buy => price + distance,
+        });
+        true
+    }
+
+    /// What the order becomes when triggered at `price`
+    fn conversion(&self, price: f64) -> (OrderType, Option<f64>) {
+        let offset = self.trail.as_ref().and_then(|t| t.limit_offset);
+        match (&self.order_type, offset) {
+            (OrderType::StopLimit, _) => (OrderType::Limit, self.limit),
+            (_, Some(offset)) => {
+                let limit = match self.side {
+                    OrderSide::Sell => price - offset,
+                    OrderSide::Buy => price + offset,
+                };
+                (OrderType::Limit, Some(limit))
+            }
+            _ => (OrderType::Market, None),
+        }
+    }
+}
+
+/// An order whose trigger was reached
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triggered {
+    pub order_id: u64,
+    pub price: f64, // The update that triggered it
+    pub stop: f64,
+    pub timestamp: u64,
+    pub order_type: OrderType, // Market or Limit
+    pub limit: Option<f64>,
+}
+
+/// What a price update did
+#[derive(Clone, Debug, Default)]
+pub struct TriggerUpdate {
+    pub triggered: Vec<Triggered>,
+    /// Trailing stops that moved: order id and new stop
+    pub moved: Vec<(u64, f64)>,
+}
+
+/// Armed orders by id
+#[derive(Default)]
+pub struct TriggerBook {
+    triggers: Mutex<HashMap<u64, Trigger>>,
+}
+
+impl TriggerBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Arm a stop, stop-limit or trailing stop order
+    pub fn arm(&self, order: &Order, now_ms: u64) {
+        let trigger = Trigger {
+            order_id: order.id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            order_type: order.order_type.clone(),
+            stop: order.stop_price.filter(|_| order.trail.is_none()),
+            limit: order.price,
+            trail: order.trail.clone(),
+            extreme: None,
+            armed_at: now_ms,
+        };
+        self.triggers.lock().insert(order.id, trigger);
+    }
+
+    /// Trail and check every order on `symbol`; triggered ones are disarmed
+    pub fn on_price(&self, symbol: &str, price: f64, now_ms: u64) -> TriggerUpdate {
+        let mut update = TriggerUpdate::default();
+        self.triggers.lock().retain(|_, trigger| {
+            if trigger.symbol != symbol {
+                return true;
+            }
+            if trigger.trail(price) {
+                update.moved.extend(trigger.stop.map(|stop| (trigger.order_id, stop)));
+            }
+            if !trigger.reached(price) {
+                return true;
+            }
+            let (order_type, limit) = trigger.conversion(price);
+            update.triggered.push(Triggered {
+                order_id: trigger.order_id,
+                price,
+                stop: trigger.stop.unwrap_or(price),
+                timestamp: now_ms,
+                order_type,
+                limit,
+            });
+            false
+        });
+        update
+    }
+
+    /// New stop for an armed fixed stop; false if it is not armed
+    pub fn set_stop(&self, order_id: u64, stop: f64) -> bool {
+        match self.triggers.lock().get_mut(&order_id) {
+            Some(trigger) if trigger.trail.is_none() => {
+                trigger.stop = Some(stop);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// New limit for an armed stop-limit
+    pub fn set_limit(&self, order_id: u64, limit: f64) -> bool {
+        match self.triggers.lock().get_mut(&order_id) {
+            Some(trigger) => {
+                trigger.limit = Some(limit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn disarm(&self, order_id: u64) -> bool {
+        self.triggers.lock().remove(&order_id).is_some()
+    }
+
+    /// Armed orders, oldest first
+    pub fn armed(&self) -> Vec<Trigger> {
+        let mut armed: Vec<Trigger> = self.triggers.lock().values().cloned().collect();
+        armed.sort_by_key(|t| t.order_id);
+        armed
+    }
+}
+
+/// Wilder's average true range over `period` candles; None with fewer than `period + 1`
+pub fn atr(candles: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || candles.len() <= period {
+        return None;
+    }
+    let ranges: Vec<f64> = candles
+        .windows(2)
+        .map(|w| {
+            let (prev_close, c) = (w[0].close, &w[1]);
+            (c.high - c.low).max((c.high - prev_close).abs()).max((c.low - prev_close).abs())
+        })
+        .collect();
+    let first = ranges[..period].iter().sum::<f64>() / period as f64;
+    Some(ranges[period..].iter().fold(first, |atr, tr| (atr * (period - 1) as f64 + tr) / period as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(id: u64, side: OrderSide, order_type: OrderType, stop: Option<f64>, trail: Option<Trail>) -> Order {
+        let mut order = Order::new(id, "BTCUSDT".to_string(), side, order_type, 1.0, None);
+        order.stop_price = stop;
+        order.trail = trail;
+        order
+    }
+
+    #[test]
+    fn test_stops() {
+        let book = TriggerBook::new();
+        book.arm(&order(1, OrderSide::Buy, OrderType::Stop, Some(105.0), None), 0);
+        let mut stop_limit = order(2, OrderSide::Sell, OrderType::StopLimit, Some(95.0), None);
+        stop_limit.price = Some(94.0);
+        book.arm(&stop_limit, 0);
+
+        assert!(book.on_price("BTCUSDT", 100.0, 1).triggered.is_empty());
+        assert!(book.on_price("ETHUSDT", 200.0, 2).triggered.is_empty());
+        let fired = book.on_price("BTCUSDT", 95.0, 3).triggered;
+        assert_eq!(fired, vec![Triggered {
+            order_id: 2,
+            price: 95.0,
+            stop: 95.0,
+            timestamp: 3,
+            order_type: OrderType::Limit,
+            limit: Some(94.0),
+        }]);
+
+        assert!(book.set_stop(1, 110.0));
+        assert!(book.on_price("BTCUSDT", 106.0, 4).triggered.is_empty());
+        assert_eq!(book.on_price("BTCUSDT", 110.5, 5).triggered[0].order_type, OrderType::Market);
+        assert!(book.armed().is_empty());
+    }
+
+    #[test]
+    fn test_trailing() {
+        let book = TriggerBook::new();
+        let trail = |by: TrailBy, limit_offset: Option<f64>| Some(Trail { by, limit_offset });
+        book.arm(&order(1, OrderSide::Sell, OrderType::TrailingStop, None, trail(TrailBy::Amount(5.0), None)), 0);
+        book.arm(&order(2, OrderSide::Sell, OrderType::TrailingStop, None, trail(TrailBy::Percent(10.0), Some(1.0))), 0);
+        book.arm(&order(3, OrderSide::Buy, OrderType::TrailingStop, None, trail(TrailBy::Atr { multiplier: 2.0, atr: 1.5 }, None)), 0);
+
+        let mut moved = book.on_price("BTCUSDT", 100.0, 1).moved;
+        moved.sort_by_key(|m| m.0);
+        assert_eq!(moved, vec![(1, 95.0), (2, 90.0), (3, 103.0)]);
+
+        // Sells follow the high; the buy, trailing the low, triggers on the rally
+        let fired = book.on_price("BTCUSDT", 120.0, 2).triggered;
+        assert_eq!((fired[0].order_id, fired[0].stop, &fired[0].order_type), (3, 103.0, &OrderType::Market));
+        let stops: Vec<Option<f64>> = book.armed().iter().map(|t| t.stop).collect();
+        assert_eq!(stops, vec![Some(115.0), Some(108.0)]);
+
+        // A pullback does not move them back
+        let fired = book.on_price("BTCUSDT", 110.0, 3).triggered;
+        assert_eq!((fired[0].order_id, fired[0].stop), (1, 115.0));
+        let fired = book.on_price("BTCUSDT", 107.0, 4).triggered;
+        assert_eq!((fired[0].order_id, &fired[0].order_type, fired[0].limit), (2, &OrderType::Limit, Some(106.0)));
+    }
+
+    #[test]
+    fn test_atr() {
+        let candle = |high: f64, low: f64, close: f64| Candle {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: 0,
+            open: close,
+            high,
+            low,
+            close,
+            volume: 0.0,
+        };
+        // True ranges 2, 4 (gap above the close), 2
+        let candles = [candle(101.0, 99.0, 100.0), candle(102.0, 100.0, 101.0), candle(105.0, 104.0, 104.5), candle(105.0, 103.0, 104.0)];
+        assert_eq!(atr(&candles, 2), Some(2.5));
+        assert_eq!(atr(&candles, 3), Some(8.0 / 3.0));
+        assert_eq!(atr(&candles, 4), None);
+    }
+}
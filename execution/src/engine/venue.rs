@@ -0,0 +1,245 @@
+//! Execution venues
+//!
+//! A venue receives orders the router has assigned to it and reports fills.
+//! `PaperVenue` simulates one against the last traded price: market orders
+//! fill immediately, limit and stop orders rest until a price update reaches them.
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+use super::order_router::{Order, OrderSide, OrderType, RouterError};
+use super::quantum_engine::MarketUpdate;
+use crate::bus::Fill;
+
+/// Where routed orders are executed
+pub trait ExecutionVenue: Send + Sync {
+    /// Must match the `ExchangeRoute` name the router assigns
+    fn name(&self) -> &str;
+
+    /// Accept an order; returns the fills that happened immediately
+    fn submit(&self, order: &Order) -> Result<Vec<Fill>, RouterError>;
+
+    /// Pull a resting order; false if the venue no longer holds it
+    fn cancel(&self, order_id: u64) -> bool;
+
+    /// Price update; returns fills of resting orders it reached
+    fn on_market_data(&self, update: &MarketUpdate) -> Vec<Fill>;
+}
+
+/// Simulated venue filling at the last price it has seen
+pub struct PaperVenue {
+    name: String,
+    fee_rate: f64,
+    last_prices: DashMap<String, f64>,
+    resting: Mutex<HashMap<u64, Order>>,
+}
+
+impl PaperVenue {
+    pub fn new(name: impl Into<String>, fee_rate: f64) -> Self {
+        Self {
+            name: name.into(),
+            fee_rate,
+            last_prices: DashMap::new(),
+            resting: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Orders waiting for the price to reach them
+    #[cfg(test)]
+    pub fn resting_orders(&self) -> Vec<Order> {
+        self.resting.lock().values().cloned().collect()
+    }
+
+    fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_prices.get(symbol).map(|p| *p)
+    }
+
+    fn fill(&self, order: &Order, price: f64) -> Fill {
+        let quantity = order.remaining_quantity();
+        Fill {
+            order_id: order.id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            quantity,
+            price,
+            fee: quantity * price * self.fee_rate,
+            venue: self.name.clone(),
+            timestamp: now_ms(),
+        }
+    }
+
+    /// Execution price if the order can trade at `last`, None if it rests.
+    /// An incoming limit order takes the better price, a resting one its limit.
+    fn execution_price(order: &Order, last: f64, resting: bool) -> Option<f64> {
+        let limit_reached = |limit: f64| match order.side {
+            OrderSide::Buy => last <= limit,
+            OrderSide::Sell => last >= limit,
+        };
+        let stop_reached = |stop: f64| match order.side {
+            OrderSide::Buy => last >= stop,
+            OrderSide::Sell => last <= stop,
+        };
+
+        match order.order_type {
+            OrderType::Limit | OrderType::PostOnly => {
+                let limit = order.price.filter(|limit| limit_reached(*limit))?;
+                Some(if resting { limit } else { better(&order.side, last, limit) })
+            }
+            OrderType::Stop => order.stop_price.filter(|stop| stop_reached(*stop)).map(|_| last),
+            _ => Some(last),
+        }
+    }
+
+    /// Like `execution_price`, first turning a triggered stop-limit into a limit order
+    fn try_execute(order: &mut Order, last: f64, resting: bool) -> Option<f64> {
+        if order.order_type == OrderType::StopLimit {
+            let stop = order.stop_price?;
+            let triggered = match order.side {
+                OrderSide::Buy => last >= stop,
+                OrderSide::Sell => last <= stop,
+            };
+            if !triggered {
+                return None;
+            }
+            // Triggering sends it in as a new limit order
+            order.order_type = OrderType::Limit;
+            return Self::execution_price(order, last, false);
+        }
+        Self::execution_price(order, last, resting)
+    }
+}
+
+impl ExecutionVenue for PaperVenue {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn submit(&self, order: &Order) -> Result<Vec<Fill>, RouterError> {
+        // Without a price feed yet, a market order may still carry a reference price
+        let reference = order.price.filter(|_| order.order_type == OrderType::Market);
+        let Some(last) = self.last_price(&order.symbol).or(reference) else {
+            if order.order_type == OrderType::Market {
+                return Err(RouterError::ExchangeError(format!("No price for {}", order.symbol)));
+            }
+            self.resting.lock().insert(order.id, order.clone());
+            return Ok(Vec::new());
+        };
+
+        // Post-only must add liquidity, so a marketable one is refused
+        if order.order_type == OrderType::PostOnly && Self::execution_price(order, last, false).is_some() {
+            return Err(RouterError::ExchangeError("Post-only order would take liquidity".to_string()));
+        }
+
+        let mut order = order.clone();
+        match Self::try_execute(&mut order, last, false) {
+            Some(price) => Ok(vec![self.fill(&order, price)]),
+            None => {
+                self.resting.lock().insert(order.id, order);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    fn cancel(&self, order_id: u64) -> bool {
+        self.resting.lock().remove(&order_id).is_some()
+    }
+
+    fn on_market_data(&self, update: &MarketUpdate) -> Vec<Fill> {
+        self.last_prices.insert(update.symbol.clone(), update.price);
+
+        let mut resting = self.resting.lock();
+        let mut fills = Vec::new();
+        resting.retain(|_, order| {
+            if order.symbol != update.symbol {
+                return true;
+            }
+            match Self::try_execute(order, update.price, true) {
+                Some(price) => {
+                    fills.push(self.fill(order, price));
+                    false
+                }
+                None => true,
+            }
+        });
+        fills
+    }
+}
+
+/// The price that is better for the order's side
+fn better(side: &OrderSide, a: f64, b: f64) -> f64 {
+    match side {
+        OrderSide::Buy => a.min(b),
+        OrderSide::Sell => a.max(b),
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(price: f64) -> MarketUpdate {
+        MarketUpdate {
+            symbol: "BTCUSDT".to_string(),
+            price,
+            volume: 0.0,
+            timestamp: 0,
+        }
+    }
+
+    fn order(id: u64, side: OrderSide, order_type: OrderType, price: Option<f64>) -> Order {
+        Order::new(id, "BTCUSDT".to_string(), side, order_type, 2.0, price)
+    }
+
+    #[test]
+    fn test_market_and_limit_fills() {
+        let venue = PaperVenue::new("paper", 0.001);
+        assert!(venue.submit(&order(1, OrderSide::Buy, OrderType::Market, None)).is_err());
+
+        venue.on_market_data(&update(100.0));
+        let fills = venue.submit(&order(2, OrderSide::Buy, OrderType::Market, None)).unwrap();
+        assert_eq!(fills[0].price, 100.0);
+        assert!((fills[0].fee - 0.2).abs() < 1e-9);
+
+        // Marketable limit fills at the better price, the other one rests
+        let fills = venue.submit(&order(3, OrderSide::Buy, OrderType::Limit, Some(105.0))).unwrap();
+        assert_eq!(fills[0].price, 100.0);
+        assert!(venue.submit(&order(4, OrderSide::Buy, OrderType::Limit, Some(95.0))).unwrap().is_empty());
+        assert!(venue.submit(&order(5, OrderSide::Buy, OrderType::PostOnly, Some(101.0))).is_err());
+
+        assert!(venue.on_market_data(&update(97.0)).is_empty());
+        let fills = venue.on_market_data(&update(94.0));
+        assert_eq!((fills[0].order_id, fills[0].price), (4, 95.0));
+        assert!(venue.resting_orders().is_empty());
+    }
+
+    #[test]
+    fn test_stops_and_cancel() {
+        let venue = PaperVenue::new("paper", 0.0);
+        venue.on_market_data(&update(100.0));
+
+        let mut stop = order(1, OrderSide::Sell, OrderType::Stop, None);
+        stop.stop_price = Some(95.0);
+        let mut stop_limit = order(2, OrderSide::Sell, OrderType::StopLimit, Some(94.0));
+        stop_limit.stop_price = Some(96.0);
+        assert!(venue.submit(&stop).unwrap().is_empty());
+        assert!(venue.submit(&stop_limit).unwrap().is_empty());
+
+        let fills = venue.on_market_data(&update(95.5));
+        assert_eq!((fills[0].order_id, fills[0].price), (2, 95.5));
+        let fills = venue.on_market_data(&update(93.0));
+        assert_eq!((fills[0].order_id, fills[0].price), (1, 93.0));
+
+        let resting = order(3, OrderSide::Buy, OrderType::Limit, Some(90.0));
+        venue.submit(&resting).unwrap();
+        assert!(venue.cancel(3));
+        assert!(!venue.cancel(3));
+    }
+}
@@ -22,7 +22,7 @@ pub struct Signal {
     pub indicator: String,
     pub signal_type: SignalType,
     pub strength: f64, // 0.0 to 1.0
-    pub metadata: serde_json::Value,
+    pub metadata: serde_json::Value, // "value"/"threshold" keys feed the signal explanation
     pub timestamp: i64,
 }
 
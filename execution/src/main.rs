@@ -9,15 +9,18 @@ mod engine;
 mod backtest;
 mod smc;
 mod signal;
+mod metrics;
 
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
     http::{header, Method},
 };
+use std::future::IntoFuture;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
@@ -25,28 +28,53 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use api::{
     routes::sse_signals, 
-    signals::{get_signals, stream_signals},
+    signals::{get_bus_stats, get_signal_calibration, get_signal_stats, get_signals, get_tracked_signals, stream_signal_lifecycle, stream_signals},
     market::{get_fear_greed_index, get_vix, get_movers},
     news::get_news,
     get_performance_metrics,
     get_chart_data,
-    LiveSignal,
+    get_smc_zones,
+    auth::require_order_token,
+    events::stream_events,
+    orders::{
+        cancel_bracket, cancel_order, get_brackets, get_engine_lanes, get_order_progress, get_orders, get_portfolio,
+        get_triggers, kill_switch, pause_order, resume_order, submit_bracket, submit_order, submit_signal_bracket,
+    },
+};
+use bus::{
+    AppBus, BackpressurePolicy, EventLog, EventLogConfig, RecvError, RedisBridge, RedisBridgeConfig, SignalBus,
+    SubscriberOptions,
 };
-use bus::SignalBus;
 use config::CONFIG;
-use scanner::Scanner;
+use engine::{
+    default_route, Conflation, EngineConfig, OrderManager, OrderRouter, PaperVenue, QuantumEngine, RiskManager, StopMode,
+    WaitStrategy,
+};
+use scanner::{market_data::MarketDataProcessor, ScanStats, Scanner};
 use market::ProviderManager;
+use smc::SmcStreams;
+use signal::{
+    EnhancedSignal, FileSignalStore, MemorySignalStore, SignalRepository, SignalTracker, StoreRetention,
+    SignalTransition, TrackerConfig,
+};
 
 /// Shared application state
 #[derive(Clone)]
 struct AppState {
-    bus: SignalBus<LiveSignal>,
+    bus: SignalBus<EnhancedSignal>,
     provider_manager: Arc<ProviderManager>,
-    signal_buffer: Arc<RwLock<Vec<LiveSignal>>>,
+    signal_store: Arc<dyn SignalRepository>,
+    tracker: Arc<SignalTracker>,
+    lifecycle_bus: SignalBus<SignalTransition>,
+    events: AppBus,
+    engine: Arc<QuantumEngine>,
+    oms: Arc<OrderManager>,
+    scan_stats: Arc<ScanStats>,
+    smc_streams: Arc<SmcStreams>,
 }
 
 // Implement FromRef to allow individual state extractors
-impl axum::extract::FromRef<AppState> for SignalBus<LiveSignal> {
+impl axum::extract::FromRef<AppState> for SignalBus<EnhancedSignal> {
     fn from_ref(state: &AppState) -> Self {
         state.bus.clone()
     }
@@ -58,9 +86,51 @@ impl axum::extract::FromRef<AppState> for Arc<ProviderManager> {
     }
 }
 
-impl axum::extract::FromRef<AppState> for Arc<RwLock<Vec<LiveSignal>>> {
+impl axum::extract::FromRef<AppState> for Arc<dyn SignalRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.signal_store.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Arc<SignalTracker> {
+    fn from_ref(state: &AppState) -> Self {
+        state.tracker.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for SignalBus<SignalTransition> {
+    fn from_ref(state: &AppState) -> Self {
+        state.lifecycle_bus.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for AppBus {
     fn from_ref(state: &AppState) -> Self {
-        state.signal_buffer.clone()
+        state.events.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Arc<QuantumEngine> {
+    fn from_ref(state: &AppState) -> Self {
+        state.engine.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Arc<OrderManager> {
+    fn from_ref(state: &AppState) -> Self {
+        state.oms.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Arc<ScanStats> {
+    fn from_ref(state: &AppState) -> Self {
+        state.scan_stats.clone()
+    }
+}
+
+impl axum::extract::FromRef<AppState> for Arc<SmcStreams> {
+    fn from_ref(state: &AppState) -> Self {
+        state.smc_streams.clone()
     }
 }
 
@@ -83,42 +153,172 @@ async fn main() {
         tracing::warn!("Continuing with partial configuration for development...");
     }
 
-    // Create signal bus with capacity for 256 messages
-    let bus = SignalBus::<LiveSignal>::new(256);
+    // Durable event log behind the buses, so SSE clients can resume after a disconnect
+    let event_log = CONFIG.event_log_dir.as_ref().and_then(|dir| {
+        EventLog::open(event_log_config(dir))
+            .map_err(|e| tracing::error!("{}; streams will not be replayable", e))
+            .ok()
+            .map(Arc::new)
+    });
+
+    // App bus: quotes, candles, signals, orders, fills, risk and news by topic.
+    // `EVENT_LOG_TOPICS` are logged (not quotes by default), apart from the SSE
+    // streams so their offsets stay dense
+    let events = match &CONFIG.event_log_dir {
+        Some(dir) if !CONFIG.event_log_topics.is_empty() => {
+            match EventLog::open(event_log_config(&format!("{}/app", dir))) {
+                Ok(log) => AppBus::with_log(65536, Arc::new(log), &CONFIG.event_log_topics),
+                Err(e) => {
+                    tracing::error!("{}; app events will not be replayable", e);
+                    AppBus::new(65536)
+                }
+            }
+        }
+        _ => AppBus::new(65536),
+    };
+
+    // Mirror app bus topics across instances through Redis pub/sub
+    let bridge = if CONFIG.has_redis_bridge() {
+        let mut config = RedisBridgeConfig::new(&CONFIG.redis_url);
+        for pattern in &CONFIG.redis_bridge_outbound {
+            config = config.outbound(pattern.clone());
+        }
+        for pattern in &CONFIG.redis_bridge_inbound {
+            config = config.inbound(pattern.clone());
+        }
+        let started = match RedisBridge::new(config).map(Arc::new) {
+            Ok(bridge) => bridge
+                .clone()
+                .start(events.topic_bus().clone())
+                .await
+                .map(|handle| (bridge, handle)),
+            Err(e) => Err(e),
+        };
+        match started {
+            Ok((bridge, handle)) => {
+                tracing::info!("✅ Redis bridge started as node {}", bridge.node_id());
+                Some((bridge, handle))
+            }
+            Err(e) => {
+                tracing::error!("Redis bridge not started: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Create signal bus, 256 queued messages per subscriber; REST/SSE emit `WireSignal`
+    let bus = match &event_log {
+        Some(log) => SignalBus::<EnhancedSignal>::with_log(256, log.clone(), "signals.emitted"),
+        None => SignalBus::<EnhancedSignal>::new(256),
+    };
 
     // Create provider manager for real market data
     let provider_manager = Arc::new(ProviderManager::new());
     tracing::info!("✅ Provider manager initialized");
 
-    // Create shared signal buffer (stores last 100 signals)
-    let signal_buffer = Arc::new(RwLock::new(Vec::<LiveSignal>::new()));
+    // Persistent signal store (falls back to memory if the file can't be opened)
+    let retention = StoreRetention {
+        max_signals: CONFIG.signal_store_max_signals,
+        compact_bytes: CONFIG.signal_store_compact_mb * 1024 * 1024,
+    };
+    let signal_store: Arc<dyn SignalRepository> = match FileSignalStore::open(&CONFIG.signal_store_path, retention) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            tracing::error!("{}; signals will not survive a restart", e);
+            Arc::new(MemorySignalStore::new())
+        }
+    };
+
+    // Signals feed the lifecycle tracker, which publishes transitions
+    let lifecycle_bus = match &event_log {
+        Some(log) => SignalBus::<SignalTransition>::with_log(256, log.clone(), "signals.lifecycle"),
+        None => SignalBus::<SignalTransition>::new(256),
+    };
+    let tracker = Arc::new(
+        SignalTracker::new(TrackerConfig::default(), lifecycle_bus.clone()).with_store(signal_store.clone()),
+    );
+    tracing::info!("✅ Signal tracker resumed {} open signals", tracker.restore());
+
+    // Order management on the paper venue
+    let engine_config = EngineConfig {
+        workers: CONFIG.engine_workers,
+        wait: if CONFIG.engine_busy_spin {
+            WaitStrategy::BusySpin
+        } else {
+            WaitStrategy::Park { timeout: Duration::from_millis(100) }
+        },
+        stop_mode: match &CONFIG.engine_pending_path {
+            Some(path) => StopMode::Persist(path.into()),
+            None => StopMode::Drain,
+        },
+        conflation: if CONFIG.engine_conflate { Conflation::LatestPerSymbol } else { Conflation::None },
+        ..EngineConfig::default()
+    };
+    let (engine, oms) = start_order_management(&events, engine_config);
+
+    // TWAP/VWAP slices also go out on market data; this keeps quiet symbols on schedule
+    let algo_oms = oms.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_millis(250));
+        loop {
+            ticker.tick().await;
+            algo_oms.run_algos(chrono::Utc::now().timestamp_millis() as u64);
+        }
+    });
+
+    let scan_stats = Arc::new(ScanStats::default());
+
+    // SMC zones per symbol/timeframe, kept across candles and fed by closed candles
+    let smc_streams = Arc::new(SmcStreams::default());
+    tokio::spawn(smc_streams.clone().run(events.clone()));
 
     // Create app state
     let app_state = AppState {
         bus: bus.clone(),
         provider_manager: provider_manager.clone(),
-        signal_buffer: signal_buffer.clone(),
+        signal_store: signal_store.clone(),
+        tracker: tracker.clone(),
+        lifecycle_bus,
+        events: events.clone(),
+        engine: engine.clone(),
+        oms,
+        scan_stats: scan_stats.clone(),
+        smc_streams: smc_streams.clone(),
     };
 
-    // Start signal buffer populator - subscribes to bus and adds signals to buffer
-    let buffer_bus = bus.clone();
-    let buffer_ref = signal_buffer.clone();
+    // Track (and store) every signal until it resolves; the scanner waits for the tracker
+    let mut tracker_rx = bus.subscribe_with(
+        SubscriberOptions::new("tracker", BackpressurePolicy::Block { timeout_ms: 1000 }).capacity(1024),
+    );
+    let tracker_ref = tracker.clone();
     tokio::spawn(async move {
-        let mut rx = buffer_bus.subscribe();
-        while let Ok(signal) = rx.recv().await {
-            let mut buffer = buffer_ref.write().await;
-            buffer.insert(0, signal); // Add to front
-            if buffer.len() > 100 {
-                buffer.truncate(100); // Keep only last 100
+        loop {
+            match tracker_rx.recv().await {
+                Ok(signal) => {
+                    tracker_ref.track(signal);
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("Signal tracker lagged, {} signals not tracked", missed);
+                }
+                Err(e) => {
+                    tracing::error!("Signal tracker stopped: {}", e);
+                    break;
+                }
             }
         }
     });
+    tokio::spawn(tracker.clone().run(provider_manager.clone(), Duration::from_secs(15)));
 
     // Start scanner in background
     let scanner_bus = bus.clone();
+    let scanner_events = events.clone();
     let scanner_provider = provider_manager.clone();
     tokio::spawn(async move {
-        let mut scanner = Scanner::new(scanner_bus.sender(), scanner_provider);
+        let mut scanner = Scanner::new(scanner_bus, scanner_events, scanner_provider)
+            .with_stats(scan_stats)
+            .with_smc_streams(smc_streams);
         
         // Add real indicators to scanner
         // For now, we run without indicators - they can be added later
@@ -127,6 +327,9 @@ async fn main() {
         scanner.run().await;
     });
 
+    // Headlines go out on `news.<source>`
+    tokio::spawn(news::publish_live_news(events.clone(), Duration::from_secs(300)));
+
     // Setup CORS
     let cors = CorsLayer::new()
         .allow_origin(
@@ -135,10 +338,25 @@ async fn main() {
                 .filter_map(|origin: &String| origin.parse().ok())
                 .collect::<Vec<_>>()
         )
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
         .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]);
 
     // Build router
+    // Endpoints that trade need the API token, or a local client without one
+    let order_routes = Router::new()
+        .route("/api/orders", post(submit_order))
+        .route("/api/orders/:id", delete(cancel_order))
+        .route("/api/orders/:id/pause", post(pause_order))
+        .route("/api/orders/:id/resume", post(resume_order))
+        .route("/api/brackets", post(submit_bracket))
+        .route("/api/brackets/:id", delete(cancel_bracket))
+        .route("/api/signals/:id/bracket", post(submit_signal_bracket))
+        .route("/api/engine/kill-switch", post(kill_switch))
+        .route_layer(middleware::from_fn(require_order_token));
+    if CONFIG.order_api_token.is_none() {
+        tracing::warn!("ORDER_API_TOKEN not set: order endpoints only accept local clients");
+    }
+
     let app = Router::new()
         // Health check
         .route("/health", get(health_check))
@@ -146,10 +364,27 @@ async fn main() {
         // Signal endpoints
         .route("/api/signals", get(get_signals))
         .route("/api/signals/stream", get(stream_signals))
+        .route("/api/signals/tracked", get(get_tracked_signals))
+        .route("/api/signals/lifecycle", get(stream_signal_lifecycle))
+        .route("/api/signals/stats", get(get_signal_stats))
+        .route("/api/signals/calibration", get(get_signal_calibration))
+        .route("/api/bus/stats", get(get_bus_stats))
+        
+        // App bus events by topic pattern
+        .route("/api/events", get(stream_events))
         
         // Legacy SSE endpoint (keep for backwards compatibility)
         .route("/signals/live", get(sse_signals))
         
+        // Order management; see `order_routes` for submitting and cancelling
+        .route("/api/orders", get(get_orders))
+        .route("/api/orders/:id/progress", get(get_order_progress))
+        .route("/api/brackets", get(get_brackets))
+        .route("/api/triggers", get(get_triggers))
+        .route("/api/portfolio", get(get_portfolio))
+        .route("/api/engine/lanes", get(get_engine_lanes))
+        .merge(order_routes)
+        
         // Market data endpoints
         .route("/api/market/fear-greed", get(get_fear_greed_index))
         .route("/api/market/vix", get(get_vix))
@@ -158,6 +393,9 @@ async fn main() {
         // Chart endpoint
         .route("/api/chart/:symbol", get(get_chart_data))
         
+        // SMC zone overlays for charts
+        .route("/api/smc/:symbol", get(get_smc_zones))
+        
         // News endpoint
         .route("/api/news", get(get_news))
         
@@ -184,9 +422,117 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .await
-        .expect("Server error");
+    // SSE clients hold their connections open, so they get a grace period, not forever
+    let shutdown = Arc::new(tokio::sync::Notify::new());
+    let signalled = shutdown.clone();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            signalled.notify_one();
+        })
+        .into_future();
+    tokio::select! {
+        result = server => result.expect("Server error"),
+        _ = async {
+            shutdown.notified().await;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        } => tracing::warn!("Open connections did not close within 10s, shutting down anyway"),
+    }
+
+    // No new orders can arrive; finish or persist what the engine still holds
+    match engine.stop() {
+        Ok(pending) => tracing::info!("Engine stopped, {} queued messages handled", pending),
+        Err(e) => tracing::error!("Engine stopped with queued messages lost: {}", e),
+    }
+    if let Some((bridge, handle)) = bridge {
+        handle.stop();
+        let stats = bridge.stats();
+        tracing::info!(
+            "Redis bridge stopped: {} sent, {} received, {} echoes skipped, {} errors",
+            stats.sent,
+            stats.received,
+            stats.echoes_skipped,
+            stats.errors
+        );
+    }
+    // Events still queued on the app bus are logged by its dispatcher
+    if !events.wait_idle(Duration::from_secs(2)) {
+        tracing::warn!("App bus still dispatching at shutdown; the last events may not be logged");
+    }
+    if let Err(e) = signal_store.flush() {
+        tracing::error!("Failed to flush the signal store: {}", e);
+    }
+}
+
+/// Event log in `dir` with the configured retention and replay caps
+fn event_log_config(dir: &str) -> EventLogConfig {
+    let mut config = EventLogConfig::new(dir);
+    config.retention = Some(Duration::from_secs(CONFIG.event_log_retention_hours * 3600));
+    config.retention_bytes = Some(CONFIG.event_log_retention_mb * 1024 * 1024);
+    config.replay_records = CONFIG.event_log_replay_max;
+    config.replay_age = Some(Duration::from_secs(CONFIG.event_log_replay_hours * 3600));
+    config
+}
+
+/// Order management: engine -> risk check -> router -> paper venue, fills
+/// back into risk. Quotes on `events` reach the engine; books published there
+/// (`OrderBook::venue` set to the venue name) are used for cost-aware routing.
+fn start_order_management(events: &AppBus, engine_config: EngineConfig) -> (Arc<QuantumEngine>, Arc<OrderManager>) {
+    let market_data = Arc::new(MarketDataProcessor::new());
+    let risk = Arc::new(RiskManager::new(CONFIG.oms_initial_capital).with_events(events.clone()));
+    let oms = Arc::new(OrderManager::new(
+        Arc::new(OrderRouter::new().with_market_data(market_data.clone())),
+        risk,
+        events.clone(),
+        CONFIG.oms_initial_capital,
+    ));
+    oms.add_venue(
+        default_route(1, CONFIG.paper_fee_rate),
+        Arc::new(PaperVenue::new("paper", CONFIG.paper_fee_rate)),
+    );
+
+    let workers = engine_config.workers;
+    let engine = Arc::new(QuantumEngine::with_config(engine_config));
+    engine.add_handler(oms.clone());
+    if let Err(e) = engine.feed_market_data(events, market_data) {
+        tracing::error!("Engine gets no market data: {}", e);
+    }
+    match engine.start() {
+        Ok(()) => tracing::info!("✅ Order management running on the paper venue, {} engine worker(s)", workers),
+        Err(e) => tracing::error!("Engine failed to start: {}", e),
+    }
+    (engine, oms)
+}
+
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Shutdown signal received, draining connections");
 }
 
 /// Health check endpoint
@@ -217,3 +563,69 @@ async fn trigger_scan() -> axum::Json<serde_json::Value> {
     }))
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::{EngineMessage, OrderParams, OrderRequest, OrderSide, OrderType};
+    use market::Quote;
+    use scanner::market_data::{OrderBook, OrderBookLevel};
+    use std::time::Instant;
+
+    fn wait_until(mut done: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !done() {
+            if Instant::now() > deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        true
+    }
+
+    #[test]
+    fn test_bus_books_and_quotes_reach_routing() {
+        let events = AppBus::new(64);
+        let (engine, oms) = start_order_management(&events, EngineConfig::default());
+
+        let mut book = OrderBook::for_venue("paper", "BTCUSDT".to_string());
+        book.asks.push(OrderBookLevel { price: 100.0, quantity: 1.0 });
+        book.asks.push(OrderBookLevel { price: 101.0, quantity: 5.0 });
+        events.publish(book).unwrap();
+        events
+            .publish(Quote {
+                symbol: "BTCUSDT".to_string(),
+                price: 100.0,
+                timestamp: 1,
+                bid: Some(99.5),
+                ask: Some(100.0),
+                volume: None,
+            })
+            .unwrap();
+        let market_data = oms.router().market_data().unwrap();
+        assert!(wait_until(|| market_data.get_venue_order_book("paper", "BTCUSDT").is_some()));
+        assert!(wait_until(|| engine.metrics().messages_received.load(std::sync::atomic::Ordering::Relaxed) == 1));
+
+        // Buy 3 up to 100.5: 1 from the book at 100, the rest rests at the limit
+        let request = OrderRequest {
+            order_id: oms.next_order_id(),
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            quantity: 3.0,
+            price: Some(100.5),
+            stop_price: None,
+            timestamp: 0,
+            params: OrderParams::default(),
+        };
+        let id = request.order_id;
+        engine.submit(EngineMessage::NewOrder(request)).unwrap();
+        assert!(wait_until(|| oms.router().get_order(id).is_some()));
+
+        let routing = oms.router().get_order(id).unwrap().routing.unwrap();
+        assert_eq!(routing.legs[0].venue, "paper");
+        let expected = routing.legs[0].expected_price.unwrap();
+        assert!((expected - 301.0 / 3.0).abs() < 1e-9, "expected price {}", expected);
+        engine.stop().unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use dashmap::DashMap;
 use crate::config::CONFIG;
@@ -13,6 +14,23 @@ use super::providers::{
     polygon::PolygonProvider,
 };
 
+/// Cache size and lookups since start
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct CacheStats {
+    pub quote_entries: usize,
+    pub candle_entries: usize,
+    pub hits: u64,
+    pub misses: u64, // Absent or expired
+}
+
+impl CacheStats {
+    /// Share of lookups served from cache, 0.0 before the first one
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 { 0.0 } else { self.hits as f64 / lookups as f64 }
+    }
+}
+
 /// Cached quote with TTL
 struct CachedQuote {
     quote: Quote,
@@ -39,6 +57,8 @@ pub struct ProviderManager {
     // In-memory caches (we'll use this instead of Redis for simplicity)
     quote_cache: Arc<DashMap<String, CachedQuote>>,
     candle_cache: Arc<DashMap<String, CachedCandles>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
     
     // Cache TTLs in milliseconds
     quote_ttl_ms: i64,
@@ -100,6 +120,8 @@ impl ProviderManager {
             polygon,
             quote_cache: Arc::new(DashMap::new()),
             candle_cache: Arc::new(DashMap::new()),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
             quote_ttl_ms: 60_000, // 60 seconds for real-time quotes
             candle_ttl_ms: 300_000, // 5 minutes for candles
         }
@@ -121,9 +143,11 @@ impl ProviderManager {
         if let Some(cached) = self.quote_cache.get(&cache_key) {
             if cached.expires_at > now {
                 tracing::debug!("Cache hit for quote: {}", symbol);
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
                 return Ok(cached.quote.clone());
             }
         }
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
         
         // Determine best provider based on symbol type
         let quote = if Self::is_crypto_symbol(symbol) {
@@ -192,9 +216,11 @@ impl ProviderManager {
         if let Some(cached) = self.candle_cache.get(&cache_key) {
             if cached.expires_at > now {
                 tracing::debug!("Cache hit for candles: {} {} {}", symbol, interval, limit);
+                self.cache_hits.fetch_add(1, Ordering::Relaxed);
                 return Ok(cached.candles.clone());
             }
         }
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
         
         // Determine best provider based on symbol type
         let candles = if Self::is_crypto_symbol(symbol) {
@@ -217,6 +243,18 @@ impl ProviderManager {
         Ok(candles)
     }
 
+    /// Serve `candles` from the cache, for tests that must not hit the network
+    #[cfg(test)]
+    pub fn cache_candles(&self, symbol: &str, interval: &str, limit: usize, candles: Vec<Candle>) {
+        self.candle_cache.insert(
+            format!("candles:{}:{}:{}", symbol, interval, limit),
+            CachedCandles {
+                candles,
+                expires_at: chrono::Utc::now().timestamp_millis() + self.candle_ttl_ms,
+            },
+        );
+    }
+
     /// Get bulk quotes with intelligent batching
     pub async fn get_bulk_quotes(&self, symbols: &[String]) -> Result<Vec<Quote>, ProviderError> {
         // Separate crypto from stocks
@@ -259,8 +297,13 @@ impl ProviderManager {
     }
 
     /// Get statistics about the cache
-    pub fn cache_stats(&self) -> (usize, usize) {
-        (self.quote_cache.len(), self.candle_cache.len())
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            quote_entries: self.quote_cache.len(),
+            candle_entries: self.candle_cache.len(),
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+        }
     }
 }
 
@@ -307,6 +307,15 @@ impl SymbolUniverse {
             .collect()
     }
 
+    /// Look up the market type of a symbol, defaulting to stock for unknown tickers
+    pub fn market_type_of(symbol: &str) -> MarketType {
+        Self::all()
+            .into_iter()
+            .find(|s| s.symbol == symbol)
+            .map(|s| s.market_type)
+            .unwrap_or(MarketType::Stock)
+    }
+
     /// Get just the symbol strings for quick access
     pub fn symbols_only() -> Vec<String> {
         Self::all().into_iter().map(|s| s.symbol).collect()
@@ -358,6 +367,13 @@ mod tests {
         assert!(crypto.iter().any(|s| s.symbol == "ETHUSDT"));
     }
 
+    #[test]
+    fn test_market_type_of() {
+        assert_eq!(SymbolUniverse::market_type_of("BTCUSDT"), MarketType::Crypto);
+        assert_eq!(SymbolUniverse::market_type_of("AAPL"), MarketType::Stock);
+        assert_eq!(SymbolUniverse::market_type_of("UNKNOWN"), MarketType::Stock);
+    }
+
     #[test]
     fn test_filter_by_market_type() {
         let stocks = SymbolUniverse::by_market_type(MarketType::Stock);
@@ -0,0 +1,160 @@
+//! Lock-free latency histogram
+//!
+//! HDR-style log-linear buckets: values below 128 get a bucket each, above
+//! that every power of two is split into 64 buckets, so any recorded value is
+//! reported within 1/64 (~1.6%) of itself across the whole `u64` range.
+//! Recording is one relaxed atomic add, safe from any number of threads.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const SUB_BUCKET_BITS: u32 = 6;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS; // 64 per power of two
+const LINEAR_LIMIT: u64 = SUB_BUCKETS * 2; // Exact below this
+const BUCKETS: usize = ((64 - SUB_BUCKET_BITS as usize) * SUB_BUCKETS as usize) + LINEAR_LIMIT as usize;
+
+/// Summary of a histogram, in nanoseconds
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LatencySnapshot {
+    pub count: u64,
+    pub mean_ns: u64,
+    pub p50_ns: u64,
+    pub p95_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+}
+
+pub struct LatencyHistogram {
+    buckets: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    max: AtomicU64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        }
+    }
+
+    pub fn record(&self, value_ns: u64) {
+        self.buckets[bucket_index(value_ns)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(value_ns, Ordering::Relaxed);
+        self.max.fetch_max(value_ns, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max.load(Ordering::Relaxed)
+    }
+
+    pub fn mean(&self) -> u64 {
+        self.sum.load(Ordering::Relaxed).checked_div(self.count()).unwrap_or(0)
+    }
+
+    /// Smallest recorded value that `quantile` (0.0 to 1.0) of all values
+    /// are at or below, to bucket precision; 0 when nothing was recorded
+    pub fn value_at_quantile(&self, quantile: f64) -> u64 {
+        let count = self.count();
+        if count == 0 {
+            return 0;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                return bucket_highest(index).min(self.max());
+            }
+        }
+        self.max()
+    }
+
+    pub fn snapshot(&self) -> LatencySnapshot {
+        LatencySnapshot {
+            count: self.count(),
+            mean_ns: self.mean(),
+            p50_ns: self.value_at_quantile(0.50),
+            p95_ns: self.value_at_quantile(0.95),
+            p99_ns: self.value_at_quantile(0.99),
+            max_ns: self.max(),
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for LatencyHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LatencyHistogram").field("snapshot", &self.snapshot()).finish()
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < LINEAR_LIMIT {
+        return value as usize;
+    }
+    // Keep the top 7 significant bits: 1 implicit plus 6 of sub-bucket
+    let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+    (shift as u64 * SUB_BUCKETS + (value >> shift)) as usize
+}
+
+/// Largest value that lands in bucket `index`
+fn bucket_highest(index: usize) -> u64 {
+    let index = index as u64;
+    if index < LINEAR_LIMIT {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let sub = index - shift * SUB_BUCKETS;
+    ((sub + 1) << shift).wrapping_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for value in [0, 1, 127, 128, 129, 1_000, 65_535, 1_000_000_007, u64::MAX / 3, u64::MAX] {
+            let index = bucket_index(value);
+            assert!(index < BUCKETS);
+            let highest = bucket_highest(index);
+            assert!(highest >= value, "{} -> {}", value, highest);
+            assert!((highest - value) as f64 <= value as f64 / SUB_BUCKETS as f64, "{} -> {}", value, highest);
+        }
+        // Buckets are contiguous
+        assert_eq!(bucket_index(bucket_highest(500) + 1), 501);
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.snapshot().p99_ns, 0);
+
+        for value in 1..=10_000u64 {
+            histogram.record(value * 1_000);
+        }
+        let snapshot = histogram.snapshot();
+        let close = |actual: u64, expected: u64| (actual as f64 - expected as f64).abs() / expected as f64 <= 0.02;
+        assert!(close(snapshot.p50_ns, 5_000_000), "{:?}", snapshot);
+        assert!(close(snapshot.p95_ns, 9_500_000), "{:?}", snapshot);
+        assert!(close(snapshot.p99_ns, 9_900_000), "{:?}", snapshot);
+        assert_eq!(snapshot.max_ns, 10_000_000);
+        assert_eq!(snapshot.mean_ns, 5_000_500);
+        assert_eq!(snapshot.count, 10_000);
+    }
+}
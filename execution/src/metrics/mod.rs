@@ -0,0 +1,5 @@
+pub mod histogram;
+pub mod rate;
+
+pub use histogram::{LatencyHistogram, LatencySnapshot};
+pub use rate::RateMeter;
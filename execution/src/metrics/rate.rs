@@ -0,0 +1,105 @@
+//! Events per second over a sliding window
+//!
+//! One slot per second in a 60 slot ring; each slot packs its second and its
+//! count into one atomic, so marking an event is a single CAS loop.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+const SLOTS: usize = 60;
+const COUNT_BITS: u32 = 32;
+const COUNT_MASK: u64 = (1 << COUNT_BITS) - 1;
+
+pub struct RateMeter {
+    started: Instant,
+    slots: [AtomicU64; SLOTS],
+    total: AtomicU64,
+}
+
+impl RateMeter {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            slots: std::array::from_fn(|_| AtomicU64::new(0)),
+            total: AtomicU64::new(0),
+        }
+    }
+
+    pub fn mark(&self) {
+        self.mark_n(1);
+    }
+
+    pub fn mark_n(&self, n: u64) {
+        self.total.fetch_add(n, Ordering::Relaxed);
+        self.mark_at(self.second(), n);
+    }
+
+    /// Events since creation
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Average per second over the last `window` complete seconds (at most 59)
+    pub fn per_sec(&self, window: u64) -> f64 {
+        self.per_sec_at(self.second(), window)
+    }
+
+    fn second(&self) -> u64 {
+        // Second 0 is reserved for empty slots
+        self.started.elapsed().as_secs() + 1
+    }
+
+    fn mark_at(&self, second: u64, n: u64) {
+        let slot = &self.slots[second as usize % SLOTS];
+        let mut current = slot.load(Ordering::Relaxed);
+        loop {
+            let next = if current >> COUNT_BITS == second {
+                (current & !COUNT_MASK) | ((current & COUNT_MASK) + n).min(COUNT_MASK)
+            } else {
+                (second << COUNT_BITS) | n.min(COUNT_MASK)
+            };
+            match slot.compare_exchange_weak(current, next, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    fn per_sec_at(&self, now: u64, window: u64) -> f64 {
+        let window = window.clamp(1, SLOTS as u64 - 1);
+        let events: u64 = (1..=window)
+            .filter_map(|ago| now.checked_sub(ago).filter(|second| *second > 0))
+            .map(|second| {
+                let slot = self.slots[second as usize % SLOTS].load(Ordering::Relaxed);
+                if slot >> COUNT_BITS == second { slot & COUNT_MASK } else { 0 }
+            })
+            .sum();
+        events as f64 / window as f64
+    }
+}
+
+impl Default for RateMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_window() {
+        let meter = RateMeter::new();
+        meter.mark_at(1, 10);
+        meter.mark_at(2, 20);
+        meter.mark_at(3, 5); // Current second, not complete yet
+
+        assert_eq!(meter.per_sec_at(3, 2), 15.0);
+        assert_eq!(meter.per_sec_at(3, 10), 3.0);
+        // A minute later the slots hold stale seconds and count as empty
+        assert_eq!(meter.per_sec_at(63, 2), 0.0);
+        meter.mark_at(62, 4);
+        assert_eq!(meter.per_sec_at(63, 2), 2.0);
+    }
+}
@@ -1,7 +1,7 @@
 use feed_rs::parser;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewsItem {
     pub source: String,
     pub title: String,
@@ -93,3 +93,28 @@ pub async fn get_live_news() -> Vec<NewsItem> {
         .take(25)
         .collect()
 }
+
+/// Poll the feeds every `every` and publish headlines not seen before on the app bus
+pub async fn publish_live_news(events: crate::bus::AppBus, every: std::time::Duration) {
+    const REMEMBERED: usize = 1000;
+    let mut seen = std::collections::HashSet::new();
+    let mut order = std::collections::VecDeque::new();
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        let mut items = get_live_news().await;
+        items.reverse(); // Oldest first
+        for item in items {
+            if !seen.insert(item.title.clone()) {
+                continue;
+            }
+            order.push_back(item.title.clone());
+            if order.len() > REMEMBERED {
+                if let Some(oldest) = order.pop_front() {
+                    seen.remove(&oldest);
+                }
+            }
+            events.emit(item);
+        }
+    }
+}
@@ -1,34 +1,46 @@
-use tokio::sync::mpsc;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use parking_lot::RwLock;
 
 /// Order book level
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderBookLevel {
     pub price: f64,
     pub quantity: f64,
 }
 
 /// Level 2 order book
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderBook {
     pub symbol: String,
+    #[serde(default)]
+    pub venue: Option<String>, // None for the consolidated book
     pub bids: Vec<OrderBookLevel>,
     pub asks: Vec<OrderBookLevel>,
-    pub timestamp: u64,
+    #[serde(default)]
+    pub timestamp: u64, // Set when the processor takes the book
 }
 
 impl OrderBook {
     pub fn new(symbol: String) -> Self {
         Self {
             symbol,
+            venue: None,
             bids: Vec::new(),
             asks: Vec::new(),
             timestamp: 0,
         }
     }
 
+    /// Book of one venue, kept apart from the consolidated one
+    pub fn for_venue(venue: impl Into<String>, symbol: String) -> Self {
+        Self {
+            venue: Some(venue.into()),
+            ..Self::new(symbol)
+        }
+    }
+
     pub fn best_bid(&self) -> Option<&OrderBookLevel> {
         self.bids.first()
     }
@@ -79,21 +91,21 @@ pub enum MarketDataEvent {
 
 /// Market data processor for tick-by-tick processing
 pub struct MarketDataProcessor {
-    /// Order books by symbol
+    /// Order books by symbol, venue books by `venue:symbol`
     order_books: Arc<RwLock<HashMap<String, OrderBook>>>,
     /// Event channel
-    event_tx: mpsc::UnboundedSender<MarketDataEvent>,
-    event_rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<MarketDataEvent>>>,
+    event_tx: flume::Sender<MarketDataEvent>,
+    event_rx: flume::Receiver<MarketDataEvent>,
 }
 
 impl MarketDataProcessor {
     pub fn new() -> Self {
-        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (event_tx, event_rx) = flume::unbounded();
 
         Self {
             order_books: Arc::new(RwLock::new(HashMap::new())),
             event_tx,
-            event_rx: Arc::new(tokio::sync::Mutex::new(event_rx)),
+            event_rx,
         }
     }
 
@@ -108,8 +120,8 @@ impl MarketDataProcessor {
             .unwrap()
             .as_millis() as u64;
 
-        let symbol = book.symbol.clone();
-        self.order_books.write().insert(symbol.clone(), book.clone());
+        let key = book_key(book.venue.as_deref(), &book.symbol);
+        self.order_books.write().insert(key, book.clone());
 
         let _ = self.event_tx.send(MarketDataEvent::OrderBook(book));
     }
@@ -139,8 +151,19 @@ impl MarketDataProcessor {
         self.order_books.read().get(symbol).cloned()
     }
 
+    /// Current book of `symbol` on one venue
+    pub fn get_venue_order_book(&self, venue: &str, symbol: &str) -> Option<OrderBook> {
+        self.order_books.read().get(&book_key(Some(venue), symbol)).cloned()
+    }
+
+    /// Queued events. Each one goes to whoever takes it first, so there
+    /// should be one consumer.
+    pub fn events(&self) -> &flume::Receiver<MarketDataEvent> {
+        &self.event_rx
+    }
+
     /// Get event receiver
-    pub fn subscribe(&self) -> mpsc::UnboundedSender<MarketDataEvent> {
+    pub fn subscribe(&self) -> flume::Sender<MarketDataEvent> {
         self.event_tx.clone()
     }
 
@@ -187,6 +210,13 @@ impl MarketDataProcessor {
     }
 }
 
+fn book_key(venue: Option<&str>, symbol: &str) -> String {
+    match venue {
+        Some(venue) => format!("{}:{}", venue, symbol),
+        None => symbol.to_string(),
+    }
+}
+
 impl Default for MarketDataProcessor {
     fn default() -> Self {
         Self::new()
@@ -281,6 +311,14 @@ mod tests {
         let retrieved = processor.get_order_book("BTCUSDT");
         assert!(retrieved.is_some());
         assert_eq!(retrieved.unwrap().bids.len(), 1);
+
+        // Venue books do not replace the consolidated one
+        let mut venue_book = OrderBook::for_venue("binance", "BTCUSDT".to_string());
+        venue_book.asks.push(OrderBookLevel { price: 50020.0, quantity: 1.0 });
+        processor.update_order_book(venue_book);
+        assert_eq!(processor.get_venue_order_book("binance", "BTCUSDT").unwrap().asks[0].price, 50020.0);
+        assert_eq!(processor.get_order_book("BTCUSDT").unwrap().asks[0].price, 50010.0);
+        assert!(processor.get_venue_order_book("paper", "BTCUSDT").is_none());
     }
 
     #[test]
@@ -5,32 +5,80 @@ pub mod quantum_scanner;
 pub mod signal_engine;
 pub mod market_data;
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
-use tokio::time::{interval, Duration};
-use crate::api::LiveSignal;
+use tokio::time::{interval, Duration, Instant};
+use crate::bus::{AppBus, CandleClosed, SignalBus};
 use crate::config::CONFIG;
 use crate::families::{Indicator, MarketData, SignalType};
 use crate::market::{ProviderManager, SymbolUniverse};
+use crate::metrics::{LatencyHistogram, RateMeter};
+use crate::signal::{
+    EnhancedSignal, IndicatorFactor, MarketRegime, SignalBuilder, SignalDirection, SmcFactor, SmcTags,
+    StructureContext, TargetPlanner,
+};
+use crate::smc::{BosDetector, FairValueGap, LiquidityDetector, OrderBlock, SmcStreams, ZoneData};
+
+/// Candles fetched per signal for structure, targets and regime
+const SIGNAL_CANDLES: usize = 100;
+
+/// Latest candles checked for sweeps of the liquidity pools before them
+const SWEEP_CANDLES: usize = 3;
+
+/// RSI period and oversold/overbought levels reported in signal explanations
+const RSI_PERIOD: usize = 14;
+const RSI_OVERSOLD: f64 = 30.0;
+const RSI_OVERBOUGHT: f64 = 70.0;
+
+/// Scan throughput, shared with `/api/metrics`
+#[derive(Default)]
+pub struct ScanStats {
+    pub cycles: AtomicU64,
+    pub symbols: RateMeter, // Symbols scanned, whether or not data came back
+    pub cycle_time: LatencyHistogram,
+}
 
 /// Scanner orchestrator that coordinates scanning and signal generation
 pub struct Scanner {
-    signal_tx: broadcast::Sender<LiveSignal>,
+    bus: SignalBus<EnhancedSignal>,
+    events: AppBus,
     indicators: Vec<Arc<dyn Indicator>>,
     provider_manager: Arc<ProviderManager>,
+    timeframe: String, // Provider candle interval, `SCAN_TIMEFRAME`
     scan_cycle_counter: std::sync::atomic::AtomicUsize,
+    last_closed_candle: dashmap::DashMap<String, i64>, // Per symbol, so each close is published once
+    stats: Arc<ScanStats>,
+    smc: Arc<SmcStreams>, // FVGs and order blocks per symbol, kept across scans
 }
 
 impl Scanner {
-    /// Create a new scanner with a broadcast channel for signals
-    pub fn new(signal_tx: broadcast::Sender<LiveSignal>, provider_manager: Arc<ProviderManager>) -> Self {
+    /// Create a new scanner publishing scored signals on `bus`, and quotes,
+    /// closed candles and signals on the app bus
+    pub fn new(bus: SignalBus<EnhancedSignal>, events: AppBus, provider_manager: Arc<ProviderManager>) -> Self {
         Self {
-            signal_tx,
+            bus,
+            events,
             indicators: Vec::new(),
             provider_manager,
+            timeframe: CONFIG.scan_timeframe.clone(),
             scan_cycle_counter: std::sync::atomic::AtomicUsize::new(0),
+            last_closed_candle: dashmap::DashMap::new(),
+            stats: Arc::new(ScanStats::default()),
+            smc: Arc::new(SmcStreams::default()),
         }
     }
+
+    /// Record throughput into `stats` instead of a private instance
+    pub fn with_stats(mut self, stats: Arc<ScanStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Read zones from shared SMC streams instead of private ones
+    pub fn with_smc_streams(mut self, smc: Arc<SmcStreams>) -> Self {
+        self.smc = smc;
+        self
+    }
     
     /// Add an indicator to the scanner
     pub fn add_indicator(&mut self, indicator: Arc<dyn Indicator>) {
@@ -60,6 +108,7 @@ impl Scanner {
     /// Execute one scan cycle with rotation strategy
     async fn scan_cycle(&self) -> Result<(), Box<dyn std::error::Error>> {
         let cycle = self.scan_cycle_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let started = Instant::now();
         tracing::debug!("Starting scan cycle #{}", cycle);
         
         // Rotate through different symbol groups on each cycle
@@ -98,7 +147,7 @@ impl Scanner {
                     // Evaluate all indicators
                     for indicator in &self.indicators {
                         if let Some(signal) = indicator.evaluate(&market_data) {
-                            self.publish_signal(signal).await;
+                            self.publish_signal(signal, market_data.close).await;
                         }
                     }
                 }
@@ -106,11 +155,14 @@ impl Scanner {
                     tracing::warn!("Failed to fetch market data for {}: {}", symbol, e);
                 }
             }
+            self.stats.symbols.mark();
             
             // Basic rate limiting
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
         
+        self.stats.cycles.fetch_add(1, Ordering::Relaxed);
+        self.stats.cycle_time.record(started.elapsed().as_nanos() as u64);
         tracing::debug!("Scan cycle #{} completed", cycle);
         Ok(())
     }
@@ -120,6 +172,7 @@ impl Scanner {
         // Get the latest quote from provider manager
         let quote = self.provider_manager.get_quote(symbol).await
             .map_err(|e| e.to_string())?;
+        self.events.emit(quote.clone());
         
         // For now, we use the quote price for OHLC (ideally we'd fetch candles)
         // This gives us real-time data while keeping it simple
@@ -134,27 +187,191 @@ impl Scanner {
         })
     }
     
-    /// Publish a signal to subscribers
-    async fn publish_signal(&self, signal: crate::families::Signal) {
-        let live_signal = LiveSignal {
-            symbol: signal.symbol.clone(),
-            horizon: "M15".to_string(), // TODO: Get from context
-            ready: matches!(signal.signal_type, SignalType::Buy | SignalType::Sell),
-            tags: signal.metadata.clone(),
-            reason: format!("{} signal from {}", 
-                match signal.signal_type {
-                    SignalType::Buy => "BUY",
-                    SignalType::Sell => "SELL",
-                    SignalType::Neutral => "NEUTRAL",
-                },
-                signal.indicator
-            ),
-            ts_unix_ms: signal.timestamp,
+    /// Score the indicator signal, plan structure-aware targets from recent
+    /// candles and publish it to subscribers
+    async fn publish_signal(&self, signal: crate::families::Signal, price: f64) {
+        let direction = match signal.signal_type {
+            SignalType::Buy => SignalDirection::Buy,
+            SignalType::Sell => SignalDirection::Sell,
+            SignalType::Neutral => SignalDirection::Neutral,
         };
-        
-        if let Err(e) = self.signal_tx.send(live_signal) {
-            tracing::warn!("Failed to broadcast signal: {}", e);
+
+        // Without candles the signal still goes out, with R-multiple targets only
+        let tuples: Vec<(f64, f64, f64, f64, i64)> =
+            match self.provider_manager.get_candles(&signal.symbol, &self.timeframe, SIGNAL_CANDLES).await {
+                Ok(candles) => {
+                    self.publish_closed_candle(&candles);
+                    candles
+                        .iter()
+                        .map(|c| (c.open, c.high, c.low, c.close, c.timestamp))
+                        .collect()
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch candles for {}: {}", signal.symbol, e);
+                    Vec::new()
+                }
+            };
+
+        let bos = BosDetector::default();
+        let breaks = bos.detect(&tuples);
+        let (fvgs, order_blocks) = self.smc_zones(&signal.symbol, &tuples);
+        // Pools form on the earlier candles; the latest ones may have run them
+        let formed = tuples.len().saturating_sub(SWEEP_CANDLES);
+        let liquidity_detector = LiquidityDetector::default();
+        let mut liquidity = liquidity_detector.detect(&tuples[..formed]);
+        liquidity_detector.detect_sweeps(&mut liquidity, &tuples[formed..]);
+        let swings = bos.find_swing_points(&tuples);
+        let ctx = StructureContext {
+            liquidity: &liquidity,
+            fvgs: &fvgs,
+            order_blocks: &order_blocks,
+            swings: &swings,
+        };
+
+        // Fallback stop 1.5 average ranges away (1% without candles) when no structure is close
+        let recent = &tuples[tuples.len().saturating_sub(14)..];
+        let stop_distance = if recent.is_empty() {
+            price * 0.01
+        } else {
+            recent.iter().map(|c| c.1 - c.2).sum::<f64>() / recent.len() as f64 * 1.5
+        };
+        let fallback_stop = match direction {
+            SignalDirection::Buy => price - stop_distance,
+            SignalDirection::Sell => price + stop_distance,
+            SignalDirection::Neutral => price,
+        };
+
+        let targets = TargetPlanner::default().plan(price, fallback_stop, &direction, &ctx);
+        let mut enhanced = SignalBuilder::new(
+            signal.symbol.clone(),
+            timeframe::label(&self.timeframe),
+            direction,
+            price,
+            targets.stop_loss,
+        )
+        .add_indicator(signal.indicator.clone())
+        .smc_tags(SmcTags::from_context(price, &ctx, !breaks.is_empty()))
+        .targets(targets)
+        .build(signal.strength);
+        enhanced.timestamp = signal.timestamp;
+
+        // RSI from the fetched candles, unless it is what fired the signal
+        let mut indicators = vec![IndicatorFactor::from_indicator_signal(&signal)];
+        if signal.indicator != "RSI" {
+            let closes: Vec<f64> = tuples.iter().map(|c| c.3).collect();
+            indicators.extend(IndicatorFactor::rsi(&closes, RSI_PERIOD, RSI_OVERSOLD, RSI_OVERBOUGHT));
         }
+
+        // Reason is the summary of the explanation tree
+        enhanced.explain(
+            indicators,
+            SmcFactor::from_context(price, &ctx, breaks.last()),
+            MarketRegime::from_candles(&tuples, breaks.last()),
+        );
+
+        self.events.emit(enhanced.clone());
+        self.bus.send(enhanced).await;
+    }
+
+    /// FVGs and order blocks, active and recently mitigated, from the symbol's
+    /// SMC stream after catching it up with the closed candles among `tuples`
+    fn smc_zones(&self, symbol: &str, tuples: &[(f64, f64, f64, f64, i64)]) -> (Vec<FairValueGap>, Vec<OrderBlock>) {
+        let timeframe = timeframe::label(&self.timeframe);
+        // The last candle is still forming; feeding it would shadow its close
+        let closed = &tuples[..tuples.len().saturating_sub(1)];
+        self.smc.catch_up(symbol, &timeframe, closed);
+        self.smc
+            .read(symbol, &timeframe, |stream| {
+                let mut fvgs = Vec::new();
+                let mut order_blocks = Vec::new();
+                for zone in stream.active_zones().iter().chain(stream.recent_zones()) {
+                    match &zone.data {
+                        ZoneData::FairValueGap(fvg) => fvgs.push(fvg.clone()),
+                        ZoneData::OrderBlock(ob) => order_blocks.push(ob.clone()),
+                        ZoneData::Liquidity(_) => {}
+                    }
+                }
+                (fvgs, order_blocks)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The last candle is still forming; the one before it is the latest close
+    fn publish_closed_candle(&self, candles: &[crate::market::ProviderCandle]) {
+        let Some(candle) = candles.len().checked_sub(2).map(|i| &candles[i]) else {
+            return;
+        };
+        if self.last_closed_candle.get(&candle.symbol).is_some_and(|ts| *ts >= candle.timestamp) {
+            return;
+        }
+        self.last_closed_candle.insert(candle.symbol.clone(), candle.timestamp);
+        self.events.emit(CandleClosed {
+            timeframe: timeframe::label(&self.timeframe),
+            candle: candle.clone(),
+        });
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::ProviderCandle;
+    use crate::signal::explanation::SmcFactorKind;
+
+    fn candle(ts: i64, open: f64, high: f64, low: f64, close: f64) -> ProviderCandle {
+        ProviderCandle {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: ts,
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publish_signal_tags_liquidity_sweep() {
+        // Equal lows at 95, a drift higher, then a wick through them and a recovery
+        let mut candles = vec![candle(1_000, 100.0, 102.0, 95.0, 101.0), candle(2_000, 101.0, 103.0, 95.05, 102.0)];
+        for i in 0..6 {
+            let step = i as f64 * 0.5;
+            candles.push(candle(3_000 + i * 1_000, 100.0 + step, 104.0 + step, 97.0 + step, 101.0 + step));
+        }
+        candles.push(candle(9_000, 99.0, 100.0, 94.0, 99.5));
+        candles.push(candle(10_000, 99.5, 101.0, 99.0, 100.5));
+        candles.push(candle(11_000, 100.5, 101.5, 100.0, 101.0));
+
+        let provider_manager = Arc::new(ProviderManager::new());
+        provider_manager.cache_candles("BTCUSDT", &CONFIG.scan_timeframe, SIGNAL_CANDLES, candles);
+        let bus = SignalBus::new(4);
+        let mut rx = bus.subscribe();
+        let scanner = Scanner::new(bus, AppBus::new(16), provider_manager);
+
+        let signal = crate::families::Signal {
+            symbol: "BTCUSDT".to_string(),
+            indicator: "RSI".to_string(),
+            signal_type: SignalType::Buy,
+            strength: 0.8,
+            metadata: serde_json::json!({}),
+            timestamp: 11_000,
+        };
+        scanner.publish_signal(signal, 101.0).await;
+
+        let published = rx.try_recv().unwrap();
+        assert!(published.smc_tags.liquidity_sweep);
+        assert_eq!(published.smc_tags.liquidity_type.as_deref(), Some("SellSideLiquidity"));
+        assert_eq!(published.timeframe, timeframe::label(&CONFIG.scan_timeframe));
+        let explanation = published.explanation.expect("published signals are explained");
+        assert!(explanation.smc.iter().any(|f| f.kind == SmcFactorKind::LiquiditySweep && f.timestamp == 9_000));
+        // An RSI signal is not listed twice
+        assert_eq!(explanation.indicators.len(), 1);
+    }
+
+    #[test]
+    fn test_timeframe_labels() {
+        assert_eq!(timeframe::label("15"), "M15");
+        assert_eq!(timeframe::label("240"), "H4");
+        assert_eq!(timeframe::label("D"), "D1");
+    }
+}
@@ -8,3 +8,16 @@ impl Timeframe {
         "M15"
     }
 }
+
+/// Signal label for a provider candle interval, e.g. "15" -> "M15", "60" -> "H1"
+pub fn label(interval: &str) -> String {
+    match interval {
+        "D" | "1D" => "D1".to_string(),
+        "W" | "1W" => "W1".to_string(),
+        minutes => match minutes.parse::<u32>() {
+            Ok(m) if m >= 60 && m % 60 == 0 => format!("H{}", m / 60),
+            Ok(m) => format!("M{}", m),
+            Err(_) => minutes.to_string(),
+        },
+    }
+}
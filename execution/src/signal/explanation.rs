@@ -0,0 +1,438 @@
+//! Structured signal explanations
+//!
+//! A `SignalExplanation` records why a signal got its grade: each indicator
+//! with its value and threshold, each SMC factor with its zone prices, the
+//! market regime, the points of every score component, and where the stop and
+//! targets came from. The human-readable `summary` is generated from the same tree.
+
+use serde::{Deserialize, Serialize};
+
+use super::scoring::{ScoreComponent, ScoringProfile};
+use super::{EnhancedSignal, SignalDirection, SignalGrade, StructureContext};
+use crate::smc::streaming::SmcCandle;
+use crate::smc::{BosType, BreakOfStructure};
+
+/// One indicator that voted for the signal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndicatorFactor {
+    pub name: String,
+    pub direction: SignalDirection,
+    pub strength: f64,            // 0-1
+    pub value: Option<f64>,       // Indicator reading, e.g. RSI 28.4
+    pub threshold: Option<f64>,   // Level it crossed, e.g. 30
+}
+
+impl IndicatorFactor {
+    /// Read `value` and `threshold` from an indicator's metadata, when present
+    pub fn from_indicator_signal(signal: &crate::families::Signal) -> Self {
+        let direction = match signal.signal_type {
+            crate::families::SignalType::Buy => SignalDirection::Buy,
+            crate::families::SignalType::Sell => SignalDirection::Sell,
+            crate::families::SignalType::Neutral => SignalDirection::Neutral,
+        };
+        Self {
+            name: signal.indicator.clone(),
+            direction,
+            strength: signal.strength,
+            value: signal.metadata.get("value").and_then(|v| v.as_f64()),
+            threshold: signal.metadata.get("threshold").and_then(|v| v.as_f64()),
+        }
+    }
+
+    /// Wilder's RSI over `closes`, oldest first; the threshold is set once the
+    /// reading is past the oversold or overbought level
+    pub fn rsi(closes: &[f64], period: usize, oversold: f64, overbought: f64) -> Option<Self> {
+        let period = period.max(1);
+        if closes.len() <= period {
+            return None;
+        }
+
+        let mut avg_gain = 0.0;
+        let mut avg_loss = 0.0;
+        for (i, pair) in closes.windows(2).enumerate() {
+            let change = pair[1] - pair[0];
+            let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+            if i < period {
+                // Simple average over the first period
+                avg_gain += gain / period as f64;
+                avg_loss += loss / period as f64;
+            } else {
+                avg_gain = (avg_gain * (period - 1) as f64 + gain) / period as f64;
+                avg_loss = (avg_loss * (period - 1) as f64 + loss) / period as f64;
+            }
+        }
+        let value = if avg_loss == 0.0 { 100.0 } else { 100.0 - 100.0 / (1.0 + avg_gain / avg_loss) };
+
+        let (direction, threshold, strength) = if value < oversold {
+            (SignalDirection::Buy, Some(oversold), (oversold - value) / oversold)
+        } else if value > overbought {
+            (SignalDirection::Sell, Some(overbought), (value - overbought) / (100.0 - overbought))
+        } else {
+            (SignalDirection::Neutral, None, 0.0)
+        };
+        Some(Self {
+            name: "RSI".to_string(),
+            direction,
+            strength: strength.min(1.0),
+            value: Some(value),
+            threshold,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SmcFactorKind {
+    FairValueGap,
+    OrderBlock,
+    BreakOfStructure,
+    LiquiditySweep,
+}
+
+/// One SMC confirmation with the prices it refers to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SmcFactor {
+    pub kind: SmcFactorKind,
+    pub label: String, // e.g. "Bullish:PartiallyFilled", "SellSideLiquidity"
+    pub top: f64,
+    pub bottom: f64,   // Equal to top for single levels (BOS, liquidity)
+    pub timestamp: i64,
+}
+
+impl SmcFactor {
+    /// The same confirmations `SmcTags::from_context` flags, with their zones
+    pub fn from_context(price: f64, ctx: &StructureContext, last_bos: Option<&BreakOfStructure>) -> Vec<Self> {
+        let mut factors = Vec::new();
+
+        if let Some(fvg) = ctx.fvgs.iter().find(|f| f.is_near(price)) {
+            factors.push(Self {
+                kind: SmcFactorKind::FairValueGap,
+                label: fvg.tag(),
+                top: fvg.top,
+                bottom: fvg.bottom,
+                timestamp: fvg.timestamp,
+            });
+        }
+        if let Some(ob) = ctx.order_blocks.iter().find(|ob| !ob.mitigated && ob.is_near(price)) {
+            factors.push(Self {
+                kind: SmcFactorKind::OrderBlock,
+                label: format!("{:?}", ob.ob_type),
+                top: ob.top,
+                bottom: ob.bottom,
+                timestamp: ob.timestamp,
+            });
+        }
+        if let Some(bos) = last_bos {
+            factors.push(Self {
+                kind: SmcFactorKind::BreakOfStructure,
+                label: format!("{:?}", bos.bos_type),
+                top: bos.break_level,
+                bottom: bos.break_level,
+                timestamp: bos.timestamp,
+            });
+        }
+        if let Some(zone) = ctx.liquidity.iter().find(|z| z.swept) {
+            factors.push(Self {
+                kind: SmcFactorKind::LiquiditySweep,
+                label: format!("{:?}", zone.liquidity_type),
+                top: zone.price_level,
+                bottom: zone.price_level,
+                timestamp: zone.sweep_timestamp.unwrap_or(zone.timestamp),
+            });
+        }
+
+        factors
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trend {
+    Bullish,
+    Bearish,
+    Ranging,
+}
+
+/// Market conditions the signal fired in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MarketRegime {
+    pub trend: Trend,          // Direction of the last break of structure
+    pub volatility_pct: f64,   // Average candle range as % of close (last 14)
+}
+
+impl MarketRegime {
+    pub fn from_candles(candles: &[SmcCandle], last_bos: Option<&BreakOfStructure>) -> Option<Self> {
+        let recent = &candles[candles.len().saturating_sub(14)..];
+        let close = recent.last()?.3;
+        if close <= 0.0 {
+            return None;
+        }
+
+        let avg_range = recent.iter().map(|c| c.1 - c.2).sum::<f64>() / recent.len() as f64;
+        let trend = match last_bos.map(|b| &b.bos_type) {
+            Some(BosType::Bullish) => Trend::Bullish,
+            Some(BosType::Bearish) => Trend::Bearish,
+            None => Trend::Ranging,
+        };
+
+        Some(Self {
+            trend,
+            volatility_pct: avg_range / close * 100.0,
+        })
+    }
+}
+
+/// Where a level of the trade plan came from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LevelFactor {
+    pub name: String, // "stop_loss", "take_profit_1", ...
+    pub price: f64,
+    pub source: String,
+    pub r_multiple: f64, // -1 for the stop
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignalExplanation {
+    pub summary: String,
+    pub score: f64,
+    pub grade: SignalGrade,
+    pub components: Vec<ScoreComponent>,
+    pub indicators: Vec<IndicatorFactor>,
+    pub smc: Vec<SmcFactor>,
+    pub regime: Option<MarketRegime>,
+    pub levels: Vec<LevelFactor>,
+}
+
+impl SignalExplanation {
+    /// Explain an already scored signal
+    pub fn build(
+        signal: &EnhancedSignal,
+        profile: &ScoringProfile,
+        indicators: Vec<IndicatorFactor>,
+        smc: Vec<SmcFactor>,
+        regime: Option<MarketRegime>,
+    ) -> Self {
+        let t = &signal.targets;
+        let mut levels = vec![LevelFactor {
+            name: "stop_loss".to_string(),
+            price: t.stop_loss,
+            source: snake_case(&t.stop_source),
+            r_multiple: -1.0,
+        }];
+        let take_profits = [t.take_profit_1, t.take_profit_2, t.take_profit_3];
+        for (i, price) in take_profits.iter().enumerate() {
+            levels.push(LevelFactor {
+                name: format!("take_profit_{}", i + 1),
+                price: *price,
+                source: snake_case(&t.target_sources[i]),
+                r_multiple: t.r_multiples[i],
+            });
+        }
+
+        let mut explanation = Self {
+            summary: String::new(),
+            score: signal.score,
+            grade: signal.grade.clone(),
+            components: profile.breakdown(signal),
+            indicators,
+            smc,
+            regime,
+            levels,
+        };
+        explanation.summary = explanation.render(signal);
+        explanation
+    }
+
+    /// Human-readable summary, generated only from the tree
+    fn render(&self, signal: &EnhancedSignal) -> String {
+        let direction = format!("{:?}", signal.direction).to_uppercase();
+        let mut parts = vec![format!(
+            "{:?}-grade {} {} {} (score {:.1})",
+            self.grade,
+            direction,
+            signal.symbol,
+            signal.timeframe,
+            self.score
+        )];
+
+        if !self.indicators.is_empty() {
+            let list: Vec<String> = self.indicators.iter().map(describe_indicator).collect();
+            parts.push(format!("indicators: {}", list.join(", ")));
+        }
+
+        if !self.smc.is_empty() {
+            let list: Vec<String> = self.smc.iter().map(describe_smc).collect();
+            parts.push(format!("SMC: {}", list.join(", ")));
+        }
+
+        if let Some(regime) = &self.regime {
+            parts.push(format!(
+                "regime: {} trend, {:.2}% volatility",
+                snake_case(&regime.trend),
+                regime.volatility_pct
+            ));
+        }
+
+        if let Some(top) = self
+            .components
+            .iter()
+            .filter(|c| c.points > 0.0)
+            .max_by(|a, b| a.points.total_cmp(&b.points))
+        {
+            parts.push(format!("largest contribution: {} ({:.1} pts)", top.name, top.points));
+        }
+
+        if signal.direction != SignalDirection::Neutral {
+            let plan: Vec<String> = self
+                .levels
+                .iter()
+                .map(|l| {
+                    if l.r_multiple < 0.0 {
+                        format!("stop {:.2} ({})", l.price, l.source)
+                    } else {
+                        format!("{} {:.2} ({}, {:.1}R)", l.name.replace("take_profit_", "TP"), l.price, l.source, l.r_multiple)
+                    }
+                })
+                .collect();
+            parts.push(format!("entry {:.2}, {}", signal.targets.entry, plan.join(", ")));
+        }
+
+        parts.join("; ")
+    }
+}
+
+fn describe_indicator(factor: &IndicatorFactor) -> String {
+    match (factor.value, factor.threshold) {
+        (Some(value), Some(threshold)) => {
+            let cmp = if value < threshold { "<" } else { ">=" };
+            format!("{} {:.2} {} {:.2}", factor.name, value, cmp, threshold)
+        }
+        (Some(value), None) => format!("{} {:.2}", factor.name, value),
+        _ => format!("{} ({:.0}% strength)", factor.name, factor.strength * 100.0),
+    }
+}
+
+fn describe_smc(factor: &SmcFactor) -> String {
+    let name = match factor.kind {
+        SmcFactorKind::FairValueGap => "FVG",
+        SmcFactorKind::OrderBlock => "order block",
+        SmcFactorKind::BreakOfStructure => "BOS",
+        SmcFactorKind::LiquiditySweep => "liquidity sweep",
+    };
+    if factor.top == factor.bottom {
+        format!("{} {} at {:.2}", factor.label, name, factor.top)
+    } else {
+        format!("{} {} {:.2}-{:.2}", factor.label, name, factor.bottom, factor.top)
+    }
+}
+
+/// Serde name of a unit enum variant, e.g. `TargetSource::FibExtension` -> "fib_extension"
+fn snake_case<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::{SignalBuilder, SmcTags};
+
+    #[test]
+    fn test_explanation_tree_and_summary() {
+        let mut signal = SignalBuilder::new("BTCUSDT".to_string(), "M15".to_string(), SignalDirection::Buy, 100.0, 98.0)
+            .add_indicator("RSI".to_string())
+            .smc_tags(SmcTags {
+                near_fvg: true,
+                bos_confirmed: true,
+                ..Default::default()
+            })
+            .build(0.8);
+
+        let indicators = vec![IndicatorFactor {
+            name: "RSI".to_string(),
+            direction: SignalDirection::Buy,
+            strength: 0.8,
+            value: Some(28.4),
+            threshold: Some(30.0),
+        }];
+        let smc = vec![
+            SmcFactor {
+                kind: SmcFactorKind::FairValueGap,
+                label: "Bullish".to_string(),
+                top: 101.0,
+                bottom: 99.5,
+                timestamp: 1_000,
+            },
+            SmcFactor {
+                kind: SmcFactorKind::BreakOfStructure,
+                label: "Bullish".to_string(),
+                top: 103.0,
+                bottom: 103.0,
+                timestamp: 2_000,
+            },
+        ];
+        let regime = MarketRegime {
+            trend: Trend::Bullish,
+            volatility_pct: 1.25,
+        };
+
+        let profile = ScoringProfile::default();
+        let explanation = SignalExplanation::build(&signal, &profile, indicators, smc, Some(regime));
+
+        // Component points add up to the score
+        let total: f64 = explanation.components.iter().map(|c| c.points).sum();
+        assert!((total - signal.score).abs() < 1e-9);
+        let smc_points = explanation.components.iter().find(|c| c.name == "smc").unwrap();
+        assert_eq!(smc_points.input, 25.0);
+
+        assert_eq!(explanation.levels.len(), 4);
+        assert_eq!(explanation.levels[0].source, "fixed");
+        assert_eq!(explanation.levels[1].source, "r_multiple");
+
+        let summary = &explanation.summary;
+        assert!(summary.starts_with(&format!("{:?}-grade BUY BTCUSDT M15", signal.grade)));
+        assert!(summary.contains("RSI 28.40 < 30.00"));
+        assert!(summary.contains("Bullish FVG 99.50-101.00"));
+        assert!(summary.contains("Bullish BOS at 103.00"));
+        assert!(summary.contains("bullish trend, 1.25% volatility"));
+        assert!(summary.contains("TP1 104.00 (r_multiple, 2.0R)"));
+
+        signal.set_explanation(explanation.clone());
+        assert_eq!(signal.reason, explanation.summary);
+    }
+
+    #[test]
+    fn test_rsi_from_closes() {
+        // Three falls and a smaller rise: average gain 0.5, loss 1.5, RSI 25
+        let mut closes = vec![100.0, 98.0, 96.0, 94.0, 96.0];
+        assert!(IndicatorFactor::rsi(&closes[..4], 4, 30.0, 70.0).is_none());
+
+        let factor = IndicatorFactor::rsi(&closes, 4, 30.0, 70.0).unwrap();
+        assert_eq!(factor.name, "RSI");
+        assert_eq!(factor.direction, SignalDirection::Buy);
+        assert!((factor.value.unwrap() - 25.0).abs() < 1e-9);
+        assert_eq!(factor.threshold, Some(30.0));
+        assert!((factor.strength - 5.0 / 30.0).abs() < 1e-9);
+
+        // A rally takes it through neutral and past 70
+        closes.push(104.0);
+        let neutral = IndicatorFactor::rsi(&closes, 4, 30.0, 70.0).unwrap();
+        assert_eq!(neutral.direction, SignalDirection::Neutral);
+        assert_eq!(neutral.threshold, None);
+        closes.push(112.0);
+        let sell = IndicatorFactor::rsi(&closes, 4, 30.0, 70.0).unwrap();
+        assert_eq!(sell.direction, SignalDirection::Sell);
+        assert_eq!(sell.threshold, Some(70.0));
+    }
+
+    #[test]
+    fn test_regime_from_candles() {
+        let candles: Vec<SmcCandle> = (0..20).map(|i| (100.0, 101.0, 99.0, 100.0, i * 1000)).collect();
+        let regime = MarketRegime::from_candles(&candles, None).unwrap();
+        assert_eq!(regime.trend, Trend::Ranging);
+        assert!((regime.volatility_pct - 2.0).abs() < 1e-9);
+        assert!(MarketRegime::from_candles(&[], None).is_none());
+    }
+}
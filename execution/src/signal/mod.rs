@@ -4,11 +4,27 @@
 /// - Signal scoring (0-100)
 /// - Confluence tracking
 /// - SMC pattern tags
-/// - Auto-generated targets
+/// - Auto-generated targets (R-multiple or structure-aware)
+
+pub mod explanation;
+pub mod scoring;
+pub mod stats;
+pub mod store;
+pub mod targets;
+pub mod tracker;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+pub use explanation::{IndicatorFactor, MarketRegime, SignalExplanation, SmcFactor};
+pub use scoring::{Calibrator, ScoringProfile, SCORING_PROFILE};
+pub use stats::{PerformanceStats, SignalStatsReport};
+pub use store::{
+    FileSignalStore, MemorySignalStore, SignalQuery, SignalRepository, StoreError, StoreRetention, StoredSignal,
+};
+pub use targets::{StructureContext, TargetPlanner};
+pub use tracker::{SignalStatus, SignalTracker, SignalTransition, TrackedSignal, TrackerConfig};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SignalDirection {
@@ -46,18 +62,53 @@ impl SignalGrade {
     }
 }
 
+/// Where a take-profit level came from
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetSource {
+    #[default]
+    RMultiple,
+    Liquidity,
+    SwingPoint,
+    FairValueGap,
+    OrderBlock,
+    FibExtension,
+}
+
+/// Where the stop loss was placed
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopSource {
+    #[default]
+    Fixed,
+    SwingPoint,
+    OrderBlock,
+    FairValueGap,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignalTargets {
     pub entry: f64,
     pub stop_loss: f64,
-    pub take_profit_1: f64, // 2R
-    pub take_profit_2: f64, // 3R
-    pub take_profit_3: f64, // 5R
-    pub risk_reward_ratio: f64,
+    pub take_profit_1: f64,
+    pub take_profit_2: f64,
+    pub take_profit_3: f64,
+    pub risk_reward_ratio: f64,  // R multiple of TP1
+    #[serde(default = "default_r_multiples")]
+    pub r_multiples: [f64; 3],   // R multiple of TP1/TP2/TP3
+    #[serde(default)]
+    pub target_sources: [TargetSource; 3],
+    #[serde(default)]
+    pub stop_source: StopSource,
+}
+
+/// Signals stored before targets were planned used fixed 2R/3R/5R targets
+fn default_r_multiples() -> [f64; 3] {
+    [2.0, 3.0, 5.0]
 }
 
 impl SignalTargets {
-    /// Generate targets based on entry and stop loss
+    /// Generate targets based on entry and stop loss (2R/3R/5R)
     pub fn new(entry: f64, stop_loss: f64, direction: &SignalDirection) -> Self {
         let risk = (entry - stop_loss).abs();
 
@@ -75,15 +126,55 @@ impl SignalTargets {
             SignalDirection::Neutral => (entry, entry, entry),
         };
 
+        Self::from_levels(
+            entry,
+            stop_loss,
+            [tp1, tp2, tp3],
+            [TargetSource::RMultiple; 3],
+            StopSource::Fixed,
+        )
+    }
+
+    /// Build targets from explicit levels, computing the R multiple of each
+    pub fn from_levels(
+        entry: f64,
+        stop_loss: f64,
+        take_profits: [f64; 3],
+        target_sources: [TargetSource; 3],
+        stop_source: StopSource,
+    ) -> Self {
+        let risk = (entry - stop_loss).abs();
+        let r_multiples = take_profits.map(|tp| {
+            if risk > 0.0 {
+                (tp - entry).abs() / risk
+            } else {
+                0.0
+            }
+        });
+
         Self {
             entry,
             stop_loss,
-            take_profit_1: tp1,
-            take_profit_2: tp2,
-            take_profit_3: tp3,
-            risk_reward_ratio: if direction == &SignalDirection::Neutral { 0.0 } else { 2.0 },
+            take_profit_1: take_profits[0],
+            take_profit_2: take_profits[1],
+            take_profit_3: take_profits[2],
+            risk_reward_ratio: r_multiples[0],
+            r_multiples,
+            target_sources,
+            stop_source,
         }
     }
+
+    /// Distance between entry and stop
+    pub fn risk(&self) -> f64 {
+        (self.entry - self.stop_loss).abs()
+    }
+
+    /// Whether any target was derived from market structure
+    pub fn is_structural(&self) -> bool {
+        self.stop_source != StopSource::Fixed
+            || self.target_sources.iter().any(|s| *s != TargetSource::RMultiple)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,7 +183,7 @@ pub struct SmcTags {
     pub near_order_block: bool,
     pub bos_confirmed: bool,
     pub liquidity_sweep: bool,
-    pub fvg_type: Option<String>,
+    pub fvg_type: Option<String>, // FairValueGap::tag(), e.g. "Bullish:PartiallyFilled", "InverseBearish"
     pub order_block_type: Option<String>,
     pub liquidity_type: Option<String>,
 }
@@ -112,29 +203,38 @@ impl Default for SmcTags {
 }
 
 impl SmcTags {
-    /// Calculate SMC bonus score
+    /// Calculate SMC bonus score with the active scoring profile
     pub fn bonus_score(&self) -> f64 {
-        let mut score = 0.0;
+        SCORING_PROFILE.smc_bonus(self)
+    }
 
-        if self.near_fvg {
-            score += 10.0;
-        }
-        if self.near_order_block {
-            score += 15.0;
+    /// Build tags from the structure around the current price
+    pub fn from_context(price: f64, ctx: &StructureContext, bos_confirmed: bool) -> Self {
+        let mut tags = SmcTags {
+            bos_confirmed,
+            ..Default::default()
+        };
+
+        if let Some(fvg) = ctx.fvgs.iter().find(|f| f.is_near(price)) {
+            tags.near_fvg = true;
+            tags.fvg_type = Some(fvg.tag());
         }
-        if self.bos_confirmed {
-            score += 15.0;
+        if let Some(ob) = ctx.order_blocks.iter().find(|ob| !ob.mitigated && ob.is_near(price)) {
+            tags.near_order_block = true;
+            tags.order_block_type = Some(format!("{:?}", ob.ob_type));
         }
-        if self.liquidity_sweep {
-            score += 20.0;
+        if let Some(zone) = ctx.liquidity.iter().find(|z| z.swept) {
+            tags.liquidity_sweep = true;
+            tags.liquidity_type = Some(format!("{:?}", zone.liquidity_type));
         }
 
-        score
+        tags
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnhancedSignal {
+    pub id: String,
     pub symbol: String,
     pub timeframe: String,
     pub direction: SignalDirection,
@@ -143,11 +243,15 @@ pub struct EnhancedSignal {
     pub confluence_count: u32, // Number of aligned indicators
     pub power_score: f64,      // Strength of setup (0-100)
     pub whale_score: f64,      // Smart money involvement (0-100)
+    #[serde(default)]
+    pub indicator_strength: f64, // 0-1, as passed to calculate_score
     pub targets: SignalTargets,
     pub smc_tags: SmcTags,
     pub indicators: Vec<String>,
     pub reason: String,
     pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<SignalExplanation>,
 }
 
 impl EnhancedSignal {
@@ -162,6 +266,7 @@ impl EnhancedSignal {
         let targets = SignalTargets::new(entry, stop_loss, &direction);
 
         Self {
+            id: uuid::Uuid::new_v4().to_string(),
             symbol,
             timeframe,
             direction,
@@ -170,11 +275,13 @@ impl EnhancedSignal {
             confluence_count: 0,
             power_score: 0.0,
             whale_score: 0.0,
+            indicator_strength: 0.0,
             targets,
             smc_tags: SmcTags::default(),
             indicators: Vec::new(),
             reason: String::new(),
             timestamp: chrono::Utc::now().timestamp_millis(),
+            explanation: None,
         }
     }
 
@@ -186,35 +293,19 @@ impl EnhancedSignal {
         }
     }
 
-    /// Calculate confluence score based on number of aligned indicators
-    fn calculate_confluence_score(&self) -> f64 {
-        // More indicators = higher confidence
-        match self.confluence_count {
-            0..=1 => 20.0,
-            2 => 40.0,
-            3 => 60.0,
-            4 => 75.0,
-            5 => 85.0,
-            _ => 95.0,
-        }
+    /// Calculate overall signal score with the active scoring profile
+    pub fn calculate_score(&mut self, indicator_strength: f64) {
+        self.calculate_score_with(indicator_strength, &SCORING_PROFILE);
     }
 
-    /// Calculate overall signal score
-    pub fn calculate_score(&mut self, indicator_strength: f64) {
-        let confluence_score = self.calculate_confluence_score();
-        let smc_bonus = self.smc_tags.bonus_score();
-
-        // Weighted components:
-        // - 40% confluence
-        // - 30% indicator strength
-        // - 20% SMC patterns
-        // - 10% whale score
-        self.score = (confluence_score * 0.4)
-            + (indicator_strength * 100.0 * 0.3)
-            + (smc_bonus * 0.2)
-            + (self.whale_score * 0.1);
-
-        self.score = self.score.min(100.0).max(0.0);
+    /// Calculate overall signal score. With the default profile:
+    /// - 40% confluence
+    /// - 30% indicator strength
+    /// - 20% SMC patterns
+    /// - 10% whale score
+    pub fn calculate_score_with(&mut self, indicator_strength: f64, profile: &ScoringProfile) {
+        self.indicator_strength = indicator_strength;
+        self.score = profile.score(self);
         self.grade = SignalGrade::from_score(self.score);
     }
 
@@ -233,11 +324,28 @@ impl EnhancedSignal {
         self.smc_tags = tags;
     }
 
+    /// Replace the generated targets (e.g. with a `TargetPlanner` plan)
+    pub fn set_targets(&mut self, targets: SignalTargets) {
+        self.targets = targets;
+    }
+
     /// Set reason for signal
     pub fn set_reason(&mut self, reason: String) {
         self.reason = reason;
     }
 
+    /// Attach an explanation; its summary becomes the signal's reason
+    pub fn set_explanation(&mut self, explanation: SignalExplanation) {
+        self.reason = explanation.summary.clone();
+        self.explanation = Some(explanation);
+    }
+
+    /// Explain the current score with the active scoring profile
+    pub fn explain(&mut self, indicators: Vec<IndicatorFactor>, smc: Vec<SmcFactor>, regime: Option<MarketRegime>) {
+        let explanation = SignalExplanation::build(self, &SCORING_PROFILE, indicators, smc, regime);
+        self.set_explanation(explanation);
+    }
+
     /// Check if this is a high-quality signal (grade B or better)
     pub fn is_high_quality(&self) -> bool {
         matches!(self.grade, SignalGrade::S | SignalGrade::A | SignalGrade::B)
@@ -289,6 +397,11 @@ impl SignalBuilder {
         self
     }
 
+    pub fn targets(mut self, targets: SignalTargets) -> Self {
+        self.signal.set_targets(targets);
+        self
+    }
+
     pub fn reason(mut self, reason: String) -> Self {
         self.signal.set_reason(reason);
         self
@@ -313,6 +426,18 @@ mod tests {
         assert_eq!(targets.take_profit_1, 104.0); // 2R
         assert_eq!(targets.take_profit_2, 106.0); // 3R
         assert_eq!(targets.take_profit_3, 110.0); // 5R
+        assert_eq!(targets.r_multiples, [2.0, 3.0, 5.0]);
+        assert_eq!(targets.risk_reward_ratio, 2.0);
+        assert!(!targets.is_structural());
+    }
+
+    #[test]
+    fn test_targets_stored_without_sources() {
+        let json = r#"{"entry":100.0,"stop_loss":98.0,"take_profit_1":104.0,"take_profit_2":106.0,"take_profit_3":110.0,"risk_reward_ratio":2.0}"#;
+        let targets: SignalTargets = serde_json::from_str(json).unwrap();
+        assert_eq!(targets.r_multiples, [2.0, 3.0, 5.0]);
+        assert_eq!(targets.target_sources, [TargetSource::RMultiple; 3]);
+        assert_eq!(targets.stop_source, StopSource::Fixed);
     }
 
     #[test]
@@ -0,0 +1,549 @@
+//! Signal scoring profile and calibration
+//!
+//! The weights behind `EnhancedSignal::calculate_score` live in a
+//! `ScoringProfile`, loaded from the JSON file at `SCORING_PROFILE_PATH`
+//! (defaults otherwise). `Calibrator` fits the weights to resolved signal
+//! history with logistic regression of win vs. score features and reports
+//! how well grades separate winners from losers.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{EnhancedSignal, PerformanceStats, SignalGrade, TrackedSignal};
+use crate::config::CONFIG;
+
+lazy_static! {
+    /// Profile used by `EnhancedSignal::calculate_score`
+    pub static ref SCORING_PROFILE: ScoringProfile = ScoringProfile::from_config();
+}
+
+#[derive(Debug)]
+pub enum ScoringError {
+    Io(String),
+    Parse(String),
+    Invalid(String),
+    NotEnoughSamples { required: usize, found: usize },
+    SingleClass, // Only winners or only losers, nothing to separate
+}
+
+impl fmt::Display for ScoringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoringError::Io(e) => write!(f, "Failed to read scoring profile: {}", e),
+            ScoringError::Parse(e) => write!(f, "Failed to parse scoring profile: {}", e),
+            ScoringError::Invalid(e) => write!(f, "Invalid scoring profile: {}", e),
+            ScoringError::NotEnoughSamples { required, found } => write!(
+                f,
+                "Not enough resolved signals to calibrate: {} required, {} found",
+                required, found
+            ),
+            ScoringError::SingleClass => write!(f, "Calibration needs both winning and losing signals"),
+        }
+    }
+}
+
+impl std::error::Error for ScoringError {}
+
+/// Weight of each component in the final 0-100 score
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub confluence: f64,
+    pub indicator_strength: f64,
+    pub smc: f64,
+    pub whale: f64,
+    pub power: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            confluence: 0.4,
+            indicator_strength: 0.3,
+            smc: 0.2,
+            whale: 0.1,
+            power: 0.0,
+        }
+    }
+}
+
+/// Points added to the SMC component per confirmation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SmcBonusWeights {
+    pub near_fvg: f64,
+    pub near_order_block: f64,
+    pub bos_confirmed: f64,
+    pub liquidity_sweep: f64,
+}
+
+impl Default for SmcBonusWeights {
+    fn default() -> Self {
+        Self {
+            near_fvg: 10.0,
+            near_order_block: 15.0,
+            bos_confirmed: 15.0,
+            liquidity_sweep: 20.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScoringProfile {
+    /// Confluence score by number of aligned indicators; the last entry
+    /// applies to any higher count
+    pub confluence_table: Vec<f64>,
+    pub smc_bonus: SmcBonusWeights,
+    pub weights: ScoreWeights,
+}
+
+impl Default for ScoringProfile {
+    fn default() -> Self {
+        Self {
+            confluence_table: vec![20.0, 20.0, 40.0, 60.0, 75.0, 85.0, 95.0],
+            smc_bonus: SmcBonusWeights::default(),
+            weights: ScoreWeights::default(),
+        }
+    }
+}
+
+impl ScoringProfile {
+    /// Load the profile from `SCORING_PROFILE_PATH`, falling back to defaults
+    pub fn from_config() -> Self {
+        let Some(path) = &CONFIG.scoring_profile_path else {
+            return Self::default();
+        };
+        match Self::load(path) {
+            Ok(profile) => {
+                tracing::info!("Loaded scoring profile from {}", path);
+                profile
+            }
+            Err(e) => {
+                tracing::error!("{}; using default scoring profile", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, ScoringError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| ScoringError::Io(e.to_string()))?;
+        Self::from_json(&contents)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ScoringError> {
+        let profile: Self = serde_json::from_str(json).map_err(|e| ScoringError::Parse(e.to_string()))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    pub fn validate(&self) -> Result<(), ScoringError> {
+        if self.confluence_table.is_empty() {
+            return Err(ScoringError::Invalid("confluence_table must not be empty".to_string()));
+        }
+        let w = &self.weights;
+        let b = &self.smc_bonus;
+        let all = [
+            w.confluence, w.indicator_strength, w.smc, w.whale, w.power,
+            b.near_fvg, b.near_order_block, b.bos_confirmed, b.liquidity_sweep,
+        ];
+        if all.iter().chain(&self.confluence_table).any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(ScoringError::Invalid("weights must be finite and non-negative".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn confluence_score(&self, confluence_count: u32) -> f64 {
+        let last = self.confluence_table.len().saturating_sub(1);
+        self.confluence_table
+            .get((confluence_count as usize).min(last))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn smc_bonus(&self, tags: &super::SmcTags) -> f64 {
+        let b = &self.smc_bonus;
+        [
+            (tags.near_fvg, b.near_fvg),
+            (tags.near_order_block, b.near_order_block),
+            (tags.bos_confirmed, b.bos_confirmed),
+            (tags.liquidity_sweep, b.liquidity_sweep),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, points)| points)
+        .sum()
+    }
+
+    /// Overall 0-100 score of a signal
+    pub fn score(&self, signal: &EnhancedSignal) -> f64 {
+        let score: f64 = self.breakdown(signal).iter().map(|c| c.points).sum();
+        score.clamp(0.0, 100.0)
+    }
+
+    /// Points each component contributes to the score (before clamping)
+    pub fn breakdown(&self, signal: &EnhancedSignal) -> Vec<ScoreComponent> {
+        let w = &self.weights;
+        [
+            ("confluence", self.confluence_score(signal.confluence_count), w.confluence),
+            ("indicator_strength", signal.indicator_strength * 100.0, w.indicator_strength),
+            ("smc", self.smc_bonus(&signal.smc_tags), w.smc),
+            ("whale", signal.whale_score, w.whale),
+            ("power", signal.power_score, w.power),
+        ]
+        .into_iter()
+        .map(|(name, input, weight)| ScoreComponent {
+            name: name.to_string(),
+            input,
+            weight,
+            points: input * weight,
+        })
+        .collect()
+    }
+}
+
+/// One weighted term of the score
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScoreComponent {
+    pub name: String,
+    pub input: f64,  // Component value on a 0-100 scale
+    pub weight: f64,
+    pub points: f64, // input * weight
+}
+
+/// Feature order used by the calibrator
+const FEATURES: [&str; 9] = [
+    "confluence",
+    "indicator_strength",
+    "near_fvg",
+    "near_order_block",
+    "bos_confirmed",
+    "liquidity_sweep",
+    "whale",
+    "power",
+    "intercept",
+];
+
+fn features(profile: &ScoringProfile, signal: &EnhancedSignal) -> [f64; 9] {
+    let flag = |set: bool| if set { 1.0 } else { 0.0 };
+    let tags = &signal.smc_tags;
+    [
+        profile.confluence_score(signal.confluence_count) / 100.0,
+        signal.indicator_strength,
+        flag(tags.near_fvg),
+        flag(tags.near_order_block),
+        flag(tags.bos_confirmed),
+        flag(tags.liquidity_sweep),
+        signal.whale_score / 100.0,
+        signal.power_score / 100.0,
+        1.0,
+    ]
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+/// How well a score ranks winners above losers, and win rate per grade
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeSeparation {
+    pub auc: f64,             // 0.5 = no better than chance
+    pub monotonic: bool,      // Win rate never improves from a better to a worse grade
+    pub by_grade: BTreeMap<String, PerformanceStats>,
+}
+
+impl GradeSeparation {
+    /// `samples` are (score, realized R)
+    pub fn from_samples(samples: &[(f64, f64)]) -> Self {
+        let mut groups: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+        for (score, r) in samples {
+            groups.entry(format!("{:?}", SignalGrade::from_score(*score))).or_default().push(*r);
+        }
+        let by_grade: BTreeMap<String, PerformanceStats> = groups
+            .into_iter()
+            .map(|(grade, rs)| (grade, PerformanceStats::from_outcomes(&rs)))
+            .collect();
+
+        // Best grade first: S, A, B, C, D, F
+        let win_rates: Vec<f64> = ["S", "A", "B", "C", "D", "F"]
+            .iter()
+            .filter_map(|g| by_grade.get(*g).map(|s| s.win_rate))
+            .collect();
+        let monotonic = win_rates.windows(2).all(|w| w[0] >= w[1]);
+
+        Self {
+            auc: auc(samples),
+            monotonic,
+            by_grade,
+        }
+    }
+}
+
+/// Probability that a random winner scores above a random loser
+fn auc(samples: &[(f64, f64)]) -> f64 {
+    let wins: Vec<f64> = samples.iter().filter(|(_, r)| *r > 0.0).map(|(s, _)| *s).collect();
+    let losses: Vec<f64> = samples.iter().filter(|(_, r)| *r <= 0.0).map(|(s, _)| *s).collect();
+    if wins.is_empty() || losses.is_empty() {
+        return 0.5;
+    }
+
+    let mut concordant = 0.0;
+    for w in &wins {
+        for l in &losses {
+            if w > l {
+                concordant += 1.0;
+            } else if w == l {
+                concordant += 0.5;
+            }
+        }
+    }
+    concordant / (wins.len() * losses.len()) as f64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationReport {
+    pub samples: usize,
+    pub coefficients: BTreeMap<String, f64>,
+    pub log_loss: f64,
+    pub current: GradeSeparation,    // Scores as recorded on the signals
+    pub calibrated: GradeSeparation, // Same signals rescored with the fitted profile
+    pub profile: ScoringProfile,
+}
+
+/// Fits scoring weights with L2-regularised logistic regression
+#[derive(Debug, Clone)]
+pub struct Calibrator {
+    pub learning_rate: f64,
+    pub iterations: usize,
+    pub l2: f64,
+    pub min_samples: usize,
+}
+
+impl Default for Calibrator {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.5,
+            iterations: 2000,
+            l2: 0.01,
+            min_samples: 30,
+        }
+    }
+}
+
+impl Calibrator {
+    /// Fit weights on resolved signals. The confluence table is kept from
+    /// `base`; component weights and SMC bonuses come from the fit, scaled so
+    /// the best possible setup scores 100.
+    pub fn calibrate(
+        &self,
+        base: &ScoringProfile,
+        signals: &[TrackedSignal],
+    ) -> Result<CalibrationReport, ScoringError> {
+        let resolved: Vec<(&EnhancedSignal, f64)> = signals
+            .iter()
+            .filter_map(|t| t.realized_r().map(|r| (&t.signal, r)))
+            .collect();
+        if resolved.len() < self.min_samples {
+            return Err(ScoringError::NotEnoughSamples {
+                required: self.min_samples,
+                found: resolved.len(),
+            });
+        }
+        let wins = resolved.iter().filter(|(_, r)| *r > 0.0).count();
+        if wins == 0 || wins == resolved.len() {
+            return Err(ScoringError::SingleClass);
+        }
+
+        let x: Vec<[f64; 9]> = resolved.iter().map(|(s, _)| features(base, s)).collect();
+        let y: Vec<f64> = resolved.iter().map(|(_, r)| if *r > 0.0 { 1.0 } else { 0.0 }).collect();
+        let n = x.len() as f64;
+
+        // Batch gradient descent; the intercept is not regularised
+        let mut coef = [0.0; 9];
+        for _ in 0..self.iterations {
+            let mut grad = [0.0; 9];
+            for (xi, yi) in x.iter().zip(&y) {
+                let z: f64 = xi.iter().zip(&coef).map(|(a, b)| a * b).sum();
+                let err = sigmoid(z) - yi;
+                for (g, xv) in grad.iter_mut().zip(xi) {
+                    *g += err * xv;
+                }
+            }
+            for (j, c) in coef.iter_mut().enumerate() {
+                let penalty = if j < 8 { self.l2 * *c } else { 0.0 };
+                *c -= self.learning_rate * (grad[j] / n + penalty);
+            }
+        }
+
+        let log_loss = x
+            .iter()
+            .zip(&y)
+            .map(|(xi, yi)| {
+                let p = sigmoid(xi.iter().zip(&coef).map(|(a, b)| a * b).sum()).clamp(1e-12, 1.0 - 1e-12);
+                -(yi * p.ln() + (1.0 - yi) * (1.0 - p).ln())
+            })
+            .sum::<f64>()
+            / n;
+
+        let profile = Self::profile_from_coefficients(base, &coef);
+
+        let current: Vec<(f64, f64)> = resolved.iter().map(|(s, r)| (s.score, *r)).collect();
+        let rescored: Vec<(f64, f64)> = resolved.iter().map(|(s, r)| (profile.score(s), *r)).collect();
+
+        Ok(CalibrationReport {
+            samples: resolved.len(),
+            coefficients: FEATURES.iter().map(|f| f.to_string()).zip(coef).collect(),
+            log_loss,
+            current: GradeSeparation::from_samples(&current),
+            calibrated: GradeSeparation::from_samples(&rescored),
+            profile,
+        })
+    }
+
+    /// Turn the linear part of the model into score weights. Negative
+    /// coefficients are dropped so a stronger feature never lowers the score.
+    fn profile_from_coefficients(base: &ScoringProfile, coef: &[f64; 9]) -> ScoringProfile {
+        let c: Vec<f64> = coef.iter().map(|v| v.max(0.0)).collect();
+        let smc_total = c[2] + c[3] + c[4] + c[5];
+        let total = c[0] + c[1] + smc_total + c[6] + c[7];
+        if total <= 0.0 {
+            return base.clone();
+        }
+
+        let smc_points = |v: f64| if smc_total > 0.0 { 100.0 * v / smc_total } else { 0.0 };
+
+        ScoringProfile {
+            confluence_table: base.confluence_table.clone(),
+            smc_bonus: SmcBonusWeights {
+                near_fvg: smc_points(c[2]),
+                near_order_block: smc_points(c[3]),
+                bos_confirmed: smc_points(c[4]),
+                liquidity_sweep: smc_points(c[5]),
+            },
+            weights: ScoreWeights {
+                confluence: c[0] / total,
+                indicator_strength: c[1] / total,
+                smc: smc_total / total,
+                whale: c[6] / total,
+                power: c[7] / total,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::{SignalDirection, SmcTags, TrackerConfig};
+
+    fn resolved(bos: bool, whale: f64, win: bool) -> TrackedSignal {
+        let mut signal = EnhancedSignal::new(
+            "BTCUSDT".to_string(),
+            "M15".to_string(),
+            SignalDirection::Buy,
+            100.0,
+            98.0,
+        );
+        signal.timestamp = 0;
+        signal.add_indicator("RSI".to_string());
+        signal.set_whale_score(whale);
+        signal.set_smc_tags(SmcTags {
+            bos_confirmed: bos,
+            ..Default::default()
+        });
+        signal.calculate_score(0.5);
+
+        let exit = if win { 104.0 } else { 97.0 };
+        let mut tracked = TrackedSignal::new(signal);
+        let config = TrackerConfig::default();
+        tracked.on_bar(100.0, 100.0, 100.0, 1, &config);
+        tracked.on_bar(exit, exit, exit, 2, &config);
+        tracked.on_bar(80.0, 80.0, 80.0, 3, &config); // Close out winners at TP1
+        tracked
+    }
+
+    #[test]
+    fn test_default_profile_matches_formula() {
+        let profile = ScoringProfile::default();
+        assert_eq!(profile.confluence_score(0), 20.0);
+        assert_eq!(profile.confluence_score(3), 60.0);
+        assert_eq!(profile.confluence_score(12), 95.0);
+
+        let tags = SmcTags {
+            near_fvg: true,
+            bos_confirmed: true,
+            liquidity_sweep: true,
+            ..Default::default()
+        };
+        assert_eq!(profile.smc_bonus(&tags), 45.0);
+    }
+
+    #[test]
+    fn test_profile_from_json() {
+        let profile = ScoringProfile::from_json(
+            r#"{"smc_bonus": {"liquidity_sweep": 30.0}, "weights": {"whale": 0.2}}"#,
+        )
+        .unwrap();
+        assert_eq!(profile.smc_bonus.liquidity_sweep, 30.0);
+        assert_eq!(profile.smc_bonus.near_fvg, 10.0); // Unset fields keep defaults
+        assert_eq!(profile.weights.whale, 0.2);
+        assert_eq!(profile.weights.confluence, 0.4);
+
+        assert!(matches!(
+            ScoringProfile::from_json(r#"{"confluence_table": []}"#),
+            Err(ScoringError::Invalid(_))
+        ));
+        assert!(matches!(ScoringProfile::from_json("{"), Err(ScoringError::Parse(_))));
+    }
+
+    #[test]
+    fn test_calibration_learns_predictive_feature() {
+        // BOS decides the outcome; whale score is noise
+        let mut signals = Vec::new();
+        for i in 0..40 {
+            let bos = i % 2 == 0;
+            let win = if i % 10 == 0 { !bos } else { bos };
+            signals.push(resolved(bos, (i % 7) as f64 * 10.0, win));
+        }
+
+        let report = Calibrator::default()
+            .calibrate(&ScoringProfile::default(), &signals)
+            .unwrap();
+        assert_eq!(report.samples, 40);
+        assert!(report.coefficients["bos_confirmed"] > report.coefficients["whale"].abs());
+        assert!(report.profile.smc_bonus.bos_confirmed > 50.0);
+        assert!(report.calibrated.auc >= report.current.auc);
+        assert!(report.calibrated.auc > 0.8);
+        assert!(report.profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_calibration_requires_samples() {
+        let signals = vec![resolved(true, 0.0, true), resolved(false, 0.0, false)];
+        assert!(matches!(
+            Calibrator::default().calibrate(&ScoringProfile::default(), &signals),
+            Err(ScoringError::NotEnoughSamples { required: 30, found: 2 })
+        ));
+
+        let calibrator = Calibrator {
+            min_samples: 1,
+            ..Default::default()
+        };
+        let winners = vec![resolved(true, 0.0, true)];
+        assert!(matches!(
+            calibrator.calibrate(&ScoringProfile::default(), &winners),
+            Err(ScoringError::SingleClass)
+        ));
+    }
+
+    #[test]
+    fn test_grade_separation() {
+        let separation = GradeSeparation::from_samples(&[(95.0, 2.0), (92.0, 1.0), (55.0, -1.0), (40.0, 1.0)]);
+        assert_eq!(separation.by_grade["S"].win_rate, 1.0);
+        assert_eq!(separation.by_grade["D"].win_rate, 0.0);
+        assert!(!separation.monotonic); // F beats D
+        assert!((separation.auc - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,234 @@
+//! Signal performance attribution
+//!
+//! Aggregates resolved signal outcomes (see `TrackedSignal::realized_r`) into
+//! win rate, average R and expectancy, grouped by grade, indicator, SMC setup,
+//! timeframe and market type.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{SmcTags, TrackedSignal};
+use crate::market::SymbolUniverse;
+
+/// Outcome statistics for one group of signals
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct PerformanceStats {
+    pub samples: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub breakevens: usize,
+    pub win_rate: f64,    // 0-1
+    pub loss_rate: f64,   // 0-1; breakevens are neither
+    pub avg_r: f64,
+    pub avg_win_r: f64,
+    pub avg_loss_r: f64,  // Size of the average loss, positive
+    pub expectancy: f64,  // win_rate * avg_win_r - loss_rate * avg_loss_r
+    pub total_r: f64,
+}
+
+impl PerformanceStats {
+    /// Compute stats from realized R values (a win is R > 0, a loss R < 0)
+    pub fn from_outcomes(outcomes: &[f64]) -> Self {
+        if outcomes.is_empty() {
+            return Self::default();
+        }
+
+        let samples = outcomes.len();
+        let wins: Vec<f64> = outcomes.iter().copied().filter(|r| *r > 0.0).collect();
+        let losses: Vec<f64> = outcomes.iter().copied().filter(|r| *r < 0.0).map(|r| -r).collect();
+        let mean = |values: &[f64]| {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / values.len() as f64
+            }
+        };
+
+        let total_r: f64 = outcomes.iter().sum();
+        let win_rate = wins.len() as f64 / samples as f64;
+        let loss_rate = losses.len() as f64 / samples as f64;
+        let avg_win_r = mean(&wins);
+        let avg_loss_r = mean(&losses);
+
+        Self {
+            samples,
+            wins: wins.len(),
+            losses: losses.len(),
+            breakevens: samples - wins.len() - losses.len(),
+            win_rate,
+            loss_rate,
+            avg_r: total_r / samples as f64,
+            avg_win_r,
+            avg_loss_r,
+            expectancy: win_rate * avg_win_r - loss_rate * avg_loss_r,
+            total_r,
+        }
+    }
+}
+
+/// Performance broken down by the attributes that feed the signal score
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SignalStatsReport {
+    pub overall: PerformanceStats,
+    pub by_grade: BTreeMap<String, PerformanceStats>,
+    pub by_indicator: BTreeMap<String, PerformanceStats>,
+    pub by_smc_setup: BTreeMap<String, PerformanceStats>,
+    pub by_timeframe: BTreeMap<String, PerformanceStats>,
+    pub by_market_type: BTreeMap<String, PerformanceStats>,
+    pub unresolved: usize, // Still open, or never triggered
+}
+
+impl SignalStatsReport {
+    pub fn from_signals(signals: &[TrackedSignal]) -> Self {
+        let mut overall = Vec::new();
+        let mut by_grade: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut by_indicator: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut by_smc_setup: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut by_timeframe: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut by_market_type: HashMap<String, Vec<f64>> = HashMap::new();
+        let mut market_types: HashMap<String, String> = HashMap::new();
+        let mut unresolved = 0;
+
+        for tracked in signals {
+            let Some(r) = tracked.realized_r() else {
+                unresolved += 1;
+                continue;
+            };
+            let signal = &tracked.signal;
+
+            overall.push(r);
+            by_grade.entry(format!("{:?}", signal.grade)).or_default().push(r);
+            for indicator in &signal.indicators {
+                by_indicator.entry(indicator.clone()).or_default().push(r);
+            }
+            by_smc_setup.entry(smc_setup(&signal.smc_tags)).or_default().push(r);
+            by_timeframe.entry(signal.timeframe.clone()).or_default().push(r);
+
+            let market_type = market_types
+                .entry(signal.symbol.clone())
+                .or_insert_with(|| format!("{:?}", SymbolUniverse::market_type_of(&signal.symbol)));
+            by_market_type.entry(market_type.clone()).or_default().push(r);
+        }
+
+        Self {
+            overall: PerformanceStats::from_outcomes(&overall),
+            by_grade: summarize(by_grade),
+            by_indicator: summarize(by_indicator),
+            by_smc_setup: summarize(by_smc_setup),
+            by_timeframe: summarize(by_timeframe),
+            by_market_type: summarize(by_market_type),
+            unresolved,
+        }
+    }
+}
+
+/// Stable key for the combination of SMC confirmations, e.g. "bos+fvg"
+pub fn smc_setup(tags: &SmcTags) -> String {
+    let parts: Vec<&str> = [
+        (tags.bos_confirmed, "bos"),
+        (tags.near_fvg, "fvg"),
+        (tags.near_order_block, "ob"),
+        (tags.liquidity_sweep, "sweep"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, name)| *name)
+    .collect();
+
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join("+")
+    }
+}
+
+fn summarize(groups: HashMap<String, Vec<f64>>) -> BTreeMap<String, PerformanceStats> {
+    groups
+        .into_iter()
+        .map(|(key, outcomes)| (key, PerformanceStats::from_outcomes(&outcomes)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal::{EnhancedSignal, SignalDirection, SignalGrade, TrackerConfig};
+
+    fn resolved(symbol: &str, grade: SignalGrade, indicators: &[&str], prices: &[f64]) -> TrackedSignal {
+        // Entry 100, stop 98, targets 104/106/110
+        let mut signal = EnhancedSignal::new(
+            symbol.to_string(),
+            "M15".to_string(),
+            SignalDirection::Buy,
+            100.0,
+            98.0,
+        );
+        signal.timestamp = 0;
+        signal.grade = grade;
+        for indicator in indicators {
+            signal.add_indicator(indicator.to_string());
+        }
+        signal.smc_tags.bos_confirmed = true;
+
+        let mut tracked = TrackedSignal::new(signal);
+        for (i, price) in prices.iter().enumerate() {
+            tracked.on_bar(*price, *price, *price, i as i64 + 1, &TrackerConfig::default());
+        }
+        tracked
+    }
+
+    #[test]
+    fn test_performance_stats() {
+        let stats = PerformanceStats::from_outcomes(&[2.0, -1.0, 3.0, -1.0]);
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.wins, 2);
+        assert_eq!(stats.win_rate, 0.5);
+        assert_eq!(stats.avg_r, 0.75);
+        assert_eq!(stats.avg_win_r, 2.5);
+        assert_eq!(stats.avg_loss_r, 1.0);
+        assert_eq!(stats.expectancy, 0.75);
+
+        // A breakeven is neither a win nor a loss
+        let stats = PerformanceStats::from_outcomes(&[3.0, -1.5, 0.0, -0.5]);
+        assert_eq!((stats.wins, stats.losses, stats.breakevens), (1, 2, 1));
+        assert_eq!((stats.win_rate, stats.loss_rate), (0.25, 0.5));
+        assert_eq!(stats.avg_loss_r, 1.0);
+        assert_eq!(stats.expectancy, 0.25 * 3.0 - 0.5 * 1.0);
+        assert_eq!(PerformanceStats::from_outcomes(&[]), PerformanceStats::default());
+    }
+
+    #[test]
+    fn test_report_groups_outcomes() {
+        let signals = vec![
+            resolved("BTCUSDT", SignalGrade::S, &["RSI", "MACD"], &[100.0, 110.0]), // TP3: 0.5*2 + 0.3*3 + 0.2*5 = +2.9R
+            resolved("AAPL", SignalGrade::S, &["RSI"], &[100.0, 97.0]),             // Stop: -1R
+            resolved("AAPL", SignalGrade::B, &["MACD"], &[100.0, 104.0, 97.0]),     // Half at TP1, rest stopped: +0.5R
+            resolved("AAPL", SignalGrade::B, &["MACD"], &[101.0]),                  // Never triggered
+        ];
+
+        let report = SignalStatsReport::from_signals(&signals);
+        assert_eq!(report.overall.samples, 3);
+        assert_eq!(report.unresolved, 1);
+        assert!((report.overall.total_r - 2.4).abs() < 1e-9);
+
+        assert_eq!(report.by_grade["S"].samples, 2);
+        assert!((report.by_grade["S"].avg_r - 0.95).abs() < 1e-9);
+        assert_eq!(report.by_grade["B"].win_rate, 1.0);
+
+        assert_eq!(report.by_indicator["RSI"].samples, 2);
+        assert!((report.by_indicator["MACD"].total_r - 3.4).abs() < 1e-9);
+        assert_eq!(report.by_market_type["Crypto"].samples, 1);
+        assert_eq!(report.by_market_type["Stock"].samples, 2);
+        assert_eq!(report.by_timeframe["M15"].samples, 3);
+        assert_eq!(report.by_smc_setup["bos"].samples, 3);
+    }
+
+    #[test]
+    fn test_smc_setup_key() {
+        let mut tags = SmcTags::default();
+        assert_eq!(smc_setup(&tags), "none");
+        tags.liquidity_sweep = true;
+        tags.near_fvg = true;
+        assert_eq!(smc_setup(&tags), "fvg+sweep");
+    }
+}
@@ -0,0 +1,743 @@
+//! Persistent signal store
+//!
+//! Emitted signals and their lifecycle transitions go through the
+//! `SignalRepository` trait. `FileSignalStore` appends them to a JSON-lines
+//! file from a background writer thread and rebuilds its index on startup,
+//! keeping the newest `max_signals` and compacting the file once it grows past
+//! `compact_bytes`; `MemorySignalStore` keeps them in memory only and also
+//! serves as that index.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use super::{EnhancedSignal, SignalDirection, SignalGrade, SignalStatus, SignalTransition, TrackedSignal};
+
+pub const DEFAULT_QUERY_LIMIT: usize = 50;
+pub const MAX_QUERY_LIMIT: usize = 500;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(String),
+    Serialization(String),
+    InvalidCursor(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "Signal store I/O error: {}", e),
+            StoreError::Serialization(e) => write!(f, "Signal store serialization error: {}", e),
+            StoreError::InvalidCursor(c) => write!(f, "Invalid cursor: {}", c),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// A signal with its lifecycle so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSignal {
+    #[serde(flatten)]
+    pub signal: EnhancedSignal,
+    pub status: SignalStatus,
+    pub events: Vec<SignalTransition>,
+}
+
+impl StoredSignal {
+    pub fn new(signal: EnhancedSignal) -> Self {
+        Self {
+            signal,
+            status: SignalStatus::PendingEntry,
+            events: Vec::new(),
+        }
+    }
+
+    /// Rebuild the tracker state from the recorded transitions
+    pub fn to_tracked(&self) -> TrackedSignal {
+        let mut tracked = TrackedSignal::new(self.signal.clone());
+        let risk = self.signal.targets.risk();
+
+        for event in &self.events {
+            match event.to {
+                SignalStatus::Triggered => tracked.triggered_at = Some(event.timestamp),
+                SignalStatus::Tp1Hit => tracked.tp_hit_at[0] = Some(event.timestamp),
+                SignalStatus::Tp2Hit => tracked.tp_hit_at[1] = Some(event.timestamp),
+                SignalStatus::Tp3Hit => tracked.tp_hit_at[2] = Some(event.timestamp),
+                _ => {}
+            }
+            if event.to.is_terminal() {
+                tracked.closed_at = Some(event.timestamp);
+                tracked.exit_price = Some(event.price);
+            }
+            tracked.status = event.to;
+            tracked.last_price = Some(event.price);
+            tracked.updated_at = tracked.updated_at.max(event.timestamp);
+            tracked.max_favorable_excursion = event.mfe_r * risk;
+            tracked.max_adverse_excursion = event.mae_r * risk;
+        }
+
+        tracked
+    }
+
+    fn cursor(&self) -> String {
+        format!("{}:{}", self.signal.timestamp, self.signal.id)
+    }
+}
+
+/// Filters for `/api/signals`. Results are newest first; pass `next_cursor`
+/// from the previous page as `cursor` to continue.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignalQuery {
+    pub symbol: Option<String>,
+    pub direction: Option<SignalDirection>,
+    pub grade: Option<SignalGrade>,
+    pub timeframe: Option<String>,
+    pub from: Option<i64>, // Unix ms, inclusive
+    pub to: Option<i64>,   // Unix ms, inclusive
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl SignalQuery {
+    fn matches(&self, signal: &EnhancedSignal) -> bool {
+        self.symbol.as_ref().is_none_or(|s| s.eq_ignore_ascii_case(&signal.symbol))
+            && self.direction.as_ref().is_none_or(|d| *d == signal.direction)
+            && self.grade.as_ref().is_none_or(|g| *g == signal.grade)
+            && self.timeframe.as_ref().is_none_or(|t| t.eq_ignore_ascii_case(&signal.timeframe))
+            && self.from.is_none_or(|from| signal.timestamp >= from)
+            && self.to.is_none_or(|to| signal.timestamp <= to)
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_QUERY_LIMIT).clamp(1, MAX_QUERY_LIMIT)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalPage {
+    pub signals: Vec<StoredSignal>,
+    pub next_cursor: Option<String>,
+}
+
+pub trait SignalRepository: Send + Sync {
+    fn insert_signal(&self, signal: &EnhancedSignal) -> Result<(), StoreError>;
+    fn append_event(&self, event: &SignalTransition) -> Result<(), StoreError>;
+    fn get(&self, id: &str) -> Result<Option<StoredSignal>, StoreError>;
+    fn query(&self, query: &SignalQuery) -> Result<SignalPage, StoreError>;
+    /// Every stored signal with its rebuilt tracker state, newest first
+    fn history(&self) -> Result<Vec<TrackedSignal>, StoreError>;
+    /// Wait until everything stored so far is written out
+    fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+}
+
+/// In-memory store, ordered by (timestamp, id)
+#[derive(Default)]
+pub struct MemorySignalStore {
+    signals: RwLock<BTreeMap<(i64, String), StoredSignal>>,
+    timestamps: RwLock<HashMap<String, i64>>,
+}
+
+impl MemorySignalStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.signals.read().len()
+    }
+
+    /// Drop the oldest signals beyond `max`
+    fn retain_newest(&self, max: usize) {
+        let mut signals = self.signals.write();
+        let mut timestamps = self.timestamps.write();
+        while signals.len() > max {
+            let Some(((_, id), _)) = signals.pop_first() else {
+                break;
+            };
+            timestamps.remove(&id);
+        }
+    }
+
+    /// Every signal with its events, oldest first
+    fn snapshot(&self) -> Vec<StoredSignal> {
+        self.signals.read().values().cloned().collect()
+    }
+}
+
+/// The file lines that recreate `signals`
+fn to_lines(signals: Vec<StoredSignal>) -> Result<String, StoreError> {
+    let mut out = String::new();
+    for stored in signals {
+        let entries = std::iter::once(StoreEntry::Signal(Box::new(stored.signal)))
+            .chain(stored.events.into_iter().map(StoreEntry::Event));
+        for entry in entries {
+            out.push_str(&entry.to_line()?);
+        }
+    }
+    Ok(out)
+}
+
+impl SignalRepository for MemorySignalStore {
+    fn insert_signal(&self, signal: &EnhancedSignal) -> Result<(), StoreError> {
+        let key = (signal.timestamp, signal.id.clone());
+        self.timestamps.write().insert(signal.id.clone(), signal.timestamp);
+        self.signals.write().insert(key, StoredSignal::new(signal.clone()));
+        Ok(())
+    }
+
+    fn append_event(&self, event: &SignalTransition) -> Result<(), StoreError> {
+        let Some(ts) = self.timestamps.read().get(&event.signal_id).copied() else {
+            tracing::debug!("Dropping event for unknown signal {}", event.signal_id);
+            return Ok(());
+        };
+        if let Some(stored) = self.signals.write().get_mut(&(ts, event.signal_id.clone())) {
+            stored.status = event.to;
+            stored.events.push(event.clone());
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<Option<StoredSignal>, StoreError> {
+        let Some(ts) = self.timestamps.read().get(id).copied() else {
+            return Ok(None);
+        };
+        Ok(self.signals.read().get(&(ts, id.to_string())).cloned())
+    }
+
+    fn query(&self, query: &SignalQuery) -> Result<SignalPage, StoreError> {
+        let before = match &query.cursor {
+            Some(cursor) => {
+                let (ts, id) = cursor
+                    .split_once(':')
+                    .and_then(|(ts, id)| ts.parse::<i64>().ok().map(|ts| (ts, id.to_string())))
+                    .ok_or_else(|| StoreError::InvalidCursor(cursor.clone()))?;
+                Some((ts, id))
+            }
+            None => None,
+        };
+
+        let limit = query.limit();
+        let signals = self.signals.read();
+        let candidates: Box<dyn Iterator<Item = &StoredSignal>> = match &before {
+            Some(key) => Box::new(signals.range(..key.clone()).rev().map(|(_, s)| s)),
+            None => Box::new(signals.values().rev()),
+        };
+
+        let mut page: Vec<StoredSignal> = candidates
+            .filter(|s| query.matches(&s.signal))
+            .take(limit + 1)
+            .cloned()
+            .collect();
+
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().map(StoredSignal::cursor)
+        } else {
+            None
+        };
+
+        Ok(SignalPage {
+            signals: page,
+            next_cursor,
+        })
+    }
+
+    fn history(&self) -> Result<Vec<TrackedSignal>, StoreError> {
+        Ok(self.signals.read().values().rev().map(StoredSignal::to_tracked).collect())
+    }
+}
+
+/// One line of the append-only file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum StoreEntry {
+    Signal(Box<EnhancedSignal>),
+    Event(SignalTransition),
+}
+
+impl StoreEntry {
+    fn to_line(&self) -> Result<String, StoreError> {
+        let mut line = serde_json::to_string(self).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+/// How much history the file store keeps
+#[derive(Debug, Clone)]
+pub struct StoreRetention {
+    pub max_signals: usize, // Oldest signals past this are dropped
+    pub compact_bytes: u64, // Rewrite the file with only the kept signals past this size
+}
+
+impl Default for StoreRetention {
+    fn default() -> Self {
+        Self {
+            max_signals: 10_000,
+            compact_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Work for the writer thread, in the order the index saw it
+enum WriterMsg {
+    Append(String),
+    Rewrite(String), // Whole file after compaction
+    Flush(mpsc::Sender<Result<(), String>>),
+}
+
+/// Queue into the writer thread; holding it orders index updates with their lines
+struct WriterQueue {
+    tx: Option<mpsc::Sender<WriterMsg>>,
+    bytes: u64,      // File size once the queue is written
+    compact_at: u64,
+    compacting: bool,  // A snapshot is being serialized outside the lock
+    held: Vec<String>, // Lines appended meanwhile, queued after the rewrite
+}
+
+impl WriterQueue {
+    fn send(&self, msg: WriterMsg) -> Result<(), StoreError> {
+        self.tx
+            .as_ref()
+            .and_then(|tx| tx.send(msg).ok())
+            .ok_or_else(|| StoreError::Io("signal store writer stopped".to_string()))
+    }
+}
+
+/// Append-only JSON-lines file with an in-memory index
+pub struct FileSignalStore {
+    queue: Mutex<WriterQueue>,
+    writer: Option<JoinHandle<()>>,
+    index: MemorySignalStore,
+    retention: StoreRetention,
+}
+
+impl FileSignalStore {
+    /// Open (or create) the store at `path` and replay it into memory.
+    /// Unreadable lines, e.g. a write cut short by a crash, are skipped.
+    pub fn open(path: impl AsRef<Path>, retention: StoreRetention) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| StoreError::Io(e.to_string()))?;
+        }
+
+        let index = MemorySignalStore::new();
+        if path.exists() {
+            let reader = BufReader::new(File::open(path).map_err(|e| StoreError::Io(e.to_string()))?);
+            for (n, line) in reader.lines().enumerate() {
+                let line = line.map_err(|e| StoreError::Io(e.to_string()))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<StoreEntry>(&line) {
+                    Ok(StoreEntry::Signal(signal)) => index.insert_signal(&signal)?,
+                    Ok(StoreEntry::Event(event)) => index.append_event(&event)?,
+                    Err(e) => tracing::warn!("Skipping line {} of {}: {}", n + 1, path.display(), e),
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(path)
+            .map_err(|e| StoreError::Io(e.to_string()))?;
+        if !ends_with_newline(&mut file)? {
+            // Terminate a torn last line so the next entry starts clean
+            file.write_all(b"\n").map_err(|e| StoreError::Io(e.to_string()))?;
+        }
+        let bytes = file.metadata().map_err(|e| StoreError::Io(e.to_string()))?.len();
+        index.retain_newest(retention.max_signals);
+
+        let (tx, rx) = mpsc::channel();
+        let writer_path = path.to_path_buf();
+        let writer = std::thread::Builder::new()
+            .name("signal-store".to_string())
+            .spawn(move || run_writer(writer_path, file, rx))
+            .map_err(|e| StoreError::Io(e.to_string()))?;
+
+        tracing::info!("Signal store opened at {} ({} signals)", path.display(), index.len());
+        let store = Self {
+            queue: Mutex::new(WriterQueue {
+                tx: Some(tx),
+                bytes,
+                compact_at: retention.compact_bytes,
+                compacting: false,
+                held: Vec::new(),
+            }),
+            writer: Some(writer),
+            index,
+            retention,
+        };
+        // A file left over size, e.g. by a lower `compact_bytes`, is compacted right away
+        if bytes > store.retention.compact_bytes {
+            store.compact()?;
+        }
+        Ok(store)
+    }
+
+    /// Apply `entry` to the index and queue its line for the writer
+    fn append(&self, entry: StoreEntry) -> Result<(), StoreError> {
+        let line = entry.to_line()?;
+        let mut queue = self.queue.lock();
+        match &entry {
+            StoreEntry::Signal(signal) => {
+                self.index.insert_signal(signal)?;
+                self.index.retain_newest(self.retention.max_signals);
+            }
+            StoreEntry::Event(event) => self.index.append_event(event)?,
+        }
+        if queue.compacting {
+            queue.held.push(line);
+            return Ok(());
+        }
+        queue.bytes += line.len() as u64;
+        queue.send(WriterMsg::Append(line))?;
+        if queue.bytes > queue.compact_at {
+            drop(queue);
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the file with the signals the index still holds. Only the
+    /// snapshot is taken under the queue lock; it is serialized outside it.
+    fn compact(&self) -> Result<(), StoreError> {
+        let snapshot = {
+            let mut queue = self.queue.lock();
+            if queue.compacting {
+                return Ok(());
+            }
+            queue.compacting = true;
+            self.index.snapshot()
+        };
+        let contents = to_lines(snapshot);
+
+        let mut queue = self.queue.lock();
+        queue.compacting = false;
+        let held = std::mem::take(&mut queue.held);
+        let result = match contents {
+            Ok(contents) => {
+                queue.bytes = contents.len() as u64;
+                // Kept history alone may be near the limit; don't rewrite on every append
+                queue.compact_at = self.retention.compact_bytes.max(queue.bytes * 2);
+                queue.send(WriterMsg::Rewrite(contents))
+            }
+            Err(e) => Err(e),
+        };
+        // Appended after the snapshot, so they go after the rewrite either way
+        for line in held {
+            queue.bytes += line.len() as u64;
+            queue.send(WriterMsg::Append(line))?;
+        }
+        result
+    }
+}
+
+impl Drop for FileSignalStore {
+    fn drop(&mut self) {
+        // Closing the queue lets the writer finish what is queued and exit
+        self.queue.lock().tx.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Write queued lines, flushing whenever the queue runs dry
+fn run_writer(path: PathBuf, file: File, rx: mpsc::Receiver<WriterMsg>) {
+    let mut out = BufWriter::new(file);
+    while let Ok(first) = rx.recv() {
+        let mut next = Some(first);
+        while let Some(msg) = next.take() {
+            let result = match msg {
+                WriterMsg::Append(line) => out.write_all(line.as_bytes()),
+                WriterMsg::Rewrite(contents) => rewrite(&path, &contents).map(|file| out = BufWriter::new(file)),
+                WriterMsg::Flush(ack) => {
+                    let _ = ack.send(out.flush().map_err(|e| e.to_string()));
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                tracing::error!("Signal store write to {} failed: {}", path.display(), e);
+            }
+            next = rx.try_recv().ok();
+        }
+        if let Err(e) = out.flush() {
+            tracing::error!("Signal store flush to {} failed: {}", path.display(), e);
+        }
+    }
+}
+
+/// Atomically replace `path` with `contents`, returning it open for appends
+fn rewrite(path: &Path, contents: &str) -> std::io::Result<File> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp = PathBuf::from(tmp_name);
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+impl SignalRepository for FileSignalStore {
+    fn insert_signal(&self, signal: &EnhancedSignal) -> Result<(), StoreError> {
+        self.append(StoreEntry::Signal(Box::new(signal.clone())))
+    }
+
+    fn append_event(&self, event: &SignalTransition) -> Result<(), StoreError> {
+        self.append(StoreEntry::Event(event.clone()))
+    }
+
+    fn get(&self, id: &str) -> Result<Option<StoredSignal>, StoreError> {
+        self.index.get(id)
+    }
+
+    fn query(&self, query: &SignalQuery) -> Result<SignalPage, StoreError> {
+        self.index.query(query)
+    }
+
+    fn history(&self) -> Result<Vec<TrackedSignal>, StoreError> {
+        self.index.history()
+    }
+
+    fn flush(&self) -> Result<(), StoreError> {
+        let (ack, done) = mpsc::channel();
+        self.queue.lock().send(WriterMsg::Flush(ack))?;
+        done.recv()
+            .map_err(|_| StoreError::Io("signal store writer stopped".to_string()))?
+            .map_err(StoreError::Io)
+    }
+}
+
+fn ends_with_newline(file: &mut File) -> Result<bool, StoreError> {
+    let len = file.metadata().map_err(|e| StoreError::Io(e.to_string()))?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(len - 1)).map_err(|e| StoreError::Io(e.to_string()))?;
+    file.read_exact(&mut last).map_err(|e| StoreError::Io(e.to_string()))?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(symbol: &str, direction: SignalDirection, timestamp: i64) -> EnhancedSignal {
+        let stop = if direction == SignalDirection::Sell { 102.0 } else { 98.0 };
+        let mut signal = EnhancedSignal::new(symbol.to_string(), "M15".to_string(), direction, 100.0, stop);
+        signal.timestamp = timestamp;
+        signal
+    }
+
+    fn event(signal: &EnhancedSignal, to: SignalStatus, price: f64, timestamp: i64) -> SignalTransition {
+        SignalTransition {
+            signal_id: signal.id.clone(),
+            symbol: signal.symbol.clone(),
+            timeframe: signal.timeframe.clone(),
+            direction: signal.direction.clone(),
+            from: None,
+            to,
+            price,
+            timestamp,
+            mfe_r: 2.0,
+            mae_r: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_query_filters_and_pagination() {
+        let store = MemorySignalStore::new();
+        for i in 0..5 {
+            store.insert_signal(&signal("BTCUSDT", SignalDirection::Buy, 1_000 + i)).unwrap();
+        }
+        store.insert_signal(&signal("AAPL", SignalDirection::Sell, 1_002)).unwrap();
+
+        let query = SignalQuery {
+            symbol: Some("btcusdt".to_string()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let first = store.query(&query).unwrap();
+        assert_eq!(first.signals.len(), 2);
+        assert_eq!(first.signals[0].signal.timestamp, 1_004);
+        assert!(first.next_cursor.is_some());
+
+        let second = store
+            .query(&SignalQuery {
+                cursor: first.next_cursor.clone(),
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(second.signals[0].signal.timestamp, 1_002);
+        assert_eq!(second.signals[1].signal.timestamp, 1_001);
+
+        let third = store
+            .query(&SignalQuery {
+                cursor: second.next_cursor.clone(),
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(third.signals.len(), 1);
+        assert!(third.next_cursor.is_none());
+
+        let sells = store
+            .query(&SignalQuery {
+                direction: Some(SignalDirection::Sell),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(sells.signals.len(), 1);
+        assert_eq!(sells.signals[0].signal.symbol, "AAPL");
+
+        let range = store
+            .query(&SignalQuery {
+                from: Some(1_001),
+                to: Some(1_002),
+                grade: Some(SignalGrade::F),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(range.signals.len(), 3);
+
+        assert!(matches!(
+            store.query(&SignalQuery {
+                cursor: Some("garbage".to_string()),
+                ..Default::default()
+            }),
+            Err(StoreError::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    fn test_events_rebuild_tracked_state() {
+        let store = MemorySignalStore::new();
+        let s = signal("BTCUSDT", SignalDirection::Buy, 1_000);
+        store.insert_signal(&s).unwrap();
+        store.append_event(&event(&s, SignalStatus::Triggered, 100.0, 2_000)).unwrap();
+        store.append_event(&event(&s, SignalStatus::Tp1Hit, 104.0, 3_000)).unwrap();
+        store.append_event(&event(&s, SignalStatus::StoppedOut, 98.0, 4_000)).unwrap();
+
+        let stored = store.get(&s.id).unwrap().unwrap();
+        assert_eq!(stored.status, SignalStatus::StoppedOut);
+        assert_eq!(stored.events.len(), 3);
+
+        let tracked = stored.to_tracked();
+        assert_eq!(tracked.triggered_at, Some(2_000));
+        assert_eq!(tracked.tp_hit_at, [Some(3_000), None, None]);
+        // Half booked at TP1 (+2R), the rest stopped out (-1R)
+        assert_eq!(tracked.realized_r(), Some(0.5));
+        assert!((tracked.mfe_r() - 2.0).abs() < 1e-9);
+        assert_eq!(store.history().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_file_store_survives_restart() {
+        let path = std::env::temp_dir().join(format!("signals-{}.jsonl", uuid::Uuid::new_v4()));
+        let s = signal("ETHUSDT", SignalDirection::Sell, 5_000);
+        {
+            let store = FileSignalStore::open(&path, StoreRetention::default()).unwrap();
+            store.insert_signal(&s).unwrap();
+            store.append_event(&event(&s, SignalStatus::Triggered, 100.0, 6_000)).unwrap();
+        }
+
+        // Simulate a torn write at the end of the file
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"type\":\"event\",\"da").unwrap();
+        drop(file);
+
+        let reopened = FileSignalStore::open(&path, StoreRetention::default()).unwrap();
+        let stored = reopened.get(&s.id).unwrap().unwrap();
+        assert_eq!(stored.signal.symbol, "ETHUSDT");
+        assert_eq!(stored.status, SignalStatus::Triggered);
+
+        // Writes after the torn line are still readable
+        reopened.append_event(&event(&s, SignalStatus::Tp1Hit, 96.0, 7_000)).unwrap();
+        drop(reopened);
+        let again = FileSignalStore::open(&path, StoreRetention::default()).unwrap();
+        assert_eq!(again.get(&s.id).unwrap().unwrap().status, SignalStatus::Tp1Hit);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_retention_and_compaction() {
+        let path = std::env::temp_dir().join(format!("signals-{}.jsonl", uuid::Uuid::new_v4()));
+        let retention = StoreRetention {
+            max_signals: 2,
+            compact_bytes: 16 * 1024,
+        };
+        let signals: Vec<EnhancedSignal> = (0..20).map(|i| signal("BTCUSDT", SignalDirection::Buy, 1_000 + i)).collect();
+        {
+            let store = FileSignalStore::open(&path, retention.clone()).unwrap();
+            for s in &signals {
+                store.insert_signal(s).unwrap();
+                store.append_event(&event(s, SignalStatus::Triggered, 100.0, s.timestamp + 1)).unwrap();
+            }
+            store.flush().unwrap();
+            assert_eq!(store.history().unwrap().len(), 2);
+            assert!(store.get(&signals[0].id).unwrap().is_none());
+        }
+
+        // Compaction kept the file near the retained history
+        assert!(std::fs::metadata(&path).unwrap().len() < 2 * retention.compact_bytes);
+        let reopened = FileSignalStore::open(&path, retention).unwrap();
+        let history = reopened.history().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].signal.id, signals[19].id);
+        assert_eq!(history[0].status, SignalStatus::Triggered);
+        assert_eq!(reopened.get(&signals[18].id).unwrap().unwrap().events.len(), 1);
+
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_appends_during_compaction_are_kept() {
+        let path = std::env::temp_dir().join(format!("signals-{}.jsonl", uuid::Uuid::new_v4()));
+        let retention = StoreRetention {
+            max_signals: 1_000,
+            compact_bytes: 8 * 1024,
+        };
+        {
+            let store = std::sync::Arc::new(FileSignalStore::open(&path, retention.clone()).unwrap());
+            let writers: Vec<_> = (0..4)
+                .map(|t| {
+                    let store = store.clone();
+                    std::thread::spawn(move || {
+                        for i in 0..25 {
+                            let s = signal("BTCUSDT", SignalDirection::Buy, t * 1_000 + i);
+                            store.insert_signal(&s).unwrap();
+                            store.append_event(&event(&s, SignalStatus::Triggered, 100.0, s.timestamp + 1)).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            store.flush().unwrap();
+        }
+
+        let reopened = FileSignalStore::open(&path, retention).unwrap();
+        let history = reopened.history().unwrap();
+        assert_eq!(history.len(), 100);
+        assert!(history.iter().all(|t| t.status == SignalStatus::Triggered));
+
+        drop(reopened);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+//! Structure-aware target planning
+//!
+//! Places the stop beyond the swing or zone that would invalidate the trade and
+//! picks take-profits from opposing liquidity, unmitigated FVGs/order blocks and
+//! Fibonacci extensions. Missing targets are filled from the R-multiple scheme.
+
+use super::{SignalDirection, SignalTargets, StopSource, TargetSource};
+use crate::smc::{
+    FairValueGap, FvgType, LiquidityType, LiquidityZone, OrderBlock, OrderBlockType, SwingPoint,
+};
+
+/// Detected structure around the entry
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StructureContext<'a> {
+    pub liquidity: &'a [LiquidityZone],
+    pub fvgs: &'a [FairValueGap],
+    pub order_blocks: &'a [OrderBlock],
+    pub swings: &'a [SwingPoint], // Oldest first
+}
+
+#[derive(Debug, Clone)]
+pub struct TargetPlannerConfig {
+    pub stop_buffer_pct: f64,    // Distance beyond the invalidating level
+    pub max_stop_pct: f64,       // Structural stops farther than this are ignored
+    pub min_stop_ratio: f64,     // Structural stops nearer than this share of the fallback distance are ignored
+    pub min_target_r: f64,       // Targets closer than this are ignored
+    pub merge_pct: f64,          // Targets closer together than this count as one
+    pub fib_extensions: Vec<f64>,
+    pub fallback_r: [f64; 3],
+}
+
+impl Default for TargetPlannerConfig {
+    fn default() -> Self {
+        Self {
+            stop_buffer_pct: 0.1,
+            max_stop_pct: 5.0,
+            min_stop_ratio: 0.5,
+            min_target_r: 1.0,
+            merge_pct: 0.1,
+            fib_extensions: vec![1.272, 1.618, 2.0],
+            fallback_r: [2.0, 3.0, 5.0],
+        }
+    }
+}
+
+/// Plans stop and take-profit levels from market structure
+pub struct TargetPlanner {
+    pub config: TargetPlannerConfig,
+}
+
+impl TargetPlanner {
+    pub fn new(config: TargetPlannerConfig) -> Self {
+        Self { config }
+    }
+
+    /// Plan targets for a trade. `fallback_stop` (e.g. ATR based) is used when
+    /// no structure invalidates the trade within `max_stop_pct` of entry; it
+    /// also sets the minimum structural stop distance, see `min_stop_ratio`.
+    pub fn plan(
+        &self,
+        entry: f64,
+        fallback_stop: f64,
+        direction: &SignalDirection,
+        ctx: &StructureContext,
+    ) -> SignalTargets {
+        if *direction == SignalDirection::Neutral || entry <= 0.0 {
+            return SignalTargets::new(entry, fallback_stop, direction);
+        }
+
+        let is_buy = *direction == SignalDirection::Buy;
+        let (stop, stop_source) = self
+            .structural_stop(entry, is_buy, (entry - fallback_stop).abs(), ctx)
+            .unwrap_or((fallback_stop, StopSource::Fixed));
+
+        let risk = (entry - stop).abs();
+        if risk <= 0.0 {
+            return SignalTargets::new(entry, stop, direction);
+        }
+
+        let r_of = |level: f64| (level - entry).abs() / risk;
+        let level_at = |r: f64| if is_buy { entry + risk * r } else { entry - risk * r };
+
+        let mut targets: Vec<(f64, TargetSource)> = Vec::with_capacity(3);
+        for (level, source) in self.structural_targets(entry, is_buy, ctx) {
+            if targets.len() == 3 {
+                break;
+            }
+            if r_of(level) < self.config.min_target_r {
+                continue;
+            }
+            let merged = targets
+                .last()
+                .is_some_and(|(prev, _)| (level - prev).abs() / entry * 100.0 < self.config.merge_pct);
+            if !merged {
+                targets.push((level, source));
+            }
+        }
+
+        // Fill the remaining slots with R multiples beyond the last target
+        while targets.len() < 3 {
+            let last_r = targets.last().map(|(level, _)| r_of(*level)).unwrap_or(0.0);
+            let r = self
+                .config
+                .fallback_r
+                .iter()
+                .copied()
+                .find(|r| *r > last_r + 1e-9)
+                .unwrap_or(last_r + 1.0);
+            targets.push((level_at(r), TargetSource::RMultiple));
+        }
+
+        SignalTargets::from_levels(
+            entry,
+            stop,
+            [targets[0].0, targets[1].0, targets[2].0],
+            [targets[0].1, targets[1].1, targets[2].1],
+            stop_source,
+        )
+    }
+
+    /// Nearest level beyond which the setup is invalid, with buffer applied,
+    /// that is at least `min_stop_ratio` of `fallback_distance` from entry
+    fn structural_stop(
+        &self,
+        entry: f64,
+        is_buy: bool,
+        fallback_distance: f64,
+        ctx: &StructureContext,
+    ) -> Option<(f64, StopSource)> {
+        let mut levels: Vec<(f64, StopSource)> = Vec::new();
+
+        for swing in ctx.swings {
+            if swing.is_high != is_buy {
+                levels.push((swing.price, StopSource::SwingPoint));
+            }
+        }
+
+        // Supporting zones: their far edge invalidates the trade
+        for ob in ctx.order_blocks.iter().filter(|ob| !ob.mitigated) {
+            match ob.ob_type {
+                OrderBlockType::Bullish if is_buy => levels.push((ob.bottom, StopSource::OrderBlock)),
+                OrderBlockType::Bearish if !is_buy => levels.push((ob.top, StopSource::OrderBlock)),
+                _ => {}
+            }
+        }
+        for fvg in ctx.fvgs.iter().filter(|f| !f.mitigated || f.is_inverted()) {
+            match fvg.polarity() {
+                FvgType::Bullish if is_buy => levels.push((fvg.bottom, StopSource::FairValueGap)),
+                FvgType::Bearish if !is_buy => levels.push((fvg.top, StopSource::FairValueGap)),
+                _ => {}
+            }
+        }
+
+        let buffer = 1.0 + self.config.stop_buffer_pct / 100.0;
+        let max_distance = entry * self.config.max_stop_pct / 100.0;
+        let min_distance = fallback_distance * self.config.min_stop_ratio;
+
+        levels
+            .into_iter()
+            .filter(|(level, _)| if is_buy { *level < entry } else { *level > entry })
+            .map(|(level, source)| {
+                let stop = if is_buy { level / buffer } else { level * buffer };
+                (stop, source)
+            })
+            .filter(|(stop, _)| (min_distance..=max_distance).contains(&(entry - stop).abs()))
+            .min_by(|a, b| (entry - a.0).abs().total_cmp(&(entry - b.0).abs()))
+    }
+
+    /// Opposing structure in the trade direction, nearest first
+    fn structural_targets(&self, entry: f64, is_buy: bool, ctx: &StructureContext) -> Vec<(f64, TargetSource)> {
+        let mut levels: Vec<(f64, TargetSource)> = Vec::new();
+
+        for zone in ctx.liquidity.iter().filter(|z| !z.swept) {
+            match zone.liquidity_type {
+                LiquidityType::BuySideLiquidity if is_buy => levels.push((zone.price_level, TargetSource::Liquidity)),
+                LiquidityType::SellSideLiquidity if !is_buy => levels.push((zone.price_level, TargetSource::Liquidity)),
+                _ => {}
+            }
+        }
+
+        for swing in ctx.swings {
+            if swing.is_high == is_buy {
+                levels.push((swing.price, TargetSource::SwingPoint));
+            }
+        }
+
+        // Opposing zones: price tends to react at their near edge
+        for ob in ctx.order_blocks.iter().filter(|ob| !ob.mitigated) {
+            match ob.ob_type {
+                OrderBlockType::Bearish if is_buy => levels.push((ob.bottom, TargetSource::OrderBlock)),
+                OrderBlockType::Bullish if !is_buy => levels.push((ob.top, TargetSource::OrderBlock)),
+                _ => {}
+            }
+        }
+        for fvg in ctx.fvgs.iter().filter(|f| !f.mitigated || f.is_inverted()) {
+            match fvg.polarity() {
+                FvgType::Bearish if is_buy => levels.push((fvg.bottom, TargetSource::FairValueGap)),
+                FvgType::Bullish if !is_buy => levels.push((fvg.top, TargetSource::FairValueGap)),
+                _ => {}
+            }
+        }
+
+        // Extensions of the last impulse leg (swing low -> swing high for buys)
+        let last_high = ctx.swings.iter().rev().find(|s| s.is_high);
+        let last_low = ctx.swings.iter().rev().find(|s| !s.is_high);
+        if let (Some(high), Some(low)) = (last_high, last_low) {
+            let range = high.price - low.price;
+            let impulse_matches = if is_buy {
+                low.timestamp < high.timestamp
+            } else {
+                high.timestamp < low.timestamp
+            };
+            if range > 0.0 && impulse_matches {
+                for ext in &self.config.fib_extensions {
+                    let level = if is_buy {
+                        low.price + range * ext
+                    } else {
+                        high.price - range * ext
+                    };
+                    levels.push((level, TargetSource::FibExtension));
+                }
+            }
+        }
+
+        levels.retain(|(level, _)| if is_buy { *level > entry } else { *level < entry });
+        levels.sort_by(|a, b| (a.0 - entry).abs().total_cmp(&(b.0 - entry).abs()));
+        levels
+    }
+}
+
+impl Default for TargetPlanner {
+    fn default() -> Self {
+        Self::new(TargetPlannerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swing(price: f64, timestamp: i64, is_high: bool) -> SwingPoint {
+        SwingPoint { price, timestamp, is_high }
+    }
+
+    #[test]
+    fn test_falls_back_to_r_multiples_without_structure() {
+        let planner = TargetPlanner::default();
+        let targets = planner.plan(100.0, 98.0, &SignalDirection::Buy, &StructureContext::default());
+
+        assert_eq!(targets.stop_loss, 98.0);
+        assert_eq!(targets.take_profit_1, 104.0);
+        assert_eq!(targets.take_profit_3, 110.0);
+        assert_eq!(targets.stop_source, StopSource::Fixed);
+        assert!(!targets.is_structural());
+    }
+
+    #[test]
+    fn test_buy_targets_from_structure() {
+        let planner = TargetPlanner::default();
+
+        let liquidity = vec![LiquidityZone::new(LiquidityType::BuySideLiquidity, 106.0, 1000, 0.8)];
+        let fvgs = vec![FairValueGap::new(FvgType::Bearish, 110.0, 108.0, 2000)];
+        let swings = vec![swing(97.0, 3000, false)];
+        let ctx = StructureContext {
+            liquidity: &liquidity,
+            fvgs: &fvgs,
+            swings: &swings,
+            ..Default::default()
+        };
+
+        let targets = planner.plan(100.0, 95.0, &SignalDirection::Buy, &ctx);
+
+        // Stop just below the swing low instead of the fallback
+        assert_eq!(targets.stop_source, StopSource::SwingPoint);
+        assert!(targets.stop_loss < 97.0 && targets.stop_loss > 96.8);
+
+        assert_eq!(targets.take_profit_1, 106.0);
+        assert_eq!(targets.target_sources[0], TargetSource::Liquidity);
+        assert_eq!(targets.take_profit_2, 108.0);
+        assert_eq!(targets.target_sources[1], TargetSource::FairValueGap);
+        assert_eq!(targets.target_sources[2], TargetSource::RMultiple);
+
+        // Real R of the structural target, not a fixed 2.0
+        let risk = 100.0 - targets.stop_loss;
+        assert!((targets.risk_reward_ratio - 6.0 / risk).abs() < 1e-9);
+        assert!(targets.r_multiples[2] > targets.r_multiples[1]);
+    }
+
+    #[test]
+    fn test_stop_skips_levels_inside_the_minimum_distance() {
+        let planner = TargetPlanner::default();
+
+        // Fallback 2 away: levels within 1 of entry are noise, not invalidation
+        let swings = vec![swing(97.5, 1000, false), swing(99.6, 2000, false)];
+        let ctx = StructureContext {
+            swings: &swings,
+            ..Default::default()
+        };
+
+        let targets = planner.plan(100.0, 98.0, &SignalDirection::Buy, &ctx);
+        assert_eq!(targets.stop_source, StopSource::SwingPoint);
+        assert!(targets.stop_loss < 97.5 && targets.stop_loss > 97.3);
+
+        // Nothing beyond the floor: the fallback stop
+        let swings = vec![swing(99.6, 2000, false)];
+        let ctx = StructureContext {
+            swings: &swings,
+            ..Default::default()
+        };
+        let targets = planner.plan(100.0, 98.0, &SignalDirection::Buy, &ctx);
+        assert_eq!(targets.stop_source, StopSource::Fixed);
+        assert_eq!(targets.stop_loss, 98.0);
+    }
+
+    #[test]
+    fn test_sell_stop_beyond_bearish_order_block() {
+        let planner = TargetPlanner::default();
+
+        let order_blocks = vec![OrderBlock::new(OrderBlockType::Bearish, 102.0, 101.0, 1000, 0.9)];
+        let liquidity = vec![
+            LiquidityZone::new(LiquidityType::SellSideLiquidity, 99.5, 1000, 0.8), // < 1R, skipped
+            LiquidityZone::new(LiquidityType::SellSideLiquidity, 96.0, 1000, 0.8),
+        ];
+        let ctx = StructureContext {
+            liquidity: &liquidity,
+            order_blocks: &order_blocks,
+            ..Default::default()
+        };
+
+        let targets = planner.plan(100.0, 103.0, &SignalDirection::Sell, &ctx);
+        assert_eq!(targets.stop_source, StopSource::OrderBlock);
+        assert!(targets.stop_loss > 102.0 && targets.stop_loss < 102.2);
+        assert_eq!(targets.take_profit_1, 96.0);
+    }
+
+    #[test]
+    fn test_fib_extension_targets() {
+        let planner = TargetPlanner::default();
+
+        // Impulse from 90 to 100, entry on the pullback
+        let swings = vec![swing(90.0, 1000, false), swing(100.0, 2000, true)];
+        let ctx = StructureContext {
+            swings: &swings,
+            ..Default::default()
+        };
+
+        let targets = planner.plan(96.0, 94.0, &SignalDirection::Buy, &ctx);
+        assert_eq!(targets.stop_source, StopSource::Fixed); // Swing low is beyond 5%
+        assert_eq!(targets.take_profit_1, 100.0);
+        assert_eq!(targets.target_sources[0], TargetSource::SwingPoint);
+        assert!((targets.take_profit_2 - 102.72).abs() < 1e-9);
+        assert_eq!(targets.target_sources[1], TargetSource::FibExtension);
+    }
+
+    #[test]
+    fn test_targets_are_ordered_in_trade_direction() {
+        let planner = TargetPlanner::default();
+        let liquidity = vec![LiquidityZone::new(LiquidityType::SellSideLiquidity, 80.0, 1000, 0.8)];
+        let ctx = StructureContext {
+            liquidity: &liquidity,
+            ..Default::default()
+        };
+
+        // Structural target at 10R, the rest extend beyond it
+        let targets = planner.plan(100.0, 102.0, &SignalDirection::Sell, &ctx);
+        assert_eq!(targets.take_profit_1, 80.0);
+        assert!(targets.take_profit_2 < targets.take_profit_1);
+        assert!(targets.take_profit_3 < targets.take_profit_2);
+    }
+}
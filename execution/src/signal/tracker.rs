@@ -0,0 +1,622 @@
+//! Signal lifecycle tracking
+//!
+//! Follows each emitted `EnhancedSignal` through subsequent prices:
+//! pending entry -> triggered -> TP1/TP2/TP3 or stopped out, with expiry and
+//! invalidation, recording timestamps and max favorable/adverse excursion.
+//! Every state change is published on a `SignalBus<SignalTransition>`.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{EnhancedSignal, SignalDirection, SignalRepository};
+use crate::bus::SignalBus;
+use crate::market::ProviderManager;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalStatus {
+    PendingEntry, // Waiting for price to reach entry
+    Triggered,    // Entry filled, no target hit yet
+    Tp1Hit,
+    Tp2Hit,
+    Tp3Hit,       // Final target, closed
+    StoppedOut,   // Stop hit after entry (possibly after TP1/TP2)
+    Expired,      // Timed out before entry or before resolution
+    Invalidated,  // Price reached stop or TP1 without filling entry
+}
+
+impl SignalStatus {
+    /// Whether the signal can no longer change state
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            SignalStatus::Tp3Hit
+                | SignalStatus::StoppedOut
+                | SignalStatus::Expired
+                | SignalStatus::Invalidated
+        )
+    }
+}
+
+/// Share of the position closed at TP1/TP2/TP3 when scoring outcomes, the
+/// same split brackets use by default
+pub const SCALE_OUT: [f64; 3] = [0.5, 0.3, 0.2];
+
+#[derive(Debug, Clone)]
+pub struct TrackerConfig {
+    pub entry_tolerance_pct: f64, // Entry counts as filled within this distance
+    pub entry_timeout_ms: i64,    // Pending signals expire after this
+    pub max_hold_ms: i64,         // Triggered signals expire after this
+    pub max_closed: usize,        // Closed signals kept for the dashboard
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            entry_tolerance_pct: 0.05,
+            entry_timeout_ms: 4 * 60 * 60 * 1000,
+            max_hold_ms: 24 * 60 * 60 * 1000,
+            max_closed: 500,
+        }
+    }
+}
+
+/// A lifecycle event for the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalTransition {
+    pub signal_id: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub direction: SignalDirection,
+    pub from: Option<SignalStatus>, // None when tracking starts
+    pub to: SignalStatus,
+    pub price: f64,
+    pub timestamp: i64,
+    pub mfe_r: f64,
+    pub mae_r: f64,
+}
+
+/// A signal and everything that happened to it since emission
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedSignal {
+    pub signal: EnhancedSignal,
+    pub status: SignalStatus,
+    pub triggered_at: Option<i64>,
+    pub tp_hit_at: [Option<i64>; 3],
+    pub closed_at: Option<i64>,
+    pub exit_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub max_favorable_excursion: f64, // Price distance from entry, >= 0
+    pub max_adverse_excursion: f64,   // Price distance from entry, >= 0
+    pub updated_at: i64,
+}
+
+impl TrackedSignal {
+    pub fn new(signal: EnhancedSignal) -> Self {
+        let updated_at = signal.timestamp;
+        Self {
+            signal,
+            status: SignalStatus::PendingEntry,
+            triggered_at: None,
+            tp_hit_at: [None; 3],
+            closed_at: None,
+            exit_price: None,
+            last_price: None,
+            max_favorable_excursion: 0.0,
+            max_adverse_excursion: 0.0,
+            updated_at,
+        }
+    }
+
+    pub fn mfe_r(&self) -> f64 {
+        self.in_r(self.max_favorable_excursion)
+    }
+
+    pub fn mae_r(&self) -> f64 {
+        self.in_r(self.max_adverse_excursion)
+    }
+
+    /// Number of take-profits reached (0-3)
+    pub fn targets_hit(&self) -> usize {
+        self.tp_hit_at.iter().filter(|t| t.is_some()).count()
+    }
+
+    /// Outcome in R once the signal is resolved. Each target reached closes
+    /// its `SCALE_OUT` share of the position at that target's R; whatever is
+    /// left closes at the exit price (-1R at the stop, marked to the last price
+    /// on expiry). `None` while open or if entry never filled.
+    pub fn realized_r(&self) -> Option<f64> {
+        if !self.status.is_terminal() || self.triggered_at.is_none() {
+            return None;
+        }
+
+        let hit = self.targets_hit();
+        let booked: f64 = (0..hit).map(|i| SCALE_OUT[i] * self.signal.targets.r_multiples[i]).sum();
+        let remaining = 1.0 - SCALE_OUT[..hit].iter().sum::<f64>();
+        if remaining <= 1e-9 {
+            return Some(booked);
+        }
+        let exit = self.exit_price?;
+        let sign = if self.signal.direction == SignalDirection::Sell { -1.0 } else { 1.0 };
+        Some(booked + remaining * sign * self.in_r(exit - self.signal.targets.entry))
+    }
+
+    fn in_r(&self, distance: f64) -> f64 {
+        let risk = self.signal.targets.risk();
+        if risk > 0.0 {
+            distance / risk
+        } else {
+            0.0
+        }
+    }
+
+    /// Advance the state machine with one bar (a quote is a bar with
+    /// high == low == close). Within a bar the stop is assumed to be hit
+    /// before any target, which keeps outcomes conservative.
+    pub fn on_bar(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        timestamp: i64,
+        config: &TrackerConfig,
+    ) -> Vec<SignalTransition> {
+        let mut transitions = Vec::new();
+        if self.status.is_terminal() || timestamp < self.updated_at {
+            return transitions;
+        }
+
+        let sign = match self.signal.direction {
+            SignalDirection::Buy => 1.0,
+            SignalDirection::Sell => -1.0,
+            SignalDirection::Neutral => return transitions,
+        };
+        let (favorable, adverse) = if sign > 0.0 { (high, low) } else { (low, high) };
+        let targets = self.signal.targets.clone();
+        let entry = targets.entry;
+        let reached = |extreme: f64, level: f64| sign * (extreme - level) >= 0.0;
+
+        self.updated_at = timestamp;
+        self.last_price = Some(close);
+
+        if self.status == SignalStatus::PendingEntry {
+            if timestamp - self.signal.timestamp > config.entry_timeout_ms {
+                self.transition(SignalStatus::Expired, close, timestamp, &mut transitions);
+                return transitions;
+            }
+
+            let tolerance = entry * config.entry_tolerance_pct / 100.0;
+            let touched = low <= entry + tolerance && high >= entry - tolerance;
+            if !touched {
+                // Price left without us: through the stop, or on to the first target
+                if reached(targets.stop_loss, adverse) || reached(favorable, targets.take_profit_1) {
+                    self.transition(SignalStatus::Invalidated, close, timestamp, &mut transitions);
+                }
+                return transitions;
+            }
+
+            self.triggered_at = Some(timestamp);
+            self.transition(SignalStatus::Triggered, entry, timestamp, &mut transitions);
+        }
+
+        self.max_favorable_excursion = self.max_favorable_excursion.max(sign * (favorable - entry));
+        self.max_adverse_excursion = self.max_adverse_excursion.max(sign * (entry - adverse));
+
+        if reached(targets.stop_loss, adverse) {
+            self.transition(SignalStatus::StoppedOut, targets.stop_loss, timestamp, &mut transitions);
+            return transitions;
+        }
+
+        let levels = [targets.take_profit_1, targets.take_profit_2, targets.take_profit_3];
+        let statuses = [SignalStatus::Tp1Hit, SignalStatus::Tp2Hit, SignalStatus::Tp3Hit];
+        for i in self.targets_hit()..3 {
+            if !reached(favorable, levels[i]) {
+                break;
+            }
+            self.tp_hit_at[i] = Some(timestamp);
+            self.transition(statuses[i], levels[i], timestamp, &mut transitions);
+        }
+
+        let held_since = self.triggered_at.unwrap_or(timestamp);
+        if !self.status.is_terminal() && timestamp - held_since > config.max_hold_ms {
+            self.transition(SignalStatus::Expired, close, timestamp, &mut transitions);
+        }
+
+        transitions
+    }
+
+    /// Expire on time alone, for symbols that stopped producing prices
+    pub fn check_expiry(&mut self, now: i64, config: &TrackerConfig) -> Option<SignalTransition> {
+        let expired = match self.status {
+            SignalStatus::PendingEntry => now - self.signal.timestamp > config.entry_timeout_ms,
+            status if status.is_terminal() => false,
+            _ => self.triggered_at.is_some_and(|t| now - t > config.max_hold_ms),
+        };
+        if !expired {
+            return None;
+        }
+
+        let price = self.last_price.unwrap_or(self.signal.targets.entry);
+        let mut transitions = Vec::new();
+        self.updated_at = now;
+        self.transition(SignalStatus::Expired, price, now, &mut transitions);
+        transitions.pop()
+    }
+
+    fn transition(
+        &mut self,
+        to: SignalStatus,
+        price: f64,
+        timestamp: i64,
+        out: &mut Vec<SignalTransition>,
+    ) {
+        let from = self.status;
+        self.status = to;
+        if to.is_terminal() {
+            self.closed_at = Some(timestamp);
+            self.exit_price = Some(price);
+        }
+        out.push(SignalTransition {
+            signal_id: self.signal.id.clone(),
+            symbol: self.signal.symbol.clone(),
+            timeframe: self.signal.timeframe.clone(),
+            direction: self.signal.direction.clone(),
+            from: Some(from),
+            to,
+            price,
+            timestamp,
+            mfe_r: self.mfe_r(),
+            mae_r: self.mae_r(),
+        });
+    }
+}
+
+/// Tracks all emitted signals and publishes their transitions
+pub struct SignalTracker {
+    config: TrackerConfig,
+    signals: DashMap<String, TrackedSignal>,
+    bus: SignalBus<SignalTransition>,
+    store: Option<Arc<dyn SignalRepository>>,
+}
+
+impl SignalTracker {
+    pub fn new(config: TrackerConfig, bus: SignalBus<SignalTransition>) -> Self {
+        Self {
+            config,
+            signals: DashMap::new(),
+            bus,
+            store: None,
+        }
+    }
+
+    /// Persist every tracked signal and transition to `store`
+    pub fn with_store(mut self, store: Arc<dyn SignalRepository>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Resume tracking signals that were still open when the store was written
+    pub fn restore(&self) -> usize {
+        let Some(store) = &self.store else {
+            return 0;
+        };
+        match store.history() {
+            Ok(history) => {
+                let open: Vec<TrackedSignal> = history
+                    .into_iter()
+                    .filter(|t| !t.status.is_terminal() && t.signal.direction != SignalDirection::Neutral)
+                    .collect();
+                let count = open.len();
+                for tracked in open {
+                    self.signals.insert(tracked.signal.id.clone(), tracked);
+                }
+                count
+            }
+            Err(e) => {
+                tracing::error!("Failed to restore tracked signals: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Store a signal and start tracking it. Neutral signals are stored but
+    /// have nothing to resolve, so they are not tracked.
+    pub fn track(&self, signal: EnhancedSignal) -> Option<SignalTransition> {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.insert_signal(&signal) {
+                tracing::error!("Failed to store signal {}: {}", signal.id, e);
+            }
+        }
+        if signal.direction == SignalDirection::Neutral {
+            return None;
+        }
+
+        let transition = SignalTransition {
+            signal_id: signal.id.clone(),
+            symbol: signal.symbol.clone(),
+            timeframe: signal.timeframe.clone(),
+            direction: signal.direction.clone(),
+            from: None,
+            to: SignalStatus::PendingEntry,
+            price: signal.targets.entry,
+            timestamp: signal.timestamp,
+            mfe_r: 0.0,
+            mae_r: 0.0,
+        };
+        self.signals.insert(signal.id.clone(), TrackedSignal::new(signal));
+        self.publish_all(std::slice::from_ref(&transition));
+        Some(transition)
+    }
+
+    pub fn on_quote(&self, symbol: &str, price: f64, timestamp: i64) -> Vec<SignalTransition> {
+        self.on_candle(symbol, price, price, price, timestamp)
+    }
+
+    /// Feed a price bar to every open signal on `symbol`
+    pub fn on_candle(
+        &self,
+        symbol: &str,
+        high: f64,
+        low: f64,
+        close: f64,
+        timestamp: i64,
+    ) -> Vec<SignalTransition> {
+        let mut transitions = Vec::new();
+        for mut entry in self.signals.iter_mut() {
+            let tracked = entry.value_mut();
+            if tracked.signal.symbol == symbol && !tracked.status.is_terminal() {
+                transitions.extend(tracked.on_bar(high, low, close, timestamp, &self.config));
+            }
+        }
+        self.publish_all(&transitions);
+        transitions
+    }
+
+    /// Expire signals whose time ran out, regardless of price updates
+    pub fn expire(&self, now: i64) -> Vec<SignalTransition> {
+        let transitions: Vec<SignalTransition> = self
+            .signals
+            .iter_mut()
+            .filter_map(|mut entry| entry.value_mut().check_expiry(now, &self.config))
+            .collect();
+        self.publish_all(&transitions);
+        self.prune();
+        transitions
+    }
+
+    pub fn get(&self, id: &str) -> Option<TrackedSignal> {
+        self.signals.get(id).map(|entry| entry.value().clone())
+    }
+
+    /// All tracked signals, newest first
+    pub fn all(&self) -> Vec<TrackedSignal> {
+        let mut signals: Vec<TrackedSignal> = self.signals.iter().map(|e| e.value().clone()).collect();
+        signals.sort_by_key(|s| std::cmp::Reverse(s.signal.timestamp));
+        signals
+    }
+
+    pub fn open_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .signals
+            .iter()
+            .filter(|e| !e.status.is_terminal())
+            .map(|e| e.signal.symbol.clone())
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Poll quotes for symbols with open signals until the task is dropped
+    pub async fn run(self: Arc<Self>, provider_manager: Arc<ProviderManager>, poll_interval: Duration) {
+        let mut ticker = tokio::time::interval(poll_interval);
+        loop {
+            ticker.tick().await;
+
+            let symbols = self.open_symbols();
+            if !symbols.is_empty() {
+                match provider_manager.get_bulk_quotes(&symbols).await {
+                    Ok(quotes) => {
+                        for quote in quotes {
+                            self.on_quote(&quote.symbol, quote.price, quote.timestamp);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Signal tracker failed to fetch quotes: {}", e),
+                }
+            }
+
+            self.expire(chrono::Utc::now().timestamp_millis());
+        }
+    }
+
+    fn publish_all(&self, transitions: &[SignalTransition]) {
+        for transition in transitions {
+            tracing::info!(
+                "Signal {} {} {:?} -> {:?} @ {}",
+                transition.symbol,
+                transition.signal_id,
+                transition.from,
+                transition.to,
+                transition.price
+            );
+            if let Some(store) = &self.store {
+                if let Err(e) = store.append_event(transition) {
+                    tracing::error!("Failed to store transition of {}: {}", transition.signal_id, e);
+                }
+            }
+            self.bus.publish(transition.clone());
+        }
+    }
+
+    /// Drop the oldest closed signals beyond `max_closed`
+    fn prune(&self) {
+        let mut closed: Vec<(i64, String)> = self
+            .signals
+            .iter()
+            .filter_map(|e| e.closed_at.map(|ts| (ts, e.key().clone())))
+            .collect();
+        if closed.len() <= self.config.max_closed {
+            return;
+        }
+        closed.sort();
+        let excess = closed.len() - self.config.max_closed;
+        for (_, id) in closed.into_iter().take(excess) {
+            self.signals.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy_signal() -> EnhancedSignal {
+        // Entry 100, stop 98, targets 104/106/110
+        let mut signal = EnhancedSignal::new(
+            "BTCUSDT".to_string(),
+            "M15".to_string(),
+            SignalDirection::Buy,
+            100.0,
+            98.0,
+        );
+        signal.timestamp = 1_000;
+        signal
+    }
+
+    fn tracker() -> SignalTracker {
+        SignalTracker::new(TrackerConfig::default(), SignalBus::new(16))
+    }
+
+    #[test]
+    fn test_buy_runs_through_targets() {
+        let tracker = tracker();
+        let mut rx = tracker.bus.subscribe();
+        let id = tracker.track(buy_signal()).unwrap().signal_id;
+
+        assert!(tracker.on_quote("BTCUSDT", 101.0, 2_000).is_empty());
+        let t = tracker.on_candle("BTCUSDT", 100.5, 99.0, 100.2, 3_000);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].to, SignalStatus::Triggered);
+
+        let t = tracker.on_candle("BTCUSDT", 106.5, 100.0, 106.0, 4_000);
+        let to: Vec<SignalStatus> = t.iter().map(|t| t.to).collect();
+        assert_eq!(to, vec![SignalStatus::Tp1Hit, SignalStatus::Tp2Hit]);
+
+        tracker.on_quote("BTCUSDT", 111.0, 5_000);
+        let tracked = tracker.get(&id).unwrap();
+        assert_eq!(tracked.status, SignalStatus::Tp3Hit);
+        assert_eq!(tracked.triggered_at, Some(3_000));
+        assert_eq!(tracked.tp_hit_at, [Some(4_000), Some(4_000), Some(5_000)]);
+        assert_eq!(tracked.exit_price, Some(110.0));
+        assert!((tracked.mfe_r() - 5.5).abs() < 1e-9);
+        assert!((tracked.mae_r() - 0.5).abs() < 1e-9);
+        assert!((tracked.realized_r().unwrap() - 2.9).abs() < 1e-9);
+        assert!(tracker.open_symbols().is_empty());
+
+        // Pending + triggered + 3 targets were published
+        let mut published = 0;
+        while rx.try_recv().is_ok() {
+            published += 1;
+        }
+        assert_eq!(published, 5);
+    }
+
+    #[test]
+    fn test_stop_wins_within_a_bar() {
+        let tracker = tracker();
+        let id = tracker.track(buy_signal()).unwrap().signal_id;
+
+        tracker.on_quote("BTCUSDT", 100.0, 2_000);
+        let t = tracker.on_candle("BTCUSDT", 105.0, 97.5, 99.0, 3_000);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].to, SignalStatus::StoppedOut);
+
+        let tracked = tracker.get(&id).unwrap();
+        assert_eq!(tracked.exit_price, Some(98.0));
+        assert_eq!(tracked.targets_hit(), 0);
+        assert_eq!(tracked.realized_r(), Some(-1.0));
+    }
+
+    #[test]
+    fn test_partial_exits_blend_into_realized_r() {
+        let tracker = tracker();
+        let id = tracker.track(buy_signal()).unwrap().signal_id;
+
+        tracker.on_quote("BTCUSDT", 100.0, 2_000);
+        tracker.on_quote("BTCUSDT", 106.0, 3_000);
+        assert_eq!(tracker.get(&id).unwrap().realized_r(), None);
+
+        // TP1 and TP2 closed 80% at +2R/+3R, the last 20% stopped at -1R
+        tracker.on_quote("BTCUSDT", 97.0, 4_000);
+        let tracked = tracker.get(&id).unwrap();
+        assert_eq!(tracked.status, SignalStatus::StoppedOut);
+        assert!((tracked.realized_r().unwrap() - 1.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalidated_before_entry() {
+        let tracker = tracker();
+        let id = tracker.track(buy_signal()).unwrap().signal_id;
+
+        // Runs to TP1 without pulling back to entry
+        let t = tracker.on_candle("BTCUSDT", 104.5, 101.0, 104.0, 2_000);
+        assert_eq!(t[0].to, SignalStatus::Invalidated);
+        assert_eq!(tracker.get(&id).unwrap().triggered_at, None);
+    }
+
+    #[test]
+    fn test_store_records_lifecycle_and_restores() {
+        let store: Arc<dyn SignalRepository> = Arc::new(crate::signal::MemorySignalStore::new());
+        let tracker = tracker().with_store(store.clone());
+        let id = tracker.track(buy_signal()).unwrap().signal_id;
+        tracker.on_quote("BTCUSDT", 100.0, 2_000);
+
+        let stored = store.get(&id).unwrap().unwrap();
+        assert_eq!(stored.status, SignalStatus::Triggered);
+        assert_eq!(stored.events.len(), 2);
+
+        // Neutral signals are kept for history but not tracked
+        let mut neutral = buy_signal();
+        neutral.direction = SignalDirection::Neutral;
+        assert!(tracker.track(neutral.clone()).is_none());
+        assert!(store.get(&neutral.id).unwrap().is_some());
+        assert!(tracker.get(&neutral.id).is_none());
+
+        // A fresh tracker picks the open signal back up and keeps resolving it
+        let restarted = SignalTracker::new(TrackerConfig::default(), SignalBus::new(16)).with_store(store.clone());
+        assert_eq!(restarted.restore(), 1);
+        let t = restarted.on_quote("BTCUSDT", 104.0, 3_000);
+        assert_eq!(t[0].to, SignalStatus::Tp1Hit);
+        assert_eq!(store.get(&id).unwrap().unwrap().status, SignalStatus::Tp1Hit);
+    }
+
+    #[test]
+    fn test_sell_and_expiry() {
+        let tracker = tracker();
+        let mut sell = EnhancedSignal::new(
+            "ETHUSDT".to_string(),
+            "M15".to_string(),
+            SignalDirection::Sell,
+            100.0,
+            102.0,
+        );
+        sell.timestamp = 1_000;
+        let sell_id = tracker.track(sell).unwrap().signal_id;
+        let buy_id = tracker.track(buy_signal()).unwrap().signal_id;
+
+        tracker.on_quote("ETHUSDT", 100.0, 2_000);
+        let t = tracker.on_quote("ETHUSDT", 95.5, 3_000);
+        assert_eq!(t[0].to, SignalStatus::Tp1Hit);
+        assert!((tracker.get(&sell_id).unwrap().mfe_r() - 2.25).abs() < 1e-9);
+
+        // BTC never traded again: pending signal times out
+        let config = TrackerConfig::default();
+        let t = tracker.expire(1_000 + config.entry_timeout_ms + 1);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t[0].signal_id, buy_id);
+        assert_eq!(tracker.get(&buy_id).unwrap().status, SignalStatus::Expired);
+        assert_eq!(tracker.get(&sell_id).unwrap().status, SignalStatus::Tp1Hit);
+    }
+}
@@ -16,15 +16,18 @@ pub enum BosType {
 pub struct BreakOfStructure {
     pub bos_type: BosType,
     pub break_level: f64,
-    pub timestamp: i64,
+    #[serde(default)]
+    pub swing_timestamp: i64, // Candle of the broken swing high/low
+    pub timestamp: i64,       // Candle that broke it
     pub strength: f64, // How far beyond the level (as percentage)
 }
 
 impl BreakOfStructure {
-    pub fn new(bos_type: BosType, break_level: f64, timestamp: i64, strength: f64) -> Self {
+    pub fn new(bos_type: BosType, swing: &SwingPoint, timestamp: i64, strength: f64) -> Self {
         Self {
             bos_type,
-            break_level,
+            break_level: swing.price,
+            swing_timestamp: swing.timestamp,
             timestamp,
             strength: strength.min(1.0).max(0.0),
         }
@@ -104,31 +107,30 @@ impl BosDetector {
         let (_, current_high, current_low, current_close, current_ts) = candles[candles.len() - 1];
 
         // Look for recent swing highs and lows
-        let recent_highs: Vec<f64> = swings
+        let recent_highs: Vec<&SwingPoint> = swings
             .iter()
             .filter(|s| s.is_high)
             .rev()
             .take(self.lookback_period)
-            .map(|s| s.price)
             .collect();
 
-        let recent_lows: Vec<f64> = swings
+        let recent_lows: Vec<&SwingPoint> = swings
             .iter()
             .filter(|s| !s.is_high)
             .rev()
             .take(self.lookback_period)
-            .map(|s| s.price)
             .collect();
 
         // Check for bullish BOS (break above previous high)
-        if let Some(&prev_high) = recent_highs.first() {
+        if let Some(&swing) = recent_highs.first() {
+            let prev_high = swing.price;
             if current_high > prev_high {
                 let break_pct = ((current_high - prev_high) / prev_high) * 100.0;
                 if break_pct >= self.min_break_pct {
                     let strength = (break_pct / (self.min_break_pct * 3.0)).min(1.0);
                     bos_events.push(BreakOfStructure::new(
                         BosType::Bullish,
-                        prev_high,
+                        swing,
                         current_ts,
                         strength,
                     ));
@@ -137,14 +139,15 @@ impl BosDetector {
         }
 
         // Check for bearish BOS (break below previous low)
-        if let Some(&prev_low) = recent_lows.first() {
+        if let Some(&swing) = recent_lows.first() {
+            let prev_low = swing.price;
             if current_low < prev_low {
                 let break_pct = ((prev_low - current_low) / prev_low) * 100.0;
                 if break_pct >= self.min_break_pct {
                     let strength = (break_pct / (self.min_break_pct * 3.0)).min(1.0);
                     bos_events.push(BreakOfStructure::new(
                         BosType::Bearish,
-                        prev_low,
+                        swing,
                         current_ts,
                         strength,
                     ));
@@ -210,6 +213,8 @@ mod tests {
 
         let bullish_bos: Vec<_> = bos_events.iter().filter(|b| b.bos_type == BosType::Bullish).collect();
         assert!(!bullish_bos.is_empty());
+        assert_eq!(bullish_bos[0].swing_timestamp, 2000);
+        assert_eq!(bullish_bos[0].timestamp, 5000);
     }
 
     #[test]
@@ -11,6 +11,17 @@ pub enum FvgType {
     Bearish,
 }
 
+/// Fill progression of a Fair Value Gap
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FvgState {
+    Open,                   // Price has not traded back into the gap
+    PartiallyFilled,        // Price entered the gap but stayed short of the 50% level
+    ConsequentEncroachment, // Price reached the 50% (CE) level
+    Filled,                 // Price traded through the whole gap
+    Inverted,               // Price closed through the gap, polarity flipped
+    Invalidated,            // An inverted gap closed through again, no longer a zone
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FairValueGap {
     pub fvg_type: FvgType,
@@ -19,6 +30,12 @@ pub struct FairValueGap {
     pub timestamp: i64,
     pub mitigated: bool,
     pub mitigation_timestamp: Option<i64>,
+    pub state: FvgState,
+    pub fill_pct: f64, // Deepest retracement into the gap (0-100)
+    pub ce_timestamp: Option<i64>,
+    pub inversion_timestamp: Option<i64>,
+    pub inverse_fill_pct: f64, // Deepest retest of the inverted gap from its new side (0-100)
+    pub invalidation_timestamp: Option<i64>,
 }
 
 impl FairValueGap {
@@ -31,26 +48,132 @@ impl FairValueGap {
             timestamp,
             mitigated: false,
             mitigation_timestamp: None,
+            state: FvgState::Open,
+            fill_pct: 0.0,
+            ce_timestamp: None,
+            inversion_timestamp: None,
+            inverse_fill_pct: 0.0,
+            invalidation_timestamp: None,
         }
     }
 
-    /// Check if price has mitigated (filled) this FVG
+    /// Check if FVG has been mitigated (filled) by `price`, updating fill
+    /// tracking. A price alone never inverts the gap; that takes a close.
     pub fn check_mitigation(&mut self, price: f64, timestamp: i64) -> bool {
-        if self.mitigated {
-            return true;
+        let near_edge = match self.fvg_type {
+            FvgType::Bullish => self.top,
+            FvgType::Bearish => self.bottom,
+        };
+        self.update(price, price, near_edge, timestamp);
+        self.mitigated
+    }
+
+    /// Whether `price` would mitigate this FVG, without updating it
+    pub fn would_mitigate(&self, price: f64) -> bool {
+        self.mitigated
+            || match self.fvg_type {
+                FvgType::Bullish => price <= self.bottom,
+                FvgType::Bearish => price >= self.top,
+            }
+    }
+
+    /// Update fill tracking with a candle that formed after the gap
+    ///
+    /// Bullish gaps fill from the top down, bearish gaps from the bottom up.
+    /// A close beyond the far edge inverts the gap. An inverted gap is then
+    /// tracked with its flipped polarity until a close through its new far
+    /// edge invalidates it.
+    pub fn update(&mut self, high: f64, low: f64, close: f64, timestamp: i64) -> &FvgState {
+        match self.state {
+            FvgState::Invalidated => return &self.state,
+            FvgState::Inverted => {
+                let polarity = self.polarity();
+                self.inverse_fill_pct = self.inverse_fill_pct.max(self.fill_depth_pct(&polarity, high, low));
+                if self.closed_through(&polarity, close) {
+                    self.state = FvgState::Invalidated;
+                    self.invalidation_timestamp = Some(timestamp);
+                }
+                return &self.state;
+            }
+            _ => {}
         }
 
-        let is_mitigated = match self.fvg_type {
-            FvgType::Bullish => price <= self.bottom,
-            FvgType::Bearish => price >= self.top,
-        };
+        self.fill_pct = self.fill_pct.max(self.fill_depth_pct(&self.fvg_type, high, low));
+
+        if self.fill_pct > 0.0 && self.state == FvgState::Open {
+            self.state = FvgState::PartiallyFilled;
+        }
 
-        if is_mitigated {
+        if self.fill_pct >= 50.0 && self.ce_timestamp.is_none() {
+            self.ce_timestamp = Some(timestamp);
+            if self.state == FvgState::PartiallyFilled {
+                self.state = FvgState::ConsequentEncroachment;
+            }
+        }
+
+        if self.fill_pct >= 100.0 && !self.mitigated {
             self.mitigated = true;
             self.mitigation_timestamp = Some(timestamp);
+            self.state = FvgState::Filled;
+        }
+
+        if self.closed_through(&self.fvg_type, close) {
+            self.state = FvgState::Inverted;
+            self.inversion_timestamp = Some(timestamp);
+        }
+
+        &self.state
+    }
+
+    /// How far a candle reached into the gap from the side a gap of
+    /// `polarity` fills from, as a percentage of the gap
+    fn fill_depth_pct(&self, polarity: &FvgType, high: f64, low: f64) -> f64 {
+        let size = self.size();
+        if size <= 0.0 {
+            return 0.0;
+        }
+        let depth = match polarity {
+            FvgType::Bullish => self.top - low,
+            FvgType::Bearish => high - self.bottom,
+        };
+        (depth / size * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Whether `close` is beyond the far edge of a gap of `polarity`
+    fn closed_through(&self, polarity: &FvgType, close: f64) -> bool {
+        match polarity {
+            FvgType::Bullish => close < self.bottom,
+            FvgType::Bearish => close > self.top,
         }
+    }
+
+    /// Price of the consequent encroachment (50%) level
+    pub fn consequent_encroachment(&self) -> f64 {
+        (self.top + self.bottom) / 2.0
+    }
+
+    /// Check if the gap has been closed through and now acts with opposite polarity
+    pub fn is_inverted(&self) -> bool {
+        self.state == FvgState::Inverted
+    }
+
+    /// Current polarity of the gap (flipped once inverted)
+    pub fn polarity(&self) -> FvgType {
+        match (&self.fvg_type, self.inversion_timestamp.is_some()) {
+            (FvgType::Bullish, false) | (FvgType::Bearish, true) => FvgType::Bullish,
+            (FvgType::Bearish, false) | (FvgType::Bullish, true) => FvgType::Bearish,
+        }
+    }
 
-        is_mitigated
+    /// Label used for `SmcTags.fvg_type`, e.g. "Bullish", "Bullish:ConsequentEncroachment"
+    /// or "InverseBearish"
+    pub fn tag(&self) -> String {
+        match self.state {
+            FvgState::Open => format!("{:?}", self.fvg_type),
+            FvgState::Inverted => format!("Inverse{:?}", self.polarity()),
+            FvgState::Invalidated => format!("Inverse{:?}:Invalidated", self.polarity()),
+            _ => format!("{:?}:{:?}", self.fvg_type, self.state),
+        }
     }
 
     /// Check if price is near this FVG (within 0.5% distance)
@@ -66,6 +189,23 @@ impl FairValueGap {
     }
 }
 
+/// Balanced Price Range: the overlap of a bullish and a bearish FVG
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalancedPriceRange {
+    pub top: f64,
+    pub bottom: f64,
+    pub timestamp: i64, // When the second gap of the pair formed
+    pub bullish_timestamp: i64,
+    pub bearish_timestamp: i64,
+}
+
+impl BalancedPriceRange {
+    /// Label used for `SmcTags.fvg_type`
+    pub fn tag(&self) -> String {
+        "BalancedPriceRange".to_string()
+    }
+}
+
 /// Detect Fair Value Gaps from candle data
 pub struct FvgDetector {
     pub min_gap_pct: f64, // Minimum gap size as percentage
@@ -82,6 +222,74 @@ impl FvgDetector {
         &self,
         candles: &[(f64, f64, f64, f64, i64)], // (open, high, low, close, timestamp)
     ) -> Vec<FairValueGap> {
+        self.detect_indexed(candles)
+            .into_iter()
+            .map(|(_, fvg)| fvg)
+            .collect()
+    }
+
+    /// Detect FVGs and replay the candles that formed after each one,
+    /// so fill percentage, CE and inversion state are up to date. Live
+    /// gaps are tracked by `SmcStreams` instead.
+    #[cfg(test)]
+    pub fn detect_and_track(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)], // (open, high, low, close, timestamp)
+    ) -> Vec<FairValueGap> {
+        self.detect_indexed(candles)
+            .into_iter()
+            .map(|(idx, mut fvg)| {
+                for &(_, high, low, close, ts) in &candles[idx + 1..] {
+                    fvg.update(high, low, close, ts);
+                }
+                fvg
+            })
+            .collect()
+    }
+
+    /// Find balanced price ranges: an FVG overlapped by a later unmitigated
+    /// FVG of the opposite direction. The move that forms the second gap
+    /// usually runs into the first, so the older one may be partially
+    /// filled, past CE or inverted; a filled or invalidated one is spent.
+    pub fn find_balanced_ranges(&self, fvgs: &[FairValueGap]) -> Vec<BalancedPriceRange> {
+        let mut ranges = Vec::new();
+        let of_type = |fvg_type: FvgType| fvgs.iter().filter(move |f| f.fvg_type == fvg_type);
+
+        for bullish in of_type(FvgType::Bullish) {
+            for bearish in of_type(FvgType::Bearish) {
+                let (older, newer) = match bullish.timestamp.cmp(&bearish.timestamp) {
+                    std::cmp::Ordering::Less => (bullish, bearish),
+                    std::cmp::Ordering::Greater => (bearish, bullish),
+                    std::cmp::Ordering::Equal => continue,
+                };
+                let older_in_play = !matches!(older.state, FvgState::Filled | FvgState::Invalidated);
+                if !older_in_play || newer.mitigated {
+                    continue;
+                }
+                let top = bullish.top.min(bearish.top);
+                let bottom = bullish.bottom.max(bearish.bottom);
+
+                if top > bottom {
+                    ranges.push(BalancedPriceRange {
+                        top,
+                        bottom,
+                        timestamp: bullish.timestamp.max(bearish.timestamp),
+                        bullish_timestamp: bullish.timestamp,
+                        bearish_timestamp: bearish.timestamp,
+                    });
+                }
+            }
+        }
+
+        ranges.sort_by_key(|r| r.timestamp);
+        ranges
+    }
+
+    /// Detect FVGs along with the index of the candle that completed each gap
+    fn detect_indexed(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)],
+    ) -> Vec<(usize, FairValueGap)> {
         if candles.len() < 3 {
             return vec![];
         }
@@ -99,7 +307,7 @@ impl FvgDetector {
                 let gap_pct = (gap_size / high1) * 100.0;
 
                 if gap_pct >= self.min_gap_pct {
-                    fvgs.push(FairValueGap::new(FvgType::Bullish, low3, high1, ts3));
+                    fvgs.push((i + 2, FairValueGap::new(FvgType::Bullish, low3, high1, ts3)));
                 }
             }
             // Bearish FVG: gap between candle 1's low and candle 3's high
@@ -108,7 +316,7 @@ impl FvgDetector {
                 let gap_pct = (gap_size / low1) * 100.0;
 
                 if gap_pct >= self.min_gap_pct {
-                    fvgs.push(FairValueGap::new(FvgType::Bearish, low1, high3, ts3));
+                    fvgs.push((i + 2, FairValueGap::new(FvgType::Bearish, low1, high3, ts3)));
                 }
             }
         }
@@ -170,6 +378,21 @@ mod tests {
         assert!(fvg.mitigated);
     }
 
+    #[test]
+    fn test_would_mitigate_leaves_gap_alone() {
+        let mut fvg = FairValueGap::new(FvgType::Bullish, 108.0, 105.0, 3000);
+
+        assert!(!fvg.would_mitigate(109.0));
+        assert!(fvg.would_mitigate(104.0));
+        assert!(!fvg.mitigated);
+        assert_eq!(fvg.state, FvgState::Open);
+
+        // A touch below the gap fills it but does not invert it
+        fvg.check_mitigation(104.0, 4000);
+        assert_eq!(fvg.state, FvgState::Filled);
+        assert_eq!(fvg.mitigation_timestamp, Some(4000));
+    }
+
     #[test]
     fn test_is_near() {
         let fvg = FairValueGap::new(FvgType::Bullish, 108.0, 105.0, 3000);
@@ -178,4 +401,111 @@ mod tests {
         assert!(fvg.is_near(107.0));  // Close to mid
         assert!(!fvg.is_near(120.0)); // Far away
     }
+
+    #[test]
+    fn test_partial_fill_and_ce() {
+        let mut fvg = FairValueGap::new(FvgType::Bullish, 108.0, 104.0, 3000);
+
+        fvg.update(112.0, 107.0, 111.0, 4000); // 25% into the gap
+        assert_eq!(fvg.state, FvgState::PartiallyFilled);
+        assert_eq!(fvg.fill_pct, 25.0);
+        assert!(!fvg.mitigated);
+
+        fvg.update(110.0, 105.5, 109.0, 5000); // Through the 106 CE level
+        assert_eq!(fvg.state, FvgState::ConsequentEncroachment);
+        assert_eq!(fvg.ce_timestamp, Some(5000));
+        assert_eq!(fvg.tag(), "Bullish:ConsequentEncroachment");
+
+        fvg.update(109.0, 103.5, 106.0, 6000); // Wick through, close back inside
+        assert_eq!(fvg.state, FvgState::Filled);
+        assert!(fvg.mitigated);
+        assert_eq!(fvg.mitigation_timestamp, Some(6000));
+    }
+
+    #[test]
+    fn test_inversion() {
+        let mut fvg = FairValueGap::new(FvgType::Bearish, 95.0, 92.0, 3000);
+
+        fvg.update(96.0, 90.0, 95.5, 4000); // Closes above the gap
+        assert!(fvg.is_inverted());
+        assert!(fvg.mitigated);
+        assert_eq!(fvg.polarity(), FvgType::Bullish);
+        assert_eq!(fvg.tag(), "InverseBullish");
+
+        // Now support: retests come from above, a close below invalidates it
+        fvg.update(97.0, 93.5, 96.0, 5000);
+        assert!(fvg.is_inverted());
+        assert_eq!(fvg.inverse_fill_pct, 50.0);
+        fvg.update(96.0, 91.0, 91.5, 6000);
+        assert_eq!(fvg.state, FvgState::Invalidated);
+        assert_eq!(fvg.invalidation_timestamp, Some(6000));
+        assert_eq!(fvg.tag(), "InverseBullish:Invalidated");
+        assert!(!fvg.is_inverted());
+    }
+
+    #[test]
+    fn test_detect_and_track() {
+        let detector = FvgDetector::default();
+
+        let candles = vec![
+            (100.0, 105.0, 95.0, 102.0, 1000),
+            (102.0, 108.0, 101.0, 107.0, 2000),
+            (110.0, 115.0, 108.0, 113.0, 3000), // Gap 105-108
+            (113.0, 114.0, 107.0, 110.0, 4000), // Retraces a third into the gap
+        ];
+
+        let fvgs = detector.detect_and_track(&candles);
+        assert_eq!(fvgs.len(), 1);
+        assert_eq!(fvgs[0].state, FvgState::PartiallyFilled);
+        assert!((fvgs[0].fill_pct - 33.33).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_balanced_price_range() {
+        let detector = FvgDetector::default();
+        let fvgs = vec![
+            FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 1000),
+            FairValueGap::new(FvgType::Bullish, 108.0, 104.0, 2000),
+            FairValueGap::new(FvgType::Bullish, 120.0, 118.0, 3000), // No overlap
+        ];
+
+        let ranges = detector.find_balanced_ranges(&fvgs);
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(ranges[0].top, 106.0);
+        assert_eq!(ranges[0].bottom, 104.0);
+        assert_eq!(ranges[0].timestamp, 2000);
+
+        // A mitigated gap, or two gaps from the same candle, form no range
+        let mut filled = FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 1000);
+        filled.update(106.5, 101.0, 105.0, 1500);
+        let same_candle = FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 2000);
+        let bullish = FairValueGap::new(FvgType::Bullish, 108.0, 104.0, 2000);
+        assert!(detector.find_balanced_ranges(&[filled, same_candle, bullish]).is_empty());
+    }
+
+    #[test]
+    fn test_balanced_range_from_traded_through_gap() {
+        let detector = FvgDetector::default();
+
+        // The rally that leaves the bullish gap first runs through the bearish one
+        let mut inverted = FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 1000);
+        inverted.update(107.0, 101.0, 106.5, 1500);
+        assert!(inverted.is_inverted());
+        let mut partial = FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 1000);
+        partial.update(105.0, 101.0, 104.0, 1500);
+        assert_eq!(partial.state, FvgState::ConsequentEncroachment);
+        let bullish = FairValueGap::new(FvgType::Bullish, 108.0, 104.0, 2000);
+
+        for older in [inverted, partial] {
+            let ranges = detector.find_balanced_ranges(&[older, bullish.clone()]);
+            assert_eq!(ranges.len(), 1);
+            assert_eq!((ranges[0].top, ranges[0].bottom), (106.0, 104.0));
+        }
+
+        // The newer gap must still be open
+        let mut filled_newer = bullish.clone();
+        filled_newer.update(108.5, 103.0, 105.0, 2500);
+        let older = FairValueGap::new(FvgType::Bearish, 106.0, 102.0, 1000);
+        assert!(detector.find_balanced_ranges(&[older, filled_newer]).is_empty());
+    }
 }
@@ -24,7 +24,7 @@ mod integration_tests {
         let bos_detector = BosDetector::default();
         let liq_detector = LiquidityDetector::default();
 
-        let fvgs = fvg_detector.detect(&candles);
+        let fvgs = fvg_detector.detect_and_track(&candles);
         let order_blocks = ob_detector.detect(&candles);
         let bos_events = bos_detector.detect(&candles);
         let liquidity_zones = liq_detector.detect(&candles);
@@ -39,7 +39,7 @@ mod integration_tests {
         for fvg in &fvgs {
             if fvg.is_near(current_price) {
                 smc_tags.near_fvg = true;
-                smc_tags.fvg_type = Some(format!("{:?}", fvg.fvg_type));
+                smc_tags.fvg_type = Some(fvg.tag());
                 break;
             }
         }
@@ -11,6 +11,23 @@ pub enum LiquidityType {
     SellSideLiquidity,  // Below support (equal lows)
 }
 
+/// What created a liquidity pool
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum LiquiditySource {
+    EqualHighs,
+    EqualLows,
+    PreviousDayHigh,
+    PreviousDayLow,
+    PreviousWeekHigh,
+    PreviousWeekLow,
+    AsiaHigh,
+    AsiaLow,
+    LondonHigh,
+    LondonLow,
+    NewYorkHigh,
+    NewYorkLow,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityZone {
     pub liquidity_type: LiquidityType,
@@ -19,10 +36,16 @@ pub struct LiquidityZone {
     pub strength: f64,       // Based on number of touches
     pub swept: bool,         // Whether liquidity has been taken
     pub sweep_timestamp: Option<i64>,
+    pub source: LiquiditySource,
 }
 
 impl LiquidityZone {
     pub fn new(liquidity_type: LiquidityType, price_level: f64, timestamp: i64, strength: f64) -> Self {
+        let source = match liquidity_type {
+            LiquidityType::BuySideLiquidity => LiquiditySource::EqualHighs,
+            LiquidityType::SellSideLiquidity => LiquiditySource::EqualLows,
+        };
+
         Self {
             liquidity_type,
             price_level,
@@ -30,9 +53,16 @@ impl LiquidityZone {
             strength: strength.min(1.0).max(0.0),
             swept: false,
             sweep_timestamp: None,
+            source,
         }
     }
 
+    /// Set the source of this liquidity pool
+    pub fn with_source(mut self, source: LiquiditySource) -> Self {
+        self.source = source;
+        self
+    }
+
     /// Check if this liquidity zone has been swept
     pub fn check_sweep(&mut self, high: f64, low: f64, timestamp: i64) -> bool {
         if self.swept {
@@ -1,15 +1,23 @@
 /// Smart Money Concepts (SMC) Module
 /// 
 /// This module provides tools for detecting smart money behavior patterns:
-/// - Fair Value Gaps (FVG): Price inefficiencies that act as magnets
+/// - Fair Value Gaps (FVG): Price inefficiencies that act as magnets, tracked
+///   through partial fill, consequent encroachment, full fill and inversion
 /// - Order Blocks (OB): Institutional order placement zones
 /// - Break of Structure (BOS): Trend confirmation signals
 /// - Liquidity Zones: Areas where stop losses cluster
+/// - Session Liquidity: Previous day/week and Asia/London/New York ranges
+///
+/// The `streaming` module wraps the detectors for candle-by-candle use;
+/// `registry` keeps one stream per symbol/timeframe, fed by closed candles.
 
 pub mod fvg;
 pub mod order_blocks;
 pub mod bos;
 pub mod liquidity;
+pub mod sessions;
+pub mod streaming;
+pub mod registry;
 
 #[cfg(test)]
 mod integration_example;
@@ -19,3 +27,6 @@ pub use fvg::{FairValueGap, FvgDetector, FvgType};
 pub use order_blocks::{OrderBlock, OrderBlockDetector, OrderBlockType};
 pub use bos::{BreakOfStructure, BosDetector, BosType, SwingPoint};
 pub use liquidity::{LiquidityZone, LiquidityDetector, LiquidityType};
+pub use sessions::SessionLiquidityDetector;
+pub use streaming::{SmcStream, SmcStreamConfig, SmcZone, ZoneData};
+pub use registry::SmcStreams;
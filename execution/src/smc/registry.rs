@@ -0,0 +1,130 @@
+//! Long-lived SMC Streams
+//!
+//! One `SmcStream` per symbol/timeframe for the life of the process, so zones
+//! keep their state across candles instead of being re-detected from a
+//! window. Streams are fed by `CandleClosed` events from the app bus; readers
+//! that fetched candles themselves call `catch_up` first, which applies only
+//! the candles the stream has not seen.
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+use super::streaming::{SmcCandle, SmcEvent, SmcStream, SmcStreamConfig};
+use crate::bus::{AppBus, CandleClosed};
+
+/// Streams keyed by symbol and timeframe label (`M15`, `H4`, like `CandleClosed`)
+pub struct SmcStreams {
+    config: SmcStreamConfig,
+    streams: DashMap<(String, String), Arc<Mutex<SmcStream>>>,
+}
+
+impl Default for SmcStreams {
+    fn default() -> Self {
+        Self::new(SmcStreamConfig::default())
+    }
+}
+
+impl SmcStreams {
+    pub fn new(config: SmcStreamConfig) -> Self {
+        Self {
+            config,
+            streams: DashMap::new(),
+        }
+    }
+
+    /// Apply one closed candle, starting the stream if there is none yet
+    pub fn on_candle(&self, symbol: &str, timeframe: &str, candle: SmcCandle) -> Vec<SmcEvent> {
+        self.stream(symbol, timeframe).lock().on_candle(candle)
+    }
+
+    /// Apply closed candles, oldest first; ones the stream has seen are skipped
+    pub fn catch_up(&self, symbol: &str, timeframe: &str, candles: &[SmcCandle]) {
+        let stream = self.stream(symbol, timeframe);
+        let mut stream = stream.lock();
+        for candle in candles {
+            stream.on_candle(*candle);
+        }
+    }
+
+    pub fn contains(&self, symbol: &str, timeframe: &str) -> bool {
+        self.streams.contains_key(&(symbol.to_string(), timeframe.to_string()))
+    }
+
+    /// Run `f` on the stream, if this symbol/timeframe has one
+    pub fn read<R>(&self, symbol: &str, timeframe: &str, f: impl FnOnce(&SmcStream) -> R) -> Option<R> {
+        let stream = self.streams.get(&(symbol.to_string(), timeframe.to_string()))?.clone();
+        let stream = stream.lock();
+        Some(f(&stream))
+    }
+
+    /// Feed `CandleClosed` events until the app bus goes away
+    pub async fn run(self: Arc<Self>, events: AppBus) {
+        let candles = match events.subscribe::<CandleClosed>(">") {
+            Ok(candles) => candles,
+            Err(e) => {
+                tracing::error!("SMC streams not fed from the app bus: {}", e);
+                return;
+            }
+        };
+        while let Some(closed) = candles.recv().await {
+            let c = &closed.candle;
+            self.on_candle(&c.symbol, &closed.timeframe, (c.open, c.high, c.low, c.close, c.timestamp));
+        }
+    }
+
+    fn stream(&self, symbol: &str, timeframe: &str) -> Arc<Mutex<SmcStream>> {
+        self.streams
+            .entry((symbol.to_string(), timeframe.to_string()))
+            .or_insert_with(|| Arc::new(Mutex::new(SmcStream::new(self.config.clone()))))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::ProviderCandle;
+    use crate::smc::ZoneData;
+    use std::time::Duration;
+
+    fn closed(ts: i64, open: f64, high: f64, low: f64, close: f64) -> CandleClosed {
+        CandleClosed {
+            timeframe: "M15".to_string(),
+            candle: ProviderCandle {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: ts,
+                open,
+                high,
+                low,
+                close,
+                volume: 1.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streams_fed_by_closed_candles() {
+        let events = AppBus::new(64);
+        let streams = Arc::new(SmcStreams::default());
+        tokio::spawn(streams.clone().run(events.clone()));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // History first, then the gap-completing candle arrives as an event
+        streams.catch_up("BTCUSDT", "M15", &[(100.0, 105.0, 95.0, 102.0, 1000), (102.0, 108.0, 101.0, 107.0, 2000)]);
+        events.emit(closed(3000, 110.0, 115.0, 108.0, 113.0)); // Gap 105-108
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let fvgs = streams
+            .read("BTCUSDT", "M15", |s| {
+                s.active_zones().iter().filter(|z| matches!(z.data, ZoneData::FairValueGap(_))).count()
+            })
+            .unwrap();
+        assert_eq!(fvgs, 1);
+        assert!(streams.read("BTCUSDT", "H1", |_| ()).is_none());
+
+        // Catching up again with candles it already has changes nothing
+        streams.catch_up("BTCUSDT", "M15", &[(100.0, 105.0, 95.0, 102.0, 1000), (110.0, 115.0, 108.0, 113.0, 3000)]);
+        assert_eq!(streams.read("BTCUSDT", "M15", |s| s.active_zones().len()), Some(1));
+    }
+}
@@ -0,0 +1,532 @@
+//! Session Liquidity Detection
+//!
+//! Time-based liquidity pools: previous day high/low, previous week high/low
+//! and the Asia/London/New York session ranges. Stops rest beyond these levels,
+//! so they are emitted as `LiquidityZone`s and swept with `check_sweep`.
+//!
+//! Session hours and the trading day rollover default per market type and can
+//! be overridden with `SESSION_DAY_<MARKET>` and `SESSIONS_<MARKET>`.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Timelike, Utc};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::liquidity::{LiquiditySource, LiquidityType, LiquidityZone};
+use crate::config::CONFIG;
+use crate::market::MarketType;
+
+lazy_static! {
+    /// Session config per market, with the overrides from `CONFIG` applied
+    static ref MARKET_SESSIONS: HashMap<&'static str, SessionConfig> =
+        [MarketType::Stock, MarketType::ETF, MarketType::Crypto, MarketType::Future]
+            .iter()
+            .map(|market_type| (market_key(market_type), SessionConfig::from_config(market_type)))
+            .collect();
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TradingSession {
+    Asia,
+    London,
+    NewYork,
+}
+
+impl TradingSession {
+    fn high_source(&self) -> LiquiditySource {
+        match self {
+            TradingSession::Asia => LiquiditySource::AsiaHigh,
+            TradingSession::London => LiquiditySource::LondonHigh,
+            TradingSession::NewYork => LiquiditySource::NewYorkHigh,
+        }
+    }
+
+    fn low_source(&self) -> LiquiditySource {
+        match self {
+            TradingSession::Asia => LiquiditySource::AsiaLow,
+            TradingSession::London => LiquiditySource::LondonLow,
+            TradingSession::NewYork => LiquiditySource::NewYorkLow,
+        }
+    }
+}
+
+/// Session hours in the session's local time zone
+#[derive(Debug, Clone)]
+pub struct SessionWindow {
+    pub session: TradingSession,
+    pub timezone: Tz,
+    pub start: NaiveTime,
+    pub end: NaiveTime, // Earlier than `start` for sessions crossing midnight
+}
+
+impl SessionWindow {
+    pub fn new(session: TradingSession, timezone: Tz, start: (u32, u32), end: (u32, u32)) -> Self {
+        Self {
+            session,
+            timezone,
+            start: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap_or(NaiveTime::MIN),
+            end: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap_or(NaiveTime::MIN),
+        }
+    }
+
+    /// Local date the session instance containing `ts` opened on, if `ts` is in session
+    fn instance(&self, ts: i64) -> Option<NaiveDate> {
+        let local = DateTime::<Utc>::from_timestamp_millis(ts)?.with_timezone(&self.timezone);
+        let time = local.time();
+        let date = local.date_naive();
+
+        if self.start <= self.end {
+            (time >= self.start && time < self.end).then_some(date)
+        } else if time >= self.start {
+            Some(date)
+        } else if time < self.end {
+            date.pred_opt()
+        } else {
+            None
+        }
+    }
+}
+
+/// Trading day rollover and session hours for a market
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    pub day_timezone: Tz,
+    pub day_start: NaiveTime, // Trading day rollover in `day_timezone`
+    pub sessions: Vec<SessionWindow>,
+}
+
+impl SessionConfig {
+    /// Session hours for a market type: the defaults, or the configured
+    /// `SESSION_DAY_<MARKET>`/`SESSIONS_<MARKET>` overrides
+    pub fn for_market(market_type: &MarketType) -> Self {
+        MARKET_SESSIONS
+            .get(market_key(market_type))
+            .cloned()
+            .unwrap_or_else(|| Self::defaults(market_type))
+    }
+
+    fn from_config(market_type: &MarketType) -> Self {
+        let key = market_key(market_type);
+        let defaults = Self::defaults(market_type);
+        let day = CONFIG.session_days.get(key).map(String::as_str);
+        let sessions = CONFIG.session_hours.get(key).map(String::as_str);
+        match defaults.clone().with_overrides(day, sessions) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::error!("Invalid session settings for {}: {}; using defaults", key, e);
+                defaults
+            }
+        }
+    }
+
+    /// Replace the trading day rollover (`"America/New_York@18:00"`) and/or the
+    /// sessions (`"asia=Asia/Tokyo@09:00-18:00,new_york=America/New_York@08:00-17:00"`)
+    pub fn with_overrides(mut self, day: Option<&str>, sessions: Option<&str>) -> Result<Self, String> {
+        if let Some(spec) = day {
+            let (timezone, start) = spec
+                .split_once('@')
+                .ok_or_else(|| format!("expected <time zone>@HH:MM, got {}", spec))?;
+            self.day_timezone = parse_timezone(timezone)?;
+            self.day_start = parse_time(start)?;
+        }
+        if let Some(spec) = sessions {
+            self.sessions = spec
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(parse_window)
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(self)
+    }
+
+    /// Built-in session hours for a market type
+    pub fn defaults(market_type: &MarketType) -> Self {
+        let asia = SessionWindow::new(TradingSession::Asia, chrono_tz::Asia::Tokyo, (9, 0), (18, 0));
+        let london = SessionWindow::new(TradingSession::London, chrono_tz::Europe::London, (8, 0), (16, 30));
+        let new_york = SessionWindow::new(TradingSession::NewYork, chrono_tz::America::New_York, (8, 0), (17, 0));
+
+        match market_type {
+            // Cash equities only trade the New York regular session
+            MarketType::Stock | MarketType::ETF => Self {
+                day_timezone: chrono_tz::America::New_York,
+                day_start: NaiveTime::MIN,
+                sessions: vec![SessionWindow::new(
+                    TradingSession::NewYork,
+                    chrono_tz::America::New_York,
+                    (9, 30),
+                    (16, 0),
+                )],
+            },
+            // Crypto rolls over at UTC midnight
+            MarketType::Crypto => Self {
+                day_timezone: chrono_tz::UTC,
+                day_start: NaiveTime::MIN,
+                sessions: vec![asia, london, new_york],
+            },
+            // Globex trading day starts at 18:00 New York time
+            MarketType::Future => Self {
+                day_timezone: chrono_tz::America::New_York,
+                day_start: NaiveTime::from_hms_opt(18, 0, 0).unwrap_or(NaiveTime::MIN),
+                sessions: vec![asia, london, new_york],
+            },
+        }
+    }
+
+    /// Trading day a timestamp belongs to, labelled by the date the day ends on
+    fn trading_day(&self, ts: i64) -> Option<NaiveDate> {
+        let local = DateTime::<Utc>::from_timestamp_millis(ts)?.with_timezone(&self.day_timezone);
+        let shift = if self.day_start == NaiveTime::MIN {
+            Duration::zero()
+        } else {
+            Duration::days(1) - Duration::seconds(self.day_start.num_seconds_from_midnight() as i64)
+        };
+        Some((local.naive_local() + shift).date())
+    }
+}
+
+/// Key of a market type in `SESSION_DAY_<MARKET>`/`SESSIONS_<MARKET>`
+fn market_key(market_type: &MarketType) -> &'static str {
+    match market_type {
+        MarketType::Stock => "STOCK",
+        MarketType::ETF => "ETF",
+        MarketType::Crypto => "CRYPTO",
+        MarketType::Future => "FUTURE",
+    }
+}
+
+/// `asia|london|new_york=<time zone>@HH:MM-HH:MM`
+fn parse_window(spec: &str) -> Result<SessionWindow, String> {
+    let malformed = || format!("expected <session>=<time zone>@HH:MM-HH:MM, got {}", spec);
+    let (name, rest) = spec.split_once('=').ok_or_else(malformed)?;
+    let (timezone, hours) = rest.split_once('@').ok_or_else(malformed)?;
+    let (start, end) = hours.split_once('-').ok_or_else(malformed)?;
+    let session = match name.trim() {
+        "asia" => TradingSession::Asia,
+        "london" => TradingSession::London,
+        "new_york" => TradingSession::NewYork,
+        other => return Err(format!("unknown session {}", other)),
+    };
+    Ok(SessionWindow {
+        session,
+        timezone: parse_timezone(timezone)?,
+        start: parse_time(start)?,
+        end: parse_time(end)?,
+    })
+}
+
+fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim().parse().map_err(|_| format!("unknown time zone {}", name.trim()))
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| format!("invalid time {}, expected HH:MM", time.trim()))
+}
+
+/// Detect time-based liquidity pools from candle data
+pub struct SessionLiquidityDetector {
+    pub config: SessionConfig,
+}
+
+impl SessionLiquidityDetector {
+    pub fn new(config: SessionConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn for_market(market_type: &MarketType) -> Self {
+        Self::new(SessionConfig::for_market(market_type))
+    }
+
+    /// Previous day high/low (buy-side/sell-side)
+    pub fn previous_day(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)], // (open, high, low, close, timestamp)
+    ) -> Vec<LiquidityZone> {
+        let days: Vec<Option<NaiveDate>> = candles
+            .iter()
+            .map(|c| self.config.trading_day(c.4))
+            .collect();
+
+        self.previous_period(
+            candles,
+            &days,
+            (LiquiditySource::PreviousDayHigh, LiquiditySource::PreviousDayLow),
+            0.8,
+        )
+    }
+
+    /// Previous week high/low (buy-side/sell-side)
+    pub fn previous_week(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)], // (open, high, low, close, timestamp)
+    ) -> Vec<LiquidityZone> {
+        let weeks: Vec<Option<(i32, u32)>> = candles
+            .iter()
+            .map(|c| {
+                self.config.trading_day(c.4).map(|d| {
+                    let week = d.iso_week();
+                    (week.year(), week.week())
+                })
+            })
+            .collect();
+
+        self.previous_period(
+            candles,
+            &weeks,
+            (LiquiditySource::PreviousWeekHigh, LiquiditySource::PreviousWeekLow),
+            1.0,
+        )
+    }
+
+    /// High/low of the most recent completed instance of each configured session
+    pub fn session_ranges(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)], // (open, high, low, close, timestamp)
+    ) -> Vec<LiquidityZone> {
+        let mut zones = Vec::new();
+        let Some(last) = candles.last() else {
+            return zones;
+        };
+
+        for window in &self.config.sessions {
+            let instances: Vec<Option<NaiveDate>> = candles.iter().map(|c| window.instance(c.4)).collect();
+            let current = window.instance(last.4);
+
+            // Latest session instance that has already closed
+            let Some(target) = instances
+                .iter()
+                .rev()
+                .flatten()
+                .find(|d| Some(**d) != current)
+                .copied()
+            else {
+                continue;
+            };
+
+            zones.extend(self.range_zones(
+                candles,
+                |i| instances[i] == Some(target),
+                (window.session.high_source(), window.session.low_source()),
+                0.6,
+            ));
+        }
+
+        zones
+    }
+
+    /// Detect all session-based liquidity pools, with sweep state applied
+    pub fn detect(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)],
+    ) -> Vec<LiquidityZone> {
+        let mut zones = Vec::new();
+        zones.extend(self.previous_week(candles));
+        zones.extend(self.previous_day(candles));
+        zones.extend(self.session_ranges(candles));
+        zones
+    }
+
+    /// High/low of the latest period before the current one
+    fn previous_period<K: PartialEq + Copy>(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)],
+        periods: &[Option<K>],
+        sources: (LiquiditySource, LiquiditySource),
+        strength: f64,
+    ) -> Vec<LiquidityZone> {
+        let Some(current) = periods.last().copied().flatten() else {
+            return vec![];
+        };
+
+        let Some(previous) = periods
+            .iter()
+            .rev()
+            .flatten()
+            .find(|p| **p != current)
+            .copied()
+        else {
+            return vec![];
+        };
+
+        self.range_zones(candles, |i| periods[i] == Some(previous), sources, strength)
+    }
+
+    /// Build high/low zones from the candles selected by `in_range`, then
+    /// check the candles that came after the range for sweeps
+    fn range_zones(
+        &self,
+        candles: &[(f64, f64, f64, f64, i64)],
+        in_range: impl Fn(usize) -> bool,
+        sources: (LiquiditySource, LiquiditySource),
+        strength: f64,
+    ) -> Vec<LiquidityZone> {
+        let mut high: Option<(f64, i64)> = None;
+        let mut low: Option<(f64, i64)> = None;
+        let mut range_end = 0;
+
+        for (i, &(_, h, l, _, ts)) in candles.iter().enumerate() {
+            if !in_range(i) {
+                continue;
+            }
+            if high.is_none_or(|(p, _)| h > p) {
+                high = Some((h, ts));
+            }
+            if low.is_none_or(|(p, _)| l < p) {
+                low = Some((l, ts));
+            }
+            range_end = i;
+        }
+
+        let (Some((high, high_ts)), Some((low, low_ts))) = (high, low) else {
+            return vec![];
+        };
+
+        let mut zones = vec![
+            LiquidityZone::new(LiquidityType::BuySideLiquidity, high, high_ts, strength)
+                .with_source(sources.0),
+            LiquidityZone::new(LiquidityType::SellSideLiquidity, low, low_ts, strength)
+                .with_source(sources.1),
+        ];
+
+        for &(_, h, l, _, ts) in &candles[range_end + 1..] {
+            for zone in zones.iter_mut() {
+                zone.check_sweep(h, l, ts);
+            }
+        }
+
+        zones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HOUR: i64 = 3_600_000;
+
+    fn utc_ms(y: i32, m: u32, d: u32, h: u32) -> i64 {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap().timestamp_millis()
+    }
+
+    /// Hourly candles with a fixed range around `mid`
+    fn hourly(start: i64, hours: i64, mid: f64) -> Vec<(f64, f64, f64, f64, i64)> {
+        (0..hours)
+            .map(|i| (mid, mid + 1.0, mid - 1.0, mid, start + i * HOUR))
+            .collect()
+    }
+
+    #[test]
+    fn test_previous_day_high_low_and_sweep() {
+        let detector = SessionLiquidityDetector::for_market(&MarketType::Crypto);
+
+        // Tuesday 2024-01-02 (UTC) trades 99-101, then Wednesday pokes above 101
+        let mut candles = hourly(utc_ms(2024, 1, 2, 0), 24, 100.0);
+        candles.push((100.0, 101.5, 99.5, 100.5, utc_ms(2024, 1, 3, 1)));
+
+        let zones = detector.previous_day(&candles);
+        assert_eq!(zones.len(), 2);
+
+        let pdh = zones.iter().find(|z| z.source == LiquiditySource::PreviousDayHigh).unwrap();
+        assert_eq!(pdh.price_level, 101.0);
+        assert!(pdh.swept);
+        assert_eq!(pdh.sweep_timestamp, Some(utc_ms(2024, 1, 3, 1)));
+
+        let pdl = zones.iter().find(|z| z.source == LiquiditySource::PreviousDayLow).unwrap();
+        assert_eq!(pdl.liquidity_type, LiquidityType::SellSideLiquidity);
+        assert!(!pdl.swept);
+    }
+
+    #[test]
+    fn test_previous_week() {
+        let detector = SessionLiquidityDetector::for_market(&MarketType::Crypto);
+
+        // Friday of ISO week 1, then Monday of week 2
+        let mut candles = hourly(utc_ms(2024, 1, 5, 0), 4, 100.0);
+        candles.extend(hourly(utc_ms(2024, 1, 8, 0), 4, 110.0));
+
+        let zones = detector.previous_week(&candles);
+        let pwh = zones.iter().find(|z| z.source == LiquiditySource::PreviousWeekHigh).unwrap();
+        let pwl = zones.iter().find(|z| z.source == LiquiditySource::PreviousWeekLow).unwrap();
+        assert_eq!(pwh.price_level, 101.0);
+        assert!(pwh.swept); // Week 2 opened above it
+        assert_eq!(pwl.price_level, 99.0);
+        assert!(!pwl.swept);
+    }
+
+    #[test]
+    fn test_session_ranges() {
+        let detector = SessionLiquidityDetector::for_market(&MarketType::Crypto);
+
+        // 2024-01-02: Tokyo 09:00-18:00 JST is 00:00-09:00 UTC,
+        // London 08:00-16:30 GMT is 08:00-16:30 UTC
+        let mut candles = hourly(utc_ms(2024, 1, 2, 0), 8, 100.0); // Asia
+        candles.extend(hourly(utc_ms(2024, 1, 2, 9), 3, 105.0)); // London (Asia closed)
+        candles.push((105.0, 106.0, 98.0, 99.0, utc_ms(2024, 1, 2, 12))); // Sweeps Asia low
+
+        let zones = detector.session_ranges(&candles);
+
+        let asia_high = zones.iter().find(|z| z.source == LiquiditySource::AsiaHigh).unwrap();
+        let asia_low = zones.iter().find(|z| z.source == LiquiditySource::AsiaLow).unwrap();
+        assert_eq!(asia_high.price_level, 101.0);
+        assert_eq!(asia_low.price_level, 99.0);
+        assert!(asia_low.swept);
+
+        // London is still open, so it has no completed range yet
+        assert!(!zones.iter().any(|z| z.source == LiquiditySource::LondonHigh));
+    }
+
+    #[test]
+    fn test_stock_session_uses_new_york_time() {
+        let config = SessionConfig::for_market(&MarketType::Stock);
+        let window = &config.sessions[0];
+
+        // 14:30 UTC is 09:30 EST in January, 10:30 EDT in July
+        assert!(window.instance(utc_ms(2024, 1, 10, 14) + HOUR / 2).is_some());
+        assert!(window.instance(utc_ms(2024, 1, 10, 14)).is_none());
+        assert!(window.instance(utc_ms(2024, 7, 10, 13) + HOUR / 2).is_some());
+    }
+
+    #[test]
+    fn test_futures_trading_day_rolls_at_six_pm() {
+        let config = SessionConfig::for_market(&MarketType::Future);
+
+        // Sunday 18:00 EST opens Monday's trading day
+        let sunday_evening = utc_ms(2024, 1, 7, 23);
+        assert_eq!(
+            config.trading_day(sunday_evening),
+            NaiveDate::from_ymd_opt(2024, 1, 8)
+        );
+    }
+
+    #[test]
+    fn test_overrides_replace_day_and_sessions() {
+        let config = SessionConfig::defaults(&MarketType::Crypto)
+            .with_overrides(
+                Some("America/New_York@17:00"),
+                Some("london=Europe/London@07:00-15:00, new_york=America/New_York@09:30-16:00"),
+            )
+            .unwrap();
+
+        assert_eq!(config.day_timezone, chrono_tz::America::New_York);
+        assert_eq!(config.day_start, NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+        assert_eq!(config.sessions.len(), 2);
+        assert_eq!(config.sessions[0].session, TradingSession::London);
+        assert_eq!(config.sessions[1].start, NaiveTime::from_hms_opt(9, 30, 0).unwrap());
+
+        // No overrides keeps the defaults
+        let defaults = SessionConfig::defaults(&MarketType::Future).with_overrides(None, None).unwrap();
+        assert_eq!(defaults.day_start, NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+        assert_eq!(defaults.sessions.len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_overrides_rejected() {
+        let defaults = SessionConfig::defaults(&MarketType::Stock);
+        assert!(defaults.clone().with_overrides(Some("Mars/Olympus@09:00"), None).is_err());
+        assert!(defaults.clone().with_overrides(Some("UTC"), None).is_err());
+        assert!(defaults.clone().with_overrides(None, Some("sydney=Australia/Sydney@09:00-17:00")).is_err());
+        assert!(defaults.with_overrides(None, Some("asia=Asia/Tokyo@9am-6pm")).is_err());
+    }
+}
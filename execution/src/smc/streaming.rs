@@ -0,0 +1,627 @@
+//! Streaming SMC Detection
+//!
+//! Incremental counterparts of the batch detectors for one symbol/timeframe.
+//! FVGs and order blocks are checked on the last few candles; the latest swing
+//! high/low and the equal-high/low clusters are kept as state, so each closed
+//! candle costs the same however long the stream runs. Active zones are
+//! updated for mitigation and sweeps in place and changes reported as events;
+//! past `max_active` the oldest ones are dropped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::bos::{BosDetector, BosType, BreakOfStructure, SwingPoint};
+use super::fvg::{FairValueGap, FvgDetector, FvgState};
+use super::liquidity::{LiquidityDetector, LiquidityType, LiquidityZone};
+use super::order_blocks::{OrderBlock, OrderBlockDetector, OrderBlockType};
+
+/// (open, high, low, close, timestamp)
+pub type SmcCandle = (f64, f64, f64, f64, i64);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneStatus {
+    Active,
+    Mitigated,
+    Inverted,
+    Swept,
+}
+
+/// Detector-specific zone payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ZoneData {
+    FairValueGap(FairValueGap),
+    OrderBlock(OrderBlock),
+    Liquidity(LiquidityZone),
+}
+
+/// A zone tracked by a stream, with a stable id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmcZone {
+    pub id: u64,
+    pub data: ZoneData,
+}
+
+impl SmcZone {
+    pub fn top(&self) -> f64 {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) => fvg.top,
+            ZoneData::OrderBlock(ob) => ob.top,
+            ZoneData::Liquidity(zone) => zone.price_level,
+        }
+    }
+
+    pub fn bottom(&self) -> f64 {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) => fvg.bottom,
+            ZoneData::OrderBlock(ob) => ob.bottom,
+            ZoneData::Liquidity(zone) => zone.price_level,
+        }
+    }
+
+    pub fn created_at(&self) -> i64 {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) => fvg.timestamp,
+            ZoneData::OrderBlock(ob) => ob.timestamp,
+            ZoneData::Liquidity(zone) => zone.timestamp,
+        }
+    }
+
+    /// When the zone stopped being active, if it has
+    pub fn ended_at(&self) -> Option<i64> {
+        match &self.data {
+            // An inverted gap is active again with its flipped polarity
+            ZoneData::FairValueGap(fvg) if fvg.is_inverted() => None,
+            ZoneData::FairValueGap(fvg) => fvg.invalidation_timestamp.or(fvg.mitigation_timestamp),
+            ZoneData::OrderBlock(ob) => ob.mitigation_timestamp,
+            ZoneData::Liquidity(zone) => zone.sweep_timestamp,
+        }
+    }
+
+    /// 0.0 to 1.0; for FVGs this is the unfilled share of the gap, from the
+    /// side it fills from now
+    pub fn strength(&self) -> f64 {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) if fvg.is_inverted() => 1.0 - fvg.inverse_fill_pct / 100.0,
+            ZoneData::FairValueGap(fvg) => 1.0 - fvg.fill_pct / 100.0,
+            ZoneData::OrderBlock(ob) => ob.strength,
+            ZoneData::Liquidity(zone) => zone.strength,
+        }
+    }
+
+    pub fn status(&self) -> ZoneStatus {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) if fvg.is_inverted() => ZoneStatus::Inverted,
+            ZoneData::FairValueGap(fvg) if fvg.mitigated => ZoneStatus::Mitigated,
+            ZoneData::OrderBlock(ob) if ob.mitigated => ZoneStatus::Mitigated,
+            ZoneData::Liquidity(zone) if zone.swept => ZoneStatus::Swept,
+            _ => ZoneStatus::Active,
+        }
+    }
+
+    /// Whether the zone currently supports price (bullish) or caps it (bearish)
+    pub fn is_bullish(&self) -> bool {
+        match &self.data {
+            ZoneData::FairValueGap(fvg) => fvg.polarity() == super::fvg::FvgType::Bullish,
+            ZoneData::OrderBlock(ob) => ob.ob_type == OrderBlockType::Bullish,
+            ZoneData::Liquidity(zone) => zone.liquidity_type == LiquidityType::SellSideLiquidity,
+        }
+    }
+}
+
+/// Something that changed on a stream after a candle closed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SmcEvent {
+    ZoneCreated(SmcZone),
+    ZoneUpdated(SmcZone), // FVG fill progression (partial, CE, inversion, invalidation)
+    ZoneMitigated(SmcZone),
+    LiquiditySwept(SmcZone),
+    StructureBreak(BreakOfStructure),
+}
+
+/// Tuning for streaming detectors
+#[derive(Debug, Clone)]
+pub struct SmcStreamConfig {
+    pub window_size: usize, // Candles a swing or equal high/low stays in play
+    pub max_active: usize,  // Unmitigated zones tracked; the oldest go first
+    pub max_recent: usize,  // Mitigated/swept zones kept for display
+    pub max_structure_breaks: usize,
+    pub min_gap_pct: f64,
+    pub min_move_pct: f64,
+    pub min_break_pct: f64,
+    pub equal_threshold_pct: f64,
+    pub min_touches: usize,
+}
+
+impl Default for SmcStreamConfig {
+    fn default() -> Self {
+        let fvg = FvgDetector::default();
+        let ob = OrderBlockDetector::default();
+        let bos = BosDetector::default();
+        let liq = LiquidityDetector::default();
+
+        Self {
+            window_size: 100,
+            max_active: 100,
+            max_recent: 50,
+            max_structure_breaks: 20,
+            min_gap_pct: fvg.min_gap_pct,
+            min_move_pct: ob.min_move_pct,
+            min_break_pct: bos.min_break_pct,
+            equal_threshold_pct: liq.equal_threshold_pct,
+            min_touches: liq.min_touches,
+        }
+    }
+}
+
+/// Equal highs or lows gathering touches before they count as liquidity
+#[derive(Debug, Clone)]
+struct LevelCluster {
+    base: f64, // First touch; later ones must be within the threshold of it
+    sum: f64,
+    touches: usize,
+    first_seen: i64,
+    position: usize, // Of the first touch in the stream
+}
+
+/// Candles kept for FVG, order block and swing patterns
+const PATTERN_CANDLES: usize = 5;
+
+/// Finite prices with the high at or above the low
+fn is_valid(candle: &SmcCandle) -> bool {
+    let (open, high, low, close, _) = *candle;
+    [open, high, low, close].iter().all(|p| p.is_finite()) && high >= low
+}
+
+/// Incremental SMC state for one symbol/timeframe
+pub struct SmcStream {
+    config: SmcStreamConfig,
+    fvg_detector: FvgDetector,
+    ob_detector: OrderBlockDetector,
+    window: VecDeque<SmcCandle>,
+    candles_seen: usize,
+    swing_high: Option<(SwingPoint, usize)>, // With the candle's position in the stream
+    swing_low: Option<(SwingPoint, usize)>,
+    equal_highs: Vec<LevelCluster>,
+    equal_lows: Vec<LevelCluster>,
+    active: Vec<SmcZone>,
+    recent: VecDeque<SmcZone>,
+    structure_breaks: VecDeque<BreakOfStructure>,
+    next_id: u64,
+}
+
+impl SmcStream {
+    pub fn new(config: SmcStreamConfig) -> Self {
+        Self {
+            fvg_detector: FvgDetector::new(config.min_gap_pct),
+            ob_detector: OrderBlockDetector::new(config.min_move_pct),
+            window: VecDeque::with_capacity(PATTERN_CANDLES + 1),
+            candles_seen: 0,
+            swing_high: None,
+            swing_low: None,
+            equal_highs: Vec::new(),
+            equal_lows: Vec::new(),
+            active: Vec::new(),
+            recent: VecDeque::new(),
+            structure_breaks: VecDeque::new(),
+            next_id: 1,
+            config,
+        }
+    }
+
+    /// Feed one closed candle; candles not newer than the last one, or with
+    /// NaN or inverted prices, are ignored
+    pub fn on_candle(&mut self, candle: SmcCandle) -> Vec<SmcEvent> {
+        if self.window.back().is_some_and(|last| candle.4 <= last.4) || !is_valid(&candle) {
+            return vec![];
+        }
+
+        let mut events = self.update_zones(candle);
+
+        self.window.push_back(candle);
+        if self.window.len() > PATTERN_CANDLES {
+            self.window.pop_front();
+        }
+        self.candles_seen += 1;
+        let window: Vec<SmcCandle> = self.window.iter().copied().collect();
+
+        // FVG completed by this candle
+        if window.len() >= 3 {
+            for fvg in self.fvg_detector.detect(&window[window.len() - 3..]) {
+                events.push(self.add_zone(ZoneData::FairValueGap(fvg)));
+            }
+        }
+
+        // Order block whose 3-candle follow-through just completed
+        if window.len() >= 5 {
+            for ob in self.ob_detector.detect(&window[window.len() - 5..]) {
+                events.push(self.add_zone(ZoneData::OrderBlock(ob)));
+            }
+        }
+
+        self.expire_levels();
+        if window.len() >= 3 {
+            self.update_swings(&window[window.len() - 3..]);
+        }
+        for bos in self.check_breaks(candle) {
+            let repeated = self
+                .structure_breaks
+                .iter()
+                .rev()
+                .find(|b| b.bos_type == bos.bos_type)
+                .is_some_and(|b| b.break_level == bos.break_level);
+
+            if !repeated {
+                self.structure_breaks.push_back(bos.clone());
+                if self.structure_breaks.len() > self.config.max_structure_breaks {
+                    self.structure_breaks.pop_front();
+                }
+                events.push(SmcEvent::StructureBreak(bos));
+            }
+        }
+
+        let (_, high, low, _, _) = candle;
+        let candidates = [
+            self.touch_level(LiquidityType::BuySideLiquidity, high, candle.4),
+            self.touch_level(LiquidityType::SellSideLiquidity, low, candle.4),
+        ];
+        for zone in candidates.into_iter().flatten() {
+            if !self.knows_liquidity(&zone) {
+                events.push(self.add_zone(ZoneData::Liquidity(zone)));
+            }
+        }
+
+        events
+    }
+
+    /// Zones that are still in play
+    pub fn active_zones(&self) -> &[SmcZone] {
+        &self.active
+    }
+
+    /// Recently mitigated, inverted or swept zones (oldest first)
+    pub fn recent_zones(&self) -> impl Iterator<Item = &SmcZone> {
+        self.recent.iter()
+    }
+
+    /// Recent structure breaks (oldest first)
+    pub fn structure_breaks(&self) -> impl Iterator<Item = &BreakOfStructure> {
+        self.structure_breaks.iter()
+    }
+
+    /// Apply a new candle to active zones and to FVGs that may still invert
+    fn update_zones(&mut self, candle: SmcCandle) -> Vec<SmcEvent> {
+        let (_, high, low, close, ts) = candle;
+        let mut events = Vec::new();
+        let mut retired = Vec::new();
+
+        for (idx, zone) in self.active.iter_mut().enumerate() {
+            let before = zone.status();
+
+            match &mut zone.data {
+                ZoneData::FairValueGap(fvg) => {
+                    let state = fvg.state.clone();
+                    if fvg.update(high, low, close, ts) != &state && !fvg.mitigated {
+                        events.push(SmcEvent::ZoneUpdated(zone.clone()));
+                    }
+                }
+                ZoneData::OrderBlock(ob) => {
+                    let mid = (ob.top + ob.bottom) / 2.0;
+                    ob.check_mitigation(mid.max(low).min(high), ts);
+                }
+                ZoneData::Liquidity(liq) => {
+                    liq.check_sweep(high, low, ts);
+                }
+            }
+
+            let after = zone.status();
+            if after != before {
+                events.push(match after {
+                    ZoneStatus::Swept => SmcEvent::LiquiditySwept(zone.clone()),
+                    _ => SmcEvent::ZoneMitigated(zone.clone()),
+                });
+                retired.push(idx);
+            }
+        }
+
+        // Filled gaps keep watching for a close through (inverse FVG), and
+        // inverse FVGs for a close back through that invalidates them
+        for zone in self.recent.iter_mut() {
+            if let ZoneData::FairValueGap(fvg) = &mut zone.data {
+                let state = fvg.state.clone();
+                if matches!(state, FvgState::Filled | FvgState::Inverted)
+                    && fvg.update(high, low, close, ts) != &state
+                {
+                    events.push(SmcEvent::ZoneUpdated(zone.clone()));
+                }
+            }
+        }
+
+        for idx in retired.into_iter().rev() {
+            let zone = self.active.remove(idx);
+            self.recent.push_back(zone);
+            if self.recent.len() > self.config.max_recent {
+                self.recent.pop_front();
+            }
+        }
+
+        events
+    }
+
+    fn add_zone(&mut self, data: ZoneData) -> SmcEvent {
+        let zone = SmcZone { id: self.next_id, data };
+        self.next_id += 1;
+        self.active.push(zone.clone());
+        if self.active.len() > self.config.max_active {
+            self.active.remove(0);
+        }
+        SmcEvent::ZoneCreated(zone)
+    }
+
+    /// Whether an equivalent liquidity level is already tracked
+    fn knows_liquidity(&self, candidate: &LiquidityZone) -> bool {
+        self.active.iter().chain(self.recent.iter()).any(|zone| match &zone.data {
+            ZoneData::Liquidity(known) => {
+                known.liquidity_type == candidate.liquidity_type
+                    && ((known.price_level - candidate.price_level).abs() / known.price_level) * 100.0
+                        <= self.config.equal_threshold_pct
+            }
+            _ => false,
+        })
+    }
+
+    /// Forget swings and level clusters older than `window_size` candles
+    fn expire_levels(&mut self) {
+        let Some(oldest) = (self.candles_seen + 1).checked_sub(self.config.window_size) else {
+            return;
+        };
+        let live = |at: &usize| *at >= oldest;
+        if self.swing_high.as_ref().is_some_and(|(_, at)| !live(at)) {
+            self.swing_high = None;
+        }
+        if self.swing_low.as_ref().is_some_and(|(_, at)| !live(at)) {
+            self.swing_low = None;
+        }
+        self.equal_highs.retain(|c| live(&c.position));
+        self.equal_lows.retain(|c| live(&c.position));
+    }
+
+    /// The middle of the last three candles may have just become a swing
+    fn update_swings(&mut self, last3: &[SmcCandle]) {
+        let (prev, mid, next) = (last3[0], last3[1], last3[2]);
+        let at = self.candles_seen - 1;
+        if mid.1 > prev.1 && mid.1 > next.1 {
+            self.swing_high = Some((SwingPoint { price: mid.1, timestamp: mid.4, is_high: true }, at));
+        }
+        if mid.2 < prev.2 && mid.2 < next.2 {
+            self.swing_low = Some((SwingPoint { price: mid.2, timestamp: mid.4, is_high: false }, at));
+        }
+    }
+
+    /// Breaks of the latest swing high/low by `candle`, as `BosDetector::detect` scores them
+    fn check_breaks(&self, candle: SmcCandle) -> Vec<BreakOfStructure> {
+        let (_, high, low, _, ts) = candle;
+        let min_break_pct = self.config.min_break_pct;
+        let strength = |break_pct: f64| (break_pct / (min_break_pct * 3.0)).min(1.0);
+        let mut breaks = Vec::new();
+
+        if let Some((swing, _)) = &self.swing_high {
+            let break_pct = ((high - swing.price) / swing.price) * 100.0;
+            if high > swing.price && break_pct >= min_break_pct {
+                breaks.push(BreakOfStructure::new(BosType::Bullish, swing, ts, strength(break_pct)));
+            }
+        }
+        if let Some((swing, _)) = &self.swing_low {
+            let break_pct = ((swing.price - low) / swing.price) * 100.0;
+            if low < swing.price && break_pct >= min_break_pct {
+                breaks.push(BreakOfStructure::new(BosType::Bearish, swing, ts, strength(break_pct)));
+            }
+        }
+        breaks
+    }
+
+    /// Add a high (buy side) or low (sell side) to the first cluster it matches,
+    /// or start one; a cluster reaching `min_touches` becomes a liquidity zone
+    fn touch_level(&mut self, liquidity_type: LiquidityType, price: f64, ts: i64) -> Option<LiquidityZone> {
+        let threshold = self.config.equal_threshold_pct;
+        let min_touches = self.config.min_touches;
+        let at = self.candles_seen - 1;
+        let clusters = match liquidity_type {
+            LiquidityType::BuySideLiquidity => &mut self.equal_highs,
+            LiquidityType::SellSideLiquidity => &mut self.equal_lows,
+        };
+
+        let idx = match clusters
+            .iter()
+            .position(|c| ((price - c.base).abs() / c.base) * 100.0 <= threshold)
+        {
+            Some(idx) => {
+                let cluster = &mut clusters[idx];
+                cluster.sum += price;
+                cluster.touches += 1;
+                idx
+            }
+            None => {
+                clusters.push(LevelCluster { base: price, sum: price, touches: 1, first_seen: ts, position: at });
+                clusters.len() - 1
+            }
+        };
+
+        if clusters[idx].touches < min_touches {
+            return None;
+        }
+        let cluster = clusters.remove(idx);
+        let strength = (cluster.touches as f64 / (min_touches as f64 * 2.0)).min(1.0);
+        Some(LiquidityZone::new(
+            liquidity_type,
+            cluster.sum / cluster.touches as f64,
+            cluster.first_seen,
+            strength,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn created(events: &[SmcEvent]) -> Vec<&SmcZone> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                SmcEvent::ZoneCreated(z) => Some(z),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fvg_created_then_mitigated() {
+        let mut stream = SmcStream::new(SmcStreamConfig::default());
+
+        stream.on_candle((100.0, 105.0, 95.0, 102.0, 1000));
+        stream.on_candle((102.0, 108.0, 101.0, 107.0, 2000));
+        let events = stream.on_candle((110.0, 115.0, 108.0, 113.0, 3000)); // Gap 105-108
+
+        let fvg_id = created(&events)
+            .iter()
+            .find(|z| matches!(z.data, ZoneData::FairValueGap(_)))
+            .map(|z| z.id)
+            .expect("FVG should be created");
+
+        let events = stream.on_candle((113.0, 114.0, 106.0, 110.0, 4000)); // Through CE
+        assert!(events.iter().any(|e| matches!(e, SmcEvent::ZoneUpdated(z) if z.id == fvg_id)));
+
+        let events = stream.on_candle((110.0, 111.0, 104.0, 106.0, 5000)); // Full fill
+        assert!(events.iter().any(|e| matches!(e, SmcEvent::ZoneMitigated(z) if z.id == fvg_id)));
+        assert!(!stream.active_zones().iter().any(|z| z.id == fvg_id));
+        assert!(stream.recent_zones().any(|z| z.id == fvg_id));
+
+        let events = stream.on_candle((106.0, 106.5, 100.0, 101.0, 6000)); // Closes below
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SmcEvent::ZoneUpdated(z) if z.id == fvg_id && z.status() == ZoneStatus::Inverted)));
+
+        // Inverse FVG: now resistance, retested from below, then closed back through
+        let events = stream.on_candle((101.0, 106.5, 100.5, 103.0, 7000));
+        assert!(!events.iter().any(|e| matches!(e, SmcEvent::ZoneUpdated(z) if z.id == fvg_id)));
+        let zone = stream.recent_zones().find(|z| z.id == fvg_id).unwrap();
+        assert!(!zone.is_bullish());
+        assert!(zone.ended_at().is_none());
+        assert!((zone.strength() - 0.5).abs() < 1e-9);
+
+        let events = stream.on_candle((103.0, 109.0, 102.0, 108.5, 8000));
+        let zone = events
+            .iter()
+            .find_map(|e| match e {
+                SmcEvent::ZoneUpdated(z) if z.id == fvg_id => Some(z),
+                _ => None,
+            })
+            .expect("invalidation should be reported");
+        assert_eq!(zone.status(), ZoneStatus::Mitigated);
+        assert_eq!(zone.ended_at(), Some(8000));
+    }
+
+    #[test]
+    fn test_liquidity_swept_in_place() {
+        let mut stream = SmcStream::new(SmcStreamConfig::default());
+
+        stream.on_candle((100.0, 105.0, 98.0, 102.0, 1000));
+        let events = stream.on_candle((102.0, 105.1, 100.0, 103.0, 2000)); // Equal high
+        let zone = created(&events)
+            .into_iter()
+            .find(|z| matches!(&z.data, ZoneData::Liquidity(l) if l.liquidity_type == LiquidityType::BuySideLiquidity))
+            .cloned()
+            .expect("equal highs should create buy-side liquidity");
+
+        // Re-detecting the same level on later candles does not duplicate it
+        let events = stream.on_candle((103.0, 104.0, 101.0, 102.0, 3000));
+        assert!(created(&events).iter().all(|z| !matches!(&z.data, ZoneData::Liquidity(l) if l.liquidity_type == LiquidityType::BuySideLiquidity)));
+
+        let events = stream.on_candle((102.0, 108.0, 101.0, 107.0, 4000)); // Runs the highs
+        assert!(events.iter().any(|e| matches!(e, SmcEvent::LiquiditySwept(z) if z.id == zone.id)));
+        assert!(stream.recent_zones().any(|z| z.id == zone.id && z.status() == ZoneStatus::Swept));
+    }
+
+    #[test]
+    fn test_structure_break_reported_once() {
+        let mut stream = SmcStream::new(SmcStreamConfig::default());
+        let candles = [
+            (100.0, 102.0, 98.0, 101.0, 1000),
+            (101.0, 105.0, 100.0, 104.0, 2000), // Swing high at 105
+            (104.0, 103.0, 99.0, 100.0, 3000),
+            (100.0, 102.0, 99.0, 101.0, 4000),
+        ];
+        for c in candles {
+            stream.on_candle(c);
+        }
+
+        let events = stream.on_candle((101.0, 107.0, 100.0, 106.0, 5000));
+        assert!(events.iter().any(|e| matches!(e, SmcEvent::StructureBreak(b) if b.bos_type == BosType::Bullish)));
+
+        let events = stream.on_candle((106.0, 107.5, 105.0, 107.0, 6000));
+        assert!(!events.iter().any(|e| matches!(e, SmcEvent::StructureBreak(b) if b.bos_type == BosType::Bullish)));
+        assert_eq!(stream.structure_breaks().filter(|b| b.bos_type == BosType::Bullish).count(), 1);
+    }
+
+    #[test]
+    fn test_stale_candles_ignored() {
+        let mut stream = SmcStream::new(SmcStreamConfig::default());
+        stream.on_candle((100.0, 105.0, 95.0, 102.0, 2000));
+        assert!(stream.on_candle((100.0, 105.0, 95.0, 102.0, 1000)).is_empty());
+    }
+
+    #[test]
+    fn test_active_zones_capped() {
+        let mut stream = SmcStream::new(SmcStreamConfig { max_active: 2, ..SmcStreamConfig::default() });
+        // A staircase of gaps up, each candle's low above the high two candles back
+        for i in 0..8 {
+            let base = 100.0 + i as f64 * 10.0;
+            stream.on_candle((base, base + 4.0, base - 1.0, base + 3.0, 1000 + i * 1000));
+        }
+
+        let fvgs: Vec<i64> = stream
+            .active_zones()
+            .iter()
+            .filter(|z| matches!(z.data, ZoneData::FairValueGap(_)))
+            .map(|z| z.created_at())
+            .collect();
+        assert!(stream.active_zones().len() <= 2);
+        assert_eq!(fvgs.last(), Some(&8000));
+    }
+
+    #[test]
+    fn test_bad_candles_ignored() {
+        let mut stream = SmcStream::new(SmcStreamConfig::default());
+        for i in 0..5 {
+            let base = 100.0 + i as f64 * 3.0;
+            stream.on_candle((base, base + 2.0, base - 1.0, base + 1.0, 1000 + i * 1000));
+        }
+        assert!(stream.on_candle((110.0, f64::NAN, 108.0, 109.0, 6000)).is_empty());
+        assert!(stream.on_candle((110.0, 100.0, 108.0, 109.0, 7000)).is_empty()); // High below low
+
+        // Still takes good candles afterwards
+        stream.on_candle((112.0, 113.0, 111.0, 112.5, 8000));
+        assert!(stream.on_candle((112.0, 113.0, 111.0, 112.5, 8000)).is_empty());
+    }
+
+    #[test]
+    fn test_levels_expire_after_window() {
+        let mut stream = SmcStream::new(SmcStreamConfig { window_size: 10, ..SmcStreamConfig::default() });
+        let is_buy_side = |z: &&SmcZone| matches!(&z.data, ZoneData::Liquidity(l) if l.liquidity_type == LiquidityType::BuySideLiquidity);
+
+        stream.on_candle((100.0, 105.0, 98.0, 102.0, 1000));
+        for i in 0..10 {
+            let ts = 2000 + i * 1000;
+            stream.on_candle((99.0 - i as f64, 100.0 - i as f64, 90.0 - i as f64, 95.0 - i as f64, ts));
+        }
+        // The first 105 high is 11 candles back, out of the window
+        let events = stream.on_candle((100.0, 105.1, 95.0, 104.0, 20_000));
+        assert!(!created(&events).iter().any(is_buy_side));
+
+        let events = stream.on_candle((104.0, 105.0, 100.0, 103.0, 21_000));
+        assert!(created(&events).iter().any(is_buy_side));
+    }
+}